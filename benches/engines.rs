@@ -0,0 +1,91 @@
+// compare tree-walking evaluator with bytecode vm.
+// run with `cargo bench --bench engines`
+
+use std::{
+    cell::RefCell,
+    rc::Rc,
+    thread,
+    time::{Duration, Instant},
+};
+
+use dlang::{
+    ast::{Nodetrait, Program},
+    eval::Evaluator,
+    lexer::Lexer,
+    object::environment::Environment,
+    parser::Parser,
+    vm::Vm,
+};
+
+const RUNS: u32 = 5;
+
+const PROGRAMS: [(&str, &str); 3] = [
+    (
+        "fib",
+        "let fib = fn(n) { if (n < 2) { n } else { fib(n - 1) + fib(n - 2) } }; fib(22)",
+    ),
+    (
+        "sum",
+        "let sum = fn(n, acc = 0) { if (n == 0) { return acc; } sum(n - 1, acc + n) }; sum(200000)",
+    ),
+    (
+        "strings",
+        "let grow = fn(s, n) { if (n == 0) { return s; } grow(s + \"ab\", n - 1) };
+         let count = fn(n, acc) { if (n == 0) { return acc; } count(n - 1, acc + [grow(\"\", 50)][0]) };
+         count(500, \"\")",
+    ),
+];
+
+fn parse(input: &str) -> Program {
+    Parser::new(Lexer::new(input.to_string())).parse().unwrap()
+}
+
+/// best time of runs
+fn measure(mut run: impl FnMut()) -> Duration {
+    let mut best = Duration::MAX;
+    for _ in 0..RUNS {
+        let start = Instant::now();
+        run();
+        best = best.min(start.elapsed());
+    }
+    best
+}
+
+fn bench() {
+    println!(
+        "{:<10} {:>12} {:>12} {:>8}",
+        "program", "tree", "vm", "speedup"
+    );
+
+    for (name, input) in PROGRAMS {
+        let program = parse(input);
+
+        let tree = measure(|| {
+            let env = Rc::new(RefCell::new(Environment::new()));
+            Evaluator::new()
+                .evaluate(program.clone().to_node(), &env)
+                .unwrap();
+        });
+        let vm = measure(|| {
+            Vm::new().evaluate(&program).unwrap();
+        });
+
+        println!(
+            "{:<10} {:>12?} {:>12?} {:>7.1}x",
+            name,
+            tree,
+            vm,
+            tree.as_secs_f64() / vm.as_secs_f64()
+        );
+    }
+}
+
+fn main() {
+    // tree-walking evaluator takes native stack on each call
+    thread::Builder::new()
+        .stack_size(256 * 1024 * 1024)
+        .spawn(bench)
+        .unwrap()
+        .join()
+        .unwrap();
+}
@@ -1,6 +1,10 @@
+pub mod fold;
+pub mod visit;
+pub mod visit_mut;
+
 use std::fmt::Debug;
 
-use crate::token;
+use crate::{object::bigint::BigInt, parser::find_precedences, token};
 
 #[derive(Debug, Clone)]
 pub enum Node {
@@ -15,12 +19,16 @@ pub enum Statement {
     ExpressionStatement(ExpressionStatement),
     ReturnStatement(ReturnStatement),
     BlockStatement(BlockStatement),
+    ThrowStatement(ThrowStatement),
+    ImportStatement(ImportStatement),
+    ExportStatement(ExportStatement),
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Expression {
     Identifier(Identifier),
     IntegerLiteral(IntegerLiteral),
+    BigIntLiteral(BigIntLiteral),
     BooleanLiteral(BooleanLiteral),
     StringLiteral(StringLiteral),
     FunctionLiteral(FunctionLiteral),
@@ -30,6 +38,8 @@ pub enum Expression {
     IfExpression(IfExpression),
     CallExpression(CallExpression),
     IndexExpression(IndexExpression),
+    TryExpression(TryExpression),
+    MemberExpression(MemberExpression),
 }
 
 pub trait Nodetrait {
@@ -45,6 +55,9 @@ impl Nodetrait for Statement {
             Statement::ExpressionStatement(stm) => stm.literal(),
             Statement::ReturnStatement(stm) => stm.literal(),
             Statement::BlockStatement(stm) => stm.literal(),
+            Statement::ThrowStatement(stm) => stm.literal(),
+            Statement::ImportStatement(stm) => stm.literal(),
+            Statement::ExportStatement(stm) => stm.literal(),
         }
     }
 
@@ -54,6 +67,9 @@ impl Nodetrait for Statement {
             Statement::ExpressionStatement(stm) => stm.to_str(),
             Statement::ReturnStatement(stm) => stm.to_str(),
             Statement::BlockStatement(stm) => stm.to_str(),
+            Statement::ThrowStatement(stm) => stm.to_str(),
+            Statement::ImportStatement(stm) => stm.to_str(),
+            Statement::ExportStatement(stm) => stm.to_str(),
         }
     }
 
@@ -62,11 +78,27 @@ impl Nodetrait for Statement {
     }
 }
 
+impl Statement {
+    /// first token of the statement
+    pub fn token(&self) -> &token::Token {
+        match self {
+            Statement::LetStatement(stm) => &stm.token,
+            Statement::ExpressionStatement(stm) => &stm.token,
+            Statement::ReturnStatement(stm) => &stm.token,
+            Statement::BlockStatement(stm) => &stm.token,
+            Statement::ThrowStatement(stm) => &stm.token,
+            Statement::ImportStatement(stm) => &stm.token,
+            Statement::ExportStatement(stm) => &stm.token,
+        }
+    }
+}
+
 impl Nodetrait for Expression {
     fn literal(&self) -> String {
         match self {
             Expression::Identifier(idt) => idt.literal(),
             Expression::IntegerLiteral(int) => int.literal(),
+            Expression::BigIntLiteral(int) => int.literal(),
             Expression::BooleanLiteral(bool) => bool.literal(),
             Expression::FunctionLiteral(flit) => flit.literal(),
             Expression::StringLiteral(slit) => slit.literal(),
@@ -76,6 +108,8 @@ impl Nodetrait for Expression {
             Expression::IfExpression(ifx) => ifx.literal(),
             Expression::CallExpression(cexp) => cexp.literal(),
             Expression::IndexExpression(idx) => idx.literal(),
+            Expression::TryExpression(texp) => texp.literal(),
+            Expression::MemberExpression(mexp) => mexp.literal(),
         }
     }
 
@@ -83,6 +117,7 @@ impl Nodetrait for Expression {
         match self {
             Expression::Identifier(idt) => idt.to_str(),
             Expression::IntegerLiteral(int) => int.to_str(),
+            Expression::BigIntLiteral(int) => int.to_str(),
             Expression::BooleanLiteral(bool) => bool.to_str(),
             Expression::FunctionLiteral(flit) => flit.to_str(),
             Expression::StringLiteral(slit) => slit.to_str(),
@@ -92,6 +127,8 @@ impl Nodetrait for Expression {
             Expression::IfExpression(ifx) => ifx.to_str(),
             Expression::CallExpression(cexp) => cexp.to_str(),
             Expression::IndexExpression(idx) => idx.to_str(),
+            Expression::TryExpression(texp) => texp.to_str(),
+            Expression::MemberExpression(mexp) => mexp.to_str(),
         }
     }
 
@@ -115,6 +152,12 @@ impl Program {
     }
 }
 
+impl Default for Program {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Nodetrait for Program {
     fn literal(&self) -> String {
         let mut buf = String::new();
@@ -129,26 +172,80 @@ impl Nodetrait for Program {
     }
 
     fn to_str(&self) -> String {
-        let mut buf = String::new();
+        statements_to_str(&self.statements)
+    }
 
-        buf.push_str("Program: ");
+    fn to_node(self) -> Node {
+        Node::Program(self)
+    }
+}
 
-        for stm in self.statements.iter() {
-            buf.push_str(&stm.to_str())
+/// statements as source, separated where they would be read as one
+fn statements_to_str(statements: &[Statement]) -> String {
+    let mut buf = String::new();
+    for statement in statements {
+        if buf.is_empty() || buf.ends_with(';') {
+            buf.push_str(&statement.to_str());
+            continue;
+        }
+        buf.push(' ');
+        // parenthesis next to an expression would be read as a call of it
+        match statement {
+            Statement::ExpressionStatement(ExpressionStatement {
+                expression: Some(Expression::InfixExpression(exp)),
+                ..
+            }) => buf.push_str(&left_spine_to_str(exp)),
+            _ => buf.push_str(&statement.to_str()),
         }
-
-        buf
     }
+    buf
+}
 
-    fn to_node(self) -> Node {
-        Node::Program(self)
+/// infix expression without parentheses on its left side, where precedence allows.
+/// they are not needed by the expression, but by the statement before
+fn left_spine_to_str(exp: &InfixExpression) -> String {
+    let left = match exp.left.as_ref() {
+        Expression::InfixExpression(left)
+            if find_precedences(left.operator.kind) >= find_precedences(exp.operator.kind) =>
+        {
+            left_spine_to_str(left)
+        }
+        left => left.to_str(),
+    };
+    format!("{} {} {}", left, exp.operator.literal, exp.right.to_str())
+}
+
+/// left side of call, index and member expression.
+/// prefix expression binds looser than them, so it is parenthesized
+fn operand_to_str(exp: &Expression) -> String {
+    match exp {
+        Expression::PrefixExpression(_) => format!("({})", exp.to_str()),
+        _ => exp.to_str(),
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone)]
 pub struct Identifier {
     pub token: token::Token, // token::IDENT
     pub value: String,
+    // filled by resolver, None means lookup by name
+    pub binding: Option<Binding>,
+}
+
+// binding is not a part of syntax
+impl PartialEq for Identifier {
+    fn eq(&self, other: &Self) -> bool {
+        self.token == other.token && self.value == other.value
+    }
+}
+
+/// where identifier is bound, computed by resolver
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Binding {
+    // slot of environment, `depth` environments outward
+    Local { depth: usize, slot: usize },
+    // top level environment, `depth` environments outward, looked up by name
+    Global { depth: usize },
 }
 
 impl Nodetrait for Identifier {
@@ -182,6 +279,26 @@ impl Nodetrait for IntegerLiteral {
     }
 }
 
+/// integer with `n` suffix, of any size. evaluated to Int if the value fits,
+/// so `2n` is the same as `2`
+#[derive(Debug, Clone, PartialEq)]
+pub struct BigIntLiteral {
+    pub token: token::Token, // token::BigInt
+    pub value: BigInt,
+}
+
+impl Nodetrait for BigIntLiteral {
+    fn literal(&self) -> String {
+        self.token.literal.clone()
+    }
+    fn to_str(&self) -> String {
+        format!("{}n", self.value)
+    }
+    fn to_node(self) -> Node {
+        Expression::BigIntLiteral(self).to_node()
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct BooleanLiteral {
     pub token: token::Token, // token::False or True
@@ -211,18 +328,38 @@ impl Nodetrait for StringLiteral {
         self.token.literal.clone()
     }
     fn to_str(&self) -> String {
-        self.value.clone().to_string()
+        format!("\"{}\"", self.value)
     }
     fn to_node(self) -> Node {
         Expression::StringLiteral(self).to_node()
     }
 }
 
+#[derive(Debug, Clone, PartialEq)]
+pub struct Parameter {
+    pub ident: Identifier,
+    // evaluated on call, when argument is not given
+    pub default: Option<Expression>,
+}
+
+impl Parameter {
+    pub fn to_str(&self) -> String {
+        let mut buf = self.ident.to_str();
+        if let Some(default) = &self.default {
+            buf.push_str(" = ");
+            buf.push_str(&default.to_str());
+        }
+        buf
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct FunctionLiteral {
     pub token: token::Token, // token::Function
     pub ident: Option<Identifier>,
-    pub parameters: Vec<Identifier>,
+    pub parameters: Vec<Parameter>,
+    // rest parameter (...ident), collects remaining arguments
+    pub rest: Option<Identifier>,
     pub body: BlockStatement,
 }
 
@@ -235,17 +372,20 @@ impl Nodetrait for FunctionLiteral {
         for param in &self.parameters {
             params.push(param.to_str())
         }
+        if let Some(rest) = &self.rest {
+            params.push(format!("...{}", rest.to_str()))
+        }
         let mut buf = String::new();
         buf.push_str(&self.literal());
-        if self.ident.is_some() {
-            buf.push_str(" ");
-            buf.push_str(&self.ident.as_ref().unwrap().to_str());
+        if let Some(ident) = &self.ident {
+            buf.push(' ');
+            buf.push_str(&ident.to_str());
         }
-        buf.push_str("(");
+        buf.push('(');
         buf.push_str(&params.join(", "));
         buf.push_str(") {");
         buf.push_str(&self.body.to_str());
-        buf.push_str("}");
+        buf.push('}');
         buf
     }
     fn to_node(self) -> Node {
@@ -269,9 +409,9 @@ impl Nodetrait for ArrayLiteral {
         }
 
         let mut buf = String::new();
-        buf.push_str("[");
+        buf.push('[');
         buf.push_str(&elements.join(", "));
-        buf.push_str("]");
+        buf.push(']');
         buf
     }
     fn to_node(self) -> Node {
@@ -293,14 +433,14 @@ impl Nodetrait for LetStatement {
     fn to_str(&self) -> String {
         let mut buf = String::new();
         buf.push_str(&self.literal());
-        buf.push_str(" ");
+        buf.push(' ');
         buf.push_str(&self.identifier.to_str());
 
-        if self.value.is_some() {
+        if let Some(value) = &self.value {
             buf.push_str(" = ");
-            buf.push_str(&self.value.as_ref().unwrap().to_str())
+            buf.push_str(&value.to_str())
         }
-        buf.push_str(";");
+        buf.push(';');
         buf
     }
     fn to_node(self) -> Node {
@@ -321,11 +461,11 @@ impl Nodetrait for ReturnStatement {
     fn to_str(&self) -> String {
         let mut buf = String::new();
         buf.push_str(&self.literal());
-        if self.value.is_some() {
-            buf.push_str(" ");
-            buf.push_str(&self.value.as_ref().unwrap().to_str())
+        if let Some(value) = &self.value {
+            buf.push(' ');
+            buf.push_str(&value.to_str())
         }
-        buf.push_str(";");
+        buf.push(';');
         buf
     }
     fn to_node(self) -> Node {
@@ -333,6 +473,92 @@ impl Nodetrait for ReturnStatement {
     }
 }
 
+#[derive(Debug, Clone, PartialEq)]
+pub struct ThrowStatement {
+    pub token: token::Token, // token::Throw
+    pub value: Expression,
+}
+
+impl Nodetrait for ThrowStatement {
+    fn literal(&self) -> String {
+        self.token.literal.clone()
+    }
+    fn to_str(&self) -> String {
+        let mut buf = String::new();
+        buf.push_str(&self.literal());
+        buf.push(' ');
+        buf.push_str(&self.value.to_str());
+        buf.push(';');
+        buf
+    }
+    fn to_node(self) -> Node {
+        Statement::ThrowStatement(self).to_node()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportStatement {
+    pub token: token::Token, // token::Import
+    pub path: StringLiteral,
+    pub alias: Identifier,
+}
+
+impl Nodetrait for ImportStatement {
+    fn literal(&self) -> String {
+        self.token.literal.clone()
+    }
+    fn to_str(&self) -> String {
+        let mut buf = String::new();
+        buf.push_str(&self.literal());
+        buf.push(' ');
+        buf.push_str(&self.path.to_str());
+        buf.push_str(" as ");
+        buf.push_str(&self.alias.to_str());
+        buf.push(';');
+        buf
+    }
+    fn to_node(self) -> Node {
+        Statement::ImportStatement(self).to_node()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExportStatement {
+    pub token: token::Token, // token::Export
+    // let statement or named function literal
+    pub statement: Box<Statement>,
+}
+
+impl ExportStatement {
+    /// name bound by the exported statement
+    pub fn name(&self) -> Option<String> {
+        match self.statement.as_ref() {
+            Statement::LetStatement(stm) => Some(stm.identifier.value.clone()),
+            Statement::ExpressionStatement(ExpressionStatement {
+                expression: Some(Expression::FunctionLiteral(func)),
+                ..
+            }) => func.ident.as_ref().map(|ident| ident.value.clone()),
+            _ => None,
+        }
+    }
+}
+
+impl Nodetrait for ExportStatement {
+    fn literal(&self) -> String {
+        self.token.literal.clone()
+    }
+    fn to_str(&self) -> String {
+        let mut buf = String::new();
+        buf.push_str(&self.literal());
+        buf.push(' ');
+        buf.push_str(&self.statement.to_str());
+        buf
+    }
+    fn to_node(self) -> Node {
+        Statement::ExportStatement(self).to_node()
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct BlockStatement {
     pub token: token::Token,
@@ -344,11 +570,7 @@ impl Nodetrait for BlockStatement {
         self.token.literal.clone()
     }
     fn to_str(&self) -> String {
-        let mut buf = String::new();
-        for statement in &self.statements {
-            buf.push_str(&statement.to_str());
-        }
-        buf
+        statements_to_str(&self.statements)
     }
     fn to_node(self) -> Node {
         Statement::BlockStatement(self).to_node()
@@ -367,8 +589,8 @@ impl Nodetrait for ExpressionStatement {
     fn to_str(&self) -> String {
         let mut buf = String::new();
         buf.push_str("");
-        if self.expression.is_some() {
-            buf.push_str(&self.expression.as_ref().unwrap().to_str());
+        if let Some(expression) = &self.expression {
+            buf.push_str(&expression.to_str());
         }
         buf
     }
@@ -389,9 +611,9 @@ impl Nodetrait for PrefixExpression {
     fn to_str(&self) -> String {
         let mut buf = String::new();
         buf.push_str(&self.literal());
-        buf.push_str("(");
+        buf.push('(');
         buf.push_str(&self.right.to_str());
-        buf.push_str(")");
+        buf.push(')');
         buf
     }
     fn to_node(self) -> Node {
@@ -412,13 +634,13 @@ impl Nodetrait for InfixExpression {
     }
     fn to_str(&self) -> String {
         let mut buf = String::new();
-        buf.push_str("(");
+        buf.push('(');
         buf.push_str(&self.left.to_str());
-        buf.push_str(" ");
+        buf.push(' ');
         buf.push_str(&self.operator.literal);
-        buf.push_str(" ");
+        buf.push(' ');
         buf.push_str(&self.right.to_str());
-        buf.push_str(")");
+        buf.push(')');
         buf
     }
     fn to_node(self) -> Node {
@@ -440,15 +662,22 @@ impl Nodetrait for IfExpression {
     fn to_str(&self) -> String {
         let mut buf = String::new();
         buf.push_str("if ");
-        buf.push_str(&self.condition.to_str());
+        // infix expression is parenthesized by itself
+        if let Expression::InfixExpression(_) = self.condition.as_ref() {
+            buf.push_str(&self.condition.to_str());
+        } else {
+            buf.push('(');
+            buf.push_str(&self.condition.to_str());
+            buf.push(')');
+        }
         buf.push_str(" {");
         buf.push_str(&self.consequence.to_str());
-        buf.push_str("}");
+        buf.push('}');
 
-        if self.alternative.is_some() {
+        if let Some(alternative) = &self.alternative {
             buf.push_str(" else {");
-            buf.push_str(&self.alternative.as_ref().unwrap().to_str());
-            buf.push_str("}");
+            buf.push_str(&alternative.to_str());
+            buf.push('}');
         }
         buf
     }
@@ -457,11 +686,25 @@ impl Nodetrait for IfExpression {
     }
 }
 
+#[derive(Debug, Clone, PartialEq)]
+pub struct NamedArgument {
+    pub ident: Identifier,
+    pub value: Expression,
+}
+
+impl NamedArgument {
+    pub fn to_str(&self) -> String {
+        format!("{} = {}", self.ident.to_str(), self.value.to_str())
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct CallExpression {
     pub token: token::Token, // Token::IDENT
     pub function: Box<Expression>,
     pub arguments: Vec<Expression>,
+    // named arguments always follow positional arguments
+    pub named_arguments: Vec<NamedArgument>,
 }
 impl Nodetrait for CallExpression {
     fn literal(&self) -> String {
@@ -474,11 +717,14 @@ impl Nodetrait for CallExpression {
         for arg in &self.arguments {
             args.push(arg.to_str())
         }
+        for arg in &self.named_arguments {
+            args.push(arg.to_str())
+        }
 
-        buf.push_str(&self.function.to_str());
-        buf.push_str("(");
+        buf.push_str(&operand_to_str(&self.function));
+        buf.push('(');
         buf.push_str(&args.join(", "));
-        buf.push_str(")");
+        buf.push(')');
         buf
     }
     fn to_node(self) -> Node {
@@ -498,13 +744,77 @@ impl Nodetrait for IndexExpression {
     }
     fn to_str(&self) -> String {
         let mut buf = String::new();
-        buf.push_str(&self.left.to_str());
-        buf.push_str("[");
+        buf.push_str(&operand_to_str(&self.left));
+        buf.push('[');
         buf.push_str(&self.index.to_str());
-        buf.push_str("]");
+        buf.push(']');
         buf
     }
     fn to_node(self) -> Node {
         Expression::IndexExpression(self).to_node()
     }
 }
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TryExpression {
+    pub token: token::Token, // Token::Try
+    pub block: BlockStatement,
+    // identifier which the caught error is bound to
+    pub catch_ident: Option<Identifier>,
+    pub catch: Option<BlockStatement>,
+    pub finally: Option<BlockStatement>,
+}
+impl Nodetrait for TryExpression {
+    fn literal(&self) -> String {
+        self.token.literal.clone()
+    }
+    fn to_str(&self) -> String {
+        let mut buf = String::new();
+        buf.push_str("try {");
+        buf.push_str(&self.block.to_str());
+        buf.push('}');
+
+        if let Some(catch) = &self.catch {
+            buf.push_str(" catch ");
+            if let Some(catch_ident) = &self.catch_ident {
+                buf.push('(');
+                buf.push_str(&catch_ident.to_str());
+                buf.push_str(") ");
+            }
+            buf.push('{');
+            buf.push_str(&catch.to_str());
+            buf.push('}');
+        }
+        if let Some(finally) = &self.finally {
+            buf.push_str(" finally {");
+            buf.push_str(&finally.to_str());
+            buf.push('}');
+        }
+        buf
+    }
+    fn to_node(self) -> Node {
+        Expression::TryExpression(self).to_node()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MemberExpression {
+    pub token: token::Token, // Token::Dot
+    pub left: Box<Expression>,
+    pub member: Identifier,
+}
+impl Nodetrait for MemberExpression {
+    fn literal(&self) -> String {
+        self.token.literal.clone()
+    }
+    fn to_str(&self) -> String {
+        let mut buf = String::new();
+        buf.push_str(&operand_to_str(&self.left));
+        buf.push('.');
+        buf.push_str(&self.member.to_str());
+        buf
+    }
+    fn to_node(self) -> Node {
+        Expression::MemberExpression(self).to_node()
+    }
+}
@@ -0,0 +1,369 @@
+// transformation of syntax tree by value, building a new tree.
+// each `fold_*` method rebuilds the node from folded children by default,
+// override only the nodes of interest. `fold_expression` and `fold_statement`
+// can replace a node with another kind of node.
+
+use super::{
+    ArrayLiteral, BigIntLiteral, BlockStatement, BooleanLiteral, CallExpression, ExportStatement,
+    Expression, ExpressionStatement, FunctionLiteral, Identifier, IfExpression, ImportStatement,
+    IndexExpression, InfixExpression, IntegerLiteral, LetStatement, MemberExpression,
+    NamedArgument, Parameter, PrefixExpression, Program, ReturnStatement, Statement, StringLiteral,
+    ThrowStatement, TryExpression,
+};
+
+pub trait Fold {
+    fn fold_program(&mut self, program: Program) -> Program {
+        fold_program(self, program)
+    }
+
+    fn fold_statement(&mut self, stm: Statement) -> Statement {
+        fold_statement(self, stm)
+    }
+    fn fold_let_statement(&mut self, stm: LetStatement) -> LetStatement {
+        fold_let_statement(self, stm)
+    }
+    fn fold_expression_statement(&mut self, stm: ExpressionStatement) -> ExpressionStatement {
+        fold_expression_statement(self, stm)
+    }
+    fn fold_return_statement(&mut self, stm: ReturnStatement) -> ReturnStatement {
+        fold_return_statement(self, stm)
+    }
+    fn fold_block_statement(&mut self, stm: BlockStatement) -> BlockStatement {
+        fold_block_statement(self, stm)
+    }
+    fn fold_throw_statement(&mut self, stm: ThrowStatement) -> ThrowStatement {
+        fold_throw_statement(self, stm)
+    }
+    fn fold_import_statement(&mut self, stm: ImportStatement) -> ImportStatement {
+        fold_import_statement(self, stm)
+    }
+    fn fold_export_statement(&mut self, stm: ExportStatement) -> ExportStatement {
+        fold_export_statement(self, stm)
+    }
+
+    fn fold_expression(&mut self, exp: Expression) -> Expression {
+        fold_expression(self, exp)
+    }
+    fn fold_identifier(&mut self, ident: Identifier) -> Identifier {
+        ident
+    }
+    fn fold_integer_literal(&mut self, lit: IntegerLiteral) -> IntegerLiteral {
+        lit
+    }
+    fn fold_big_int_literal(&mut self, lit: BigIntLiteral) -> BigIntLiteral {
+        lit
+    }
+    fn fold_boolean_literal(&mut self, lit: BooleanLiteral) -> BooleanLiteral {
+        lit
+    }
+    fn fold_string_literal(&mut self, lit: StringLiteral) -> StringLiteral {
+        lit
+    }
+    fn fold_function_literal(&mut self, func: FunctionLiteral) -> FunctionLiteral {
+        fold_function_literal(self, func)
+    }
+    fn fold_parameter(&mut self, param: Parameter) -> Parameter {
+        fold_parameter(self, param)
+    }
+    fn fold_array_literal(&mut self, arr: ArrayLiteral) -> ArrayLiteral {
+        fold_array_literal(self, arr)
+    }
+    fn fold_infix_expression(&mut self, exp: InfixExpression) -> InfixExpression {
+        fold_infix_expression(self, exp)
+    }
+    fn fold_prefix_expression(&mut self, exp: PrefixExpression) -> PrefixExpression {
+        fold_prefix_expression(self, exp)
+    }
+    fn fold_if_expression(&mut self, exp: IfExpression) -> IfExpression {
+        fold_if_expression(self, exp)
+    }
+    fn fold_call_expression(&mut self, exp: CallExpression) -> CallExpression {
+        fold_call_expression(self, exp)
+    }
+    fn fold_named_argument(&mut self, arg: NamedArgument) -> NamedArgument {
+        fold_named_argument(self, arg)
+    }
+    fn fold_index_expression(&mut self, exp: IndexExpression) -> IndexExpression {
+        fold_index_expression(self, exp)
+    }
+    fn fold_try_expression(&mut self, exp: TryExpression) -> TryExpression {
+        fold_try_expression(self, exp)
+    }
+    fn fold_member_expression(&mut self, exp: MemberExpression) -> MemberExpression {
+        fold_member_expression(self, exp)
+    }
+}
+
+pub fn fold_program<F: Fold + ?Sized>(folder: &mut F, program: Program) -> Program {
+    Program {
+        statements: program
+            .statements
+            .into_iter()
+            .map(|stm| folder.fold_statement(stm))
+            .collect(),
+    }
+}
+
+pub fn fold_statement<F: Fold + ?Sized>(folder: &mut F, stm: Statement) -> Statement {
+    match stm {
+        Statement::LetStatement(stm) => Statement::LetStatement(folder.fold_let_statement(stm)),
+        Statement::ExpressionStatement(stm) => {
+            Statement::ExpressionStatement(folder.fold_expression_statement(stm))
+        }
+        Statement::ReturnStatement(stm) => {
+            Statement::ReturnStatement(folder.fold_return_statement(stm))
+        }
+        Statement::BlockStatement(stm) => {
+            Statement::BlockStatement(folder.fold_block_statement(stm))
+        }
+        Statement::ThrowStatement(stm) => {
+            Statement::ThrowStatement(folder.fold_throw_statement(stm))
+        }
+        Statement::ImportStatement(stm) => {
+            Statement::ImportStatement(folder.fold_import_statement(stm))
+        }
+        Statement::ExportStatement(stm) => {
+            Statement::ExportStatement(folder.fold_export_statement(stm))
+        }
+    }
+}
+
+pub fn fold_let_statement<F: Fold + ?Sized>(folder: &mut F, stm: LetStatement) -> LetStatement {
+    LetStatement {
+        token: stm.token,
+        identifier: folder.fold_identifier(stm.identifier),
+        value: stm.value.map(|exp| folder.fold_expression(exp)),
+    }
+}
+
+pub fn fold_expression_statement<F: Fold + ?Sized>(
+    folder: &mut F,
+    stm: ExpressionStatement,
+) -> ExpressionStatement {
+    ExpressionStatement {
+        token: stm.token,
+        expression: stm.expression.map(|exp| folder.fold_expression(exp)),
+    }
+}
+
+pub fn fold_return_statement<F: Fold + ?Sized>(
+    folder: &mut F,
+    stm: ReturnStatement,
+) -> ReturnStatement {
+    ReturnStatement {
+        token: stm.token,
+        value: stm.value.map(|exp| folder.fold_expression(exp)),
+    }
+}
+
+pub fn fold_block_statement<F: Fold + ?Sized>(
+    folder: &mut F,
+    stm: BlockStatement,
+) -> BlockStatement {
+    BlockStatement {
+        token: stm.token,
+        statements: stm
+            .statements
+            .into_iter()
+            .map(|stm| folder.fold_statement(stm))
+            .collect(),
+    }
+}
+
+pub fn fold_throw_statement<F: Fold + ?Sized>(
+    folder: &mut F,
+    stm: ThrowStatement,
+) -> ThrowStatement {
+    ThrowStatement {
+        token: stm.token,
+        value: folder.fold_expression(stm.value),
+    }
+}
+
+pub fn fold_import_statement<F: Fold + ?Sized>(
+    folder: &mut F,
+    stm: ImportStatement,
+) -> ImportStatement {
+    ImportStatement {
+        token: stm.token,
+        path: folder.fold_string_literal(stm.path),
+        alias: folder.fold_identifier(stm.alias),
+    }
+}
+
+pub fn fold_export_statement<F: Fold + ?Sized>(
+    folder: &mut F,
+    stm: ExportStatement,
+) -> ExportStatement {
+    ExportStatement {
+        token: stm.token,
+        statement: Box::new(folder.fold_statement(*stm.statement)),
+    }
+}
+
+pub fn fold_expression<F: Fold + ?Sized>(folder: &mut F, exp: Expression) -> Expression {
+    match exp {
+        Expression::Identifier(ident) => Expression::Identifier(folder.fold_identifier(ident)),
+        Expression::IntegerLiteral(lit) => {
+            Expression::IntegerLiteral(folder.fold_integer_literal(lit))
+        }
+        Expression::BigIntLiteral(lit) => {
+            Expression::BigIntLiteral(folder.fold_big_int_literal(lit))
+        }
+        Expression::BooleanLiteral(lit) => {
+            Expression::BooleanLiteral(folder.fold_boolean_literal(lit))
+        }
+        Expression::StringLiteral(lit) => {
+            Expression::StringLiteral(folder.fold_string_literal(lit))
+        }
+        Expression::FunctionLiteral(func) => {
+            Expression::FunctionLiteral(folder.fold_function_literal(func))
+        }
+        Expression::ArrayLiteral(arr) => Expression::ArrayLiteral(folder.fold_array_literal(arr)),
+        Expression::InfixExpression(exp) => {
+            Expression::InfixExpression(folder.fold_infix_expression(exp))
+        }
+        Expression::PrefixExpression(exp) => {
+            Expression::PrefixExpression(folder.fold_prefix_expression(exp))
+        }
+        Expression::IfExpression(exp) => Expression::IfExpression(folder.fold_if_expression(exp)),
+        Expression::CallExpression(exp) => {
+            Expression::CallExpression(folder.fold_call_expression(exp))
+        }
+        Expression::IndexExpression(exp) => {
+            Expression::IndexExpression(folder.fold_index_expression(exp))
+        }
+        Expression::TryExpression(exp) => {
+            Expression::TryExpression(folder.fold_try_expression(exp))
+        }
+        Expression::MemberExpression(exp) => {
+            Expression::MemberExpression(folder.fold_member_expression(exp))
+        }
+    }
+}
+
+pub fn fold_function_literal<F: Fold + ?Sized>(
+    folder: &mut F,
+    func: FunctionLiteral,
+) -> FunctionLiteral {
+    FunctionLiteral {
+        token: func.token,
+        ident: func.ident.map(|ident| folder.fold_identifier(ident)),
+        parameters: func
+            .parameters
+            .into_iter()
+            .map(|param| folder.fold_parameter(param))
+            .collect(),
+        rest: func.rest.map(|ident| folder.fold_identifier(ident)),
+        body: folder.fold_block_statement(func.body),
+    }
+}
+
+pub fn fold_parameter<F: Fold + ?Sized>(folder: &mut F, param: Parameter) -> Parameter {
+    Parameter {
+        ident: folder.fold_identifier(param.ident),
+        default: param.default.map(|exp| folder.fold_expression(exp)),
+    }
+}
+
+pub fn fold_array_literal<F: Fold + ?Sized>(folder: &mut F, arr: ArrayLiteral) -> ArrayLiteral {
+    ArrayLiteral {
+        elements: arr
+            .elements
+            .into_iter()
+            .map(|exp| folder.fold_expression(exp))
+            .collect(),
+    }
+}
+
+pub fn fold_infix_expression<F: Fold + ?Sized>(
+    folder: &mut F,
+    exp: InfixExpression,
+) -> InfixExpression {
+    InfixExpression {
+        token: exp.token,
+        left: Box::new(folder.fold_expression(*exp.left)),
+        operator: exp.operator,
+        right: Box::new(folder.fold_expression(*exp.right)),
+    }
+}
+
+pub fn fold_prefix_expression<F: Fold + ?Sized>(
+    folder: &mut F,
+    exp: PrefixExpression,
+) -> PrefixExpression {
+    PrefixExpression {
+        token: exp.token,
+        right: Box::new(folder.fold_expression(*exp.right)),
+    }
+}
+
+pub fn fold_if_expression<F: Fold + ?Sized>(folder: &mut F, exp: IfExpression) -> IfExpression {
+    IfExpression {
+        token: exp.token,
+        condition: Box::new(folder.fold_expression(*exp.condition)),
+        consequence: folder.fold_block_statement(exp.consequence),
+        alternative: exp
+            .alternative
+            .map(|block| folder.fold_block_statement(block)),
+    }
+}
+
+pub fn fold_call_expression<F: Fold + ?Sized>(
+    folder: &mut F,
+    exp: CallExpression,
+) -> CallExpression {
+    CallExpression {
+        token: exp.token,
+        function: Box::new(folder.fold_expression(*exp.function)),
+        arguments: exp
+            .arguments
+            .into_iter()
+            .map(|exp| folder.fold_expression(exp))
+            .collect(),
+        named_arguments: exp
+            .named_arguments
+            .into_iter()
+            .map(|arg| folder.fold_named_argument(arg))
+            .collect(),
+    }
+}
+
+pub fn fold_named_argument<F: Fold + ?Sized>(folder: &mut F, arg: NamedArgument) -> NamedArgument {
+    NamedArgument {
+        ident: folder.fold_identifier(arg.ident),
+        value: folder.fold_expression(arg.value),
+    }
+}
+
+pub fn fold_index_expression<F: Fold + ?Sized>(
+    folder: &mut F,
+    exp: IndexExpression,
+) -> IndexExpression {
+    IndexExpression {
+        token: exp.token,
+        left: Box::new(folder.fold_expression(*exp.left)),
+        index: Box::new(folder.fold_expression(*exp.index)),
+    }
+}
+
+pub fn fold_try_expression<F: Fold + ?Sized>(folder: &mut F, exp: TryExpression) -> TryExpression {
+    TryExpression {
+        token: exp.token,
+        block: folder.fold_block_statement(exp.block),
+        catch_ident: exp.catch_ident.map(|ident| folder.fold_identifier(ident)),
+        catch: exp.catch.map(|block| folder.fold_block_statement(block)),
+        finally: exp.finally.map(|block| folder.fold_block_statement(block)),
+    }
+}
+
+pub fn fold_member_expression<F: Fold + ?Sized>(
+    folder: &mut F,
+    exp: MemberExpression,
+) -> MemberExpression {
+    MemberExpression {
+        token: exp.token,
+        left: Box::new(folder.fold_expression(*exp.left)),
+        member: folder.fold_identifier(exp.member),
+    }
+}
@@ -0,0 +1,291 @@
+// traversal of syntax tree by shared reference.
+// each `visit_*` method walks into children by default,
+// override only the nodes of interest and call `walk_*` to keep descending.
+
+use super::{
+    ArrayLiteral, BigIntLiteral, BlockStatement, BooleanLiteral, CallExpression, ExportStatement,
+    Expression, ExpressionStatement, FunctionLiteral, Identifier, IfExpression, ImportStatement,
+    IndexExpression, InfixExpression, IntegerLiteral, LetStatement, MemberExpression,
+    NamedArgument, Parameter, PrefixExpression, Program, ReturnStatement, Statement, StringLiteral,
+    ThrowStatement, TryExpression,
+};
+
+pub trait Visitor<'ast> {
+    fn visit_program(&mut self, program: &'ast Program) {
+        walk_program(self, program)
+    }
+
+    fn visit_statement(&mut self, stm: &'ast Statement) {
+        walk_statement(self, stm)
+    }
+    fn visit_let_statement(&mut self, stm: &'ast LetStatement) {
+        walk_let_statement(self, stm)
+    }
+    fn visit_expression_statement(&mut self, stm: &'ast ExpressionStatement) {
+        walk_expression_statement(self, stm)
+    }
+    fn visit_return_statement(&mut self, stm: &'ast ReturnStatement) {
+        walk_return_statement(self, stm)
+    }
+    fn visit_block_statement(&mut self, stm: &'ast BlockStatement) {
+        walk_block_statement(self, stm)
+    }
+    fn visit_throw_statement(&mut self, stm: &'ast ThrowStatement) {
+        walk_throw_statement(self, stm)
+    }
+    fn visit_import_statement(&mut self, stm: &'ast ImportStatement) {
+        walk_import_statement(self, stm)
+    }
+    fn visit_export_statement(&mut self, stm: &'ast ExportStatement) {
+        walk_export_statement(self, stm)
+    }
+
+    fn visit_expression(&mut self, exp: &'ast Expression) {
+        walk_expression(self, exp)
+    }
+    fn visit_identifier(&mut self, _ident: &'ast Identifier) {}
+    fn visit_integer_literal(&mut self, _lit: &'ast IntegerLiteral) {}
+    fn visit_big_int_literal(&mut self, _lit: &'ast BigIntLiteral) {}
+    fn visit_boolean_literal(&mut self, _lit: &'ast BooleanLiteral) {}
+    fn visit_string_literal(&mut self, _lit: &'ast StringLiteral) {}
+    fn visit_function_literal(&mut self, func: &'ast FunctionLiteral) {
+        walk_function_literal(self, func)
+    }
+    fn visit_parameter(&mut self, param: &'ast Parameter) {
+        walk_parameter(self, param)
+    }
+    fn visit_array_literal(&mut self, arr: &'ast ArrayLiteral) {
+        walk_array_literal(self, arr)
+    }
+    fn visit_infix_expression(&mut self, exp: &'ast InfixExpression) {
+        walk_infix_expression(self, exp)
+    }
+    fn visit_prefix_expression(&mut self, exp: &'ast PrefixExpression) {
+        walk_prefix_expression(self, exp)
+    }
+    fn visit_if_expression(&mut self, exp: &'ast IfExpression) {
+        walk_if_expression(self, exp)
+    }
+    fn visit_call_expression(&mut self, exp: &'ast CallExpression) {
+        walk_call_expression(self, exp)
+    }
+    fn visit_named_argument(&mut self, arg: &'ast NamedArgument) {
+        walk_named_argument(self, arg)
+    }
+    fn visit_index_expression(&mut self, exp: &'ast IndexExpression) {
+        walk_index_expression(self, exp)
+    }
+    fn visit_try_expression(&mut self, exp: &'ast TryExpression) {
+        walk_try_expression(self, exp)
+    }
+    fn visit_member_expression(&mut self, exp: &'ast MemberExpression) {
+        walk_member_expression(self, exp)
+    }
+}
+
+pub fn walk_program<'ast, V: Visitor<'ast> + ?Sized>(visitor: &mut V, program: &'ast Program) {
+    for stm in &program.statements {
+        visitor.visit_statement(stm);
+    }
+}
+
+pub fn walk_statement<'ast, V: Visitor<'ast> + ?Sized>(visitor: &mut V, stm: &'ast Statement) {
+    match stm {
+        Statement::LetStatement(stm) => visitor.visit_let_statement(stm),
+        Statement::ExpressionStatement(stm) => visitor.visit_expression_statement(stm),
+        Statement::ReturnStatement(stm) => visitor.visit_return_statement(stm),
+        Statement::BlockStatement(stm) => visitor.visit_block_statement(stm),
+        Statement::ThrowStatement(stm) => visitor.visit_throw_statement(stm),
+        Statement::ImportStatement(stm) => visitor.visit_import_statement(stm),
+        Statement::ExportStatement(stm) => visitor.visit_export_statement(stm),
+    }
+}
+
+pub fn walk_let_statement<'ast, V: Visitor<'ast> + ?Sized>(
+    visitor: &mut V,
+    stm: &'ast LetStatement,
+) {
+    visitor.visit_identifier(&stm.identifier);
+    if let Some(value) = &stm.value {
+        visitor.visit_expression(value);
+    }
+}
+
+pub fn walk_expression_statement<'ast, V: Visitor<'ast> + ?Sized>(
+    visitor: &mut V,
+    stm: &'ast ExpressionStatement,
+) {
+    if let Some(expression) = &stm.expression {
+        visitor.visit_expression(expression);
+    }
+}
+
+pub fn walk_return_statement<'ast, V: Visitor<'ast> + ?Sized>(
+    visitor: &mut V,
+    stm: &'ast ReturnStatement,
+) {
+    if let Some(value) = &stm.value {
+        visitor.visit_expression(value);
+    }
+}
+
+pub fn walk_block_statement<'ast, V: Visitor<'ast> + ?Sized>(
+    visitor: &mut V,
+    stm: &'ast BlockStatement,
+) {
+    for stm in &stm.statements {
+        visitor.visit_statement(stm);
+    }
+}
+
+pub fn walk_throw_statement<'ast, V: Visitor<'ast> + ?Sized>(
+    visitor: &mut V,
+    stm: &'ast ThrowStatement,
+) {
+    visitor.visit_expression(&stm.value);
+}
+
+pub fn walk_import_statement<'ast, V: Visitor<'ast> + ?Sized>(
+    visitor: &mut V,
+    stm: &'ast ImportStatement,
+) {
+    visitor.visit_string_literal(&stm.path);
+    visitor.visit_identifier(&stm.alias);
+}
+
+pub fn walk_export_statement<'ast, V: Visitor<'ast> + ?Sized>(
+    visitor: &mut V,
+    stm: &'ast ExportStatement,
+) {
+    visitor.visit_statement(&stm.statement);
+}
+
+pub fn walk_expression<'ast, V: Visitor<'ast> + ?Sized>(visitor: &mut V, exp: &'ast Expression) {
+    match exp {
+        Expression::Identifier(ident) => visitor.visit_identifier(ident),
+        Expression::IntegerLiteral(lit) => visitor.visit_integer_literal(lit),
+        Expression::BigIntLiteral(lit) => visitor.visit_big_int_literal(lit),
+        Expression::BooleanLiteral(lit) => visitor.visit_boolean_literal(lit),
+        Expression::StringLiteral(lit) => visitor.visit_string_literal(lit),
+        Expression::FunctionLiteral(func) => visitor.visit_function_literal(func),
+        Expression::ArrayLiteral(arr) => visitor.visit_array_literal(arr),
+        Expression::InfixExpression(exp) => visitor.visit_infix_expression(exp),
+        Expression::PrefixExpression(exp) => visitor.visit_prefix_expression(exp),
+        Expression::IfExpression(exp) => visitor.visit_if_expression(exp),
+        Expression::CallExpression(exp) => visitor.visit_call_expression(exp),
+        Expression::IndexExpression(exp) => visitor.visit_index_expression(exp),
+        Expression::TryExpression(exp) => visitor.visit_try_expression(exp),
+        Expression::MemberExpression(exp) => visitor.visit_member_expression(exp),
+    }
+}
+
+pub fn walk_function_literal<'ast, V: Visitor<'ast> + ?Sized>(
+    visitor: &mut V,
+    func: &'ast FunctionLiteral,
+) {
+    if let Some(ident) = &func.ident {
+        visitor.visit_identifier(ident);
+    }
+    for param in &func.parameters {
+        visitor.visit_parameter(param);
+    }
+    if let Some(rest) = &func.rest {
+        visitor.visit_identifier(rest);
+    }
+    visitor.visit_block_statement(&func.body);
+}
+
+pub fn walk_parameter<'ast, V: Visitor<'ast> + ?Sized>(visitor: &mut V, param: &'ast Parameter) {
+    visitor.visit_identifier(&param.ident);
+    if let Some(default) = &param.default {
+        visitor.visit_expression(default);
+    }
+}
+
+pub fn walk_array_literal<'ast, V: Visitor<'ast> + ?Sized>(
+    visitor: &mut V,
+    arr: &'ast ArrayLiteral,
+) {
+    for element in &arr.elements {
+        visitor.visit_expression(element);
+    }
+}
+
+pub fn walk_infix_expression<'ast, V: Visitor<'ast> + ?Sized>(
+    visitor: &mut V,
+    exp: &'ast InfixExpression,
+) {
+    visitor.visit_expression(&exp.left);
+    visitor.visit_expression(&exp.right);
+}
+
+pub fn walk_prefix_expression<'ast, V: Visitor<'ast> + ?Sized>(
+    visitor: &mut V,
+    exp: &'ast PrefixExpression,
+) {
+    visitor.visit_expression(&exp.right);
+}
+
+pub fn walk_if_expression<'ast, V: Visitor<'ast> + ?Sized>(
+    visitor: &mut V,
+    exp: &'ast IfExpression,
+) {
+    visitor.visit_expression(&exp.condition);
+    visitor.visit_block_statement(&exp.consequence);
+    if let Some(alternative) = &exp.alternative {
+        visitor.visit_block_statement(alternative);
+    }
+}
+
+pub fn walk_call_expression<'ast, V: Visitor<'ast> + ?Sized>(
+    visitor: &mut V,
+    exp: &'ast CallExpression,
+) {
+    visitor.visit_expression(&exp.function);
+    for arg in &exp.arguments {
+        visitor.visit_expression(arg);
+    }
+    for arg in &exp.named_arguments {
+        visitor.visit_named_argument(arg);
+    }
+}
+
+pub fn walk_named_argument<'ast, V: Visitor<'ast> + ?Sized>(
+    visitor: &mut V,
+    arg: &'ast NamedArgument,
+) {
+    visitor.visit_identifier(&arg.ident);
+    visitor.visit_expression(&arg.value);
+}
+
+pub fn walk_index_expression<'ast, V: Visitor<'ast> + ?Sized>(
+    visitor: &mut V,
+    exp: &'ast IndexExpression,
+) {
+    visitor.visit_expression(&exp.left);
+    visitor.visit_expression(&exp.index);
+}
+
+pub fn walk_try_expression<'ast, V: Visitor<'ast> + ?Sized>(
+    visitor: &mut V,
+    exp: &'ast TryExpression,
+) {
+    visitor.visit_block_statement(&exp.block);
+    if let Some(catch_ident) = &exp.catch_ident {
+        visitor.visit_identifier(catch_ident);
+    }
+    if let Some(catch) = &exp.catch {
+        visitor.visit_block_statement(catch);
+    }
+    if let Some(finally) = &exp.finally {
+        visitor.visit_block_statement(finally);
+    }
+}
+
+pub fn walk_member_expression<'ast, V: Visitor<'ast> + ?Sized>(
+    visitor: &mut V,
+    exp: &'ast MemberExpression,
+) {
+    visitor.visit_expression(&exp.left);
+    visitor.visit_identifier(&exp.member);
+}
@@ -0,0 +1,267 @@
+// traversal of syntax tree by mutable reference, to rewrite nodes in place.
+// each `visit_*_mut` method walks into children by default,
+// override only the nodes of interest and call `walk_*_mut` to keep descending.
+
+use super::{
+    ArrayLiteral, BigIntLiteral, BlockStatement, BooleanLiteral, CallExpression, ExportStatement,
+    Expression, ExpressionStatement, FunctionLiteral, Identifier, IfExpression, ImportStatement,
+    IndexExpression, InfixExpression, IntegerLiteral, LetStatement, MemberExpression,
+    NamedArgument, Parameter, PrefixExpression, Program, ReturnStatement, Statement, StringLiteral,
+    ThrowStatement, TryExpression,
+};
+
+pub trait VisitorMut {
+    fn visit_program_mut(&mut self, program: &mut Program) {
+        walk_program_mut(self, program)
+    }
+
+    fn visit_statement_mut(&mut self, stm: &mut Statement) {
+        walk_statement_mut(self, stm)
+    }
+    fn visit_let_statement_mut(&mut self, stm: &mut LetStatement) {
+        walk_let_statement_mut(self, stm)
+    }
+    fn visit_expression_statement_mut(&mut self, stm: &mut ExpressionStatement) {
+        walk_expression_statement_mut(self, stm)
+    }
+    fn visit_return_statement_mut(&mut self, stm: &mut ReturnStatement) {
+        walk_return_statement_mut(self, stm)
+    }
+    fn visit_block_statement_mut(&mut self, stm: &mut BlockStatement) {
+        walk_block_statement_mut(self, stm)
+    }
+    fn visit_throw_statement_mut(&mut self, stm: &mut ThrowStatement) {
+        walk_throw_statement_mut(self, stm)
+    }
+    fn visit_import_statement_mut(&mut self, stm: &mut ImportStatement) {
+        walk_import_statement_mut(self, stm)
+    }
+    fn visit_export_statement_mut(&mut self, stm: &mut ExportStatement) {
+        walk_export_statement_mut(self, stm)
+    }
+
+    fn visit_expression_mut(&mut self, exp: &mut Expression) {
+        walk_expression_mut(self, exp)
+    }
+    fn visit_identifier_mut(&mut self, _ident: &mut Identifier) {}
+    fn visit_integer_literal_mut(&mut self, _lit: &mut IntegerLiteral) {}
+    fn visit_big_int_literal_mut(&mut self, _lit: &mut BigIntLiteral) {}
+    fn visit_boolean_literal_mut(&mut self, _lit: &mut BooleanLiteral) {}
+    fn visit_string_literal_mut(&mut self, _lit: &mut StringLiteral) {}
+    fn visit_function_literal_mut(&mut self, func: &mut FunctionLiteral) {
+        walk_function_literal_mut(self, func)
+    }
+    fn visit_parameter_mut(&mut self, param: &mut Parameter) {
+        walk_parameter_mut(self, param)
+    }
+    fn visit_array_literal_mut(&mut self, arr: &mut ArrayLiteral) {
+        walk_array_literal_mut(self, arr)
+    }
+    fn visit_infix_expression_mut(&mut self, exp: &mut InfixExpression) {
+        walk_infix_expression_mut(self, exp)
+    }
+    fn visit_prefix_expression_mut(&mut self, exp: &mut PrefixExpression) {
+        walk_prefix_expression_mut(self, exp)
+    }
+    fn visit_if_expression_mut(&mut self, exp: &mut IfExpression) {
+        walk_if_expression_mut(self, exp)
+    }
+    fn visit_call_expression_mut(&mut self, exp: &mut CallExpression) {
+        walk_call_expression_mut(self, exp)
+    }
+    fn visit_named_argument_mut(&mut self, arg: &mut NamedArgument) {
+        walk_named_argument_mut(self, arg)
+    }
+    fn visit_index_expression_mut(&mut self, exp: &mut IndexExpression) {
+        walk_index_expression_mut(self, exp)
+    }
+    fn visit_try_expression_mut(&mut self, exp: &mut TryExpression) {
+        walk_try_expression_mut(self, exp)
+    }
+    fn visit_member_expression_mut(&mut self, exp: &mut MemberExpression) {
+        walk_member_expression_mut(self, exp)
+    }
+}
+
+pub fn walk_program_mut<V: VisitorMut + ?Sized>(visitor: &mut V, program: &mut Program) {
+    for stm in program.statements.iter_mut() {
+        visitor.visit_statement_mut(stm);
+    }
+}
+
+pub fn walk_statement_mut<V: VisitorMut + ?Sized>(visitor: &mut V, stm: &mut Statement) {
+    match stm {
+        Statement::LetStatement(stm) => visitor.visit_let_statement_mut(stm),
+        Statement::ExpressionStatement(stm) => visitor.visit_expression_statement_mut(stm),
+        Statement::ReturnStatement(stm) => visitor.visit_return_statement_mut(stm),
+        Statement::BlockStatement(stm) => visitor.visit_block_statement_mut(stm),
+        Statement::ThrowStatement(stm) => visitor.visit_throw_statement_mut(stm),
+        Statement::ImportStatement(stm) => visitor.visit_import_statement_mut(stm),
+        Statement::ExportStatement(stm) => visitor.visit_export_statement_mut(stm),
+    }
+}
+
+pub fn walk_let_statement_mut<V: VisitorMut + ?Sized>(visitor: &mut V, stm: &mut LetStatement) {
+    visitor.visit_identifier_mut(&mut stm.identifier);
+    if let Some(value) = &mut stm.value {
+        visitor.visit_expression_mut(value);
+    }
+}
+
+pub fn walk_expression_statement_mut<V: VisitorMut + ?Sized>(
+    visitor: &mut V,
+    stm: &mut ExpressionStatement,
+) {
+    if let Some(expression) = &mut stm.expression {
+        visitor.visit_expression_mut(expression);
+    }
+}
+
+pub fn walk_return_statement_mut<V: VisitorMut + ?Sized>(
+    visitor: &mut V,
+    stm: &mut ReturnStatement,
+) {
+    if let Some(value) = &mut stm.value {
+        visitor.visit_expression_mut(value);
+    }
+}
+
+pub fn walk_block_statement_mut<V: VisitorMut + ?Sized>(visitor: &mut V, stm: &mut BlockStatement) {
+    for stm in stm.statements.iter_mut() {
+        visitor.visit_statement_mut(stm);
+    }
+}
+
+pub fn walk_throw_statement_mut<V: VisitorMut + ?Sized>(visitor: &mut V, stm: &mut ThrowStatement) {
+    visitor.visit_expression_mut(&mut stm.value);
+}
+
+pub fn walk_import_statement_mut<V: VisitorMut + ?Sized>(
+    visitor: &mut V,
+    stm: &mut ImportStatement,
+) {
+    visitor.visit_string_literal_mut(&mut stm.path);
+    visitor.visit_identifier_mut(&mut stm.alias);
+}
+
+pub fn walk_export_statement_mut<V: VisitorMut + ?Sized>(
+    visitor: &mut V,
+    stm: &mut ExportStatement,
+) {
+    visitor.visit_statement_mut(&mut stm.statement);
+}
+
+pub fn walk_expression_mut<V: VisitorMut + ?Sized>(visitor: &mut V, exp: &mut Expression) {
+    match exp {
+        Expression::Identifier(ident) => visitor.visit_identifier_mut(ident),
+        Expression::IntegerLiteral(lit) => visitor.visit_integer_literal_mut(lit),
+        Expression::BigIntLiteral(lit) => visitor.visit_big_int_literal_mut(lit),
+        Expression::BooleanLiteral(lit) => visitor.visit_boolean_literal_mut(lit),
+        Expression::StringLiteral(lit) => visitor.visit_string_literal_mut(lit),
+        Expression::FunctionLiteral(func) => visitor.visit_function_literal_mut(func),
+        Expression::ArrayLiteral(arr) => visitor.visit_array_literal_mut(arr),
+        Expression::InfixExpression(exp) => visitor.visit_infix_expression_mut(exp),
+        Expression::PrefixExpression(exp) => visitor.visit_prefix_expression_mut(exp),
+        Expression::IfExpression(exp) => visitor.visit_if_expression_mut(exp),
+        Expression::CallExpression(exp) => visitor.visit_call_expression_mut(exp),
+        Expression::IndexExpression(exp) => visitor.visit_index_expression_mut(exp),
+        Expression::TryExpression(exp) => visitor.visit_try_expression_mut(exp),
+        Expression::MemberExpression(exp) => visitor.visit_member_expression_mut(exp),
+    }
+}
+
+pub fn walk_function_literal_mut<V: VisitorMut + ?Sized>(
+    visitor: &mut V,
+    func: &mut FunctionLiteral,
+) {
+    if let Some(ident) = &mut func.ident {
+        visitor.visit_identifier_mut(ident);
+    }
+    for param in func.parameters.iter_mut() {
+        visitor.visit_parameter_mut(param);
+    }
+    if let Some(rest) = &mut func.rest {
+        visitor.visit_identifier_mut(rest);
+    }
+    visitor.visit_block_statement_mut(&mut func.body);
+}
+
+pub fn walk_parameter_mut<V: VisitorMut + ?Sized>(visitor: &mut V, param: &mut Parameter) {
+    visitor.visit_identifier_mut(&mut param.ident);
+    if let Some(default) = &mut param.default {
+        visitor.visit_expression_mut(default);
+    }
+}
+
+pub fn walk_array_literal_mut<V: VisitorMut + ?Sized>(visitor: &mut V, arr: &mut ArrayLiteral) {
+    for element in arr.elements.iter_mut() {
+        visitor.visit_expression_mut(element);
+    }
+}
+
+pub fn walk_infix_expression_mut<V: VisitorMut + ?Sized>(
+    visitor: &mut V,
+    exp: &mut InfixExpression,
+) {
+    visitor.visit_expression_mut(&mut exp.left);
+    visitor.visit_expression_mut(&mut exp.right);
+}
+
+pub fn walk_prefix_expression_mut<V: VisitorMut + ?Sized>(
+    visitor: &mut V,
+    exp: &mut PrefixExpression,
+) {
+    visitor.visit_expression_mut(&mut exp.right);
+}
+
+pub fn walk_if_expression_mut<V: VisitorMut + ?Sized>(visitor: &mut V, exp: &mut IfExpression) {
+    visitor.visit_expression_mut(&mut exp.condition);
+    visitor.visit_block_statement_mut(&mut exp.consequence);
+    if let Some(alternative) = &mut exp.alternative {
+        visitor.visit_block_statement_mut(alternative);
+    }
+}
+
+pub fn walk_call_expression_mut<V: VisitorMut + ?Sized>(visitor: &mut V, exp: &mut CallExpression) {
+    visitor.visit_expression_mut(&mut exp.function);
+    for arg in exp.arguments.iter_mut() {
+        visitor.visit_expression_mut(arg);
+    }
+    for arg in exp.named_arguments.iter_mut() {
+        visitor.visit_named_argument_mut(arg);
+    }
+}
+
+pub fn walk_named_argument_mut<V: VisitorMut + ?Sized>(visitor: &mut V, arg: &mut NamedArgument) {
+    visitor.visit_identifier_mut(&mut arg.ident);
+    visitor.visit_expression_mut(&mut arg.value);
+}
+
+pub fn walk_index_expression_mut<V: VisitorMut + ?Sized>(
+    visitor: &mut V,
+    exp: &mut IndexExpression,
+) {
+    visitor.visit_expression_mut(&mut exp.left);
+    visitor.visit_expression_mut(&mut exp.index);
+}
+
+pub fn walk_try_expression_mut<V: VisitorMut + ?Sized>(visitor: &mut V, exp: &mut TryExpression) {
+    visitor.visit_block_statement_mut(&mut exp.block);
+    if let Some(catch_ident) = &mut exp.catch_ident {
+        visitor.visit_identifier_mut(catch_ident);
+    }
+    if let Some(catch) = &mut exp.catch {
+        visitor.visit_block_statement_mut(catch);
+    }
+    if let Some(finally) = &mut exp.finally {
+        visitor.visit_block_statement_mut(finally);
+    }
+}
+
+pub fn walk_member_expression_mut<V: VisitorMut + ?Sized>(
+    visitor: &mut V,
+    exp: &mut MemberExpression,
+) {
+    visitor.visit_expression_mut(&mut exp.left);
+    visitor.visit_identifier_mut(&mut exp.member);
+}
@@ -0,0 +1,48 @@
+use std::{io, process, sync::mpsc, thread};
+
+use dlang::{
+    dap::serve,
+    lsp::{json, read_message, write_message},
+};
+
+// native stack of the debugged program, same as the interpreter
+const STACK_SIZE: usize = 256 * 1024 * 1024;
+// half of it is left for the rest of the server
+const STACK_LIMIT: usize = STACK_SIZE / 2;
+
+// debug adapter of dlang, editors start it and talk over stdio
+fn main() {
+    let (request_tx, request_rx) = mpsc::channel();
+    let (event_tx, event_rx) = mpsc::channel::<json::Json>();
+
+    // client may pause while program runs, so requests are read aside
+    thread::spawn(move || {
+        let mut input = io::stdin().lock();
+        while let Ok(Some(body)) = read_message(&mut input) {
+            // malformed messages are ignored
+            let Ok(request) = json::parse(&body) else {
+                continue;
+            };
+            if request_tx.send(request).is_err() {
+                return;
+            }
+        }
+    });
+
+    let writer = thread::spawn(move || {
+        let mut output = io::stdout().lock();
+        for event in event_rx {
+            if write_message(&mut output, &event.to_str()).is_err() {
+                return;
+            }
+        }
+    });
+
+    let server = thread::Builder::new()
+        .stack_size(STACK_SIZE)
+        .spawn(move || serve(request_rx, event_tx, STACK_LIMIT));
+    let code = server.unwrap().join().unwrap_or(101);
+    // all events are written once serve drops its sender
+    let _ = writer.join();
+    process::exit(code);
+}
@@ -0,0 +1,9 @@
+use std::{io, process};
+
+use dlang::lsp::run;
+
+// language server of dlang, editors start it and talk over stdio
+fn main() {
+    let code = run(&mut io::stdin().lock(), &mut io::stdout().lock());
+    process::exit(code);
+}
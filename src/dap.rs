@@ -0,0 +1,584 @@
+// debug adapter of dlang, speaking debug adapter protocol.
+// program is evaluated on the thread serving the client,
+// which waits for requests inside the evaluator hook while paused
+
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+    rc::Rc,
+    sync::mpsc::{Receiver, Sender},
+};
+
+use crate::{
+    ast::{
+        visit::{self, Visitor},
+        Statement,
+    },
+    eval::{errors::EvalError, hook::Hook, interrupt::InterruptHandle, Evaluator},
+    lexer::Lexer,
+    lsp::json::Json,
+    object::{
+        environment::{Environ, Environment},
+        Function, Object,
+    },
+    parser::Parser,
+};
+
+// dlang runs a program on a single thread
+const THREAD_ID: usize = 1;
+
+/// serve a client until it disconnects, returns exit code of the process.
+/// `requests` are messages from client, replies and events are sent to `events`.
+/// `stack_limit` bounds native stack taken by calls of the program
+pub fn serve(requests: Receiver<Json>, events: Sender<Json>, stack_limit: usize) -> i32 {
+    let session = Rc::new(RefCell::new(Session::new(requests, events)));
+
+    // configure until client is done with it
+    loop {
+        let request = session.borrow().requests.recv();
+        let Ok(request) = request else {
+            return 1;
+        };
+        match session.borrow_mut().handle(&request) {
+            Control::Start => break,
+            Control::Disconnect => return 0,
+            Control::Resume | Control::None => {}
+        }
+    }
+
+    let program = session.borrow().program.clone();
+    if let Some(program) = program {
+        let code = launch(&session, &program, stack_limit);
+        session.borrow_mut().terminate(code);
+    } else {
+        session.borrow_mut().terminate(1);
+    }
+    if session.borrow().disconnected {
+        return 0;
+    }
+
+    // answer until client disconnects
+    loop {
+        let request = session.borrow().requests.recv();
+        let Ok(request) = request else {
+            return 1;
+        };
+        if let Control::Disconnect = session.borrow_mut().handle(&request) {
+            return 0;
+        }
+    }
+}
+
+/// evaluate program under debugger, returns exit code of the program
+fn launch(session: &Rc<RefCell<Session>>, program: &Path, stack_limit: usize) -> i32 {
+    let mut evaluator = Evaluator::new();
+    evaluator.set_stack_limit(stack_limit);
+    {
+        let mut session = session.borrow_mut();
+        session.interrupt = evaluator.interrupt_handle();
+        session.frames.push(Frame {
+            name: "main".to_string(),
+            file: Some(program.to_path_buf()),
+            line: 0,
+            env: None,
+        });
+    }
+    evaluator.set_hook(Some(Box::new(Debugger {
+        session: Rc::clone(session),
+    })));
+
+    let env = Rc::new(RefCell::new(Environment::new()));
+    let result = evaluator.evaluate_file(program, &env);
+    evaluator.set_hook(None);
+
+    let mut session = session.borrow_mut();
+    session.frames.clear();
+    match result {
+        Ok(Some(obj)) => {
+            session.output("stdout", &obj.to_string());
+            0
+        }
+        Ok(None) => 0,
+        // client asked to stop
+        Err(EvalError::Interrupted) if session.disconnected => 0,
+        Err(err) => {
+            session.output("stderr", &err.message());
+            1
+        }
+    }
+}
+
+/// what the handled request asks the caller to do
+enum Control {
+    None,
+    // start evaluation, configuration is done
+    Start,
+    // leave pause
+    Resume,
+    Disconnect,
+}
+
+/// where evaluation stops next
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Step {
+    // only on breakpoints
+    Continue,
+    // on next statement, reason is given to client
+    Pause(&'static str),
+    In,
+    // on next statement within the depth
+    Over(usize),
+    // on next statement out of the depth
+    Out(usize),
+}
+
+/// function call being evaluated
+struct Frame {
+    name: String,
+    file: Option<PathBuf>,
+    // 0-based line of the statement being evaluated
+    line: usize,
+    // environment of the statement being evaluated
+    env: Option<Environ<String>>,
+}
+
+/// what client can expand by variablesReference while paused
+enum Handle {
+    Environment(Environ<String>),
+    Array(Vec<Object>),
+}
+
+struct Session {
+    requests: Receiver<Json>,
+    events: Sender<Json>,
+    seq: usize,
+    program: Option<PathBuf>,
+    // 0-based lines of breakpoints for each file
+    breakpoints: HashMap<PathBuf, HashSet<usize>>,
+    step: Step,
+    // outermost first
+    frames: Vec<Frame>,
+    // (depth, file, line) of the statement seen last and the one stopped at last
+    last_seen: Option<(usize, Option<PathBuf>, usize)>,
+    last_stopped: Option<(usize, Option<PathBuf>, usize)>,
+    // handle of reference n is at n - 1, dropped on resume
+    handles: Vec<Handle>,
+    interrupt: InterruptHandle,
+    disconnected: bool,
+}
+
+impl Session {
+    fn new(requests: Receiver<Json>, events: Sender<Json>) -> Self {
+        Session {
+            requests,
+            events,
+            seq: 0,
+            program: None,
+            breakpoints: HashMap::new(),
+            step: Step::Continue,
+            frames: Vec::new(),
+            last_seen: None,
+            last_stopped: None,
+            handles: Vec::new(),
+            interrupt: InterruptHandle::new(),
+            disconnected: false,
+        }
+    }
+
+    fn send(&mut self, mut fields: Vec<(&str, Json)>) {
+        self.seq += 1;
+        fields.insert(0, ("seq", Json::from(self.seq)));
+        // client is gone if it fails, disconnect is not sent then
+        let _ = self.events.send(Json::object(fields));
+    }
+
+    fn event(&mut self, event: &str, body: Json) {
+        self.send(vec![
+            ("type", Json::from("event")),
+            ("event", Json::from(event)),
+            ("body", body),
+        ]);
+    }
+
+    fn respond(&mut self, request: &Json, body: Result<Json, String>) {
+        let mut fields = vec![
+            ("type", Json::from("response")),
+            ("request_seq", request.get("seq").clone()),
+            ("success", Json::from(body.is_ok())),
+            ("command", request.get("command").clone()),
+        ];
+        match body {
+            Ok(body) => fields.push(("body", body)),
+            Err(message) => fields.push(("message", Json::from(message))),
+        }
+        self.send(fields);
+    }
+
+    fn output(&mut self, category: &str, output: &str) {
+        let body = Json::object(vec![
+            ("category", Json::from(category)),
+            ("output", Json::from(format!("{}\n", output))),
+        ]);
+        self.event("output", body);
+    }
+
+    fn terminate(&mut self, code: i32) {
+        self.event(
+            "exited",
+            Json::object(vec![("exitCode", Json::from(code as i64))]),
+        );
+        self.event("terminated", Json::object(Vec::new()));
+    }
+
+    fn handle(&mut self, request: &Json) -> Control {
+        let args = request.get("arguments");
+        let command = request.get("command").as_str().unwrap_or("");
+        match command {
+            "initialize" => {
+                let capabilities = Json::object(vec![
+                    ("supportsConfigurationDoneRequest", Json::from(true)),
+                    ("supportsTerminateRequest", Json::from(true)),
+                ]);
+                self.respond(request, Ok(capabilities));
+                self.event("initialized", Json::object(Vec::new()));
+                Control::None
+            }
+            "launch" => {
+                let program = args.get("program").as_str();
+                if program.is_none() {
+                    self.respond(request, Err("program is not given".to_string()));
+                    return Control::None;
+                }
+                let program = PathBuf::from(program.unwrap());
+                self.program = Some(program.canonicalize().unwrap_or(program));
+                if args.get("stopOnEntry").as_bool() == Some(true) {
+                    self.step = Step::Pause("entry");
+                }
+                self.respond(request, Ok(Json::object(Vec::new())));
+                Control::None
+            }
+            "setBreakpoints" => {
+                let body = self.set_breakpoints(args);
+                self.respond(request, body);
+                Control::None
+            }
+            "configurationDone" => {
+                self.respond(request, Ok(Json::object(Vec::new())));
+                Control::Start
+            }
+            "threads" => {
+                let thread = Json::object(vec![
+                    ("id", Json::from(THREAD_ID)),
+                    ("name", Json::from("main")),
+                ]);
+                let body = Json::object(vec![("threads", Json::Array(vec![thread]))]);
+                self.respond(request, Ok(body));
+                Control::None
+            }
+            "stackTrace" => {
+                let body = self.stack_trace();
+                self.respond(request, Ok(body));
+                Control::None
+            }
+            "scopes" => {
+                let frame = args.get("frameId").as_i64().unwrap_or(0) as usize;
+                let body = self.scopes(frame);
+                self.respond(request, body);
+                Control::None
+            }
+            "variables" => {
+                let reference = args.get("variablesReference").as_i64().unwrap_or(0) as usize;
+                let body = self.variables(reference);
+                self.respond(request, body);
+                Control::None
+            }
+            "continue" => {
+                self.step = Step::Continue;
+                let body = Json::object(vec![("allThreadsContinued", Json::from(true))]);
+                self.respond(request, Ok(body));
+                Control::Resume
+            }
+            "next" | "stepIn" | "stepOut" => {
+                let depth = self.frames.len();
+                self.step = match command {
+                    "next" => Step::Over(depth),
+                    "stepIn" => Step::In,
+                    _ => Step::Out(depth),
+                };
+                self.respond(request, Ok(Json::object(Vec::new())));
+                Control::Resume
+            }
+            "pause" => {
+                self.step = Step::Pause("pause");
+                self.respond(request, Ok(Json::object(Vec::new())));
+                Control::None
+            }
+            "disconnect" | "terminate" => {
+                self.disconnected = true;
+                self.interrupt.interrupt();
+                self.respond(request, Ok(Json::object(Vec::new())));
+                Control::Disconnect
+            }
+            _ => {
+                let message = format!("{} is not supported", command);
+                self.respond(request, Err(message));
+                Control::None
+            }
+        }
+    }
+
+    /// replace breakpoints of a file.
+    /// breakpoint is verified if a statement starts on its line
+    fn set_breakpoints(&mut self, args: &Json) -> Result<Json, String> {
+        let path = args.get("source").get("path").as_str();
+        if path.is_none() {
+            return Err("source.path is not given".to_string());
+        }
+        let path = PathBuf::from(path.unwrap());
+        let path = path.canonicalize().unwrap_or(path);
+        let lines = statement_lines(&path);
+
+        let mut set = HashSet::new();
+        let mut breakpoints = Vec::new();
+        for breakpoint in args.get("breakpoints").as_array().unwrap_or(&Vec::new()) {
+            let Some(line) = breakpoint.get("line").as_i64() else {
+                continue;
+            };
+            // client lines start at 1
+            let line = (line.max(1) - 1) as usize;
+            let verified = lines.contains(&line);
+            if verified {
+                set.insert(line);
+            }
+            breakpoints.push(Json::object(vec![
+                ("verified", Json::from(verified)),
+                ("line", Json::from(line + 1)),
+            ]));
+        }
+        self.breakpoints.insert(path, set);
+        Ok(Json::object(vec![(
+            "breakpoints",
+            Json::Array(breakpoints),
+        )]))
+    }
+
+    fn stack_trace(&self) -> Json {
+        let mut frames = Vec::new();
+        for (id, frame) in self.frames.iter().enumerate().rev() {
+            let mut fields = vec![
+                ("id", Json::from(id)),
+                ("name", Json::from(frame.name.clone())),
+                ("line", Json::from(frame.line + 1)),
+                ("column", Json::from(1_usize)),
+            ];
+            if let Some(path) = &frame.file {
+                let name = path.file_name().unwrap_or_default().to_string_lossy();
+                fields.push((
+                    "source",
+                    Json::object(vec![
+                        ("name", Json::from(name.to_string())),
+                        ("path", Json::from(path.display().to_string())),
+                    ]),
+                ));
+            }
+            frames.push(Json::object(fields));
+        }
+        Json::object(vec![
+            ("totalFrames", Json::from(frames.len())),
+            ("stackFrames", Json::Array(frames)),
+        ])
+    }
+
+    /// environment chain of the frame, innermost first
+    fn scopes(&mut self, frame: usize) -> Result<Json, String> {
+        let Some(frame) = self.frames.get(frame) else {
+            return Err(format!("frame {} not found", frame));
+        };
+
+        let mut envs = Vec::new();
+        let mut env = frame.env.clone();
+        while env.is_some() {
+            let outer = env.as_ref().unwrap().borrow().outer();
+            envs.push(env.unwrap());
+            env = outer;
+        }
+
+        let mut scopes = Vec::new();
+        let last = envs.len().saturating_sub(1);
+        for (idx, env) in envs.into_iter().enumerate() {
+            let name = if idx == last {
+                "Global".to_string()
+            } else if idx == 0 {
+                "Local".to_string()
+            } else {
+                format!("Outer {}", idx)
+            };
+            // blocks without names are left out
+            if idx != 0 && idx != last && env.borrow().bindings().is_empty() {
+                continue;
+            }
+            self.handles.push(Handle::Environment(env));
+            scopes.push(Json::object(vec![
+                ("name", Json::from(name)),
+                ("variablesReference", Json::from(self.handles.len())),
+                ("expensive", Json::from(false)),
+            ]));
+        }
+        Ok(Json::object(vec![("scopes", Json::Array(scopes))]))
+    }
+
+    fn variables(&mut self, reference: usize) -> Result<Json, String> {
+        let named: Vec<(String, Object)> = match self.handles.get(reference.wrapping_sub(1)) {
+            Some(Handle::Environment(env)) => {
+                let mut bindings = env.borrow().bindings();
+                bindings.sort_by(|a, b| a.0.cmp(&b.0));
+                bindings
+            }
+            Some(Handle::Array(elements)) => elements
+                .iter()
+                .enumerate()
+                .map(|(idx, obj)| (idx.to_string(), obj.clone()))
+                .collect(),
+            None => return Err(format!("variables {} not found", reference)),
+        };
+
+        let mut variables = Vec::new();
+        for (name, obj) in named {
+            let value = obj.inspect();
+            let reference = match obj {
+                Object::Array(array) if !array.elements.is_empty() => {
+                    self.handles.push(Handle::Array(array.elements));
+                    self.handles.len()
+                }
+                _ => 0,
+            };
+            variables.push(Json::object(vec![
+                ("name", Json::from(name)),
+                ("value", Json::from(value)),
+                ("variablesReference", Json::from(reference)),
+            ]));
+        }
+        Ok(Json::object(vec![("variables", Json::Array(variables))]))
+    }
+
+    /// why evaluation stops at the statement, None to go on
+    fn stop_reason(&self, here: &(usize, Option<PathBuf>, usize)) -> Option<&'static str> {
+        let (depth, file, line) = here;
+        let moved = self.last_stopped.as_ref() != Some(here);
+        let step = match self.step {
+            Step::Continue => None,
+            Step::Pause(reason) => Some(reason),
+            Step::In => moved.then_some("step"),
+            Step::Over(from) => (moved && *depth <= from).then_some("step"),
+            Step::Out(from) => (*depth < from).then_some("step"),
+        };
+        if step.is_some() {
+            return step;
+        }
+
+        // statements nested on a line hit its breakpoint once
+        if self.last_seen.as_ref() == Some(here) || file.is_none() {
+            return None;
+        }
+        let lines = self.breakpoints.get(file.as_ref().unwrap());
+        lines
+            .is_some_and(|lines| lines.contains(line))
+            .then_some("breakpoint")
+    }
+}
+
+/// hook of evaluator, pausing it for the client
+struct Debugger {
+    session: Rc<RefCell<Session>>,
+}
+
+impl Hook for Debugger {
+    fn before_statement(&mut self, stm: &Statement, env: &Environ<String>, file: Option<&Path>) {
+        let mut session = self.session.borrow_mut();
+        let line = stm.token().position.line_num;
+        if let Some(frame) = session.frames.last_mut() {
+            frame.line = line;
+            frame.file = file.map(Path::to_path_buf);
+            frame.env = Some(Rc::clone(env));
+        }
+
+        // requests while running, e.g. pause or breakpoints
+        while let Ok(request) = session.requests.try_recv() {
+            session.handle(&request);
+        }
+        if session.disconnected {
+            return;
+        }
+
+        let here = (session.frames.len(), file.map(Path::to_path_buf), line);
+        let reason = session.stop_reason(&here);
+        session.last_seen = Some(here.clone());
+        if reason.is_none() {
+            return;
+        }
+
+        session.last_stopped = Some(here);
+        let body = Json::object(vec![
+            ("reason", Json::from(reason.unwrap())),
+            ("threadId", Json::from(THREAD_ID)),
+            ("allThreadsStopped", Json::from(true)),
+        ]);
+        session.event("stopped", body);
+
+        // paused until client resumes
+        loop {
+            let request = session.requests.recv();
+            let Ok(request) = request else {
+                session.disconnected = true;
+                session.interrupt.interrupt();
+                return;
+            };
+            match session.handle(&request) {
+                Control::Resume | Control::Disconnect => break,
+                Control::Start | Control::None => {}
+            }
+        }
+        session.handles.clear();
+    }
+
+    fn on_call(&mut self, function: &Function, _args: &[Object]) {
+        let mut session = self.session.borrow_mut();
+        let caller = session.frames.last();
+        let file = caller.and_then(|frame| frame.file.clone());
+        let line = caller.map(|frame| frame.line).unwrap_or(0);
+        let name = function.identifier.clone().unwrap_or("fn".to_string());
+        session.frames.push(Frame {
+            name,
+            file,
+            line,
+            env: None,
+        });
+    }
+
+    fn on_return(&mut self, _function: &Function, _result: &Result<Option<Object>, EvalError>) {
+        self.session.borrow_mut().frames.pop();
+    }
+}
+
+/// 0-based lines where statements of the file start
+fn statement_lines(path: &Path) -> HashSet<usize> {
+    let mut lines = StatementLines(HashSet::new());
+    let Ok(source) = fs::read_to_string(path) else {
+        return lines.0;
+    };
+    if let Ok(program) = Parser::new(Lexer::new(source)).parse() {
+        lines.visit_program(&program);
+    }
+    lines.0
+}
+
+struct StatementLines(HashSet<usize>);
+
+impl<'ast> Visitor<'ast> for StatementLines {
+    fn visit_statement(&mut self, stm: &'ast Statement) {
+        self.0.insert(stm.token().position.line_num);
+        visit::walk_statement(self, stm);
+    }
+}
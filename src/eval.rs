@@ -1,341 +1,1503 @@
 pub mod errors;
-
-use std::rc::Rc;
+pub mod hook;
+pub mod interrupt;
+pub mod memory;
+pub mod module;
+
+use std::{
+    cmp::Ordering,
+    fs,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
 
 use std::cell::RefCell;
 
 use crate::{
     ast::{
-        CallExpression, Expression, IfExpression, IndexExpression, InfixExpression,
-        Node, Nodetrait, PrefixExpression, Program, Statement,
+        Binding, BlockStatement, CallExpression, Expression, ExpressionStatement, Identifier,
+        IfExpression, ImportStatement, IndexExpression, InfixExpression, MemberExpression, Node,
+        Nodetrait, PrefixExpression, Program, ReturnStatement, Statement, TryExpression,
     },
+    lexer::{Lexer, Position},
     object::{
+        bigint::{BigInt, MAX_LIMBS},
         environment::{Environ, Environment},
-        is_same_type, Array, Bool, Function, Int, Object, ObjectTrait, ObjectType,
-        Return, StringObject,
+        is_same_type, Array, BigIntObject, Bool, ErrorObject, Function, Int, Module, Object,
+        ObjectTrait, ObjectType, Return, StringObject,
     },
+    parser::Parser,
+    resolver::{resolve, resolve_program},
     token::Kind,
 };
 
-use self::errors::{ArgumentsLength, EvalError, IndexErrorDetail};
+use self::{
+    errors::{ArgumentsLength, EvalError, ImportErrorDetail, IndexErrorDetail},
+    hook::Hook,
+    interrupt::InterruptHandle,
+    memory::{
+        big_int_size, binding_size, object_size, string_size, AllocationBudget,
+        ENVIRONMENT_SIZE,
+    },
+    module::{exported_names, ModuleLoader},
+};
 
-pub fn evaluate(
-    node: Node,
-    env: &Environ<String>,
-) -> Result<Option<Object>, EvalError> {
-    match node {
-        Node::Program(pro) => eval_program(pro, env),
-        Node::Statement(stm) => eval_stm(stm, env),
-        Node::Expression(exp) => eval_exp(exp, env),
+/// default limit of nested function calls
+pub const DEFAULT_MAX_DEPTH: usize = 500;
+
+/// default limit of native stack taken by nested function calls, in bytes.
+/// a call takes up to ~200KiB in debug build, this leaves room for one more
+/// on a thread of 2MiB, the default of spawned threads
+pub const DEFAULT_STACK_LIMIT: usize = 1024 * 1024;
+
+/// tree-walking evaluator.
+/// holds the state of an evaluation which is not a part of environment.
+pub struct Evaluator {
+    // position of the statement currently evaluated
+    position: Position,
+    loader: ModuleLoader,
+    // number of function calls currently evaluated
+    depth: usize,
+    max_depth: usize,
+    // native stack address at the outermost call, calls may go stack_limit from it
+    stack_base: usize,
+    stack_limit: usize,
+    // remaining evaluation steps, None if unlimited
+    fuel: Option<u64>,
+    // rest of program which ran out of fuel
+    suspended: Option<Suspended>,
+    memory: AllocationBudget,
+    interrupt: InterruptHandle,
+    hook: Option<Box<dyn Hook>>,
+}
+
+/// top level statements left when program ran out of fuel
+struct Suspended {
+    statements: Vec<Statement>,
+    env: Environ<String>,
+    // file which statements belong to, for imports
+    file: Option<PathBuf>,
+}
+
+impl Default for Evaluator {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
-fn eval_program(
-    pro: Program,
-    env: &Environ<String>,
-) -> Result<Option<Object>, EvalError> {
-    let mut result: Result<Option<Object>, EvalError> = Err(EvalError::BlankResult);
+impl Evaluator {
+    pub fn new() -> Self {
+        Evaluator {
+            position: Position::default(),
+            loader: ModuleLoader::new(),
+            depth: 0,
+            max_depth: DEFAULT_MAX_DEPTH,
+            stack_base: 0,
+            stack_limit: DEFAULT_STACK_LIMIT,
+            fuel: None,
+            suspended: None,
+            memory: AllocationBudget::new(),
+            interrupt: InterruptHandle::new(),
+            hook: None,
+        }
+    }
 
-    for stm in pro.statements {
-        result = eval_stm(stm, env);
+    /// limit nested function calls, calls in tail position are not counted.
+    /// exceeding it results in StackOverflow error
+    pub fn set_max_depth(&mut self, max_depth: usize) {
+        self.max_depth = max_depth;
+    }
 
-        match result.clone() {
-            Ok(opt) => match opt {
-                Some(obj) => match obj {
-                    Object::Return(rtv) => match rtv.value {
-                        Some(val) => {
-                            return Ok(Some(*val));
-                        }
-                        None => {
-                            return Ok(None);
+    /// limit bytes of native stack taken by nested function calls,
+    /// raise it when evaluating on a thread with larger stack.
+    /// exceeding it results in StackOverflow error
+    pub fn set_stack_limit(&mut self, stack_limit: usize) {
+        self.stack_limit = stack_limit;
+    }
+
+    /// limit number of evaluated statements and expressions, None for unlimited.
+    /// exhausting it results in OutOfFuel error
+    pub fn set_fuel(&mut self, fuel: Option<u64>) {
+        self.fuel = fuel;
+    }
+
+    /// top up fuel, has no effect if fuel is unlimited
+    pub fn add_fuel(&mut self, fuel: u64) {
+        self.fuel = self.fuel.map(|left| left.saturating_add(fuel));
+    }
+
+    pub fn remaining_fuel(&self) -> Option<u64> {
+        self.fuel
+    }
+
+    /// limit approximate bytes of strings, arrays and environments allocated
+    /// in total by an evaluation, None for unlimited. memory freed during the
+    /// evaluation still counts, so it bounds work of a loop as well.
+    /// exceeding it results in MemoryLimitExceeded error
+    pub fn set_memory_limit(&mut self, limit: Option<usize>) {
+        self.memory.set_limit(limit);
+    }
+
+    /// approximate bytes allocated in total by last evaluation
+    pub fn allocated_memory(&self) -> usize {
+        self.memory.allocated()
+    }
+
+    /// handle to stop evaluation from another thread.
+    /// interrupted evaluation results in Interrupted error
+    pub fn interrupt_handle(&self) -> InterruptHandle {
+        self.interrupt.clone()
+    }
+
+    /// observe statements and calls of evaluation, None to stop observing
+    pub fn set_hook(&mut self, hook: Option<Box<dyn Hook>>) {
+        self.hook = hook;
+    }
+
+    /// whether a program ran out of fuel and can be resumed
+    pub fn is_suspended(&self) -> bool {
+        self.suspended.is_some()
+    }
+
+    /// continue program which ran out of fuel, after topping up fuel.
+    /// top level statement which ran out of fuel is evaluated again from its start,
+    /// so fuel should be enough for the whole statement. what the statement did
+    /// before running out is done again, e.g. calls of functions and reports to hook
+    pub fn resume(&mut self) -> Result<Option<Object>, EvalError> {
+        let Some(suspended) = self.suspended.take() else {
+            return Err(EvalError::BlankResult);
+        };
+
+        let program = Program {
+            statements: suspended.statements,
+        };
+        if suspended.file.is_none() {
+            return self.eval_program(program, &suspended.env);
+        }
+
+        self.loader.enter(suspended.file.unwrap()).unwrap();
+        let result = self.eval_program(program, &suspended.env);
+        self.loader.leave();
+
+        result
+    }
+
+    /// evaluate source file in given environment.
+    /// imports in the file are resolved relative to the file.
+    pub fn evaluate_file(
+        &mut self,
+        path: &Path,
+        env: &Environ<String>,
+    ) -> Result<Option<Object>, EvalError> {
+        let import_path = path.display().to_string();
+        let path = path.canonicalize();
+        if path.is_err() {
+            return Err(EvalError::ModuleNotFound(ImportErrorDetail {
+                path: import_path,
+                position: None,
+                reason: path.err().unwrap().to_string(),
+            }));
+        }
+        let path = path.unwrap();
+
+        let program = read_program(&path, &import_path, None);
+        if program.is_err() {
+            return Err(program.err().unwrap());
+        }
+
+        self.suspended = None;
+        self.memory.reset();
+
+        // root file can't be a part of cycle yet
+        self.loader.enter(path).unwrap();
+        let result = self.eval_program(program.unwrap(), env);
+        self.loader.leave();
+
+        result
+    }
+
+    pub fn evaluate(
+        &mut self,
+        node: Node,
+        env: &Environ<String>,
+    ) -> Result<Option<Object>, EvalError> {
+        // undefined names are left to fail on evaluation
+        let mut node = node;
+        resolve(&mut node, &env.borrow().names());
+
+        self.evaluate_resolved(node, env)
+    }
+
+    /// evaluate node whose identifiers are resolved against names of `env` already
+    pub fn evaluate_resolved(
+        &mut self,
+        node: Node,
+        env: &Environ<String>,
+    ) -> Result<Option<Object>, EvalError> {
+        self.suspended = None;
+        self.memory.reset();
+
+        match node {
+            Node::Program(pro) => self.eval_program(pro, env),
+            Node::Statement(stm) => self.eval_stm(stm, env),
+            Node::Expression(exp) => self.eval_exp(exp, env),
+        }
+    }
+
+    fn eval_program(
+        &mut self,
+        pro: Program,
+        env: &Environ<String>,
+    ) -> Result<Option<Object>, EvalError> {
+        let mut statements = pro.statements;
+        let mut result: Result<Option<Object>, EvalError> =
+            Err(EvalError::BlankResult);
+
+        for idx in 0..statements.len() {
+            result = self.eval_stm(statements[idx].clone(), env);
+
+            // outermost program overrides suspension of imported module
+            if matches!(result, Err(EvalError::OutOfFuel)) {
+                self.suspended = Some(Suspended {
+                    statements: statements.split_off(idx),
+                    env: env.clone(),
+                    file: self.loader.current(),
+                });
+                return result;
+            }
+
+            match result.clone() {
+                Ok(opt) => match opt {
+                    Some(obj) => {
+                        if let Object::Return(rtv) = obj {
+                            match rtv.value {
+                                Some(val) => {
+                                    return Ok(Some(*val));
+                                }
+                                None => {
+                                    return Ok(None);
+                                }
+                            }
                         }
-                    },
-                    __ => {}
+                    }
+                    None => {
+                        result = Ok(opt);
+                    }
                 },
-                None => {
-                    result = Ok(opt);
-                }
-            },
-            Err(err) => return Err(err),
+                Err(err) => return Err(err),
+            }
         }
+
+        result
     }
 
-    result
-}
+    fn eval_stm(
+        &mut self,
+        stm: Statement,
+        env: &Environ<String>,
+    ) -> Result<Option<Object>, EvalError> {
+        // on error, position is kept to point the innermost statement
+        let outer_position = self.position;
+        self.position = stm.token().position;
+
+        let fueled = self.consume_fuel();
+        if fueled.is_err() {
+            return Err(fueled.err().unwrap());
+        }
+        self.observe_statement(&stm, env);
+        if self.interrupt.take() {
+            return Err(EvalError::Interrupted);
+        }
 
-fn eval_stm(
-    stm: Statement,
-    env: &Environ<String>,
-) -> Result<Option<Object>, EvalError> {
-    match stm {
-        Statement::LetStatement(stm) => {
-            let ident = stm.identifier;
+        let result = self.dispatch_stm(stm, env);
+        if result.is_ok() {
+            self.position = outer_position;
+        }
+        result
+    }
 
-            if stm.value.is_none() {
-                return Err(EvalError::LetStatementValueIsNone);
+    /// hand statement to hook, which may interrupt evaluation before it
+    fn observe_statement(&mut self, stm: &Statement, env: &Environ<String>) {
+        if self.hook.is_none() {
+            return;
+        }
+        let file = self.loader.current();
+        let hook = self.hook.as_mut().unwrap();
+        hook.before_statement(stm, env, file.as_deref());
+    }
+
+    fn new_inner_env(&mut self, outer: &Environ<String>) -> Result<Environ<String>, EvalError> {
+        let allocated = self.memory.allocate(ENVIRONMENT_SIZE);
+        if allocated.is_err() {
+            return Err(allocated.err().unwrap());
+        }
+        Ok(Rc::new(RefCell::new(Environment::new_inner(outer))))
+    }
+
+    /// take a step of fuel, if fuel is limited
+    fn consume_fuel(&mut self) -> Result<(), EvalError> {
+        match self.fuel {
+            Some(0) => Err(EvalError::OutOfFuel),
+            Some(left) => {
+                self.fuel = Some(left - 1);
+                Ok(())
             }
+            None => Ok(()),
+        }
+    }
 
-            let result = eval_exp(stm.value.clone().unwrap(), env);
-            if result.is_ok() {
-                let value = result.unwrap();
-                if value.is_some() {
-                    let obj = value.unwrap();
+    fn dispatch_stm(
+        &mut self,
+        stm: Statement,
+        env: &Environ<String>,
+    ) -> Result<Option<Object>, EvalError> {
+        match stm {
+            Statement::LetStatement(stm) => {
+                let ident = stm.identifier;
+
+                if stm.value.is_none() {
+                    return Err(EvalError::LetStatementValueIsNone);
+                }
+
+                let result = self.eval_exp(stm.value.clone().unwrap(), env);
+                if result.is_err() {
+                    return Err(result.err().unwrap());
+                }
+                if let Some(obj) = result.unwrap() {
+                    let allocated = self.memory.allocate(binding_size(&ident.value));
+                    if allocated.is_err() {
+                        return Err(allocated.err().unwrap());
+                    }
 
                     // if obj is a function,
-                    if obj.get_type() == ObjectType::Function {
-                        let Object::Function(mut fun) = obj else {unreachable!()};
+                    if let Object::Function(mut fun) = obj {
                         fun.identifier = Some(ident.clone().value);
-                        env.borrow_mut()
-                            .set(ident.clone().value, Object::Function(fun));
+                        bind(env, &ident, Object::Function(fun));
                     // if obj is not a function,
                     } else {
-                        env.borrow_mut().set(ident.clone().value, obj);
+                        bind(env, &ident, obj);
                     }
 
                     return Ok(None);
                 }
-                Err(EvalError::EvaluationOfExpressionIsNone(stm.value.unwrap()))
-            } else {
-                Err(result.err().unwrap())
+                Err(EvalError::EvaluationOfExpressionIsNone(Box::new(stm.value.unwrap())))
             }
-        }
 
-        Statement::ExpressionStatement(stm) => {
-            let exp = stm.expression.unwrap();
+            Statement::ExpressionStatement(stm) => {
+                let exp = stm.expression.unwrap();
+
+                self.eval_exp(exp, env)
+            }
 
-            eval_exp(exp, env)
+            Statement::BlockStatement(stm) => {
+                let stms = stm.statements;
+                // result of evaluation of statement block
+                let mut result: Result<Option<Object>, EvalError>;
+
+                // clone outer-context here
+                let env = self.new_inner_env(env);
+                if env.is_err() {
+                    return Err(env.err().unwrap());
+                }
+                let env = env.unwrap();
+
+                // initialize result to prepare case of blank block
+                result = Ok(None);
+
+                for stm in stms {
+                    result = self.eval_stm(stm, &env);
+                    // this is clone for prenventing falty error of move
+                    match result.clone() {
+                        Err(_) => {
+                            // if there was error, stop evaluation
+                            return result;
+                        }
+                        Ok(rst) => {
+                            // on return, skip rest of block and pass it to outer,
+                            // it is unwrapped by function or program.
+                            // else continue evaluation of block statement
+                            if let Some(Object::Return(rt)) = rst {
+                                return Ok(Some(Object::Return(rt)));
+                            }
+                        }
+                    }
+                }
+                result
+            }
+
+            Statement::ThrowStatement(stm) => {
+                let val = self.eval_exp(stm.value.clone(), env);
+                if val.is_err() {
+                    return Err(val.err().unwrap());
+                }
+                let val = val.unwrap();
+                if val.is_none() {
+                    return Err(EvalError::EvaluationOfExpressionIsNone(Box::new(stm.value)));
+                }
+
+                Err(thrown_error(val.unwrap(), self.position))
+            }
+
+            Statement::ImportStatement(stm) => {
+                let module = self.load_module(&stm);
+                if module.is_err() {
+                    return Err(module.err().unwrap());
+                }
+                bind(env, &stm.alias, Object::Module(module.unwrap()));
+                Ok(None)
+            }
+
+            Statement::ExportStatement(stm) => self.eval_stm(*stm.statement, env),
+
+            Statement::ReturnStatement(stm) => {
+                if stm.value.is_none() {
+                    return Ok(Some(Object::Return(Return { value: None })));
+                }
+                let val = self.eval_exp(stm.value.unwrap(), env);
+                if val.is_err() {
+                    return Err(val.err().unwrap());
+                }
+
+                let return_val = val.unwrap();
+                if return_val.is_none() {
+                    return Ok(Some(Object::Return(Return { value: None })));
+                }
+                // unwrap and rewrap with box
+                let value = Some(Box::new(return_val.unwrap()));
+
+                Ok(Some(Object::Return(Return { value })))
+            }
+        }
+    }
+
+    fn eval_exp(
+        &mut self,
+        exp: Expression,
+        env: &Environ<String>,
+    ) -> Result<Option<Object>, EvalError> {
+        let fueled = self.consume_fuel();
+        if fueled.is_err() {
+            return Err(fueled.err().unwrap());
         }
 
-        Statement::BlockStatement(stm) => {
-            let stms = stm.statements;
-            // result of evaluation of statement block
-            let mut result: Result<Option<Object>, EvalError>;
+        match exp {
+            Expression::Identifier(id_exp) => {
+                let key = id_exp.value;
+                let obj = match id_exp.binding {
+                    Some(Binding::Local { depth, slot }) => {
+                        env.borrow().get_slot_clone(depth, slot)
+                    }
+                    Some(Binding::Global { depth }) => env.borrow().get_outer_clone(depth, &key),
+                    None => env.borrow().get_clone(&key),
+                };
+                if obj.is_some() {
+                    Ok(obj)
+                } else {
+                    // identifier not found
+                    Err(EvalError::IdentifierNotFound(key.clone()))
+                }
+            }
+            Expression::IntegerLiteral(lit) => Ok(Some(Object::Int(Int { value: lit.value }))),
+            Expression::BigIntLiteral(lit) => {
+                let obj = Object::from(lit.value);
+                let allocated = self.memory.allocate(object_size(&obj));
+                if allocated.is_err() {
+                    return Err(allocated.err().unwrap());
+                }
+                Ok(Some(obj))
+            }
+            Expression::BooleanLiteral(lit) => Ok(Some(Object::Bool(Bool { value: lit.value }))),
+            Expression::StringLiteral(lit) => {
+                let allocated = self.memory.allocate(string_size(lit.value.len()));
+                if allocated.is_err() {
+                    return Err(allocated.err().unwrap());
+                }
+                Ok(Some(Object::String(StringObject { value: lit.value })))
+            }
 
-            // clone outer-context here
-            let env = Rc::new(RefCell::new(Environment::new_inner(env)));
+            Expression::FunctionLiteral(func) => {
+                let mut fun = Function {
+                    identifier: None,
+                    args: func.parameters,
+                    rest: func.rest,
+                    block: Rc::new(func.body),
+                    // have to clone to catch the current lexical environment
+                    env: env.clone(),
+                };
+
+                // if this function have identifier, bind to environment
+                if let Some(ident) = &func.ident {
+                    fun.identifier = Some(ident.to_str());
+                    bind(
+                        env,
+                        ident,
+                        Object::Function(fun.clone()),
+                    );
+                }
+                Ok(Some(Object::Function(fun)))
+            }
 
-            // initialize result to prepare case of blank block
-            result = Ok(None);
+            Expression::ArrayLiteral(arr) => {
+                let mut elements = Vec::new();
 
-            for stm in stms {
-                result = eval_stm(stm, &env);
-                // this is clone for prenventing falty error of move
-                match result.clone() {
-                    Err(_) => {
-                        // if there was error, stop evaluation
-                        return result;
+                for exp in arr.elements {
+                    let obj = self.eval_exp(exp, env);
+                    if obj.is_err() {
+                        return Err(obj.err().unwrap());
                     }
-                    Ok(rst) => {
-                        match rst {
-                            Some(obj) => match obj {
-                                // catch unwrap return and skip block
-                                Object::Return(rt) => {
-                                    if rt.value.is_some() {
-                                        let val: Object = *rt.value.unwrap();
-                                        return Ok(Some(val));
-                                    }
-                                    return Ok(None);
-                                }
-                                // else continue evaluation of block statement
-                                _ => {}
-                            },
-                            None => {}
-                        }
+                    if obj.as_ref().unwrap().is_none() {
+                        return Err(EvalError::ElementIsNone);
                     }
+                    elements.push(obj.unwrap().unwrap())
+                }
+
+                // array holds its own copies of elements
+                let array = Object::Array(Array { elements });
+                let allocated = self.memory.allocate(object_size(&array));
+                if allocated.is_err() {
+                    return Err(allocated.err().unwrap());
                 }
+                Ok(Some(array))
             }
-            result
+
+            Expression::InfixExpression(exp) => self.eval_infix_exp(exp, env),
+            Expression::PrefixExpression(exp) => self.eval_prefix_exp(exp, env),
+            Expression::IfExpression(exp) => self.eval_if_exp(exp, env),
+            Expression::CallExpression(exp) => self.eval_call_exp(exp, env),
+            Expression::IndexExpression(exp) => self.eval_index_exp(exp, env),
+            Expression::TryExpression(exp) => self.eval_try_exp(exp, env),
+            Expression::MemberExpression(exp) => self.eval_member_exp(exp, env),
         }
+    }
+
+    fn eval_infix_exp(
+        &mut self,
+        exp: InfixExpression,
+        env: &Environ<String>,
+    ) -> Result<Option<Object>, EvalError> {
+        // check left, right is valid
+        let left = self.eval_exp(*exp.left, env);
+        if left.is_err() {
+            return Err(left.err().unwrap());
+        }
+        let left = left.unwrap();
+        if left.is_none() {
+            return Err(EvalError::LeftExpressionIsNone);
+        }
+        let left = left.unwrap();
 
-        Statement::ReturnStatement(stm) => {
-            if stm.value.is_none() {
-                return Ok(Some(Object::Return(Return { value: None })));
+        let right = self.eval_exp(*exp.right, env);
+        if right.is_err() {
+            return Err(right.err().unwrap());
+        }
+        let right = right.unwrap();
+        if right.is_none() {
+            return Err(EvalError::RightExpressionIsNone);
+        }
+        let right = right.unwrap();
+
+        // account concatenated string before building it
+        if let (Object::String(l), Object::String(r)) = (&left, &right) {
+            if exp.operator.kind == Kind::Plus {
+                let allocated = self
+                    .memory
+                    .allocate(string_size(l.value.len() + r.value.len()));
+                if allocated.is_err() {
+                    return Err(allocated.err().unwrap());
+                }
             }
-            let val = eval_exp(stm.value.unwrap(), env);
-            if val.is_err() {
-                return val;
+        }
+
+        let result = infix_object(left, exp.operator.kind, right);
+        if result.is_err() {
+            return Err(result.err().unwrap());
+        }
+        let result = result.unwrap();
+
+        // big integer is built before accounted, its size is bounded anyway
+        if let Object::BigInt(int) = &result {
+            let allocated = self.memory.allocate(big_int_size(int.value.limbs().len()));
+            if allocated.is_err() {
+                return Err(allocated.err().unwrap());
             }
+        }
+        Ok(Some(result))
+    }
+
+    fn eval_prefix_exp(
+        &mut self,
+        exp: PrefixExpression,
+        env: &Environ<String>,
+    ) -> Result<Option<Object>, EvalError> {
+        let operator = exp.token.kind;
+
+        // evaluate first
+        let result = self.eval_exp(*exp.right.clone(), env);
+
+        if result.is_err() {
+            return Err(result.err().unwrap());
+        }
+
+        let result = result.unwrap();
+        if result.is_none() {
+            return Err(EvalError::EvaluationOfExpressionIsNone(Box::new(*exp.right)));
+        }
+        let result = prefix_object(operator, result.unwrap());
+        if result.is_err() {
+            return Err(result.err().unwrap());
+        }
+        Ok(Some(result.unwrap()))
+    }
+
+    fn eval_condition(
+        &mut self,
+        condition: Expression,
+        env: &Environ<String>,
+    ) -> Result<bool, EvalError> {
+        let condition_val = self.eval_exp(condition, env);
+        if condition_val.is_err() {
+            return Err(condition_val.err().unwrap());
+        };
+
+        let obj = condition_val.unwrap();
+        if obj.is_none() {
+            return Err(EvalError::ConditionIsNone);
+        }
 
-            let return_val = val.unwrap();
-            if return_val.is_none() {
-                return Ok(Some(Object::Return(Return { value: None })));
+        let object = obj.unwrap();
+        let Object::Bool(flag) = object else {
+            return Err(EvalError::NotABoolean(Box::new(object)));
+        };
+
+        Ok(flag.value)
+    }
+
+    fn eval_if_exp(
+        &mut self,
+        exp: IfExpression,
+        env: &Environ<String>,
+    ) -> Result<Option<Object>, EvalError> {
+        let flag = self.eval_condition(*exp.condition, env);
+        if flag.is_err() {
+            return Err(flag.err().unwrap());
+        }
+
+        if flag.unwrap() {
+            return self.eval_stm(Statement::BlockStatement(exp.consequence), env);
+        }
+        if let Some(alternative) = exp.alternative {
+            return self.eval_stm(Statement::BlockStatement(alternative), env);
+        }
+
+        Ok(None)
+    }
+
+    fn eval_try_exp(
+        &mut self,
+        exp: TryExpression,
+        env: &Environ<String>,
+    ) -> Result<Option<Object>, EvalError> {
+        let position = self.position;
+
+        let mut result = self.eval_stm(Statement::BlockStatement(exp.block), env);
+
+        // exhausted limits of evaluator are out of reach of the script,
+        // neither catch nor finally block is evaluated
+        if result.as_ref().is_err_and(|err| !err.is_catchable()) {
+            return result;
+        }
+
+        if let Some(catch) = exp.catch.filter(|_| result.is_err()) {
+            let error = error_object(result.err().unwrap(), self.position);
+            // error is handled, back to the position of try expression
+            self.position = position;
+
+            let catch_env = self.new_inner_env(env);
+            if catch_env.is_err() {
+                return Err(catch_env.err().unwrap());
             }
-            // unwrap and rewrap with box
-            let value = Some(Box::new(return_val.unwrap()));
+            let catch_env = catch_env.unwrap();
+            if let Some(catch_ident) = &exp.catch_ident {
+                bind(
+                    &catch_env,
+                    catch_ident,
+                    Object::Error(error),
+                );
+            }
+            result = self.eval_stm(Statement::BlockStatement(catch), &catch_env);
+        }
 
-            Ok(Some(Object::Return(Return { value })))
+        if let Some(finally) = exp.finally {
+            let finalized = self.eval_stm(Statement::BlockStatement(finally), env);
+            // error on finally block overrides the result
+            if finalized.is_err() {
+                return Err(finalized.err().unwrap());
+            }
         }
+
+        result
     }
-}
 
-fn eval_exp(
-    exp: Expression,
-    env: &Environ<String>,
-) -> Result<Option<Object>, EvalError> {
-    match exp {
-        Expression::Identifier(id_exp) => {
-            let key = id_exp.value;
-            let obj = env.borrow().get_clone(&key);
-            if obj.is_some() {
-                Ok(obj)
-            } else {
-                // identifier not found
-                Err(EvalError::IdentifierNotFound(key.clone()))
+    fn eval_member_exp(
+        &mut self,
+        exp: MemberExpression,
+        env: &Environ<String>,
+    ) -> Result<Option<Object>, EvalError> {
+        let left = self.eval_exp(*exp.left.clone(), env);
+        if left.is_err() {
+            return Err(left.err().unwrap());
+        }
+        let left = left.unwrap();
+        if left.is_none() {
+            return Err(EvalError::EvaluationOfExpressionIsNone(Box::new(*exp.left)));
+        }
+        let left = left.unwrap();
+
+        let Object::Module(module) = left else {
+            return Err(EvalError::NotAModule(Box::new(left)));
+        };
+
+        let name = exp.member.value;
+        if !module.exports.contains(&name) {
+            return Err(EvalError::NotExported(name));
+        }
+
+        let obj = module.env.borrow().get_clone(&name);
+        if obj.is_none() {
+            return Err(EvalError::IdentifierNotFound(name));
+        }
+        Ok(obj)
+    }
+
+    fn load_module(&mut self, stm: &ImportStatement) -> Result<Module, EvalError> {
+        let import_path = stm.path.value.clone();
+        let position = stm.token.position;
+
+        let path = self.loader.resolve(&import_path).canonicalize();
+        if path.is_err() {
+            return Err(EvalError::ModuleNotFound(ImportErrorDetail {
+                path: import_path,
+                position: Some(position),
+                reason: path.err().unwrap().to_string(),
+            }));
+        }
+        let path = path.unwrap();
+
+        // each module is evaluated only once
+        let cached = self.loader.get(&path);
+        if let Some(cached) = cached {
+            return Ok(cached);
+        }
+
+        let entered = self.loader.enter(path.clone());
+        if entered.is_err() {
+            let mut chain = Vec::new();
+            for file in entered.err().unwrap() {
+                chain.push(file.display().to_string());
             }
+            return Err(EvalError::ImportCycle(ImportErrorDetail {
+                path: import_path,
+                position: Some(position),
+                reason: format!("import cycle {}", chain.join(" -> ")),
+            }));
+        }
+
+        let program = read_program(&path, &import_path, Some(position));
+        if program.is_err() {
+            self.loader.leave();
+            return Err(program.err().unwrap());
         }
-        Expression::IntegerLiteral(lit) => {
-            Ok(Some(Object::Int(Int { value: lit.value })))
+        let program = program.unwrap();
+
+        let module = Module {
+            path: path.display().to_string(),
+            env: Rc::new(RefCell::new(Environment::new())),
+            exports: exported_names(&program),
+        };
+
+        let mut result = Ok(None);
+        if !program.statements.is_empty() {
+            result = self.eval_program(program, &module.env);
         }
-        Expression::BooleanLiteral(lit) => {
-            Ok(Some(Object::Bool(Bool { value: lit.value })))
+        self.loader.leave();
+
+        if result.is_err() {
+            return Err(result.err().unwrap());
         }
-        Expression::StringLiteral(lit) => {
-            Ok(Some(Object::String(StringObject { value: lit.value })))
+
+        self.loader.insert(path, module.clone());
+        Ok(module)
+    }
+
+    fn eval_call_exp(
+        &mut self,
+        exp: CallExpression,
+        env: &Environ<String>,
+    ) -> Result<Option<Object>, EvalError> {
+        let call = self.eval_call_target(exp, env);
+        if call.is_err() {
+            return Err(call.err().unwrap());
         }
+        let call = call.unwrap();
+
+        self.apply_function(call.function, call.args, call.named_args)
+    }
+
+    /// evaluate callee and arguments of call, without calling it
+    fn eval_call_target(
+        &mut self,
+        exp: CallExpression,
+        env: &Environ<String>,
+    ) -> Result<TailCall, EvalError> {
+        let func = self.eval_exp(*exp.function, env);
+
+        if func.is_err() {
+            return Err(func.err().unwrap());
+        }
+        if func.clone().unwrap().is_none() {
+            return Err(EvalError::FunctionIsNone);
+        }
+        let func = func.unwrap().unwrap();
+        match func {
+            Object::Function(func) => {
+                let args = self.eval_function_parameters(exp.arguments, env);
+                if args.is_err() {
+                    return Err(args.err().unwrap());
+                }
+                let args = args.unwrap();
+
+                let mut named_args = Vec::new();
+                for arg in exp.named_arguments {
+                    let evaluated = self.eval_exp(arg.value.clone(), env);
+
+                    if evaluated.is_err() {
+                        return Err(evaluated.err().unwrap());
+                    }
+                    if evaluated.clone().unwrap().is_none() {
+                        let exp = arg.value;
+                        return Err(EvalError::EvaluationOfExpressionIsNone(Box::new(exp)));
+                    }
+                    named_args.push((arg.ident.value, evaluated.unwrap().unwrap()))
+                }
+                Ok(TailCall {
+                    function: func,
+                    args,
+                    named_args,
+                })
+            }
+            // func is not a function
+            obj => Err(EvalError::NotAFunction(Box::new(obj))),
+        }
+    }
+
+    fn eval_function_parameters(
+        &mut self,
+        args: Vec<Expression>,
+        env: &Environ<String>,
+    ) -> Result<Vec<Object>, EvalError> {
+        let mut result: Vec<Object> = Vec::new();
 
-        Expression::FunctionLiteral(func) => {
-            let mut fun = Function {
-                identifier: None,
-                args: func.parameters,
-                block: func.body,
-                // have to clone to catch the current lexical environment
-                env: Rc::downgrade(&env),
+        for arg in args.iter() {
+            let evaluated = self.eval_exp(arg.clone(), env);
+
+            if evaluated.is_err() {
+                return Err(evaluated.err().unwrap());
+            }
+            if evaluated.clone().unwrap().is_none() {
+                return Err(EvalError::EvaluationOfExpressionIsNone(Box::new(arg.clone())));
+            }
+
+            result.push(evaluated.unwrap().unwrap())
+        }
+
+        Ok(result)
+    }
+
+    fn apply_function(
+        &mut self,
+        fun: Function,
+        args: Vec<Object>,
+        named_args: Vec<(String, Object)>,
+    ) -> Result<Option<Object>, EvalError> {
+        let here = stack_address();
+        if self.depth == 0 {
+            self.stack_base = here;
+        }
+        let stack_used = self.stack_base.abs_diff(here);
+        if self.depth >= self.max_depth || stack_used > self.stack_limit {
+            return Err(EvalError::StackOverflow { depth: self.depth });
+        }
+
+        let observed = self.hook.is_some().then(|| fun.clone());
+        if let Some(observed) = &observed {
+            let mut all_args = args.clone();
+            all_args.extend(named_args.iter().map(|(_, arg)| arg.clone()));
+            let hook = self.hook.as_mut().unwrap();
+            hook.on_call(observed, &all_args);
+        }
+
+        // depth is restored on error too, so that evaluator stays usable
+        self.depth += 1;
+        let result = self.trampoline(TailCall {
+            function: fun,
+            args,
+            named_args,
+        });
+        self.depth -= 1;
+
+        if let (Some(observed), Some(hook)) = (&observed, &mut self.hook) {
+            hook.on_return(observed, &result);
+        }
+        result
+    }
+
+    /// call function without counting depth
+    fn trampoline(&mut self, mut call: TailCall) -> Result<Option<Object>, EvalError> {
+        // calls in tail position come back here
+        // instead of growing native stack
+        loop {
+            if self.interrupt.take() {
+                return Err(EvalError::Interrupted);
+            }
+
+            let fun = call.function;
+
+            let min_args = fun.args.iter().filter(|arg| arg.default.is_none()).count();
+            let max_args = if fun.rest.is_some() {
+                None
+            } else {
+                Some(fun.args.len())
             };
+            let called_with = call.args.len() + call.named_args.len();
+
+            if called_with < min_args || max_args.is_some_and(|max| called_with > max) {
+                return Err(EvalError::FunctionArgLengthNotMatched(ArgumentsLength {
+                    min_args,
+                    max_args,
+                    called_with,
+                }));
+            }
 
-            // if this function have identifier, bind to environment
-            if func.ident.is_some() {
-                fun.identifier = Some(func.ident.as_ref().unwrap().to_str());
-                env.borrow_mut().set(
-                    func.ident.unwrap().to_str(),
-                    Object::Function(fun.clone()),
-                );
+            let extended_env = self.extend_function_env(&fun, call.args, call.named_args);
+
+            if extended_env.is_err() {
+                return Err(extended_env.err().unwrap());
+            }
+            let extended_env = extended_env.unwrap();
+
+            let evaluated = self.eval_tail_block(&fun.block, &extended_env, true);
+
+            if evaluated.is_err() {
+                return Err(evaluated.err().unwrap());
+            }
+
+            match evaluated.unwrap() {
+                Evaluated::TailCall(next) => call = next,
+                Evaluated::Value(value) => return Ok(unwrap_return_value(value)),
             }
-            Ok(Some(Object::Function(fun)))
         }
+    }
 
-        Expression::ArrayLiteral(arr) => {
-            let mut elements = Vec::new();
+    /// evaluate block of function body.
+    /// `return f(x)`, and call on the last expression of block if `tail` is set,
+    /// are not evaluated but handed back to apply_function.
+    fn eval_tail_block(
+        &mut self,
+        block: &BlockStatement,
+        env: &Environ<String>,
+        tail: bool,
+    ) -> Result<Evaluated, EvalError> {
+        let env = self.new_inner_env(env);
+        if env.is_err() {
+            return Err(env.err().unwrap());
+        }
+        let env = env.unwrap();
+        let last_idx = block.statements.len().saturating_sub(1);
 
-            for exp in arr.elements {
-                let obj = eval_exp(exp, env);
-                if obj.is_err() {
-                    return obj;
+        let mut result = Ok(Evaluated::Value(None));
+
+        for (idx, stm) in block.statements.iter().enumerate() {
+            let is_tail = tail && idx == last_idx;
+
+            result = self.eval_tail_stm(stm, &env, is_tail);
+
+            match &result {
+                Err(_) => return result,
+                Ok(Evaluated::TailCall(_)) => return result,
+                // skip rest of block on return
+                Ok(Evaluated::Value(Some(Object::Return(_)))) => return result,
+                Ok(Evaluated::Value(_)) => {}
+            }
+        }
+        result
+    }
+
+    fn eval_tail_stm(
+        &mut self,
+        stm: &Statement,
+        env: &Environ<String>,
+        tail: bool,
+    ) -> Result<Evaluated, EvalError> {
+        let outer_position = self.position;
+        self.position = stm.token().position;
+
+        let fueled = self.consume_fuel();
+        if fueled.is_err() {
+            return Err(fueled.err().unwrap());
+        }
+        self.observe_statement(stm, env);
+        if self.interrupt.take() {
+            return Err(EvalError::Interrupted);
+        }
+
+        let result = match stm {
+            Statement::ReturnStatement(ReturnStatement {
+                value: Some(Expression::CallExpression(call)),
+                ..
+            }) => self
+                .eval_call_target(call.clone(), env)
+                .map(Evaluated::TailCall),
+
+            Statement::ExpressionStatement(ExpressionStatement {
+                expression: Some(Expression::CallExpression(call)),
+                ..
+            }) if tail => self
+                .eval_call_target(call.clone(), env)
+                .map(Evaluated::TailCall),
+
+            // branches may hold return statement in tail position
+            Statement::ExpressionStatement(ExpressionStatement {
+                expression: Some(Expression::IfExpression(exp)),
+                ..
+            }) => {
+                let flag = self.eval_condition(*exp.condition.clone(), env);
+                if flag.is_err() {
+                    return Err(flag.err().unwrap());
                 }
-                if obj.as_ref().unwrap().is_none() {
-                    return Err(EvalError::ElementIsNone);
+
+                if flag.unwrap() {
+                    self.eval_tail_block(&exp.consequence, env, tail)
+                } else if exp.alternative.is_some() {
+                    self.eval_tail_block(exp.alternative.as_ref().unwrap(), env, tail)
+                } else {
+                    Ok(Evaluated::Value(None))
                 }
-                elements.push(obj.unwrap().unwrap())
             }
-            Ok(Some(Object::Array(Array { elements })))
+
+            stm => self.dispatch_stm(stm.clone(), env).map(Evaluated::Value),
+        };
+
+        if result.is_ok() {
+            self.position = outer_position;
+        }
+        result
+    }
+
+    fn extend_function_env(
+        &mut self,
+        fun: &Function,
+        args: Vec<Object>,
+        named_args: Vec<(String, Object)>,
+    ) -> Result<Environ<String>, EvalError> {
+        let env = self.new_inner_env(&fun.env);
+        if env.is_err() {
+            return Err(env.err().unwrap());
         }
+        let env = env.unwrap();
 
-        Expression::InfixExpression(exp) => eval_infix_exp(exp, env),
-        Expression::PrefixExpression(exp) => eval_prefix_exp(exp, env),
-        Expression::IfExpression(exp) => eval_if_exp(exp, env),
-        Expression::CallExpression(exp) => eval_call_exp(exp, env),
-        Expression::IndexExpression(exp) => eval_index_exp(exp, env),
+        // slot for each parameter, filled by positional args first
+        let mut slots: Vec<Option<Object>> = vec![None; fun.args.len()];
+        let mut rest = Vec::new();
+
+        for (idx, arg) in args.into_iter().enumerate() {
+            if idx < slots.len() {
+                slots[idx] = Some(arg);
+            } else {
+                rest.push(arg);
+            }
+        }
+
+        for (name, arg) in named_args {
+            let idx = fun.args.iter().position(|param| param.ident.value == name);
+            if idx.is_none() {
+                return Err(EvalError::UnknownNamedArgument(name));
+            }
+            let idx = idx.unwrap();
+            if slots[idx].is_some() {
+                return Err(EvalError::DuplicatedArgument(name));
+            }
+            slots[idx] = Some(arg);
+        }
+
+        // bind given args(object) to fun's parameters(ident),
+        // defaults are evaluated in order so they can refer to former parameters
+        for (param, slot) in fun.args.iter().zip(slots) {
+            let value = match slot {
+                Some(obj) => obj,
+                None => {
+                    if param.default.is_none() {
+                        let name = param.ident.value.clone();
+                        return Err(EvalError::MissingArgument(name));
+                    }
+                    let default = param.default.clone().unwrap();
+                    let evaluated = self.eval_exp(default.clone(), &env);
+                    if evaluated.is_err() {
+                        return Err(evaluated.err().unwrap());
+                    }
+                    let evaluated = evaluated.unwrap();
+                    if evaluated.is_none() {
+                        return Err(EvalError::EvaluationOfExpressionIsNone(Box::new(default)));
+                    }
+                    evaluated.unwrap()
+                }
+            };
+            let allocated = self.memory.allocate(binding_size(&param.ident.value));
+            if allocated.is_err() {
+                return Err(allocated.err().unwrap());
+            }
+            bind(&env, &param.ident, value);
+        }
+
+        if let Some(rest_ident) = &fun.rest {
+            let rest = Object::Array(Array { elements: rest });
+            let allocated = self.memory.allocate(object_size(&rest));
+            if allocated.is_err() {
+                return Err(allocated.err().unwrap());
+            }
+            bind(&env, rest_ident, rest);
+        }
+
+        Ok(env)
+    }
+
+    fn eval_index_exp(
+        &mut self,
+        exp: IndexExpression,
+        env: &Environ<String>,
+    ) -> Result<Option<Object>, EvalError> {
+        let left_rst = self.eval_exp(*exp.left, env);
+
+        if left_rst.is_err() {
+            return Err(left_rst.err().unwrap());
+        }
+        if left_rst.as_ref().unwrap().is_none() {
+            return Err(EvalError::ArrayIsNone);
+        }
+        let left = left_rst.unwrap().unwrap();
+
+        if left.get_type() != ObjectType::Array
+            && left.get_type() != ObjectType::Error
+        {
+            return Err(EvalError::NotArray);
+        }
+
+        let index_rst = self.eval_exp(*exp.index, env);
+        if index_rst.is_err() {
+            return Err(index_rst.err().unwrap());
+        }
+        if index_rst.as_ref().unwrap().is_none() {
+            return Err(EvalError::ArrayIsNone);
+        }
+        let index = index_rst.unwrap().unwrap();
+
+        index_object(left, index)
     }
 }
 
-fn eval_infix_exp(
-    exp: InfixExpression,
+/// address in native stack of the caller, to measure stack taken by calls
+#[inline(never)]
+fn stack_address() -> usize {
+    let marker = 0u8;
+    std::hint::black_box(&marker) as *const u8 as usize
+}
+
+pub fn evaluate(
+    node: Node,
     env: &Environ<String>,
 ) -> Result<Option<Object>, EvalError> {
-    // check left, right is valid
-    let left = eval_exp(*exp.left, env);
-    if left.is_err() {
-        return left;
-    }
-    let left = left.unwrap();
-    if left.is_none() {
-        return Err(EvalError::LeftExpressionIsNone);
-    }
-    let left = left.unwrap();
+    Evaluator::new().evaluate(node, env)
+}
 
-    let right = eval_exp(*exp.right, env);
-    if right.is_err() {
-        return right;
+/// apply infix operator to evaluated operands
+pub fn infix_object(left: Object, operator: Kind, right: Object) -> Result<Object, EvalError> {
+    // any two values are equal or not, values of different types are not,
+    // except integers of both sizes
+    match operator {
+        Kind::EQ => {
+            let value = objects_equal(&left, &right);
+            return Ok(Object::Bool(Bool { value }));
+        }
+        Kind::NOT_EQ => {
+            let value = !objects_equal(&left, &right);
+            return Ok(Object::Bool(Bool { value }));
+        }
+        _ => {}
     }
-    let right = right.unwrap();
-    if right.is_none() {
-        return Err(EvalError::RightExpressionIsNone);
+
+    // integer mixed with big integer is computed as big
+    if matches!(left, Object::BigInt(_)) || matches!(right, Object::BigInt(_)) {
+        if let (Some(left), Some(right)) = (big_int_of(&left), big_int_of(&right)) {
+            return eval_infix_big_int_exp(left, operator, right);
+        }
     }
-    let right = right.unwrap();
 
     if !is_same_type(&left, &right) {
         return Err(EvalError::NotSameType);
     }
 
-    match left.get_type() {
-        ObjectType::Int => {
-            let Object::Int(left) = left else {unreachable!()};
-            let Object::Int(right) = right else {unreachable!()};
-            let result = eval_infix_int_exp(left, exp.operator.kind, right);
-            if result.is_err() {
-                return Err(result.err().unwrap());
+    match (left, right) {
+        (Object::Int(left), Object::Int(right)) => eval_infix_int_exp(left, operator, right),
+        (Object::Bool(left), Object::Bool(right)) => eval_infix_bool_exp(left, operator, right),
+        (Object::String(left), Object::String(right)) => {
+            eval_infix_string_exp(left, operator, right)
+        }
+        (left, right) if is_ordering(operator) && left.get_type() == ObjectType::Array => {
+            let ordering = compare_objects(&left, operator, &right);
+            if ordering.is_err() {
+                return Err(ordering.err().unwrap());
             }
-            let result = Some(result.unwrap());
-            Ok(result)
+            Ok(ordering_object(operator, ordering.unwrap()))
         }
-        ObjectType::Bool => {
-            let Object::Bool(left) = left else {unreachable!()};
-            let Object::Bool(right) = right else {unreachable!()};
+        (left, _) => Err(EvalError::InvalidInfixOperationTarget(
+            left.get_type(),
+            operator,
+        )),
+    }
+}
 
-            let result = eval_infix_bool_exp(left, exp.operator.kind, right);
-            if result.is_err() {
-                return Err(result.err().unwrap());
-            }
-            let result = Some(result.unwrap());
-            Ok(result)
+/// equality of `==`.
+/// values are compared by their contents deeply, functions and modules by identity
+pub fn objects_equal(left: &Object, right: &Object) -> bool {
+    match (left, right) {
+        (Object::Int(left), Object::Int(right)) => left.value == right.value,
+        (Object::BigInt(_), Object::Int(_) | Object::BigInt(_))
+        | (Object::Int(_), Object::BigInt(_)) => big_int_of(left) == big_int_of(right),
+        (Object::Bool(left), Object::Bool(right)) => left.value == right.value,
+        (Object::String(left), Object::String(right)) => left.value == right.value,
+        (Object::Array(left), Object::Array(right)) => {
+            left.elements.len() == right.elements.len()
+                && left
+                    .elements
+                    .iter()
+                    .zip(right.elements.iter())
+                    .all(|(left, right)| objects_equal(left, right))
+        }
+        // made by the same evaluation of a literal
+        (Object::Function(left), Object::Function(right)) => {
+            Rc::ptr_eq(&left.block, &right.block) && Rc::ptr_eq(&left.env, &right.env)
+        }
+        (Object::Closure(left), Object::Closure(right)) => {
+            Rc::ptr_eq(&left.identity, &right.identity)
+        }
+        (Object::Module(left), Object::Module(right)) => Rc::ptr_eq(&left.env, &right.env),
+        (Object::Error(left), Object::Error(right)) => {
+            let values = match (left.value.as_ref(), right.value.as_ref()) {
+                (Some(left), Some(right)) => objects_equal(left, right),
+                (left, right) => left.is_none() && right.is_none(),
+            };
+            left.kind == right.kind && left.message == right.message && values
         }
-        ObjectType::String => {
-            let Object::String(left) = left else {unreachable!()};
-            let Object::String(right) = right else {unreachable!()};
-            let result = eval_infix_string_exp(left, exp.operator.kind, right);
-            if result.is_err() {
-                return Err(result.err().unwrap());
+        _ => false,
+    }
+}
+
+/// ordering of `<` and such, integers of both sizes by value,
+/// strings and arrays lexicographically
+pub fn compare_objects(
+    left: &Object,
+    operator: Kind,
+    right: &Object,
+) -> Result<Ordering, EvalError> {
+    match (left, right) {
+        (Object::Int(left), Object::Int(right)) => Ok(left.value.cmp(&right.value)),
+        (Object::BigInt(_), Object::Int(_) | Object::BigInt(_))
+        | (Object::Int(_), Object::BigInt(_)) => Ok(big_int_of(left).cmp(&big_int_of(right))),
+        (Object::String(left), Object::String(right)) => Ok(left.value.cmp(&right.value)),
+        (Object::Array(left), Object::Array(right)) => {
+            for (left, right) in left.elements.iter().zip(right.elements.iter()) {
+                // error or the first difference
+                let ordering = compare_objects(left, operator, right);
+                if ordering != Ok(Ordering::Equal) {
+                    return ordering;
+                }
             }
-            let result = Some(result.unwrap());
-            Ok(result)
+            // shorter one first, if one is a prefix of another
+            Ok(left.elements.len().cmp(&right.elements.len()))
         }
-        ObjectType::Array | ObjectType::Function | ObjectType::Return => {
-            Err(EvalError::InvalidInfixOperationTarget(
-                left.get_type(),
-                exp.operator.kind,
-            ))
+        (left, right) if !is_same_type(left, right) => Err(EvalError::NotSameType),
+        (left, _) => Err(EvalError::InvalidInfixOperationTarget(
+            left.get_type(),
+            operator,
+        )),
+    }
+}
+
+fn is_ordering(operator: Kind) -> bool {
+    matches!(
+        operator,
+        Kind::LT | Kind::LT_OR_EQ | Kind::GT | Kind::GT_OR_EQ
+    )
+}
+
+/// result of ordering operator on compared values
+fn ordering_object(operator: Kind, ordering: Ordering) -> Object {
+    let value = match operator {
+        Kind::LT => ordering == Ordering::Less,
+        Kind::LT_OR_EQ => ordering != Ordering::Greater,
+        Kind::GT => ordering == Ordering::Greater,
+        _ => ordering != Ordering::Less,
+    };
+    Object::Bool(Bool { value })
+}
+
+/// apply prefix operator to evaluated operand
+pub fn prefix_object(operator: Kind, right: Object) -> Result<Object, EvalError> {
+    match right {
+        Object::Int(right) => eval_prefix_int_exp(operator, right),
+        Object::BigInt(right) => eval_prefix_big_int_exp(operator, right),
+        Object::Bool(right) => eval_prefix_bool_exp(operator, right),
+        obj => Err(EvalError::InvalidPrefixOperationTarget(
+            obj.get_type(),
+            operator,
+        )),
+    }
+}
+
+/// index evaluated array, or get field of caught error
+pub fn index_object(left: Object, index: Object) -> Result<Option<Object>, EvalError> {
+    if let Object::Error(err) = left {
+        return eval_error_field(err, index);
+    }
+
+    if index.get_type() != ObjectType::Int {
+        return Err(EvalError::IndexIsNotAInt(Box::new(index)));
+    }
+
+    let Object::Array(arr) = left else {
+        return Err(EvalError::NotArray);
+    };
+    let Object::Int(idx) = index else {
+        unreachable!()
+    };
+
+    if idx.value < 0 {
+        return Err(EvalError::IndexIsNegative(Box::new(index)));
+    }
+
+    let idx = idx.value as usize;
+
+    if arr.elements.len() <= idx {
+        return Err(EvalError::IndexOutOfRange(IndexErrorDetail {
+            array_length: arr.elements.len(),
+            called_with: idx,
+        }));
+    }
+
+    Ok(Some(arr.elements[idx].clone()))
+}
+
+/// error raised by throw statement
+pub fn thrown_error(obj: Object, span: Position) -> EvalError {
+    match obj {
+        // rethrow caught error as it is
+        Object::Error(err) => EvalError::Thrown(err),
+        // message is the value as users read it, e.g. string without quotes
+        obj => {
+            EvalError::Thrown(ErrorObject {
+                kind: "Thrown".to_string(),
+                message: obj.to_string(),
+                span,
+                value: Some(Box::new(obj)),
+            })
         }
     }
 }
 
+/// error bound by catch clause
+pub fn error_object(err: EvalError, span: Position) -> ErrorObject {
+    match err {
+        EvalError::Thrown(err) => err,
+        err => ErrorObject {
+            kind: err.kind(),
+            message: err.message(),
+            span,
+            value: None,
+        },
+    }
+}
+
 fn eval_infix_int_exp(
     left: Int,
     operator: Kind,
     right: Int,
 ) -> Result<Object, EvalError> {
-    match operator {
-        Kind::Plus => {
-            let value = left.value + right.value;
-            Ok(Object::Int(Int { value }))
-        }
-        Kind::Minus => {
-            let value = left.value - right.value;
-            Ok(Object::Int(Int { value }))
-        }
-        Kind::Product => {
-            let value = left.value * right.value;
-            Ok(Object::Int(Int { value }))
-        }
-        Kind::Divide => {
-            if right.value == 0 {
+    let value = match operator {
+        Kind::Plus => left.value.checked_add(right.value),
+        Kind::Minus => left.value.checked_sub(right.value),
+        Kind::Product => left.value.checked_mul(right.value),
+        Kind::Divide | Kind::Mod if right.value == 0 => return Err(EvalError::DivideWithZero),
+        // only i64::MIN by -1 overflows
+        Kind::Divide => left.value.checked_div(right.value),
+        Kind::Mod => left.value.checked_rem(right.value),
+        _ => return eval_compare_int_exp(left, operator, right),
+    };
+    match value {
+        Some(value) => Ok(Object::Int(Int { value })),
+        // promoted to big integer on overflow
+        None => eval_infix_big_int_exp(
+            BigInt::from(left.value),
+            operator,
+            BigInt::from(right.value),
+        ),
+    }
+}
+
+fn eval_infix_big_int_exp(
+    left: BigInt,
+    operator: Kind,
+    right: BigInt,
+) -> Result<Object, EvalError> {
+    let value = match operator {
+        Kind::Plus => &left + &right,
+        Kind::Minus => &left - &right,
+        Kind::Product => &left * &right,
+        Kind::Divide | Kind::Mod => {
+            let Some((quotient, remainder)) = left.div_rem(&right) else {
                 return Err(EvalError::DivideWithZero);
+            };
+            if operator == Kind::Divide {
+                quotient
+            } else {
+                remainder
             }
-            let value = left.value / right.value;
-            Ok(Object::Int(Int { value }))
-        }
-        Kind::Mod => {
-            let value = left.value % right.value;
-            Ok(Object::Int(Int { value }))
         }
+        Kind::Bit_And => &left & &right,
+        Kind::Bit_Or => &left | &right,
+        oper if is_ordering(oper) => return Ok(ordering_object(oper, left.cmp(&right))),
+        oper => return Err(EvalError::InvalidIntegerInfixOperation(oper)),
+    };
+    big_int_object(value)
+}
+
+/// integer of the value bounded by its size, Int if it fits
+fn big_int_object(value: BigInt) -> Result<Object, EvalError> {
+    if value.limbs().len() > MAX_LIMBS {
+        return Err(EvalError::IntegerOverflow);
+    }
+    Ok(Object::from(value))
+}
+
+/// integer of either size as big integer
+fn big_int_of(obj: &Object) -> Option<BigInt> {
+    match obj {
+        Object::Int(int) => Some(BigInt::from(int.value)),
+        Object::BigInt(int) => Some(int.value.clone()),
+        _ => None,
+    }
+}
+
+fn eval_compare_int_exp(left: Int, operator: Kind, right: Int) -> Result<Object, EvalError> {
+    match operator {
         Kind::LT => Ok(Object::Bool(Bool {
             value: left.value < right.value,
         })),
@@ -389,6 +1551,7 @@ fn eval_infix_bool_exp(
         }
     }
 }
+
 fn eval_infix_string_exp(
     left: StringObject,
     operator: Kind,
@@ -405,6 +1568,7 @@ fn eval_infix_string_exp(
             let value = left.value + &right.value;
             Ok(Object::String(StringObject { value }))
         }
+        oper if is_ordering(oper) => Ok(ordering_object(oper, left.value.cmp(&right.value))),
 
         oper => {
             // add err
@@ -413,62 +1577,24 @@ fn eval_infix_string_exp(
     }
 }
 
-fn eval_prefix_exp(
-    exp: PrefixExpression,
-    env: &Environ<String>,
-) -> Result<Option<Object>, EvalError> {
-    let operator = exp.token.kind;
-
-    // evaluate first
-    let result = eval_exp(*exp.right.clone(), env);
-
-    if result.is_err() {
-        return result;
-    }
-
-    let result = result.unwrap();
-    if result.is_none() {
-        return Err(EvalError::EvaluationOfExpressionIsNone(*exp.right));
-    }
-    let obj = result.unwrap();
-
-    match obj.get_type() {
-        ObjectType::Int => {
-            let Object::Int(obj) = obj else {unreachable!()};
-
-            let result = eval_prefix_int_exp(operator, obj);
-            if result.is_err() {
-                return Err(result.err().unwrap());
-            }
-            Ok(Some(result.unwrap()))
-        }
-        ObjectType::Bool => {
-            let Object::Bool(obj) = obj else {unreachable!()};
-
-            let result = eval_prefix_bool_exp(operator, obj);
-            if result.is_err() {
-                return Err(result.err().unwrap());
-            }
-            Ok(Some(result.unwrap()))
-        }
-        ObjectType::Array
-        | ObjectType::Function
-        | ObjectType::Return
-        | ObjectType::String => Err(EvalError::InvalidPrefixOperationTarget(
-            obj.get_type(),
-            operator,
-        )),
-    }
-}
-
 fn eval_prefix_int_exp(operator: Kind, right: Int) -> Result<Object, EvalError> {
     match operator {
         Kind::Bang => Ok(Object::Int(Int {
             value: !right.value,
         })),
-        Kind::Minus => Ok(Object::Int(Int {
-            value: -right.value,
-        })),
+        Kind::Minus => match right.value.checked_neg() {
+            Some(value) => Ok(Object::Int(Int { value })),
+            None => big_int_object(-&BigInt::from(right.value)),
+        },
+        oper => Err(EvalError::InvalidIntegerPrefixOperation(oper)),
+    }
+}
+
+fn eval_prefix_big_int_exp(operator: Kind, right: BigIntObject) -> Result<Object, EvalError> {
+    match operator {
+        // same as bitwise not of Int in two's complement
+        Kind::Bang => big_int_object(&-&right.value - &BigInt::from(1)),
+        Kind::Minus => big_int_object(-&right.value),
         oper => Err(EvalError::InvalidIntegerPrefixOperation(oper)),
     }
 }
@@ -482,129 +1608,115 @@ fn eval_prefix_bool_exp(operator: Kind, right: Bool) -> Result<Object, EvalError
     }
 }
 
-fn eval_if_exp(
-    exp: IfExpression,
-    env: &Environ<String>,
-) -> Result<Option<Object>, EvalError> {
-    let condition_val = eval_exp(*exp.condition, env);
-    if condition_val.is_err() {
-        return condition_val;
-    };
-
-    let obj = condition_val.unwrap();
-    if obj.is_none() {
-        return Err(EvalError::ConditionIsNone);
-    }
-
-    let object = obj.unwrap();
-    let Object::Bool(flag) = object else { return Err(EvalError::NotABoolean(object))};
-
-    if flag.value {
-        return eval_stm(Statement::BlockStatement(exp.consequence), env);
-    }
-    if exp.alternative.is_some() {
-        return eval_stm(Statement::BlockStatement(exp.alternative.unwrap()), env);
-    }
-
-    Ok(None)
-}
-
-fn eval_call_exp(
-    exp: CallExpression,
-    env: &Environ<String>,
-) -> Result<Option<Object>, EvalError> {
-    let func = eval_exp(*exp.function, env);
-
-    if func.is_err() {
-        return Err(func.err().unwrap());
-    }
-    if func.clone().unwrap().is_none() {
-        return Err(EvalError::FunctionIsNone);
-    }
-    let func = func.unwrap().unwrap();
-    match func {
-        Object::Function(func) => {
-            let args = eval_function_parameters(exp.arguments, env);
-            if args.is_err() {
-                return Err(args.err().unwrap());
-            }
-            let args = args.unwrap();
-            apply_function(func, args)
-        }
-        // func is not a function
-        obj => Err(EvalError::NotAFunction(obj)),
-    }
+/// call which is not applied yet
+#[derive(Debug)]
+struct TailCall {
+    function: Function,
+    args: Vec<Object>,
+    named_args: Vec<(String, Object)>,
 }
 
-fn eval_function_parameters(
-    args: Vec<Expression>,
-    env: &Environ<String>,
-) -> Result<Vec<Object>, EvalError> {
-    let mut result: Vec<Object> = Vec::new();
-
-    for (_, arg) in args.iter().enumerate() {
-        let evaluated = eval_exp(arg.clone(), env);
-
-        if evaluated.is_err() {
-            return Err(evaluated.err().unwrap());
-        }
-        if evaluated.clone().unwrap().is_none() {
-            return Err(EvalError::EvaluationOfExpressionIsNone(arg.clone()));
-        }
-
-        result.push(evaluated.unwrap().unwrap())
-    }
-
-    Ok(result)
+/// result of statement in function body
+#[derive(Debug)]
+enum Evaluated {
+    Value(Option<Object>),
+    TailCall(TailCall),
 }
 
-fn apply_function(
-    fun: Function,
-    args: Vec<Object>,
-) -> Result<Option<Object>, EvalError> {
-    if args.len() != fun.args.len() {
-        return Err(EvalError::FunctionArgLengthNotMatched(ArgumentsLength {
-            function_args: fun.args.len(),
-            called_with: args.len(),
+/// read, parse and resolve source file of module
+pub fn read_program(
+    path: &Path,
+    import_path: &str,
+    position: Option<Position>,
+) -> Result<Program, EvalError> {
+    let source = fs::read_to_string(path);
+    if source.is_err() {
+        return Err(EvalError::ModuleNotFound(ImportErrorDetail {
+            path: import_path.to_string(),
+            position,
+            reason: source.err().unwrap().to_string(),
         }));
     }
 
-    let extended_env = extend_function_env(fun.clone(), args);
-
-    if extended_env.is_err() {
-        return Err(extended_env.unwrap_err());
+    let mut parser = Parser::new(Lexer::new(source.unwrap()));
+    let program = parser.parse();
+    if program.is_err() {
+        let mut details = Vec::new();
+        for errs in program.err().unwrap() {
+            // deeper first
+            let err = &errs[0];
+            details.push(format!(
+                "{} (line {})",
+                err.detail(),
+                err.position().line_num + 1
+            ));
+        }
+        return Err(EvalError::ModuleParseFailed(ImportErrorDetail {
+            path: import_path.to_string(),
+            position,
+            reason: details.join("; "),
+        }));
     }
 
-    let evaluated =
-        eval_stm(Statement::BlockStatement(fun.block), &extended_env.unwrap());
-
-    if evaluated.is_err() {
-        return evaluated;
+    let mut program = program.unwrap();
+    let errors = resolve_program(&mut program, &[]);
+    if !errors.is_empty() {
+        let mut details = Vec::new();
+        for err in errors {
+            details.push(format!(
+                "{} (line {})",
+                err.message(),
+                err.position().line_num + 1
+            ));
+        }
+        return Err(EvalError::ModuleResolveFailed(ImportErrorDetail {
+            path: import_path.to_string(),
+            position,
+            reason: details.join("; "),
+        }));
     }
-    let evaluated = evaluated.unwrap();
 
-    Ok(unwrap_return_value(evaluated))
+    Ok(program)
 }
 
-fn extend_function_env(
-    fun: Function,
-    args: Vec<Object>,
-) -> Result<Environ<String>, EvalError> {
-    let opt_rc = &fun.env.upgrade();
-
-    if opt_rc.is_none() {
-        return Err(EvalError::EnvironmentHasDropped);
+/// bind object to identifier, on its slot if it is resolved to one
+fn bind(env: &Environ<String>, ident: &Identifier, obj: Object) {
+    match ident.binding {
+        Some(Binding::Local { slot, .. }) => {
+            env.borrow_mut().set_slot(slot, ident.value.clone(), obj)
+        }
+        _ => {
+            env.borrow_mut().set(ident.value.clone(), obj);
+        }
     }
+}
 
-    let mut env = Environment::new_inner(&opt_rc.clone().unwrap());
+/// get field of caught error by its name
+fn eval_error_field(err: ErrorObject, key: Object) -> Result<Option<Object>, EvalError> {
+    let Object::String(key) = key else {
+        return Err(EvalError::IndexIsNotAString(Box::new(key)));
+    };
 
-    // bind given args(object) to fun's parameters(ident)
-    for (idx, arg) in fun.args.iter().enumerate() {
-        env.set(arg.value.clone(), args[idx].clone());
+    match key.value.as_str() {
+        "kind" => Ok(Some(Object::String(StringObject { value: err.kind }))),
+        "message" => Ok(Some(Object::String(StringObject { value: err.message }))),
+        // [line, offset] of the statement which raised the error, counted from 1
+        // like lines in messages. offset is of bytes from the start of source
+        "span" => Ok(Some(Object::Array(Array {
+            elements: vec![
+                Object::Int(Int {
+                    value: err.span.line_num as i64 + 1,
+                }),
+                Object::Int(Int {
+                    value: err.span.pos as i64 + 1,
+                }),
+            ],
+        }))),
+        "value" if err.value.is_some() => Ok(Some(*err.value.unwrap())),
+        _ => Err(EvalError::KeyNotFound(key.value)),
     }
-
-    Ok(Rc::new(RefCell::new(env)))
 }
+
 /// unwrap return value to object.
 /// if given obj is not a return value, don't do anything
 fn unwrap_return_value(obj: Option<Object>) -> Option<Object> {
@@ -614,59 +1726,7 @@ fn unwrap_return_value(obj: Option<Object>) -> Option<Object> {
     let obj = obj.unwrap();
 
     match obj.clone() {
-        Object::Return(rtv) => match rtv.value {
-            Some(val) => Some(*val),
-            None => None,
-        },
-        __ => Some(obj),
-    }
-}
-
-fn eval_index_exp(
-    exp: IndexExpression,
-    env: &Environ<String>,
-) -> Result<Option<Object>, EvalError> {
-    let left_rst = eval_exp(*exp.left, env);
-
-    if left_rst.is_err() {
-        return left_rst;
-    }
-    if left_rst.as_ref().unwrap().is_none() {
-        return Err(EvalError::ArrayIsNone);
-    }
-    let left = left_rst.unwrap().unwrap();
-
-    if left.get_type() != ObjectType::Array {
-        return Err(EvalError::NotArray);
-    }
-
-    let index_rst = eval_exp(*exp.index, env);
-    if index_rst.is_err() {
-        return index_rst;
+        Object::Return(rtv) => rtv.value.map(|val| *val),
+        _ => Some(obj),
     }
-    if index_rst.as_ref().unwrap().is_none() {
-        return Err(EvalError::ArrayIsNone);
-    }
-    let index = index_rst.unwrap().unwrap();
-    if index.get_type() != ObjectType::Int {
-        return Err(EvalError::IndexIsNotAInt(index));
-    }
-
-    let Object::Array(arr) = left else {unreachable!()};
-    let Object::Int(idx) = index else {unreachable!()};
-
-    if idx.value < 0 {
-        return Err(EvalError::IndexIsNegative(index));
-    }
-
-    let idx = idx.value as usize;
-
-    if arr.elements.len() < idx {
-        return Err(EvalError::IndexOutOfRange(IndexErrorDetail {
-            array_length: arr.elements.len(),
-            called_with: idx as usize,
-        }));
-    }
-
-    return Ok(Some(arr.elements[idx as usize].clone()));
 }
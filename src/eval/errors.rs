@@ -1,6 +1,7 @@
 use crate::{
-    ast::Expression,
-    object::{Object, ObjectType},
+    ast::{Expression, Nodetrait},
+    lexer::Position,
+    object::{bigint::MAX_LIMBS, ErrorObject, Object, ObjectType},
     token::Kind,
 };
 
@@ -10,12 +11,12 @@ pub enum EvalError {
     EnvironmentHasDropped,
 
     LetStatementValueIsNone,
-    EvaluationOfExpressionIsNone(Expression),
+    EvaluationOfExpressionIsNone(Box<Expression>),
     LeftExpressionIsNone,
     RightExpressionIsNone,
 
-    NotABoolean(Object),
-    NotAFunction(Object),
+    NotABoolean(Box<Object>),
+    NotAFunction(Box<Object>),
 
     ConditionIsNone,
     FunctionIsNone,
@@ -27,13 +28,17 @@ pub enum EvalError {
     NotSameType,
     NotArray,
 
-    IndexIsNotAInt(Object),
-    IndexIsNegative(Object),
+    IndexIsNotAInt(Box<Object>),
+    IndexIsNegative(Box<Object>),
     IndexOutOfRange(IndexErrorDetail),
 
     FunctionArgLengthNotMatched(ArgumentsLength),
+    UnknownNamedArgument(String),
+    DuplicatedArgument(String),
+    MissingArgument(String),
 
     DivideWithZero,
+    IntegerOverflow,
 
     InvalidPrefixOperationTarget(ObjectType, Kind),
     InvalidInfixOperationTarget(ObjectType, Kind),
@@ -45,11 +50,219 @@ pub enum EvalError {
 
     InvalidBoolInfixOperation(Kind),
     InvalidBoolPrefixOperation(Kind),
+
+    IndexIsNotAString(Box<Object>),
+    KeyNotFound(String),
+
+    // raised by throw statement
+    Thrown(ErrorObject),
+
+    ModuleNotFound(ImportErrorDetail),
+    ModuleParseFailed(ImportErrorDetail),
+    // undefined variables or duplicated parameters, found by resolver
+    ModuleResolveFailed(ImportErrorDetail),
+    ImportCycle(ImportErrorDetail),
+    NotAModule(Box<Object>),
+    NotExported(String),
+
+    StackOverflow { depth: usize },
+    OutOfFuel,
+    MemoryLimitExceeded { limit: usize },
+    Interrupted,
+}
+
+impl EvalError {
+    /// name of the error, exposed as `kind` of caught error
+    pub fn kind(&self) -> String {
+        let kind = match self {
+            EvalError::BlankResult => "BlankResult",
+            EvalError::EnvironmentHasDropped => "EnvironmentHasDropped",
+            EvalError::LetStatementValueIsNone => "LetStatementValueIsNone",
+            EvalError::EvaluationOfExpressionIsNone(_) => {
+                "EvaluationOfExpressionIsNone"
+            }
+            EvalError::LeftExpressionIsNone => "LeftExpressionIsNone",
+            EvalError::RightExpressionIsNone => "RightExpressionIsNone",
+            EvalError::NotABoolean(_) => "NotABoolean",
+            EvalError::NotAFunction(_) => "NotAFunction",
+            EvalError::ConditionIsNone => "ConditionIsNone",
+            EvalError::FunctionIsNone => "FunctionIsNone",
+            EvalError::ElementIsNone => "ElementIsNone",
+            EvalError::ArrayIsNone => "ArrayIsNone",
+            EvalError::IdentifierNotFound(_) => "IdentifierNotFound",
+            EvalError::NotSameType => "NotSameType",
+            EvalError::NotArray => "NotArray",
+            EvalError::IndexIsNotAInt(_) => "IndexIsNotAInt",
+            EvalError::IndexIsNegative(_) => "IndexIsNegative",
+            EvalError::IndexOutOfRange(_) => "IndexOutOfRange",
+            EvalError::FunctionArgLengthNotMatched(_) => {
+                "FunctionArgLengthNotMatched"
+            }
+            EvalError::UnknownNamedArgument(_) => "UnknownNamedArgument",
+            EvalError::DuplicatedArgument(_) => "DuplicatedArgument",
+            EvalError::MissingArgument(_) => "MissingArgument",
+            EvalError::DivideWithZero => "DivideWithZero",
+            EvalError::IntegerOverflow => "IntegerOverflow",
+            EvalError::InvalidPrefixOperationTarget(_, _) => {
+                "InvalidPrefixOperationTarget"
+            }
+            EvalError::InvalidInfixOperationTarget(_, _) => {
+                "InvalidInfixOperationTarget"
+            }
+            EvalError::InvalidStringInfixOperation(_) => {
+                "InvalidStringInfixOperation"
+            }
+            EvalError::InvalidIntegerInfixOperation(_) => {
+                "InvalidIntegerInfixOperation"
+            }
+            EvalError::InvalidIntegerPrefixOperation(_) => {
+                "InvalidIntegerPrefixOperation"
+            }
+            EvalError::InvalidBoolInfixOperation(_) => "InvalidBoolInfixOperation",
+            EvalError::InvalidBoolPrefixOperation(_) => "InvalidBoolPrefixOperation",
+            EvalError::IndexIsNotAString(_) => "IndexIsNotAString",
+            EvalError::KeyNotFound(_) => "KeyNotFound",
+            EvalError::Thrown(err) => return err.kind.clone(),
+            EvalError::ModuleNotFound(_) => "ModuleNotFound",
+            EvalError::ModuleParseFailed(_) => "ModuleParseFailed",
+            EvalError::ModuleResolveFailed(_) => "ModuleResolveFailed",
+            EvalError::ImportCycle(_) => "ImportCycle",
+            EvalError::NotAModule(_) => "NotAModule",
+            EvalError::NotExported(_) => "NotExported",
+            EvalError::StackOverflow { .. } => "StackOverflow",
+            EvalError::OutOfFuel => "OutOfFuel",
+            EvalError::MemoryLimitExceeded { .. } => "MemoryLimitExceeded",
+            EvalError::Interrupted => "Interrupted",
+        };
+        kind.to_string()
+    }
+
+    /// whether try expression can catch the error
+    pub fn is_catchable(&self) -> bool {
+        !matches!(
+            self,
+            EvalError::OutOfFuel | EvalError::MemoryLimitExceeded { .. } | EvalError::Interrupted
+        )
+    }
+
+    /// human readable description of the error
+    pub fn message(&self) -> String {
+        match self {
+            EvalError::BlankResult => "nothing to evaluate".to_string(),
+            EvalError::EnvironmentHasDropped => {
+                "environment of function has dropped".to_string()
+            }
+            EvalError::LetStatementValueIsNone => {
+                "let statement without value".to_string()
+            }
+            EvalError::EvaluationOfExpressionIsNone(exp) => {
+                format!("{} evaluated to nothing", exp.to_str())
+            }
+            EvalError::LeftExpressionIsNone => {
+                "left side of infix evaluated to nothing".to_string()
+            }
+            EvalError::RightExpressionIsNone => {
+                "right side of infix evaluated to nothing".to_string()
+            }
+            EvalError::NotABoolean(obj) => {
+                format!("{} is not a boolean", obj.inspect())
+            }
+            EvalError::NotAFunction(obj) => {
+                format!("{} is not a function", obj.inspect())
+            }
+            EvalError::ConditionIsNone => {
+                "condition evaluated to nothing".to_string()
+            }
+            EvalError::FunctionIsNone => "function evaluated to nothing".to_string(),
+            EvalError::ElementIsNone => "element evaluated to nothing".to_string(),
+            EvalError::ArrayIsNone => "array evaluated to nothing".to_string(),
+            EvalError::IdentifierNotFound(key) => {
+                format!("identifier {} not found", key)
+            }
+            EvalError::NotSameType => "operands are not a same type".to_string(),
+            EvalError::NotArray => "indexed value is not an array".to_string(),
+            EvalError::IndexIsNotAInt(obj) => {
+                format!("index {} is not a integer", obj.inspect())
+            }
+            EvalError::IndexIsNegative(obj) => {
+                format!("index {} is negative", obj.inspect())
+            }
+            EvalError::IndexOutOfRange(detail) => format!(
+                "index {} out of range for array of length {}",
+                detail.called_with, detail.array_length
+            ),
+            EvalError::FunctionArgLengthNotMatched(detail) => {
+                let expect = match detail.max_args {
+                    Some(max) if max == detail.min_args => format!("{}", max),
+                    Some(max) => format!("{} to {}", detail.min_args, max),
+                    None => format!("at least {}", detail.min_args),
+                };
+                format!(
+                    "function takes {} arguments but called with {}",
+                    expect, detail.called_with
+                )
+            }
+            EvalError::UnknownNamedArgument(name) => {
+                format!("function has no parameter named {}", name)
+            }
+            EvalError::DuplicatedArgument(name) => {
+                format!("argument {} is given more than once", name)
+            }
+            EvalError::MissingArgument(name) => {
+                format!("argument {} is missing", name)
+            }
+            EvalError::DivideWithZero => "divide with zero".to_string(),
+            EvalError::IntegerOverflow => {
+                format!("integer overflow, larger than {} bits", MAX_LIMBS * 32)
+            }
+            EvalError::InvalidPrefixOperationTarget(typ, kind)
+            | EvalError::InvalidInfixOperationTarget(typ, kind) => {
+                format!("{} is not applicable to {:?}", kind.to_str(), typ)
+            }
+            EvalError::InvalidStringInfixOperation(kind)
+            | EvalError::InvalidIntegerInfixOperation(kind)
+            | EvalError::InvalidIntegerPrefixOperation(kind)
+            | EvalError::InvalidBoolInfixOperation(kind)
+            | EvalError::InvalidBoolPrefixOperation(kind) => {
+                format!("invalid operator {}", kind.to_str())
+            }
+            EvalError::IndexIsNotAString(obj) => {
+                format!("key {} is not a string", obj.inspect())
+            }
+            EvalError::KeyNotFound(key) => format!("key {} not found", key),
+            EvalError::Thrown(err) => err.message.clone(),
+            EvalError::ModuleNotFound(detail)
+            | EvalError::ModuleParseFailed(detail)
+            | EvalError::ModuleResolveFailed(detail)
+            | EvalError::ImportCycle(detail) => match detail.position {
+                Some(position) => format!(
+                    "import of \"{}\" failed at line {}: {}",
+                    detail.path,
+                    position.line_num + 1,
+                    detail.reason
+                ),
+                None => format!("{}: {}", detail.path, detail.reason),
+            },
+            EvalError::NotAModule(obj) => format!("{} is not a module", obj.inspect()),
+            EvalError::NotExported(name) => format!("{} is not exported", name),
+            EvalError::StackOverflow { depth } => {
+                format!("maximum call depth of {} exceeded", depth)
+            }
+            EvalError::OutOfFuel => "evaluation ran out of fuel".to_string(),
+            EvalError::MemoryLimitExceeded { limit } => {
+                format!("memory limit of {} bytes exceeded", limit)
+            }
+            EvalError::Interrupted => "evaluation is interrupted".to_string(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct ArgumentsLength {
-    pub function_args: usize,
+    // number of parameters without default value
+    pub min_args: usize,
+    // None if function takes rest parameter
+    pub max_args: Option<usize>,
     pub called_with: usize,
 }
 
@@ -58,3 +271,12 @@ pub struct IndexErrorDetail {
     pub array_length: usize,
     pub called_with: usize,
 }
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportErrorDetail {
+    // path as written in import statement
+    pub path: String,
+    // position of the import statement, None for the file being run
+    pub position: Option<Position>,
+    pub reason: String,
+}
@@ -0,0 +1,24 @@
+use std::path::Path;
+
+use crate::{
+    ast::Statement,
+    object::{environment::Environ, Function, Object},
+};
+
+use super::errors::EvalError;
+
+/// observer of an evaluation, e.g. a debugger.
+/// evaluation waits until each method returns,
+/// an observer can stop it by interrupt handle of the evaluator
+pub trait Hook {
+    /// before each statement, `file` is the module holding it if evaluated from a file
+    fn before_statement(&mut self, _stm: &Statement, _env: &Environ<String>, _file: Option<&Path>) {
+    }
+
+    /// function is called with arguments.
+    /// calls in tail position reuse the call of the caller, like they reuse native stack
+    fn on_call(&mut self, _function: &Function, _args: &[Object]) {}
+
+    /// function called returns with the result
+    fn on_return(&mut self, _function: &Function, _result: &Result<Option<Object>, EvalError>) {}
+}
@@ -0,0 +1,39 @@
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+/// thread-safe flag to stop an evaluation from outside.
+/// evaluator polls it on each statement and function call
+#[derive(Debug, Clone, Default)]
+pub struct InterruptHandle {
+    flag: Arc<AtomicBool>,
+}
+
+impl InterruptHandle {
+    pub fn new() -> Self {
+        InterruptHandle {
+            flag: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// request evaluation to stop with Interrupted error
+    pub fn interrupt(&self) {
+        self.flag.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_interrupted(&self) -> bool {
+        self.flag.load(Ordering::SeqCst)
+    }
+
+    /// withdraw request which is not observed yet
+    pub fn reset(&self) {
+        self.flag.store(false, Ordering::SeqCst);
+    }
+
+    /// observe request, a request stops only one evaluation
+    pub fn take(&self) -> bool {
+        // cheap load first, it is polled on every statement
+        self.flag.load(Ordering::Relaxed) && self.flag.swap(false, Ordering::SeqCst)
+    }
+}
@@ -0,0 +1,84 @@
+use std::{cell::RefCell, mem::size_of};
+
+use crate::object::{environment::Environment, Array, BigIntObject, Object, StringObject};
+
+use super::errors::EvalError;
+
+/// approximate size of an environment without its bindings
+pub const ENVIRONMENT_SIZE: usize = size_of::<RefCell<Environment<String>>>();
+
+/// budget of approximate bytes a script may allocate in total.
+/// it is cumulative, freed memory is not given back to it. a long loop fails
+/// once its allocations add up to the limit, even if it holds little at a time
+#[derive(Debug, Default)]
+pub struct AllocationBudget {
+    allocated: usize,
+    // None if unlimited
+    limit: Option<usize>,
+}
+
+impl AllocationBudget {
+    pub fn new() -> Self {
+        AllocationBudget {
+            allocated: 0,
+            limit: None,
+        }
+    }
+
+    pub fn set_limit(&mut self, limit: Option<usize>) {
+        self.limit = limit;
+    }
+
+    pub fn allocated(&self) -> usize {
+        self.allocated
+    }
+
+    pub fn reset(&mut self) {
+        self.allocated = 0;
+    }
+
+    /// account bytes before allocating them, never released
+    pub fn allocate(&mut self, bytes: usize) -> Result<(), EvalError> {
+        let allocated = self.allocated.saturating_add(bytes);
+        if let Some(limit) = self.limit {
+            if allocated > limit {
+                return Err(EvalError::MemoryLimitExceeded { limit });
+            }
+        }
+        self.allocated = allocated;
+        Ok(())
+    }
+}
+
+pub fn string_size(len: usize) -> usize {
+    size_of::<StringObject>() + len
+}
+
+pub fn big_int_size(limbs: usize) -> usize {
+    size_of::<BigIntObject>() + limbs * size_of::<u32>()
+}
+
+pub fn array_size(len: usize) -> usize {
+    size_of::<Array>() + len * size_of::<Object>()
+}
+
+/// size of heap memory held by object, copied whenever object is cloned
+pub fn object_size(obj: &Object) -> usize {
+    match obj {
+        Object::String(string) => string_size(string.value.len()),
+        Object::BigInt(int) => big_int_size(int.value.limbs().len()),
+        Object::Array(array) => {
+            let mut size = array_size(array.elements.len());
+            for element in array.elements.iter() {
+                size += object_size(element);
+            }
+            size
+        }
+        _ => 0,
+    }
+}
+
+/// size of a binding of environment, value itself is accounted on creation
+pub fn binding_size(key: &str) -> usize {
+    size_of::<String>() + key.len() + size_of::<Object>()
+}
@@ -0,0 +1,83 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    ast::{Program, Statement},
+    object::Module,
+};
+
+/// keeps track of modules loaded by import statements
+#[derive(Debug, Default)]
+pub struct ModuleLoader {
+    // evaluated modules by canonical path
+    cache: HashMap<PathBuf, Module>,
+    // files currently being evaluated, innermost last
+    loading: Vec<PathBuf>,
+}
+
+impl ModuleLoader {
+    pub fn new() -> Self {
+        ModuleLoader {
+            cache: HashMap::new(),
+            loading: Vec::new(),
+        }
+    }
+
+    /// resolve path of import, relative to the file currently evaluated.
+    /// outside of any file, relative to working directory.
+    pub fn resolve(&self, path: &str) -> PathBuf {
+        let path = Path::new(path);
+        if path.is_absolute() {
+            return path.to_path_buf();
+        }
+        match self.loading.last().and_then(|file| file.parent()) {
+            Some(dir) => dir.join(path),
+            None => path.to_path_buf(),
+        }
+    }
+
+    pub fn get(&self, path: &Path) -> Option<Module> {
+        self.cache.get(path).cloned()
+    }
+
+    pub fn insert(&mut self, path: PathBuf, module: Module) {
+        self.cache.insert(path, module);
+    }
+
+    /// mark file as being evaluated.
+    /// if it is already being evaluated, return the chain of the cycle
+    pub fn enter(&mut self, path: PathBuf) -> Result<(), Vec<PathBuf>> {
+        let idx = self.loading.iter().position(|file| file == &path);
+        if let Some(idx) = idx {
+            let mut cycle = self.loading[idx..].to_vec();
+            cycle.push(path);
+            return Err(cycle);
+        }
+        self.loading.push(path);
+        Ok(())
+    }
+
+    /// file currently being evaluated
+    pub fn current(&self) -> Option<PathBuf> {
+        self.loading.last().cloned()
+    }
+
+    pub fn leave(&mut self) {
+        self.loading.pop();
+    }
+}
+
+/// names exported by top level export statements of program
+pub fn exported_names(program: &Program) -> Vec<String> {
+    let mut names = Vec::new();
+    for stm in program.statements.iter() {
+        if let Statement::ExportStatement(stm) = stm {
+            if let Some(name) = stm.name() {
+                names.push(name);
+            }
+        }
+    }
+    names
+}
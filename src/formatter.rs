@@ -0,0 +1,462 @@
+use std::collections::HashMap;
+
+use crate::{
+    ast::{
+        BlockStatement, Expression, FunctionLiteral, IfExpression, Nodetrait, Program, Statement,
+        TryExpression,
+    },
+    lexer::{Comment, Lexer, Position},
+    parser::{find_precedences, Parser, Precedence},
+    token::Kind,
+};
+
+const INDENT: &str = "    ";
+
+/// format source code in canonical style.
+/// comments are kept, and a blank line between statements is kept as one blank line.
+/// a comment inside an expression is moved to its own line after the statement.
+/// error is the reason source can't be parsed
+pub fn format(source: &str) -> Result<String, String> {
+    let program = Parser::new(Lexer::new(source.to_string())).parse();
+    if program.is_err() {
+        let mut details = Vec::new();
+        for errs in program.err().unwrap() {
+            // deeper first
+            let err = &errs[0];
+            details.push(format!(
+                "{} (line {})",
+                err.detail(),
+                err.position().line_num + 1
+            ));
+        }
+        return Err(details.join("; "));
+    }
+
+    let mut printer = Printer::new(Layout::of(source));
+    printer.program(&program.unwrap());
+    Ok(printer.out)
+}
+
+/// format program in canonical style, without comments
+pub fn format_program(program: &Program) -> String {
+    let mut printer = Printer::new(Layout::default());
+    printer.program(program);
+    printer.out
+}
+
+/// tokens and comments of the source, where formatted program came from
+#[derive(Default)]
+struct Layout {
+    // positions of tokens, in order
+    tokens: Vec<Position>,
+    comments: Vec<Comment>,
+    // position of `}` of each block, by position of its `{`
+    block_ends: HashMap<usize, usize>,
+}
+
+impl Layout {
+    fn of(source: &str) -> Self {
+        let mut lexer = Lexer::new(source.to_string());
+        let mut tokens = Vec::new();
+        let mut block_ends = HashMap::new();
+        let mut open = Vec::new();
+
+        loop {
+            let token = lexer.next();
+            match token.kind {
+                Kind::EOF => break,
+                Kind::LBRACE => open.push(token.position.pos),
+                Kind::RBRACE => {
+                    if let Some(start) = open.pop() {
+                        block_ends.insert(start, token.position.pos);
+                    }
+                }
+                _ => {}
+            }
+            tokens.push(token.position);
+        }
+
+        Layout {
+            tokens,
+            comments: lexer.comments().to_vec(),
+            block_ends,
+        }
+    }
+
+    /// last token before `pos`
+    fn token_before(&self, pos: usize) -> Option<Position> {
+        let idx = self.tokens.partition_point(|token| token.pos < pos);
+        if idx == 0 {
+            return None;
+        }
+        Some(self.tokens[idx - 1])
+    }
+
+    /// whether source has a blank line right before something at `at`
+    fn blank_line_before(&self, at: Position) -> bool {
+        let token = self.token_before(at.pos);
+        let comment = self
+            .comments
+            .iter()
+            .rev()
+            .find(|comment| comment.position.pos < at.pos);
+
+        let previous = match (token, comment) {
+            (Some(token), Some(comment)) => token.line_num.max(comment.position.line_num),
+            (Some(token), None) => token.line_num,
+            (None, Some(comment)) => comment.position.line_num,
+            (None, None) => return false,
+        };
+        at.line_num > previous + 1
+    }
+}
+
+struct Printer {
+    out: String,
+    indent: usize,
+    layout: Layout,
+    // comments before this are printed
+    next_comment: usize,
+}
+
+impl Printer {
+    fn new(layout: Layout) -> Self {
+        Printer {
+            out: String::new(),
+            indent: 0,
+            layout,
+            next_comment: 0,
+        }
+    }
+
+    fn program(&mut self, program: &Program) {
+        self.statements(&program.statements, usize::MAX);
+    }
+
+    /// statements of a block, each on its own line.
+    /// `end` is position of the end of block
+    fn statements(&mut self, stms: &[Statement], end: usize) {
+        let mut first = true;
+
+        for (idx, stm) in stms.iter().enumerate() {
+            let at = stm.token().position;
+            self.comments_before(at.pos, &mut first);
+
+            if !first && self.layout.blank_line_before(at) {
+                self.out.push('\n');
+            }
+            first = false;
+
+            self.line_start();
+            self.statement(stm);
+
+            let next = stms
+                .get(idx + 1)
+                .map_or(end, |next| next.token().position.pos);
+            self.trailing_comment(next);
+            self.out.push('\n');
+        }
+
+        self.comments_before(end, &mut first);
+    }
+
+    /// print comments not printed yet, which are before `pos`, on their own lines
+    fn comments_before(&mut self, pos: usize, first: &mut bool) {
+        while self.next_comment < self.layout.comments.len() {
+            let comment = self.layout.comments[self.next_comment].clone();
+            if comment.position.pos >= pos {
+                return;
+            }
+            if !*first && self.layout.blank_line_before(comment.position) {
+                self.out.push('\n');
+            }
+            *first = false;
+
+            self.line_start();
+            self.comment(&comment);
+            self.out.push('\n');
+            self.next_comment += 1;
+        }
+    }
+
+    /// comment on the line where statement ends.
+    /// statement is printed on one line, so comments left inside it follow on their own lines
+    fn trailing_comment(&mut self, next: usize) {
+        let Some(last) = self.layout.token_before(next) else {
+            return;
+        };
+        let mut inner = Vec::new();
+        while let Some(comment) = self.layout.comments.get(self.next_comment) {
+            if comment.position.pos >= last.pos {
+                break;
+            }
+            inner.push(comment.clone());
+            self.next_comment += 1;
+        }
+
+        if let Some(comment) = self.layout.comments.get(self.next_comment).cloned() {
+            if comment.position.pos > last.pos
+                && comment.position.pos < next
+                && comment.position.line_num == last.line_num
+            {
+                self.out.push(' ');
+                self.comment(&comment);
+                self.next_comment += 1;
+            }
+        }
+
+        for comment in inner {
+            self.out.push('\n');
+            self.line_start();
+            self.comment(&comment);
+        }
+    }
+
+    fn comment(&mut self, comment: &Comment) {
+        self.out.push_str("//");
+        self.out.push_str(&comment.text);
+    }
+
+    fn line_start(&mut self) {
+        for _ in 0..self.indent {
+            self.out.push_str(INDENT);
+        }
+    }
+
+    fn block(&mut self, block: &BlockStatement) {
+        let end = self
+            .layout
+            .block_ends
+            .get(&block.token.position.pos)
+            .copied()
+            .unwrap_or(block.token.position.pos);
+        let has_comment = self
+            .layout
+            .comments
+            .get(self.next_comment)
+            .is_some_and(|comment| comment.position.pos < end);
+
+        if block.statements.is_empty() && !has_comment {
+            self.out.push_str("{}");
+            return;
+        }
+
+        self.out.push('{');
+        // comment right after `{`
+        let opening = self.layout.comments.get(self.next_comment).cloned();
+        if opening.as_ref().is_some_and(|comment| {
+            comment.position.line_num == block.token.position.line_num
+                && self.layout.token_before(comment.position.pos) == Some(block.token.position)
+        }) {
+            self.out.push(' ');
+            self.comment(&opening.unwrap());
+            self.next_comment += 1;
+        }
+        self.out.push('\n');
+        self.indent += 1;
+        self.statements(&block.statements, end);
+        self.indent -= 1;
+        self.line_start();
+        self.out.push('}');
+    }
+
+    fn statement(&mut self, stm: &Statement) {
+        match stm {
+            Statement::LetStatement(stm) => {
+                self.out.push_str("let ");
+                self.out.push_str(&stm.identifier.value);
+                if let Some(value) = &stm.value {
+                    self.out.push_str(" = ");
+                    self.expression(value);
+                }
+                self.out.push(';');
+            }
+            // expression statement can't be followed by semicolon
+            Statement::ExpressionStatement(stm) => {
+                if let Some(expression) = &stm.expression {
+                    self.expression(expression);
+                }
+            }
+            Statement::ReturnStatement(stm) => {
+                self.out.push_str("return");
+                if let Some(value) = &stm.value {
+                    self.out.push(' ');
+                    self.expression(value);
+                }
+                self.out.push(';');
+            }
+            Statement::BlockStatement(stm) => self.block(stm),
+            Statement::ThrowStatement(stm) => {
+                self.out.push_str("throw ");
+                self.expression(&stm.value);
+                self.out.push(';');
+            }
+            Statement::ImportStatement(stm) => {
+                self.out.push_str(&format!(
+                    "import \"{}\" as {};",
+                    stm.path.value, stm.alias.value
+                ));
+            }
+            Statement::ExportStatement(stm) => {
+                self.out.push_str("export ");
+                self.statement(&stm.statement);
+            }
+        }
+    }
+
+    fn expression(&mut self, exp: &Expression) {
+        match exp {
+            Expression::Identifier(ident) => self.out.push_str(&ident.value),
+            Expression::IntegerLiteral(lit) => self.out.push_str(&lit.value.to_string()),
+            Expression::BigIntLiteral(lit) => self.out.push_str(&lit.to_str()),
+            Expression::BooleanLiteral(lit) => self.out.push_str(&lit.value.to_string()),
+            Expression::StringLiteral(lit) => {
+                self.out.push('"');
+                self.out.push_str(&lit.value);
+                self.out.push('"');
+            }
+            Expression::FunctionLiteral(func) => self.function(func),
+            Expression::ArrayLiteral(arr) => {
+                self.out.push('[');
+                for (idx, element) in arr.elements.iter().enumerate() {
+                    if idx > 0 {
+                        self.out.push_str(", ");
+                    }
+                    self.expression(element);
+                }
+                self.out.push(']');
+            }
+            Expression::InfixExpression(exp) => {
+                let operator = find_precedences(exp.operator.kind);
+                // operators are left associative
+                self.operand(&exp.left, precedence(&exp.left) < operator);
+                self.out.push(' ');
+                self.out.push_str(&exp.operator.literal);
+                self.out.push(' ');
+                self.operand(&exp.right, precedence(&exp.right) <= operator);
+            }
+            Expression::PrefixExpression(exp) => {
+                self.out.push_str(&exp.token.literal);
+                self.operand(&exp.right, precedence(&exp.right) < Precedence::Prefix);
+            }
+            Expression::IfExpression(exp) => self.if_expression(exp),
+            Expression::CallExpression(exp) => {
+                self.operand(&exp.function, precedence(&exp.function) < Precedence::Call);
+                self.out.push('(');
+                for (idx, arg) in exp.arguments.iter().enumerate() {
+                    if idx > 0 {
+                        self.out.push_str(", ");
+                    }
+                    self.expression(arg);
+                }
+                for (idx, arg) in exp.named_arguments.iter().enumerate() {
+                    if idx > 0 || !exp.arguments.is_empty() {
+                        self.out.push_str(", ");
+                    }
+                    self.out.push_str(&arg.ident.value);
+                    self.out.push_str(" = ");
+                    self.expression(&arg.value);
+                }
+                self.out.push(')');
+            }
+            Expression::IndexExpression(exp) => {
+                self.operand(&exp.left, precedence(&exp.left) < Precedence::Call);
+                self.out.push('[');
+                self.expression(&exp.index);
+                self.out.push(']');
+            }
+            Expression::TryExpression(exp) => self.try_expression(exp),
+            Expression::MemberExpression(exp) => {
+                self.operand(&exp.left, precedence(&exp.left) < Precedence::Call);
+                self.out.push('.');
+                self.out.push_str(&exp.member.value);
+            }
+        }
+    }
+
+    fn operand(&mut self, exp: &Expression, grouped: bool) {
+        if grouped {
+            self.out.push('(');
+        }
+        self.expression(exp);
+        if grouped {
+            self.out.push(')');
+        }
+    }
+
+    fn function(&mut self, func: &FunctionLiteral) {
+        self.out.push_str(&func.literal());
+        if let Some(ident) = &func.ident {
+            self.out.push(' ');
+            self.out.push_str(&ident.value);
+        }
+        self.out.push('(');
+        for (idx, param) in func.parameters.iter().enumerate() {
+            if idx > 0 {
+                self.out.push_str(", ");
+            }
+            self.out.push_str(&param.ident.value);
+            if let Some(default) = &param.default {
+                self.out.push_str(" = ");
+                self.expression(default);
+            }
+        }
+        if let Some(rest) = &func.rest {
+            if !func.parameters.is_empty() {
+                self.out.push_str(", ");
+            }
+            self.out.push_str("...");
+            self.out.push_str(&rest.value);
+        }
+        self.out.push_str(") ");
+        self.block(&func.body);
+    }
+
+    fn if_expression(&mut self, exp: &IfExpression) {
+        self.out.push_str("if (");
+        self.expression(&exp.condition);
+        self.out.push_str(") ");
+        self.block(&exp.consequence);
+        if let Some(alternative) = &exp.alternative {
+            self.out.push_str(" else ");
+            self.block(alternative);
+        }
+    }
+
+    fn try_expression(&mut self, exp: &TryExpression) {
+        self.out.push_str("try ");
+        self.block(&exp.block);
+        if let Some(catch) = &exp.catch {
+            self.out.push_str(" catch ");
+            if let Some(catch_ident) = &exp.catch_ident {
+                self.out.push('(');
+                self.out.push_str(&catch_ident.value);
+                self.out.push_str(") ");
+            }
+            self.block(catch);
+        }
+        if let Some(finally) = &exp.finally {
+            self.out.push_str(" finally ");
+            self.block(finally);
+        }
+    }
+}
+
+/// how tight expression binds, when it is an operand
+fn precedence(exp: &Expression) -> Precedence {
+    match exp {
+        Expression::InfixExpression(exp) => find_precedences(exp.operator.kind),
+        Expression::PrefixExpression(_) => Precedence::Prefix,
+        // negative literal is printed as prefix expression
+        Expression::IntegerLiteral(lit) if lit.value < 0 => Precedence::Prefix,
+        Expression::BigIntLiteral(lit) if lit.value.is_negative() => Precedence::Prefix,
+        Expression::CallExpression(_)
+        | Expression::IndexExpression(_)
+        | Expression::MemberExpression(_) => Precedence::Call,
+        // expressions with block are grouped when they are operands
+        Expression::IfExpression(_)
+        | Expression::TryExpression(_)
+        | Expression::FunctionLiteral(_) => Precedence::Lowest,
+        _ => Precedence::Index,
+    }
+}
@@ -11,14 +11,26 @@ pub struct Lexer {
     line_num: usize,
     next_pos: usize,
     cur: char,
+    // comments skipped so far
+    comments: Vec<Comment>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
 pub struct Position {
+    // byte offset in input
     pub pos: usize,
     pub line_num: usize,
 }
 
+/// `// comment` to the end of line, skipped like whitespace
+#[derive(Debug, Clone, PartialEq)]
+pub struct Comment {
+    // where `//` starts
+    pub position: Position,
+    // text after `//`, without trailing whitespace
+    pub text: String,
+}
+
 impl Lexer {
     /// create new lexer from given input.
     ///
@@ -30,37 +42,74 @@ impl Lexer {
             line_num: 0,
             next_pos: 0,
             cur: '\0',
+            comments: Vec::new(),
         };
         lex.read_char();
         lex
     }
 
+    /// character starting at byte `pos`, '\0' past the end of input
+    fn char_at(&self, pos: usize) -> char {
+        self.input
+            .get(pos..)
+            .and_then(|rest| rest.chars().next())
+            .unwrap_or('\0')
+    }
+
     fn read_char(&mut self) {
-        if self.next_pos >= self.input.len() {
-            self.cur = '\0'
-        } else {
-            self.cur = self.input.chars().nth(self.next_pos).unwrap()
-        }
         self.pos = self.next_pos;
-        self.next_pos += 1;
+        self.cur = self.char_at(self.pos);
+        self.next_pos = self.pos + self.cur.len_utf8();
     }
 
     fn peek_char(&self) -> char {
-        if self.next_pos >= self.input.len() {
-            return '\0';
-        }
-        self.input.chars().nth(self.next_pos).unwrap()
+        self.char_at(self.next_pos)
+    }
+
+    fn peek_nth_char(&self, nth: usize) -> char {
+        self.input
+            .get(self.next_pos..)
+            .and_then(|rest| rest.chars().nth(nth))
+            .unwrap_or('\0')
     }
 
     fn skip_whitespace(&mut self) {
-        while self.cur.is_whitespace() {
-            if self.cur == '\n' {
-                self.line_num += 1;
+        loop {
+            while self.cur.is_whitespace() {
+                if self.cur == '\n' {
+                    self.line_num += 1;
+                }
+                self.read_char()
+            }
+            if self.cur != '/' || self.peek_char() != '/' {
+                return;
             }
-            self.read_char()
+            self.read_comment();
         }
     }
 
+    fn read_comment(&mut self) {
+        let position = self.get_pos();
+        // skip `//`
+        self.read_char();
+        self.read_char();
+
+        let mut text = String::new();
+        while self.cur != '\n' && self.cur != '\0' {
+            text.push(self.cur);
+            self.read_char();
+        }
+        self.comments.push(Comment {
+            position,
+            text: text.trim_end().to_string(),
+        });
+    }
+
+    /// comments skipped by tokens read so far
+    pub fn comments(&self) -> &[Comment] {
+        &self.comments
+    }
+
     fn read_id(&mut self) -> String {
         let start = self.pos;
         while !self.peek_char().is_whitespace()
@@ -69,7 +118,7 @@ impl Lexer {
         {
             self.read_char();
         }
-        self.input[start..self.pos + 1].to_string()
+        self.input[start..self.next_pos].to_string()
     }
 
     fn read_num(&mut self) -> Result<String, errors::LexerError> {
@@ -77,18 +126,22 @@ impl Lexer {
         while !self.peek_char().is_whitespace()
             && self.peek_char().is_alphanumeric()
         {
+            // suffix of big integer, read by caller
+            if self.peek_char() == 'n' && !self.peek_nth_char(1).is_alphanumeric() {
+                break;
+            }
             if self.peek_char().is_alphabetic() {
                 let err = errors::LexerError {
                     pos_start: start,
                     pos_end: self.pos,
-                    reason: self.input[start..self.pos + 1].to_string()
+                    reason: self.input[start..self.next_pos].to_string()
                         + " is not a numeric",
                 };
                 return Err(err);
             }
             self.read_char();
         }
-        Ok(self.input[start..self.pos + 1].to_string())
+        Ok(self.input[start..self.next_pos].to_string())
     }
 
     fn read_string(&mut self) -> Result<String, errors::LexerError> {
@@ -101,7 +154,7 @@ impl Lexer {
             let err = errors::LexerError {
                 pos_start: start,
                 pos_end: self.pos,
-                reason: "closing (\") not found".to_string(),
+                reason: errors::UNTERMINATED_STRING.to_string(),
             };
             return Err(err);
         }
@@ -118,9 +171,12 @@ impl Lexer {
     }
 
     /// get next token
+    // not an `Iterator`: the lexer keeps returning EOF once input runs out.
+    #[allow(clippy::should_implement_trait)]
     pub fn next(&mut self) -> Token {
         self.skip_whitespace();
 
+        let position = self.get_pos();
         let mut token: Token = Token::new(token::Kind::Illegal);
 
         match self.cur {
@@ -146,6 +202,15 @@ impl Lexer {
                     token = Token::new(token::Kind::Assign);
                 }
             }
+            '.' => {
+                if self.peek_char() == '.' && self.peek_nth_char(1) == '.' {
+                    self.read_char();
+                    self.read_char();
+                    token = Token::new(token::Kind::Ellipsis);
+                } else {
+                    token = Token::new(token::Kind::Dot);
+                }
+            }
             '!' => {
                 if self.peek_char() == '=' {
                     self.read_char();
@@ -201,17 +266,22 @@ impl Lexer {
                     token.literal = self.read_id();
                     token.kind = token::get_token_kind(&token.literal);
                 } else if any.is_numeric() {
-                    let read_result = self.read_num();
-                    if read_result.is_ok() {
-                        token.literal = read_result.unwrap();
-                        token.kind = token::Kind::Int;
-                    } else {
-                        token.literal = read_result.err().unwrap().reason;
+                    match self.read_num() {
+                        Ok(literal) => {
+                            token.literal = literal;
+                            token.kind = token::Kind::Int;
+                            if self.peek_char() == 'n' {
+                                self.read_char();
+                                token.kind = token::Kind::BigInt;
+                            }
+                        }
+                        Err(err) => token.literal = err.reason,
                     }
                 }
             }
         }
         self.read_char();
+        token.position = position;
         token
     }
 }
@@ -1,3 +1,6 @@
+// reason of string without closing quote, which is the literal of its illegal token
+pub const UNTERMINATED_STRING: &str = "closing (\") not found";
+
 #[derive(Debug)]
 pub struct LexerError {
     pub pos_start: usize,
@@ -1,7 +1,13 @@
 pub mod ast;
+pub mod dap;
 pub mod eval;
+pub mod formatter;
 pub mod lexer;
+pub mod lsp;
 pub mod object;
+pub mod optimizer;
 pub mod parser;
 pub mod repl;
+pub mod resolver;
 pub mod token;
+pub mod vm;
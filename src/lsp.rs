@@ -0,0 +1,516 @@
+// language server of dlang, speaking json-rpc over stdio.
+// documents are synced in full and analyzed on each change.
+// positions are byte offsets inside, and utf-16 code units in messages as lsp defaults
+
+pub mod analysis;
+pub mod json;
+
+use std::{
+    collections::HashMap,
+    io::{self, BufRead, Write},
+};
+
+use crate::{
+    lexer::{Lexer, Position},
+    parser::Parser,
+    token::KEYWORDS,
+};
+
+use self::{
+    analysis::{Analysis, SymbolKind},
+    json::Json,
+};
+
+// error codes of json-rpc
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+/// serve a client until it exits, returns exit code of the process
+pub fn run<R: BufRead, W: Write>(input: &mut R, output: &mut W) -> i32 {
+    let mut server = Server::new();
+    loop {
+        let message = read_message(input);
+        if message.is_err() {
+            return 1;
+        }
+        // client closed the stream without exit
+        let Some(message) = message.unwrap() else {
+            return 1;
+        };
+
+        let replies = match json::parse(&message) {
+            Ok(message) => server.handle(&message),
+            Err(reason) => vec![error_response(Json::Null, PARSE_ERROR, &reason)],
+        };
+        for reply in replies {
+            if write_message(output, &reply.to_str()).is_err() {
+                return 1;
+            }
+        }
+        if server.exit_code().is_some() {
+            return server.exit_code().unwrap();
+        }
+    }
+}
+
+/// read a message framed by `Content-Length` header, None at the end of input
+pub fn read_message<R: BufRead>(input: &mut R) -> io::Result<Option<String>> {
+    let mut length = None;
+    loop {
+        let mut line = String::new();
+        let read = input.read_line(&mut line);
+        if read.is_err() {
+            return Err(read.err().unwrap());
+        }
+        if read.unwrap() == 0 {
+            return Ok(None);
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                length = value.trim().parse::<usize>().ok();
+            }
+        }
+    }
+
+    let Some(length) = length else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Content-Length not found",
+        ));
+    };
+    let mut body = vec![0; length];
+    let read = input.read_exact(&mut body);
+    if read.is_err() {
+        return Err(read.err().unwrap());
+    }
+    match String::from_utf8(body) {
+        Ok(body) => Ok(Some(body)),
+        Err(err) => Err(io::Error::new(io::ErrorKind::InvalidData, err)),
+    }
+}
+
+pub fn write_message<W: Write>(output: &mut W, body: &str) -> io::Result<()> {
+    let written = write!(output, "Content-Length: {}\r\n\r\n{}", body.len(), body);
+    if written.is_err() {
+        return Err(written.err().unwrap());
+    }
+    output.flush()
+}
+
+/// text of an open document and what is known about it
+pub struct Document {
+    pub text: String,
+    // byte offset of the start of each line
+    line_starts: Vec<usize>,
+    // analysis of the last text which parsed
+    analysis: Analysis,
+    diagnostics: Vec<Json>,
+}
+
+impl Document {
+    /// analyze text, analysis of the former text is kept while it doesn't parse
+    pub fn new(text: String, former: Option<Document>) -> Self {
+        let mut line_starts = vec![0];
+        for (idx, ch) in text.char_indices() {
+            if ch == '\n' {
+                line_starts.push(idx + 1);
+            }
+        }
+        let mut document = Document {
+            text,
+            line_starts,
+            analysis: former.map(|doc| doc.analysis).unwrap_or_default(),
+            diagnostics: Vec::new(),
+        };
+
+        match Parser::new(Lexer::new(document.text.clone())).parse() {
+            Ok(program) => document.analysis = Analysis::of(&program, &document.text),
+            Err(errors) => {
+                for errs in errors {
+                    // deeper first
+                    let err = &errs[0];
+                    let start = document.position(err.position());
+                    let end = Json::object(vec![
+                        ("line", start.get("line").clone()),
+                        (
+                            "character",
+                            Json::from(start.get("character").as_i64().unwrap() + 1),
+                        ),
+                    ]);
+                    document.diagnostics.push(Json::object(vec![
+                        ("range", Json::object(vec![("start", start), ("end", end)])),
+                        ("severity", Json::from(1_usize)),
+                        ("source", Json::from("dlang")),
+                        ("message", Json::from(err.detail())),
+                    ]));
+                }
+            }
+        }
+        document
+    }
+
+    pub fn analysis(&self) -> &Analysis {
+        &self.analysis
+    }
+
+    /// byte offset of lsp position, None if it is out of the text.
+    /// character past the end of line is the end of line
+    pub fn offset(&self, position: &Json) -> Option<usize> {
+        let line = position.get("line").as_i64();
+        let character = position.get("character").as_i64();
+        if line.is_none() || character.is_none() {
+            return None;
+        }
+        let start = *self.line_starts.get(line.unwrap() as usize)?;
+        let text = self.text[start..].split('\n').next().unwrap_or("");
+
+        let character = character.unwrap() as usize;
+        let mut units = 0;
+        for (idx, ch) in text.char_indices() {
+            if units >= character {
+                return Some(start + idx);
+            }
+            units += ch.len_utf16();
+        }
+        Some(start + text.len())
+    }
+
+    /// lsp position of lexer position
+    pub fn position(&self, position: Position) -> Json {
+        let line = position.line_num.min(self.line_starts.len() - 1);
+        let start = self.line_starts[line];
+        let end = position.pos.clamp(start, self.text.len());
+        let character = self
+            .text
+            .get(start..end)
+            .map_or(0, |text| text.encode_utf16().count());
+        Json::object(vec![
+            ("line", Json::from(line)),
+            ("character", Json::from(character)),
+        ])
+    }
+
+    /// lsp range of name written at position
+    pub fn range(&self, name: &str, position: Position) -> Json {
+        let end = Position {
+            pos: position.pos + name.len(),
+            line_num: position.line_num,
+        };
+        Json::object(vec![
+            ("start", self.position(position)),
+            ("end", self.position(end)),
+        ])
+    }
+}
+
+pub struct Server {
+    documents: HashMap<String, Document>,
+    shutdown: bool,
+    exit_code: Option<i32>,
+}
+
+impl Default for Server {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Server {
+    pub fn new() -> Self {
+        Server {
+            documents: HashMap::new(),
+            shutdown: false,
+            exit_code: None,
+        }
+    }
+
+    /// exit code, once client sent exit
+    pub fn exit_code(&self) -> Option<i32> {
+        self.exit_code
+    }
+
+    pub fn document(&self, uri: &str) -> Option<&Document> {
+        self.documents.get(uri)
+    }
+
+    /// handle a message of client, returns messages to send back
+    pub fn handle(&mut self, message: &Json) -> Vec<Json> {
+        let method = message.get("method").as_str();
+        // response of client, no request is sent by server
+        if method.is_none() {
+            return Vec::new();
+        }
+        let method = method.unwrap();
+        let params = message.get("params");
+
+        let id = message.get("id");
+        if id.is_null() {
+            return self.notification(method, params);
+        }
+        if self.shutdown && method != "shutdown" {
+            return vec![error_response(
+                id.clone(),
+                INVALID_REQUEST,
+                "server is shut down",
+            )];
+        }
+        match self.request(method, params) {
+            Ok(result) => vec![Json::object(vec![
+                ("jsonrpc", Json::from("2.0")),
+                ("id", id.clone()),
+                ("result", result),
+            ])],
+            Err((code, reason)) => vec![error_response(id.clone(), code, &reason)],
+        }
+    }
+
+    fn notification(&mut self, method: &str, params: &Json) -> Vec<Json> {
+        let uri = params.get("textDocument").get("uri").as_str();
+        match method {
+            "exit" => {
+                self.exit_code = Some(if self.shutdown { 0 } else { 1 });
+                Vec::new()
+            }
+            "textDocument/didOpen" => {
+                let text = params.get("textDocument").get("text").as_str();
+                if uri.is_none() || text.is_none() {
+                    return Vec::new();
+                }
+                self.update(uri.unwrap(), text.unwrap().to_string())
+            }
+            "textDocument/didChange" => {
+                // full sync, the last change holds whole text
+                let changes = params.get("contentChanges").as_array();
+                let text = changes
+                    .and_then(|changes| changes.last())
+                    .and_then(|change| change.get("text").as_str());
+                if uri.is_none() || text.is_none() {
+                    return Vec::new();
+                }
+                self.update(uri.unwrap(), text.unwrap().to_string())
+            }
+            "textDocument/didClose" => {
+                if uri.is_none() {
+                    return Vec::new();
+                }
+                self.documents.remove(uri.unwrap());
+                vec![diagnostics(uri.unwrap(), Vec::new())]
+            }
+            // initialized, didSave, $/ notifications and others need nothing
+            _ => Vec::new(),
+        }
+    }
+
+    fn update(&mut self, uri: &str, text: String) -> Vec<Json> {
+        let former = self.documents.remove(uri);
+        let document = Document::new(text, former);
+        let published = diagnostics(uri, document.diagnostics.clone());
+        self.documents.insert(uri.to_string(), document);
+        vec![published]
+    }
+
+    fn request(&mut self, method: &str, params: &Json) -> Result<Json, (i64, String)> {
+        match method {
+            "initialize" => Ok(capabilities()),
+            "shutdown" => {
+                self.shutdown = true;
+                Ok(Json::Null)
+            }
+            "textDocument/definition" => self.at_cursor(params, |document, def| {
+                let def = &document.analysis.definitions[def];
+                location(params, document.range(&def.name, def.position))
+            }),
+            "textDocument/references" => {
+                let declaration = params
+                    .get("context")
+                    .get("includeDeclaration")
+                    .as_bool()
+                    .unwrap_or(true);
+                let found = self.at_cursor(params, |document, def| {
+                    let name = &document.analysis.definitions[def].name;
+                    let mut positions = Vec::new();
+                    if declaration {
+                        positions.push(document.analysis.definitions[def].position);
+                    }
+                    positions.extend(document.analysis.references_of(def));
+                    Json::Array(
+                        positions
+                            .into_iter()
+                            .map(|position| location(params, document.range(name, position)))
+                            .collect(),
+                    )
+                });
+                found.map(|found| {
+                    if found.is_null() {
+                        Json::Array(Vec::new())
+                    } else {
+                        found
+                    }
+                })
+            }
+            "textDocument/hover" => self.at_cursor(params, |document, def| {
+                let def = &document.analysis.definitions[def];
+                Json::object(vec![(
+                    "contents",
+                    Json::object(vec![
+                        ("kind", Json::from("markdown")),
+                        (
+                            "value",
+                            Json::from(format!("```dlang\n{}\n```", def.detail)),
+                        ),
+                    ]),
+                )])
+            }),
+            "textDocument/documentSymbol" => {
+                let document = self.opened(params);
+                if document.is_err() {
+                    return Err(document.err().unwrap());
+                }
+                let document = document.unwrap();
+                let mut symbols = Vec::new();
+                for def in document.analysis.definitions.iter() {
+                    let kind = match def.kind {
+                        SymbolKind::Function => 12,
+                        SymbolKind::Variable => 13,
+                        SymbolKind::Module => 2,
+                        SymbolKind::Parameter => continue,
+                    };
+                    let range = document.range(&def.name, def.position);
+                    symbols.push(Json::object(vec![
+                        ("name", Json::from(def.name.clone())),
+                        ("detail", Json::from(def.detail.clone())),
+                        ("kind", Json::from(kind as usize)),
+                        ("range", range.clone()),
+                        ("selectionRange", range),
+                    ]));
+                }
+                Ok(Json::Array(symbols))
+            }
+            "textDocument/completion" => {
+                let document = self.opened(params);
+                if document.is_err() {
+                    return Err(document.err().unwrap());
+                }
+                let document = document.unwrap();
+                let offset = document.offset(params.get("position")).unwrap_or(0);
+
+                let mut items = Vec::new();
+                for def in document.analysis.visible_at(offset) {
+                    let kind = match def.kind {
+                        SymbolKind::Function => 3,
+                        SymbolKind::Variable | SymbolKind::Parameter => 6,
+                        SymbolKind::Module => 9,
+                    };
+                    items.push(completion(&def.name, kind, &def.detail));
+                }
+                for (keyword, _) in KEYWORDS {
+                    items.push(completion(keyword, 14, "keyword"));
+                }
+                Ok(Json::Array(items))
+            }
+            _ => Err((METHOD_NOT_FOUND, format!("{} is not supported", method))),
+        }
+    }
+
+    fn opened(&self, params: &Json) -> Result<&Document, (i64, String)> {
+        let uri = params.get("textDocument").get("uri").as_str();
+        if uri.is_none() {
+            return Err((INVALID_PARAMS, "textDocument.uri not found".to_string()));
+        }
+        match self.documents.get(uri.unwrap()) {
+            Some(document) => Ok(document),
+            None => Err((INVALID_PARAMS, format!("{} is not open", uri.unwrap()))),
+        }
+    }
+
+    /// result of definition under the cursor, null if there is no such name
+    fn at_cursor<F>(&self, params: &Json, result: F) -> Result<Json, (i64, String)>
+    where
+        F: Fn(&Document, usize) -> Json,
+    {
+        let document = self.opened(params);
+        if document.is_err() {
+            return Err(document.err().unwrap());
+        }
+        let document = document.unwrap();
+        let offset = document.offset(params.get("position"));
+        if offset.is_none() {
+            return Err((INVALID_PARAMS, "position not found".to_string()));
+        }
+        match document.analysis.definition_at(offset.unwrap()) {
+            Some(def) => Ok(result(document, def)),
+            None => Ok(Json::Null),
+        }
+    }
+}
+
+fn capabilities() -> Json {
+    Json::object(vec![
+        (
+            "capabilities",
+            Json::object(vec![
+                ("positionEncoding", Json::from("utf-16")),
+                // full text on each change
+                ("textDocumentSync", Json::from(1_usize)),
+                ("definitionProvider", Json::from(true)),
+                ("referencesProvider", Json::from(true)),
+                ("hoverProvider", Json::from(true)),
+                ("documentSymbolProvider", Json::from(true)),
+                ("completionProvider", Json::object(Vec::new())),
+            ]),
+        ),
+        (
+            "serverInfo",
+            Json::object(vec![("name", Json::from("dlang-lsp"))]),
+        ),
+    ])
+}
+
+fn location(params: &Json, range: Json) -> Json {
+    Json::object(vec![
+        ("uri", params.get("textDocument").get("uri").clone()),
+        ("range", range),
+    ])
+}
+
+fn completion(label: &str, kind: usize, detail: &str) -> Json {
+    Json::object(vec![
+        ("label", Json::from(label)),
+        ("kind", Json::from(kind)),
+        ("detail", Json::from(detail)),
+    ])
+}
+
+fn diagnostics(uri: &str, diagnostics: Vec<Json>) -> Json {
+    Json::object(vec![
+        ("jsonrpc", Json::from("2.0")),
+        ("method", Json::from("textDocument/publishDiagnostics")),
+        (
+            "params",
+            Json::object(vec![
+                ("uri", Json::from(uri)),
+                ("diagnostics", Json::Array(diagnostics)),
+            ]),
+        ),
+    ])
+}
+
+fn error_response(id: Json, code: i64, reason: &str) -> Json {
+    Json::object(vec![
+        ("jsonrpc", Json::from("2.0")),
+        ("id", id),
+        (
+            "error",
+            Json::object(vec![
+                ("code", Json::from(code)),
+                ("message", Json::from(reason)),
+            ]),
+        ),
+    ])
+}
@@ -0,0 +1,339 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    ast::{
+        visit::{self, Visitor},
+        BlockStatement, Expression, FunctionLiteral, Identifier, ImportStatement, LetStatement,
+        MemberExpression, NamedArgument, Nodetrait, Program, TryExpression,
+    },
+    lexer::{Lexer, Position},
+    token::Kind,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SymbolKind {
+    Variable,
+    Function,
+    Parameter,
+    Module,
+}
+
+/// name bound by let, named function literal, parameter, catch or import
+#[derive(Debug, Clone, PartialEq)]
+pub struct Definition {
+    pub name: String,
+    pub kind: SymbolKind,
+    // where the name is written
+    pub position: Position,
+    // how the name is declared, e.g. `fn add(a, b = 1)`
+    pub detail: String,
+    // scope which the name is bound to
+    pub scope: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reference {
+    pub name: String,
+    pub position: Position,
+    // None when the name is not defined in the document
+    pub definition: Option<usize>,
+}
+
+/// part of source where names of the scope are visible, by char offset
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Scope {
+    pub start: usize,
+    pub end: usize,
+    pub parent: Option<usize>,
+}
+
+/// names of a document, where they are defined and referred
+#[derive(Debug, Clone, Default)]
+pub struct Analysis {
+    pub definitions: Vec<Definition>,
+    pub references: Vec<Reference>,
+    // first one is the whole document
+    pub scopes: Vec<Scope>,
+}
+
+impl Analysis {
+    /// analyze parsed program of the source.
+    /// names are bound like the resolver does, so nested functions see names defined later
+    pub fn of(program: &Program, source: &str) -> Self {
+        let mut collector = Collector {
+            analysis: Analysis {
+                definitions: Vec::new(),
+                references: Vec::new(),
+                scopes: vec![Scope {
+                    start: 0,
+                    end: usize::MAX,
+                    parent: None,
+                }],
+            },
+            block_ends: block_ends(source),
+            stack: vec![0],
+            functions: Vec::new(),
+            pending: Vec::new(),
+        };
+        collector.visit_program(program);
+        collector.resolve();
+        collector.analysis
+    }
+
+    /// definition of the name at offset, which is the definition itself or a reference of it
+    pub fn definition_at(&self, offset: usize) -> Option<usize> {
+        for (idx, def) in self.definitions.iter().enumerate() {
+            if covers(&def.name, def.position, offset) {
+                return Some(idx);
+            }
+        }
+        for reference in self.references.iter() {
+            if covers(&reference.name, reference.position, offset) {
+                return reference.definition;
+            }
+        }
+        None
+    }
+
+    /// positions of the names referring the definition
+    pub fn references_of(&self, definition: usize) -> Vec<Position> {
+        self.references
+            .iter()
+            .filter(|reference| reference.definition == Some(definition))
+            .map(|reference| reference.position)
+            .collect()
+    }
+
+    /// definitions visible at offset, innermost first.
+    /// names shadowed by inner or later definitions are left out
+    pub fn visible_at(&self, offset: usize) -> Vec<&Definition> {
+        let mut innermost = 0;
+        for (idx, scope) in self.scopes.iter().enumerate() {
+            if scope.start <= offset && offset <= scope.end {
+                innermost = idx;
+            }
+        }
+
+        let mut names = HashSet::new();
+        let mut visible = Vec::new();
+        let mut scope = Some(innermost);
+        while scope.is_some() {
+            let idx = scope.unwrap();
+            for def in self.definitions.iter().rev() {
+                if def.scope == idx && def.position.pos < offset && names.insert(&def.name) {
+                    visible.push(def);
+                }
+            }
+            scope = self.scopes[idx].parent;
+        }
+        visible
+    }
+}
+
+fn covers(name: &str, position: Position, offset: usize) -> bool {
+    position.pos <= offset && offset <= position.pos + name.len()
+}
+
+/// offset of matching `}` for offset of each `{`
+fn block_ends(source: &str) -> HashMap<usize, usize> {
+    let mut ends = HashMap::new();
+    let mut opens = Vec::new();
+    let mut lexer = Lexer::new(source.to_string());
+    loop {
+        let token = lexer.next();
+        match token.kind {
+            Kind::LBRACE => opens.push(token.position.pos),
+            Kind::RBRACE => {
+                if let Some(open) = opens.pop() {
+                    ends.insert(open, token.position.pos);
+                }
+            }
+            Kind::EOF => return ends,
+            _ => {}
+        }
+    }
+}
+
+/// reference waiting for all definitions, to be bound like the resolver does
+struct Pending {
+    reference: usize,
+    // scopes enclosing the reference, innermost last
+    chain: Vec<usize>,
+    // index of chain where the function holding the reference starts
+    function_start: usize,
+    // definitions made before the reference
+    seen: usize,
+}
+
+struct Collector {
+    analysis: Analysis,
+    block_ends: HashMap<usize, usize>,
+    // scopes being visited, innermost last
+    stack: Vec<usize>,
+    // index of stack where each function being visited starts
+    functions: Vec<usize>,
+    pending: Vec<Pending>,
+}
+
+impl Collector {
+    fn push_scope(&mut self, start: usize, open: usize) {
+        let end = self.block_ends.get(&open).copied().unwrap_or(usize::MAX);
+        self.analysis.scopes.push(Scope {
+            start,
+            end,
+            parent: self.stack.last().copied(),
+        });
+        self.stack.push(self.analysis.scopes.len() - 1);
+    }
+
+    fn pop_scope(&mut self) {
+        self.stack.pop();
+    }
+
+    fn define(&mut self, ident: &Identifier, kind: SymbolKind, detail: String) {
+        self.analysis.definitions.push(Definition {
+            name: ident.value.clone(),
+            kind,
+            position: ident.token.position,
+            detail,
+            scope: *self.stack.last().unwrap(),
+        });
+    }
+
+    fn resolve(&mut self) {
+        for pending in self.pending.iter() {
+            let name = &self.analysis.references[pending.reference].name;
+            let mut found = None;
+            for (depth, scope) in pending.chain.iter().enumerate().rev() {
+                let mut candidates = self
+                    .analysis
+                    .definitions
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, def)| def.scope == *scope && &def.name == name);
+                // latest one bound before the reference
+                found = candidates
+                    .clone()
+                    .rfind(|(idx, _)| *idx < pending.seen)
+                    .map(|(idx, _)| idx);
+                // functions can refer names of enclosing scopes bound later
+                if found.is_none() && depth < pending.function_start {
+                    found = candidates.next().map(|(idx, _)| idx);
+                }
+                if found.is_some() {
+                    break;
+                }
+            }
+            self.analysis.references[pending.reference].definition = found;
+        }
+    }
+}
+
+fn signature(name: &str, func: &FunctionLiteral) -> String {
+    let mut params = Vec::new();
+    for param in func.parameters.iter() {
+        params.push(param.to_str());
+    }
+    if let Some(rest) = &func.rest {
+        params.push(format!("...{}", rest.to_str()));
+    }
+    format!("{}({})", name, params.join(", "))
+}
+
+impl<'ast> Visitor<'ast> for Collector {
+    fn visit_let_statement(&mut self, stm: &'ast LetStatement) {
+        if let Some(value) = &stm.value {
+            self.visit_expression(value);
+        }
+        let name = format!("let {}", stm.identifier.value);
+        match stm.value.as_ref() {
+            Some(Expression::FunctionLiteral(func)) => {
+                let detail = signature(&format!("{} = fn", name), func);
+                self.define(&stm.identifier, SymbolKind::Function, detail)
+            }
+            _ => self.define(&stm.identifier, SymbolKind::Variable, name),
+        }
+    }
+
+    fn visit_import_statement(&mut self, stm: &'ast ImportStatement) {
+        let detail = format!("import {} as {}", stm.path.to_str(), stm.alias.value);
+        self.define(&stm.alias, SymbolKind::Module, detail);
+    }
+
+    fn visit_block_statement(&mut self, stm: &'ast BlockStatement) {
+        let open = stm.token.position.pos;
+        self.push_scope(open, open);
+        visit::walk_block_statement(self, stm);
+        self.pop_scope();
+    }
+
+    fn visit_function_literal(&mut self, func: &'ast FunctionLiteral) {
+        // body may call the function by its name
+        if let Some(ident) = &func.ident {
+            let detail = signature(&format!("fn {}", ident.value), func);
+            self.define(ident, SymbolKind::Function, detail);
+        }
+
+        // scope of parameters
+        self.functions.push(self.stack.len());
+        self.push_scope(func.token.position.pos, func.body.token.position.pos);
+        for param in func.parameters.iter() {
+            // defaults can refer to former parameters
+            if let Some(default) = &param.default {
+                self.visit_expression(default);
+            }
+            self.define(&param.ident, SymbolKind::Parameter, param.to_str());
+        }
+        if let Some(rest) = &func.rest {
+            self.define(rest, SymbolKind::Parameter, format!("...{}", rest.value));
+        }
+        self.visit_block_statement(&func.body);
+        self.pop_scope();
+        self.functions.pop();
+    }
+
+    fn visit_try_expression(&mut self, exp: &'ast TryExpression) {
+        self.visit_block_statement(&exp.block);
+        if let Some(catch) = &exp.catch {
+            let start = match exp.catch_ident.as_ref() {
+                Some(ident) => ident.token.position.pos,
+                None => catch.token.position.pos,
+            };
+            self.push_scope(start, catch.token.position.pos);
+            if let Some(ident) = &exp.catch_ident {
+                // caught error is passed to catch block like an argument
+                let detail = format!("catch ({})", ident.value);
+                self.define(ident, SymbolKind::Parameter, detail);
+            }
+            self.visit_block_statement(catch);
+            self.pop_scope();
+        }
+        if let Some(finally) = &exp.finally {
+            self.visit_block_statement(finally);
+        }
+    }
+
+    fn visit_identifier(&mut self, ident: &'ast Identifier) {
+        self.analysis.references.push(Reference {
+            name: ident.value.clone(),
+            position: ident.token.position,
+            definition: None,
+        });
+        self.pending.push(Pending {
+            reference: self.analysis.references.len() - 1,
+            chain: self.stack.clone(),
+            function_start: self.functions.last().copied().unwrap_or(0),
+            seen: self.analysis.definitions.len(),
+        });
+    }
+
+    // member is a name of the module, not of the document
+    fn visit_member_expression(&mut self, exp: &'ast MemberExpression) {
+        self.visit_expression(&exp.left);
+    }
+
+    // name of named argument is matched with parameters on call
+    fn visit_named_argument(&mut self, arg: &'ast NamedArgument) {
+        self.visit_expression(&arg.value);
+    }
+}
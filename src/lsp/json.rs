@@ -0,0 +1,394 @@
+// json values of language server messages, read and written without external crates
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    // fields in written order
+    Object(Vec<(String, Json)>),
+}
+
+static NULL: Json = Json::Null;
+
+impl Json {
+    /// object of given fields
+    pub fn object(fields: Vec<(&str, Json)>) -> Json {
+        Json::Object(
+            fields
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), value))
+                .collect(),
+        )
+    }
+
+    pub fn string(value: &str) -> Json {
+        Json::String(value.to_string())
+    }
+
+    /// field of object, null if it is missing or self is not an object
+    pub fn get(&self, key: &str) -> &Json {
+        if let Json::Object(fields) = self {
+            for (name, value) in fields {
+                if name == key {
+                    return value;
+                }
+            }
+        }
+        &NULL
+    }
+
+    pub fn is_null(&self) -> bool {
+        matches!(self, Json::Null)
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Json::Number(value) if value.fract() == 0.0 => Some(*value as i64),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Json::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&Vec<Json>> {
+        match self {
+            Json::Array(values) => Some(values),
+            _ => None,
+        }
+    }
+
+    pub fn to_str(&self) -> String {
+        let mut buf = String::new();
+        self.write(&mut buf);
+        buf
+    }
+
+    fn write(&self, buf: &mut String) {
+        match self {
+            Json::Null => buf.push_str("null"),
+            Json::Bool(value) => buf.push_str(if *value { "true" } else { "false" }),
+            Json::Number(value) => {
+                if value.fract() == 0.0 && value.abs() < 1e15 {
+                    buf.push_str(&(*value as i64).to_string());
+                } else {
+                    buf.push_str(&value.to_string());
+                }
+            }
+            Json::String(value) => write_string(value, buf),
+            Json::Array(values) => {
+                buf.push('[');
+                for (idx, value) in values.iter().enumerate() {
+                    if idx > 0 {
+                        buf.push(',');
+                    }
+                    value.write(buf);
+                }
+                buf.push(']');
+            }
+            Json::Object(fields) => {
+                buf.push('{');
+                for (idx, (key, value)) in fields.iter().enumerate() {
+                    if idx > 0 {
+                        buf.push(',');
+                    }
+                    write_string(key, buf);
+                    buf.push(':');
+                    value.write(buf);
+                }
+                buf.push('}');
+            }
+        }
+    }
+}
+
+impl From<&str> for Json {
+    fn from(value: &str) -> Self {
+        Json::string(value)
+    }
+}
+
+impl From<String> for Json {
+    fn from(value: String) -> Self {
+        Json::String(value)
+    }
+}
+
+impl From<bool> for Json {
+    fn from(value: bool) -> Self {
+        Json::Bool(value)
+    }
+}
+
+impl From<usize> for Json {
+    fn from(value: usize) -> Self {
+        Json::Number(value as f64)
+    }
+}
+
+impl From<i64> for Json {
+    fn from(value: i64) -> Self {
+        Json::Number(value as f64)
+    }
+}
+
+fn write_string(value: &str, buf: &mut String) {
+    buf.push('"');
+    for ch in value.chars() {
+        match ch {
+            '"' => buf.push_str("\\\""),
+            '\\' => buf.push_str("\\\\"),
+            '\n' => buf.push_str("\\n"),
+            '\r' => buf.push_str("\\r"),
+            '\t' => buf.push_str("\\t"),
+            ch if (ch as u32) < 0x20 => buf.push_str(&format!("\\u{:04x}", ch as u32)),
+            ch => buf.push(ch),
+        }
+    }
+    buf.push('"');
+}
+
+/// parse json text, error tells what is wrong and where
+pub fn parse(input: &str) -> Result<Json, String> {
+    let mut reader = Reader {
+        chars: input.chars().collect(),
+        pos: 0,
+    };
+    let value = reader.value();
+    if value.is_err() {
+        return Err(value.err().unwrap());
+    }
+    reader.skip_whitespace();
+    if reader.pos != reader.chars.len() {
+        return Err(reader.error("end of input"));
+    }
+    value
+}
+
+struct Reader {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Reader {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn error(&self, expected: &str) -> String {
+        format!("expected {} at {}", expected, self.pos)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(|ch| ch.is_whitespace()) {
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, word: &str) -> Result<(), String> {
+        for ch in word.chars() {
+            if self.peek() != Some(ch) {
+                return Err(self.error(word));
+            }
+            self.pos += 1;
+        }
+        Ok(())
+    }
+
+    fn value(&mut self) -> Result<Json, String> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some('n') => self.expect("null").map(|_| Json::Null),
+            Some('t') => self.expect("true").map(|_| Json::Bool(true)),
+            Some('f') => self.expect("false").map(|_| Json::Bool(false)),
+            Some('"') => self.string().map(Json::String),
+            Some('[') => self.array(),
+            Some('{') => self.object(),
+            Some(ch) if ch == '-' || ch.is_ascii_digit() => self.number(),
+            _ => Err(self.error("value")),
+        }
+    }
+
+    fn number(&mut self) -> Result<Json, String> {
+        let start = self.pos;
+        while self
+            .peek()
+            .is_some_and(|ch| ch.is_ascii_digit() || "+-.eE".contains(ch))
+        {
+            self.pos += 1;
+        }
+        let text: String = self.chars[start..self.pos].iter().collect();
+        match text.parse::<f64>() {
+            Ok(value) => Ok(Json::Number(value)),
+            Err(_) => {
+                self.pos = start;
+                Err(self.error("number"))
+            }
+        }
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        // opening quote
+        self.pos += 1;
+        let mut buf = String::new();
+        loop {
+            let ch = self.peek();
+            if ch.is_none() {
+                return Err(self.error("closing quote"));
+            }
+            self.pos += 1;
+            match ch.unwrap() {
+                '"' => return Ok(buf),
+                '\\' => {
+                    let escaped = self.peek();
+                    self.pos += 1;
+                    match escaped {
+                        Some('"') => buf.push('"'),
+                        Some('\\') => buf.push('\\'),
+                        Some('/') => buf.push('/'),
+                        Some('b') => buf.push('\u{8}'),
+                        Some('f') => buf.push('\u{c}'),
+                        Some('n') => buf.push('\n'),
+                        Some('r') => buf.push('\r'),
+                        Some('t') => buf.push('\t'),
+                        Some('u') => {
+                            let ch = self.unicode();
+                            if ch.is_err() {
+                                return Err(ch.err().unwrap());
+                            }
+                            buf.push(ch.unwrap());
+                        }
+                        _ => {
+                            self.pos -= 1;
+                            return Err(self.error("escape"));
+                        }
+                    }
+                }
+                ch => buf.push(ch),
+            }
+        }
+    }
+
+    /// `\uXXXX` after `\u`, with surrogate pair of two escapes
+    fn unicode(&mut self) -> Result<char, String> {
+        let high = self.hex();
+        if high.is_err() {
+            return Err(high.err().unwrap());
+        }
+        let high = high.unwrap();
+        if !(0xd800..0xdc00).contains(&high) {
+            return char::from_u32(high).ok_or_else(|| self.error("unicode scalar"));
+        }
+
+        if self.expect("\\u").is_err() {
+            return Err(self.error("low surrogate"));
+        }
+        let low = self.hex();
+        if low.is_err() {
+            return Err(low.err().unwrap());
+        }
+        let low = low.unwrap();
+        if !(0xdc00..0xe000).contains(&low) {
+            return Err(self.error("low surrogate"));
+        }
+        let code = 0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00);
+        char::from_u32(code).ok_or_else(|| self.error("unicode scalar"))
+    }
+
+    fn hex(&mut self) -> Result<u32, String> {
+        if self.pos + 4 > self.chars.len() {
+            return Err(self.error("4 hex digits"));
+        }
+        let text: String = self.chars[self.pos..self.pos + 4].iter().collect();
+        match u32::from_str_radix(&text, 16) {
+            Ok(value) => {
+                self.pos += 4;
+                Ok(value)
+            }
+            Err(_) => Err(self.error("4 hex digits")),
+        }
+    }
+
+    fn array(&mut self) -> Result<Json, String> {
+        // [
+        self.pos += 1;
+        let mut values = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some(']') {
+            self.pos += 1;
+            return Ok(Json::Array(values));
+        }
+        loop {
+            let value = self.value();
+            if value.is_err() {
+                return Err(value.err().unwrap());
+            }
+            values.push(value.unwrap());
+
+            self.skip_whitespace();
+            match self.peek() {
+                Some(',') => self.pos += 1,
+                Some(']') => {
+                    self.pos += 1;
+                    return Ok(Json::Array(values));
+                }
+                _ => return Err(self.error(", or ]")),
+            }
+        }
+    }
+
+    fn object(&mut self) -> Result<Json, String> {
+        // {
+        self.pos += 1;
+        let mut fields = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some('}') {
+            self.pos += 1;
+            return Ok(Json::Object(fields));
+        }
+        loop {
+            self.skip_whitespace();
+            if self.peek() != Some('"') {
+                return Err(self.error("key"));
+            }
+            let key = self.string();
+            if key.is_err() {
+                return Err(key.err().unwrap());
+            }
+
+            self.skip_whitespace();
+            if self.expect(":").is_err() {
+                return Err(self.error(":"));
+            }
+            let value = self.value();
+            if value.is_err() {
+                return Err(value.err().unwrap());
+            }
+            fields.push((key.unwrap(), value.unwrap()));
+
+            self.skip_whitespace();
+            match self.peek() {
+                Some(',') => self.pos += 1,
+                Some('}') => {
+                    self.pos += 1;
+                    return Ok(Json::Object(fields));
+                }
+                _ => return Err(self.error(", or }")),
+            }
+        }
+    }
+}
@@ -1,5 +1,172 @@
-use dlang::repl::start;
+use std::{
+    cell::RefCell,
+    env, fs,
+    path::{Path, PathBuf},
+    process,
+    rc::Rc,
+    thread,
+};
+
+use dlang::{
+    eval::{errors::EvalError, read_program, Evaluator},
+    formatter::format,
+    object::{environment::Environment, Object},
+    optimizer::optimize,
+    repl::start,
+    vm::{
+        compiler::compile,
+        format::{deserialize, serialize},
+        Vm,
+    },
+};
+
+const USAGE: &str = "usage:
+  dlang [--vm] <file>
+  dlang compile <file> [-o <output>]
+  dlang fmt [--check] <file>
+  dlang run <compiled file>";
+
+// native stack of the interpreter thread, deep enough for DEFAULT_MAX_DEPTH calls
+const STACK_SIZE: usize = 256 * 1024 * 1024;
+// native stack calls of tree-walking evaluator may take, the rest is left for
+// evaluation between calls
+const STACK_LIMIT: usize = STACK_SIZE / 2;
 
 fn main() {
-    start()
+    let interpreter = thread::Builder::new().stack_size(STACK_SIZE).spawn(run);
+    if interpreter.unwrap().join().is_err() {
+        process::exit(101);
+    }
+}
+
+fn run() {
+    let args: Vec<String> = env::args().collect();
+
+    // without file, start repl
+    if args.len() < 2 {
+        start(STACK_LIMIT);
+        return;
+    }
+
+    let result = match args[1].as_str() {
+        "compile" => {
+            compile_file(&args[2..]);
+            return;
+        }
+        "run" => run_compiled(&args[2..]),
+        "fmt" => {
+            format_file(&args[2..]);
+            return;
+        }
+        // runs file on bytecode vm instead of tree-walking evaluator
+        "--vm" => {
+            if args.len() != 3 {
+                usage();
+            }
+            Vm::new().evaluate_file(Path::new(&args[2]))
+        }
+        file => {
+            let env = Rc::new(RefCell::new(Environment::new()));
+            let mut evaluator = Evaluator::new();
+            evaluator.set_stack_limit(STACK_LIMIT);
+            evaluator.evaluate_file(Path::new(file), &env)
+        }
+    };
+
+    match result {
+        Ok(Some(obj)) => println!("{}", obj),
+        Ok(None) => {}
+        Err(err) => fail(&err.message()),
+    }
+}
+
+fn usage() -> ! {
+    println!("{}", USAGE);
+    process::exit(1);
+}
+
+fn fail(message: &str) -> ! {
+    println!("!!!> ERROR OCCURED <!!!");
+    println!("{}", message);
+    process::exit(1);
+}
+
+/// `compile in.dl -o out.dlc`, output defaults to input with dlc extension
+fn compile_file(args: &[String]) {
+    let (input, output) = match args {
+        [input] => (input, Path::new(input).with_extension("dlc")),
+        [input, flag, output] if flag == "-o" => (input, PathBuf::from(output)),
+        _ => usage(),
+    };
+
+    let path = Path::new(input);
+    let program = read_program(path, input, None);
+    if program.is_err() {
+        fail(&program.err().unwrap().message());
+    }
+    // compiled once and run many times, worth folding constants
+    let bytecode = compile(&optimize(program.unwrap()));
+    if bytecode.is_err() {
+        fail(&bytecode.err().unwrap().message());
+    }
+
+    let written = fs::write(&output, serialize(&bytecode.unwrap()));
+    if written.is_err() {
+        fail(&format!(
+            "can't write {}: {}",
+            output.display(),
+            written.err().unwrap()
+        ));
+    }
+}
+
+/// `run out.dlc`
+fn run_compiled(args: &[String]) -> Result<Option<Object>, EvalError> {
+    let [input] = args else { usage() };
+
+    let bytes = fs::read(input);
+    if bytes.is_err() {
+        fail(&format!("can't read {}: {}", input, bytes.err().unwrap()));
+    }
+    let bytecode = deserialize(&bytes.unwrap());
+    if bytecode.is_err() {
+        fail(&format!("{}: {}", input, bytecode.err().unwrap().message()));
+    }
+
+    Vm::new().run_file(Path::new(input), bytecode.unwrap())
+}
+
+/// `fmt file.dl` rewrites the file formatted,
+/// `fmt --check file.dl` fails if the file is not formatted
+fn format_file(args: &[String]) {
+    let (input, check) = match args {
+        [input] => (input, false),
+        [flag, input] if flag == "--check" => (input, true),
+        _ => usage(),
+    };
+
+    let source = fs::read_to_string(input);
+    if source.is_err() {
+        fail(&format!("can't read {}: {}", input, source.err().unwrap()));
+    }
+    let source = source.unwrap();
+
+    let formatted = format(&source);
+    if formatted.is_err() {
+        fail(&format!("{}: {}", input, formatted.err().unwrap()));
+    }
+    let formatted = formatted.unwrap();
+
+    if formatted == source {
+        return;
+    }
+    if check {
+        println!("{} is not formatted", input);
+        process::exit(1);
+    }
+
+    let written = fs::write(input, formatted);
+    if written.is_err() {
+        fail(&format!("can't write {}: {}", input, written.err().unwrap()));
+    }
 }
@@ -1,29 +1,48 @@
+pub mod bigint;
 pub mod environment;
 
-use std::{cell::RefCell, fmt::Debug, rc::Weak};
+use std::{
+    cell::RefCell,
+    fmt::{self, Debug},
+    rc::Rc,
+};
 
-use crate::ast::{BlockStatement, Identifier, Nodetrait};
+use crate::{
+    ast::{BlockStatement, Identifier, Nodetrait, Parameter},
+    lexer::Position,
+    vm::code::{Bytecode, Cell, Globals},
+};
 
-use self::environment::Environment;
+use self::{
+    bigint::BigInt,
+    environment::Environ,
+};
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Object {
     Return(Return),
     Int(Int),
+    BigInt(BigIntObject),
     Bool(Bool),
     String(StringObject),
     Function(Function),
+    Closure(Closure),
     Array(Array),
+    Error(ErrorObject),
+    Module(Module),
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum ObjectType {
     Return,
     Int,
+    BigInt,
     Bool,
     String,
     Function,
     Array,
+    Error,
+    Module,
 }
 
 pub trait ObjectTrait {
@@ -36,23 +55,73 @@ impl ObjectTrait for Object {
         match self {
             Object::Return(x) => x.get_type(),
             Object::Int(x) => x.get_type(),
+            Object::BigInt(x) => x.get_type(),
             Object::Bool(x) => x.get_type(),
             Object::Function(x) => x.get_type(),
+            Object::Closure(x) => x.get_type(),
             Object::String(x) => x.get_type(),
             Object::Array(x) => x.get_type(),
+            Object::Error(x) => x.get_type(),
+            Object::Module(x) => x.get_type(),
         }
     }
 
     fn to_str(&self) -> String {
-        let inner = match self {
+        match self {
             Object::Return(x) => x.to_str(),
             Object::Int(x) => x.to_str(),
+            Object::BigInt(x) => x.to_str(),
             Object::Bool(x) => x.to_str(),
             Object::String(x) => x.to_str(),
             Object::Function(x) => x.to_str(),
+            Object::Closure(x) => x.to_str(),
             Object::Array(x) => x.to_str(),
-        };
-        inner
+            Object::Error(x) => x.to_str(),
+            Object::Module(x) => x.to_str(),
+        }
+    }
+}
+
+/// value as users read it, e.g. printed result of a program.
+/// strings and big integers are written as they are, and marked inside arrays
+impl fmt::Display for Object {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Object::String(string) => write!(f, "{}", string.value),
+            Object::BigInt(int) => write!(f, "{}", int.value),
+            Object::Error(err) => write!(f, "{}: {}", err.kind, err.message),
+            other => write!(f, "{}", other.inspect()),
+        }
+    }
+}
+
+impl Object {
+    /// value written unambiguously, e.g. result shown by repl.
+    /// strings are quoted with escapes, so that `"1"` differs from `1`,
+    /// and big integers have `n` suffix of their literal
+    pub fn inspect(&self) -> String {
+        match self {
+            Object::Return(rtv) => match rtv.value.as_ref() {
+                Some(value) => value.inspect(),
+                None => String::new(),
+            },
+            Object::Int(int) => int.value.to_string(),
+            Object::BigInt(int) => format!("{}n", int.value),
+            Object::Bool(boolean) => boolean.value.to_string(),
+            Object::String(string) => format!("{:?}", string.value),
+            Object::Function(function) => function.to_str(),
+            Object::Closure(closure) => closure.to_str(),
+            Object::Array(array) => {
+                let elements: Vec<String> = array
+                    .elements
+                    .iter()
+                    .map(|element| element.inspect())
+                    .collect();
+                format!("[{}]", elements.join(", "))
+            }
+            Object::Error(err) => format!("<error {}: {:?}>", err.kind, err.message),
+            Object::Module(module) => format!("<module {}>", module.path),
+        }
     }
 }
 
@@ -67,7 +136,10 @@ impl ObjectTrait for Return {
     }
 
     fn to_str(&self) -> String {
-        format!("{:?}", self)
+        match self.value.as_ref() {
+            Some(value) => format!("Return:{}", value.to_str()),
+            None => "Return".to_string(),
+        }
     }
 }
 
@@ -84,6 +156,31 @@ impl ObjectTrait for Int {
     }
 }
 
+/// integer of arbitrary size, made by `n` literal or overflow of Int.
+/// holds only values out of Int, made by `Object::from`
+#[derive(Debug, Clone, PartialEq)]
+pub struct BigIntObject {
+    pub value: BigInt,
+}
+
+/// Int if the value fits, so that an integer has one representation
+impl From<BigInt> for Object {
+    fn from(value: BigInt) -> Self {
+        match value.to_i64() {
+            Some(value) => Object::Int(Int { value }),
+            None => Object::BigInt(BigIntObject { value }),
+        }
+    }
+}
+impl ObjectTrait for BigIntObject {
+    fn get_type(&self) -> ObjectType {
+        ObjectType::BigInt
+    }
+    fn to_str(&self) -> String {
+        format!("BigInt:{}", self.value)
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Copy)]
 pub struct Bool {
     pub value: bool,
@@ -110,18 +207,34 @@ impl ObjectTrait for StringObject {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct Function {
     pub identifier: Option<String>,
-    pub args: Vec<Identifier>,
-    pub block: BlockStatement,
-    pub env: Weak<RefCell<Environment<String>>>,
+    pub args: Vec<Parameter>,
+    pub rest: Option<Identifier>,
+    pub block: Rc<BlockStatement>,
+    // kept alive by the function, so that it can be called after returned from
+    // the call which made it. a function bound in its environment makes a cycle
+    pub env: Environ<String>,
+}
+
+// environment is left out, it may hold the function itself
+impl Debug for Function {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Function")
+            .field("identifier", &self.identifier)
+            .field("args", &self.args)
+            .field("rest", &self.rest)
+            .field("block", &self.block)
+            .finish_non_exhaustive()
+    }
 }
 
 impl PartialEq for Function {
     fn eq(&self, other: &Self) -> bool {
         self.identifier == other.identifier
             && self.args == other.args
+            && self.rest == other.rest
             && self.block == other.block
     }
 }
@@ -130,19 +243,76 @@ impl ObjectTrait for Function {
     fn get_type(&self) -> ObjectType {
         ObjectType::Function
     }
+    /// source of the function literal, which can be parsed again
     fn to_str(&self) -> String {
-        let mut buf = String::new();
+        let mut buf = String::from("fn");
         if self.identifier.is_some() {
-            buf += &format!("fn {}", &self.identifier.clone().unwrap());
+            buf += &format!(" {}", &self.identifier.clone().unwrap());
         }
         let mut arguments = Vec::new();
-        for arg in self.args.clone() {
+        for arg in self.args.iter() {
             arguments.push(arg.to_str())
         }
+        if let Some(rest) = &self.rest {
+            arguments.push(format!("...{}", rest.to_str()))
+        }
         buf += "(";
         buf += &arguments.join(", ");
-        buf += ") {\n";
-        format!("{:?}", self.block);
+        buf += ") {";
+        buf += &self.block.to_str();
+        buf += "}";
+
+        buf
+    }
+}
+
+/// function compiled to bytecode, created by vm
+#[derive(Clone)]
+pub struct Closure {
+    pub identifier: Option<String>,
+    pub program: Rc<Bytecode>,
+    // index of function table of program
+    pub function: usize,
+    pub captures: Vec<Cell>,
+    pub globals: Rc<RefCell<Globals>>,
+    // shared by copies of the closure, to tell it from closures made by the same literal
+    pub identity: Rc<()>,
+}
+
+// captures and globals are left out, they may hold the closure itself
+impl Debug for Closure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Closure")
+            .field("identifier", &self.identifier)
+            .field("function", &self.function)
+            .finish_non_exhaustive()
+    }
+}
+
+impl PartialEq for Closure {
+    fn eq(&self, other: &Self) -> bool {
+        self.identifier == other.identifier
+            && Rc::ptr_eq(&self.program, &other.program)
+            && self.function == other.function
+    }
+}
+
+impl ObjectTrait for Closure {
+    // same as tree-walking function, so that results of both are alike
+    fn get_type(&self) -> ObjectType {
+        ObjectType::Function
+    }
+    fn to_str(&self) -> String {
+        let function = &self.program.functions[self.function];
+
+        let mut buf = String::from("fn");
+        if self.identifier.is_some() {
+            buf += &format!(" {}", &self.identifier.clone().unwrap());
+        }
+        buf += "(";
+        buf += &function.signature.join(", ");
+        buf += ") {";
+        buf += &function.body;
         buf += "}";
 
         buf
@@ -173,6 +343,50 @@ impl ObjectTrait for Array {
     }
 }
 
+/// error caught by try expression, or raised by throw statement
+#[derive(Debug, Clone, PartialEq)]
+pub struct ErrorObject {
+    pub kind: String,
+    pub message: String,
+    // position of the statement which raised the error
+    pub span: Position,
+    // thrown value, None if error is raised by evaluator
+    pub value: Option<Box<Object>>,
+}
+
+impl ObjectTrait for ErrorObject {
+    fn get_type(&self) -> ObjectType {
+        ObjectType::Error
+    }
+    fn to_str(&self) -> String {
+        format!("Error:{}: {}", self.kind, self.message)
+    }
+}
+
+/// evaluated module, bound by import statement
+#[derive(Debug, Clone)]
+pub struct Module {
+    pub path: String,
+    // global environment of the module, shared between importers
+    pub env: Environ<String>,
+    pub exports: Vec<String>,
+}
+
+impl PartialEq for Module {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.env, &other.env)
+    }
+}
+
+impl ObjectTrait for Module {
+    fn get_type(&self) -> ObjectType {
+        ObjectType::Module
+    }
+    fn to_str(&self) -> String {
+        format!("Module:{}", self.path)
+    }
+}
+
 pub fn is_same_type(left: &Object, right: &Object) -> bool {
     left.get_type() == right.get_type()
 }
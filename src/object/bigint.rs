@@ -0,0 +1,420 @@
+use std::{
+    cmp::Ordering,
+    fmt,
+    ops::{Add, BitAnd, BitOr, Mul, Neg, Sub},
+};
+
+/// limbs a big integer may have, about 79000 decimal digits.
+/// bounds time of an operation, as multiplication is quadratic
+pub const MAX_LIMBS: usize = 8192;
+
+// largest power of ten in a limb, for decimal conversion
+const DECIMAL_BASE: u32 = 1_000_000_000;
+const DECIMAL_DIGITS: usize = 9;
+
+/// integer of arbitrary size, sign and magnitude of 32 bit limbs
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct BigInt {
+    negative: bool,
+    // least significant first, without leading zero limbs. empty for zero
+    limbs: Vec<u32>,
+}
+
+impl BigInt {
+    /// integer of the sign and limbs, least significant first
+    pub fn from_limbs(negative: bool, limbs: Vec<u32>) -> Self {
+        let mut int = BigInt { negative, limbs };
+        int.normalize();
+        int
+    }
+
+    pub fn limbs(&self) -> &[u32] {
+        &self.limbs
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.negative
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.limbs.is_empty()
+    }
+
+    /// parse decimal digits with optional leading `-`, None if not a number
+    pub fn parse(text: &str) -> Option<Self> {
+        let (negative, digits) = match text.strip_prefix('-') {
+            Some(digits) => (true, digits),
+            None => (false, text),
+        };
+        if digits.is_empty() || !digits.bytes().all(|byte| byte.is_ascii_digit()) {
+            return None;
+        }
+
+        let mut limbs = Vec::new();
+        // leading chunk is shorter, so that the rest have full digits
+        let mut start = 0;
+        let mut end = digits.len() % DECIMAL_DIGITS;
+        if end == 0 {
+            end = DECIMAL_DIGITS;
+        }
+        while start < digits.len() {
+            let chunk: u32 = digits[start..end].parse().unwrap();
+            let scale = 10u32.pow((end - start) as u32);
+            mul_small_add(&mut limbs, scale, chunk);
+            start = end;
+            end += DECIMAL_DIGITS;
+        }
+        Some(BigInt::from_limbs(negative, limbs))
+    }
+
+    /// value as i64, None if it doesn't fit
+    pub fn to_i64(&self) -> Option<i64> {
+        if self.limbs.len() > 2 {
+            return None;
+        }
+        let mut magnitude = 0u64;
+        for (idx, limb) in self.limbs.iter().enumerate() {
+            magnitude |= (*limb as u64) << (32 * idx);
+        }
+        if self.negative {
+            0i64.checked_sub_unsigned(magnitude)
+        } else {
+            i64::try_from(magnitude).ok()
+        }
+    }
+
+    /// quotient truncated toward zero and remainder of the dividend's sign,
+    /// same as i64. None if divided by zero
+    pub fn div_rem(&self, other: &BigInt) -> Option<(BigInt, BigInt)> {
+        if other.is_zero() {
+            return None;
+        }
+        let (quotient, remainder) = div_rem_magnitude(&self.limbs, &other.limbs);
+        Some((
+            BigInt::from_limbs(self.negative != other.negative, quotient),
+            BigInt::from_limbs(self.negative, remainder),
+        ))
+    }
+
+    fn normalize(&mut self) {
+        while self.limbs.last() == Some(&0) {
+            self.limbs.pop();
+        }
+        // no negative zero
+        if self.limbs.is_empty() {
+            self.negative = false;
+        }
+    }
+}
+
+impl From<i64> for BigInt {
+    fn from(value: i64) -> Self {
+        let magnitude = value.unsigned_abs();
+        BigInt::from_limbs(value < 0, vec![magnitude as u32, (magnitude >> 32) as u32])
+    }
+}
+
+impl Ord for BigInt {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.negative, other.negative) {
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+            (false, false) => cmp_magnitude(&self.limbs, &other.limbs),
+            (true, true) => cmp_magnitude(&other.limbs, &self.limbs),
+        }
+    }
+}
+
+impl PartialOrd for BigInt {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Neg for &BigInt {
+    type Output = BigInt;
+
+    fn neg(self) -> BigInt {
+        BigInt::from_limbs(!self.negative, self.limbs.clone())
+    }
+}
+
+impl Add for &BigInt {
+    type Output = BigInt;
+
+    fn add(self, other: &BigInt) -> BigInt {
+        if self.negative == other.negative {
+            return BigInt::from_limbs(self.negative, add_magnitude(&self.limbs, &other.limbs));
+        }
+        // signs differ, the larger magnitude decides the sign
+        match cmp_magnitude(&self.limbs, &other.limbs) {
+            Ordering::Less => {
+                BigInt::from_limbs(other.negative, sub_magnitude(&other.limbs, &self.limbs))
+            }
+            _ => BigInt::from_limbs(self.negative, sub_magnitude(&self.limbs, &other.limbs)),
+        }
+    }
+}
+
+impl Sub for &BigInt {
+    type Output = BigInt;
+
+    fn sub(self, other: &BigInt) -> BigInt {
+        self + &-other
+    }
+}
+
+impl Mul for &BigInt {
+    type Output = BigInt;
+
+    fn mul(self, other: &BigInt) -> BigInt {
+        let mut limbs = vec![0u32; self.limbs.len() + other.limbs.len()];
+        for (i, left) in self.limbs.iter().enumerate() {
+            let mut carry = 0u64;
+            for (j, right) in other.limbs.iter().enumerate() {
+                let product = (*left as u64) * (*right as u64) + limbs[i + j] as u64 + carry;
+                limbs[i + j] = product as u32;
+                carry = product >> 32;
+            }
+            limbs[i + other.limbs.len()] = carry as u32;
+        }
+        BigInt::from_limbs(self.negative != other.negative, limbs)
+    }
+}
+
+/// bitwise and of two's complement, same as i64
+impl BitAnd for &BigInt {
+    type Output = BigInt;
+
+    fn bitand(self, other: &BigInt) -> BigInt {
+        bitwise(self, other, |left, right| left & right)
+    }
+}
+
+/// bitwise or of two's complement, same as i64
+impl BitOr for &BigInt {
+    type Output = BigInt;
+
+    fn bitor(self, other: &BigInt) -> BigInt {
+        bitwise(self, other, |left, right| left | right)
+    }
+}
+
+/// decimal digits, with `-` if negative
+impl fmt::Display for BigInt {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_zero() {
+            return write!(f, "0");
+        }
+        // chunks of nine digits, least significant first
+        let mut chunks = Vec::new();
+        let mut limbs = self.limbs.clone();
+        while !limbs.is_empty() {
+            chunks.push(div_small(&mut limbs, DECIMAL_BASE));
+        }
+
+        let mut buf = String::new();
+        if self.negative {
+            buf.push('-');
+        }
+        buf += &chunks.pop().unwrap().to_string();
+        for chunk in chunks.iter().rev() {
+            buf += &format!("{:09}", chunk);
+        }
+        write!(f, "{}", buf)
+    }
+}
+
+// operation on limbs of two's complement, with a limb more than either
+// magnitude for the sign
+fn bitwise(left: &BigInt, right: &BigInt, oper: fn(u32, u32) -> u32) -> BigInt {
+    let len = left.limbs.len().max(right.limbs.len()) + 1;
+    let left = to_twos_complement(left, len);
+    let right = to_twos_complement(right, len);
+    let limbs: Vec<u32> = left.iter().zip(right.iter()).map(|(l, r)| oper(*l, *r)).collect();
+    from_twos_complement(limbs)
+}
+
+fn to_twos_complement(int: &BigInt, len: usize) -> Vec<u32> {
+    let mut limbs = int.limbs.clone();
+    limbs.resize(len, 0);
+    if int.negative {
+        negate_limbs(&mut limbs);
+    }
+    limbs
+}
+
+fn from_twos_complement(mut limbs: Vec<u32>) -> BigInt {
+    // sign bit of the most significant limb
+    let negative = limbs.last().is_some_and(|limb| limb >> 31 == 1);
+    if negative {
+        negate_limbs(&mut limbs);
+    }
+    BigInt::from_limbs(negative, limbs)
+}
+
+// limbs = !limbs + 1, wrapping
+fn negate_limbs(limbs: &mut [u32]) {
+    let mut carry = 1u64;
+    for limb in limbs.iter_mut() {
+        let sum = !*limb as u64 + carry;
+        *limb = sum as u32;
+        carry = sum >> 32;
+    }
+}
+
+fn cmp_magnitude(left: &[u32], right: &[u32]) -> Ordering {
+    left.len()
+        .cmp(&right.len())
+        .then_with(|| left.iter().rev().cmp(right.iter().rev()))
+}
+
+fn add_magnitude(left: &[u32], right: &[u32]) -> Vec<u32> {
+    let (long, short) = if left.len() < right.len() {
+        (right, left)
+    } else {
+        (left, right)
+    };
+    let mut limbs = Vec::with_capacity(long.len() + 1);
+    let mut carry = 0u64;
+    for (idx, limb) in long.iter().enumerate() {
+        let sum = *limb as u64 + *short.get(idx).unwrap_or(&0) as u64 + carry;
+        limbs.push(sum as u32);
+        carry = sum >> 32;
+    }
+    limbs.push(carry as u32);
+    limbs
+}
+
+// left has to be larger than or equal to right
+fn sub_magnitude(left: &[u32], right: &[u32]) -> Vec<u32> {
+    let mut limbs = Vec::with_capacity(left.len());
+    let mut borrow = 0i64;
+    for (idx, limb) in left.iter().enumerate() {
+        let mut diff = *limb as i64 - *right.get(idx).unwrap_or(&0) as i64 - borrow;
+        borrow = 0;
+        if diff < 0 {
+            diff += 1 << 32;
+            borrow = 1;
+        }
+        limbs.push(diff as u32);
+    }
+    limbs
+}
+
+// limbs = limbs * scale + add
+fn mul_small_add(limbs: &mut Vec<u32>, scale: u32, add: u32) {
+    let mut carry = add as u64;
+    for limb in limbs.iter_mut() {
+        let product = (*limb as u64) * (scale as u64) + carry;
+        *limb = product as u32;
+        carry = product >> 32;
+    }
+    if carry > 0 {
+        limbs.push(carry as u32);
+    }
+}
+
+// limbs = limbs / divisor, returns remainder
+fn div_small(limbs: &mut Vec<u32>, divisor: u32) -> u32 {
+    let mut remainder = 0u64;
+    for limb in limbs.iter_mut().rev() {
+        let current = (remainder << 32) | *limb as u64;
+        *limb = (current / divisor as u64) as u32;
+        remainder = current % divisor as u64;
+    }
+    while limbs.last() == Some(&0) {
+        limbs.pop();
+    }
+    remainder as u32
+}
+
+/// long division of magnitudes, algorithm D of Knuth.
+/// divisor has to be non-zero and normalized
+fn div_rem_magnitude(dividend: &[u32], divisor: &[u32]) -> (Vec<u32>, Vec<u32>) {
+    if cmp_magnitude(dividend, divisor) == Ordering::Less {
+        return (Vec::new(), dividend.to_vec());
+    }
+    if divisor.len() == 1 {
+        let mut quotient = dividend.to_vec();
+        let remainder = div_small(&mut quotient, divisor[0]);
+        return (quotient, vec![remainder]);
+    }
+
+    // shift so that the top limb of divisor has its highest bit set,
+    // then estimated quotient digit is off by at most two
+    let shift = divisor.last().unwrap().leading_zeros();
+    let mut divisor = shift_left(divisor, shift);
+    // top limb shifted out of divisor is always zero
+    divisor.pop();
+    let mut rest = shift_left(dividend, shift);
+
+    let n = divisor.len();
+    let m = dividend.len() - n;
+    let top = divisor[n - 1] as u64;
+    let next = divisor[n - 2] as u64;
+    let mut quotient = vec![0u32; m + 1];
+
+    for j in (0..=m).rev() {
+        let numerator = ((rest[j + n] as u64) << 32) | rest[j + n - 1] as u64;
+        let mut estimate = numerator / top;
+        let mut remainder = numerator % top;
+        while estimate >> 32 != 0 || estimate * next > ((remainder << 32) | rest[j + n - 2] as u64)
+        {
+            estimate -= 1;
+            remainder += top;
+            if remainder >> 32 != 0 {
+                break;
+            }
+        }
+
+        // subtract estimate * divisor from the window of rest
+        let mut borrow = 0i64;
+        for i in 0..n {
+            let product = estimate * divisor[i] as u64;
+            let diff = rest[i + j] as i64 - borrow - (product & 0xffff_ffff) as i64;
+            rest[i + j] = diff as u32;
+            borrow = (product >> 32) as i64 - (diff >> 32);
+        }
+        let diff = rest[j + n] as i64 - borrow;
+        rest[j + n] = diff as u32;
+
+        // estimate was one too large, add divisor back
+        if diff < 0 {
+            estimate -= 1;
+            let mut carry = 0u64;
+            for i in 0..n {
+                let sum = rest[i + j] as u64 + divisor[i] as u64 + carry;
+                rest[i + j] = sum as u32;
+                carry = sum >> 32;
+            }
+            rest[j + n] = rest[j + n].wrapping_add(carry as u32);
+        }
+        quotient[j] = estimate as u32;
+    }
+
+    rest.truncate(n);
+    (quotient, shift_right(&rest, shift))
+}
+
+// one limb longer than the given, top limb holds bits shifted out
+fn shift_left(limbs: &[u32], shift: u32) -> Vec<u32> {
+    let mut shifted = Vec::with_capacity(limbs.len() + 1);
+    let mut carry = 0u32;
+    for limb in limbs.iter() {
+        let wide = (*limb as u64) << shift;
+        shifted.push(wide as u32 | carry);
+        carry = (wide >> 32) as u32;
+    }
+    shifted.push(carry);
+    shifted
+}
+
+fn shift_right(limbs: &[u32], shift: u32) -> Vec<u32> {
+    let mut shifted = Vec::with_capacity(limbs.len());
+    for idx in 0..limbs.len() {
+        let high = *limbs.get(idx + 1).unwrap_or(&0) as u64;
+        shifted.push((((high << 32) | limbs[idx] as u64) >> shift) as u32);
+    }
+    shifted
+}
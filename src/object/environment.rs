@@ -7,6 +7,8 @@ pub type Environ<T> = Rc<RefCell<Environment<T>>>;
 #[derive(Debug, Clone, PartialEq)]
 pub struct Environment<T: Hash + Eq + PartialEq> {
     binding: HashMap<T, Object>,
+    // names resolved to a slot and their objects, None until bound
+    slots: Vec<Option<(T, Object)>>,
     outer: Option<Environ<T>>,
     // 0 for global, the bigger is the outter
     level: usize,
@@ -18,9 +20,11 @@ impl<T: Hash + Eq + PartialEq> Environment<T> {
         // first, try get object from current scope
         let rst = self.binding.get(key);
 
-        if rst.is_none() && self.outer.is_some() {
-            // if not found, try get object from outer scope
-            return self.outer.as_ref().unwrap().borrow().get_clone(&key);
+        if rst.is_none() {
+            if let Some(outer) = &self.outer {
+                // if not found, try get object from outer scope
+                return outer.borrow().get_clone(key);
+            }
         }
         rst.cloned()
     }
@@ -30,9 +34,72 @@ impl<T: Hash + Eq + PartialEq> Environment<T> {
         self.binding.insert(key, obj)
     }
 
+    // get object clone from slot of environment `depth` outward
+    pub fn get_slot_clone(&self, depth: usize, slot: usize) -> Option<Object> {
+        if depth > 0 {
+            return match &self.outer {
+                Some(outer) => outer.borrow().get_slot_clone(depth - 1, slot),
+                None => None,
+            };
+        }
+        match self.slots.get(slot) {
+            Some(Some((_, obj))) => Some(obj.clone()),
+            _ => None,
+        }
+    }
+
+    // get object clone by name, starting from environment `depth` outward
+    pub fn get_outer_clone(&self, depth: usize, key: &T) -> Option<Object> {
+        if depth > 0 {
+            if let Some(outer) = &self.outer {
+                return outer.borrow().get_outer_clone(depth - 1, key);
+            }
+        }
+        self.get_clone(key)
+    }
+
+    // set object to slot of environment, name is kept for inspection
+    pub fn set_slot(&mut self, slot: usize, key: T, obj: Object)
+    where
+        T: Clone,
+    {
+        if self.slots.len() <= slot {
+            self.slots.resize(slot + 1, None);
+        }
+        self.slots[slot] = Some((key, obj));
+    }
+
+    // names bound in this environment, without outer
+    pub fn names(&self) -> Vec<T>
+    where
+        T: Clone,
+    {
+        self.binding.keys().cloned().collect()
+    }
+
+    // names and objects bound in this environment, without outer
+    pub fn bindings(&self) -> Vec<(T, Object)>
+    where
+        T: Clone,
+    {
+        let mut bindings: Vec<(T, Object)> = self
+            .binding
+            .iter()
+            .map(|(key, obj)| (key.clone(), obj.clone()))
+            .collect();
+        bindings.extend(self.slots.iter().flatten().cloned());
+        bindings
+    }
+
+    // environment enclosing this one, None for global
+    pub fn outer(&self) -> Option<Environ<T>> {
+        self.outer.clone()
+    }
+
     pub fn new() -> Self {
         Environment {
             binding: HashMap::new(),
+            slots: Vec::new(),
             outer: None,
             level: 0,
         }
@@ -41,8 +108,15 @@ impl<T: Hash + Eq + PartialEq> Environment<T> {
     pub fn new_inner(outer: &Environ<T>) -> Self {
         Environment {
             binding: HashMap::new(),
+            slots: Vec::new(),
             level: outer.borrow().level + 1,
             outer: Some(Rc::clone(outer)),
         }
     }
 }
+
+impl<T: Hash + Eq + PartialEq> Default for Environment<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,127 @@
+use crate::{
+    ast::{
+        fold::{self, Fold},
+        BigIntLiteral, BlockStatement, BooleanLiteral, Expression, IfExpression, IntegerLiteral,
+        Program, StringLiteral,
+    },
+    eval::{infix_object, prefix_object},
+    object::{Bool, Int, Object, StringObject},
+    token::{Kind, Token},
+};
+
+/// fold constant expressions and prune branches decided by constant condition.
+/// expressions which fail on evaluation are left as they are,
+/// so the error is raised on evaluation as before.
+/// scopes are kept, identifiers resolved before stay valid
+pub fn optimize(program: Program) -> Program {
+    ConstantFolder.fold_program(program)
+}
+
+struct ConstantFolder;
+
+impl Fold for ConstantFolder {
+    fn fold_expression(&mut self, exp: Expression) -> Expression {
+        // children first, so nested constants are folded bottom up
+        match fold::fold_expression(self, exp) {
+            Expression::InfixExpression(exp) => {
+                let (Some(left), Some(right)) = (constant(&exp.left), constant(&exp.right)) else {
+                    return Expression::InfixExpression(exp);
+                };
+                match infix_object(left, exp.operator.kind, right) {
+                    Ok(obj) => literal(obj, &exp.token),
+                    Err(_) => Expression::InfixExpression(exp),
+                }
+            }
+            Expression::PrefixExpression(exp) => {
+                let Some(right) = constant(&exp.right) else {
+                    return Expression::PrefixExpression(exp);
+                };
+                match prefix_object(exp.token.kind, right) {
+                    Ok(obj) => literal(obj, &exp.token),
+                    Err(_) => Expression::PrefixExpression(exp),
+                }
+            }
+            Expression::IfExpression(exp) => {
+                let Expression::BooleanLiteral(condition) = exp.condition.as_ref() else {
+                    return Expression::IfExpression(exp);
+                };
+                let taken = if condition.value {
+                    exp.consequence
+                } else if exp.alternative.is_some() {
+                    exp.alternative.unwrap()
+                } else {
+                    BlockStatement {
+                        token: exp.consequence.token,
+                        statements: Vec::new(),
+                    }
+                };
+                // block is kept to keep its scope
+                Expression::IfExpression(IfExpression {
+                    token: exp.token.clone(),
+                    condition: Box::new(Expression::BooleanLiteral(BooleanLiteral {
+                        token: Token {
+                            kind: Kind::True,
+                            literal: "true".to_string(),
+                            position: condition.token.position,
+                        },
+                        value: true,
+                    })),
+                    consequence: taken,
+                    alternative: None,
+                })
+            }
+            exp => exp,
+        }
+    }
+}
+
+fn constant(exp: &Expression) -> Option<Object> {
+    match exp {
+        Expression::IntegerLiteral(lit) => Some(Object::Int(Int { value: lit.value })),
+        Expression::BigIntLiteral(lit) => Some(Object::from(lit.value.clone())),
+        Expression::BooleanLiteral(lit) => Some(Object::Bool(Bool { value: lit.value })),
+        Expression::StringLiteral(lit) => Some(Object::String(StringObject {
+            value: lit.value.clone(),
+        })),
+        _ => None,
+    }
+}
+
+/// literal of folded object, at the position of the folded expression
+fn literal(obj: Object, at: &Token) -> Expression {
+    match obj {
+        Object::Int(int) => Expression::IntegerLiteral(IntegerLiteral {
+            token: Token {
+                kind: Kind::Int,
+                literal: int.value.to_string(),
+                position: at.position,
+            },
+            value: int.value,
+        }),
+        Object::BigInt(int) => Expression::BigIntLiteral(BigIntLiteral {
+            token: Token {
+                kind: Kind::BigInt,
+                literal: int.value.to_string(),
+                position: at.position,
+            },
+            value: int.value,
+        }),
+        Object::Bool(bool) => Expression::BooleanLiteral(BooleanLiteral {
+            token: Token {
+                kind: if bool.value { Kind::True } else { Kind::False },
+                literal: bool.value.to_string(),
+                position: at.position,
+            },
+            value: bool.value,
+        }),
+        Object::String(string) => Expression::StringLiteral(StringLiteral {
+            token: Token {
+                kind: Kind::String,
+                literal: string.value.clone(),
+                position: at.position,
+            },
+            value: string.value,
+        }),
+        obj => unreachable!("{:?} is not a constant", obj),
+    }
+}
@@ -1,21 +1,23 @@
 use crate::{
     ast::{
-        ArrayLiteral, BlockStatement, BooleanLiteral, CallExpression, Expression,
-        ExpressionStatement, FunctionLiteral, Identifier, IfExpression,
-        IndexExpression, InfixExpression, IntegerLiteral, LetStatement,
-        PrefixExpression, Program, ReturnStatement, Statement, StringLiteral,
+        ArrayLiteral, BigIntLiteral, BlockStatement, BooleanLiteral, CallExpression,
+        ExportStatement, Expression, ExpressionStatement, FunctionLiteral, Identifier,
+        IfExpression, ImportStatement, IndexExpression, InfixExpression, IntegerLiteral,
+        LetStatement, MemberExpression, NamedArgument, Parameter, PrefixExpression, Program,
+        ReturnStatement, Statement, StringLiteral, ThrowStatement, TryExpression,
     },
     lexer::Lexer,
+    object::bigint::{BigInt, MAX_LIMBS},
     parser::errors::InfixFunctionError,
     token::{Kind, Token},
 };
 
-use self::errors::{ParserError, PrefixFunctionError};
+use self::errors::{ParserError, ParserErrors, PrefixFunctionError};
 
 pub mod errors;
 
-#[derive(PartialEq, PartialOrd)]
-enum Precedence {
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub enum Precedence {
     Lowest,
     Or,
     And,
@@ -27,9 +29,10 @@ enum Precedence {
     Product, // *
     Prefix,  // -(val) or !(val)
     Call,    // calling function like func(val)
+    Index,   // indexing like arr[idx] or module.member
 }
 
-fn find_precedences(kind: Kind) -> Precedence {
+pub fn find_precedences(kind: Kind) -> Precedence {
     match kind {
         Kind::Or => Precedence::Or,
         Kind::And => Precedence::And,
@@ -40,30 +43,33 @@ fn find_precedences(kind: Kind) -> Precedence {
         Kind::Plus | Kind::Minus => Precedence::Sum,
         Kind::Product | Kind::Divide | Kind::Mod => Precedence::Product,
         Kind::LPAREN => Precedence::Call,
-        __ => Precedence::Lowest,
+        Kind::LBRACKET | Kind::Dot => Precedence::Index,
+        _ => Precedence::Lowest,
     }
 }
 
 fn is_infix(kind: &Kind) -> bool {
-    match kind {
+    matches!(
+        kind,
         Kind::Plus
-        | Kind::Minus
-        | Kind::Divide
-        | Kind::Product
-        | Kind::Mod
-        | Kind::EQ
-        | Kind::NOT_EQ
-        | Kind::LT
-        | Kind::LT_OR_EQ
-        | Kind::GT
-        | Kind::GT_OR_EQ
-        | Kind::Bit_Or
-        | Kind::Bit_And
-        | Kind::Or
-        | Kind::And
-        | Kind::LPAREN => true,
-        __ => false,
-    }
+            | Kind::Minus
+            | Kind::Divide
+            | Kind::Product
+            | Kind::Mod
+            | Kind::EQ
+            | Kind::NOT_EQ
+            | Kind::LT
+            | Kind::LT_OR_EQ
+            | Kind::GT
+            | Kind::GT_OR_EQ
+            | Kind::Bit_Or
+            | Kind::Bit_And
+            | Kind::Or
+            | Kind::And
+            | Kind::LPAREN
+            | Kind::LBRACKET
+            | Kind::Dot
+    )
 }
 
 pub struct Parser {
@@ -144,13 +150,14 @@ impl Parser {
                 if res.is_ok() {
                     Ok(Statement::LetStatement(res.ok().unwrap()))
                 } else {
-                    let mut err_vec: Vec<Box<dyn ParserError>> = Vec::new();
-                    // deeper first.
-                    err_vec.push(Box::new(res.err().unwrap()));
-                    err_vec.push(Box::new(errors::ParseError {
-                        detail: "faild to parse let statement".to_string(),
-                        position: self.lexer.get_pos(),
-                    }));
+                    let err_vec: Vec<Box<dyn ParserError>> = vec![
+                        // deeper first.
+                        Box::new(res.err().unwrap()),
+                        Box::new(errors::ParseError {
+                            detail: "faild to parse let statement".to_string(),
+                            position: self.lexer.get_pos(),
+                        }),
+                    ];
 
                     Err(err_vec)
                 }
@@ -163,7 +170,39 @@ impl Parser {
                     Err(res.err().unwrap())
                 }
             }
-            __ => {
+            Kind::Throw => {
+                let res = self.parse_throw_statement();
+                if res.is_ok() {
+                    Ok(Statement::ThrowStatement(res.ok().unwrap()))
+                } else {
+                    Err(res.err().unwrap())
+                }
+            }
+            Kind::Import => {
+                let res = self.parse_import_statement();
+                if res.is_ok() {
+                    Ok(Statement::ImportStatement(res.ok().unwrap()))
+                } else {
+                    let err_vec: Vec<Box<dyn ParserError>> = vec![
+                        Box::new(res.err().unwrap()),
+                        Box::new(errors::ParseError {
+                            detail: "faild to parse import statement".to_string(),
+                            position: self.lexer.get_pos(),
+                        }),
+                    ];
+
+                    Err(err_vec)
+                }
+            }
+            Kind::Export => {
+                let res = self.parse_export_statement();
+                if res.is_ok() {
+                    Ok(Statement::ExportStatement(res.ok().unwrap()))
+                } else {
+                    Err(res.err().unwrap())
+                }
+            }
+            _ => {
                 let res = self.parse_expression_statement();
                 if res.is_ok() {
                     Ok(Statement::ExpressionStatement(res.ok().unwrap()))
@@ -191,6 +230,7 @@ impl Parser {
             identifier: Identifier {
                 token: self.cur_token.clone(),
                 value: self.cur_token.literal.clone(),
+                binding: None,
             },
             value: None,
         };
@@ -223,7 +263,7 @@ impl Parser {
             self.next();
             let res = self.parse_expression(Precedence::Lowest);
             if res.is_err() {
-                let mut errs = res.unwrap_err();
+                let mut errs = res.err().unwrap();
                 errs.push(Box::new(errors::ParseError {
                     detail: "faild on parsing value (on return statement)"
                         .to_string(),
@@ -244,6 +284,105 @@ impl Parser {
         Ok(stm)
     }
 
+    fn parse_throw_statement(
+        &mut self,
+    ) -> Result<ThrowStatement, Vec<Box<dyn ParserError>>> {
+        let token = self.cur_token.clone(); // cur token is throw
+
+        self.next();
+        let res = self.parse_expression(Precedence::Lowest);
+        if res.is_err() {
+            let mut errs = res.err().unwrap();
+            errs.push(Box::new(errors::ParseError {
+                detail: "faild on parsing value (on throw statement)".to_string(),
+                position: self.lexer.get_pos(),
+            }));
+            return Err(errs);
+        }
+        if self.peek_next_is(&Kind::Semicolon) {
+            // consume Semicolon
+            self.next();
+        }
+
+        Ok(ThrowStatement {
+            token,
+            value: res.unwrap(),
+        })
+    }
+
+    fn parse_import_statement(
+        &mut self,
+    ) -> Result<ImportStatement, errors::ParseError> {
+        let token = self.cur_token.clone(); // cur token is import
+
+        if !self.expect_next_is(&Kind::String) {
+            return Err(errors::ParseError {
+                detail: "next_token is not a Kind::String (path of module)"
+                    .to_string(),
+                position: self.lexer.get_pos(),
+            });
+        }
+        let path = self.parse_string_literal();
+
+        if !self.expect_next_is(&Kind::As) {
+            return Err(errors::ParseError {
+                detail: "next_token is not a Kind::As".to_string(),
+                position: self.lexer.get_pos(),
+            });
+        }
+        if !self.expect_next_is(&Kind::Ident) {
+            return Err(errors::ParseError {
+                detail: "next_token is not a Kind::Ident (alias of module)"
+                    .to_string(),
+                position: self.lexer.get_pos(),
+            });
+        }
+        let alias = self.parse_identifier();
+
+        if !self.expect_next_is(&Kind::Semicolon) {
+            return Err(errors::ParseError {
+                detail: "next_token is not a Kind::Semicolon".to_string(),
+                position: self.lexer.get_pos(),
+            });
+        }
+
+        Ok(ImportStatement { token, path, alias })
+    }
+
+    fn parse_export_statement(
+        &mut self,
+    ) -> Result<ExportStatement, Vec<Box<dyn ParserError>>> {
+        let token = self.cur_token.clone(); // cur token is export
+
+        self.next();
+        let res = self.parse_statement();
+        if res.is_err() {
+            let mut errs = res.err().unwrap();
+            errs.push(Box::new(errors::ParseError {
+                detail: "faild on parsing exported statement".to_string(),
+                position: self.lexer.get_pos(),
+            }));
+            return Err(errs);
+        }
+
+        let stm = ExportStatement {
+            token,
+            statement: Box::new(res.unwrap()),
+        };
+        if stm.name().is_none() {
+            let errs: Vec<Box<dyn ParserError>> = vec![
+                Box::new(errors::ParseError {
+                    detail: "only let statement or named function can be exported"
+                        .to_string(),
+                    position: self.lexer.get_pos(),
+                }),
+            ];
+            return Err(errs);
+        }
+
+        Ok(stm)
+    }
+
     fn parse_expression_statement(
         &mut self,
     ) -> Result<ExpressionStatement, Vec<Box<dyn ParserError>>> {
@@ -262,6 +401,7 @@ impl Parser {
         Identifier {
             token: self.cur_token.clone(),
             value: self.cur_token.literal.clone(),
+            binding: None,
         }
     }
 
@@ -286,6 +426,28 @@ impl Parser {
         })
     }
 
+    fn parse_big_int_literal(&mut self) -> Result<BigIntLiteral, errors::PrefixFunctionError> {
+        let value = BigInt::parse(&self.cur_token.literal);
+        let too_large = value
+            .as_ref()
+            .is_some_and(|value| value.limbs().len() > MAX_LIMBS);
+        if value.is_none() || too_large {
+            return Err(errors::PrefixFunctionError {
+                detail: format!(
+                    "failed to parse BigIntLiteral {}, it has to be at most {} bits",
+                    self.cur_token.literal,
+                    MAX_LIMBS * 32
+                ),
+                position: self.lexer.get_pos(),
+                kind: errors::PrefixFunctionErrorKind::IntegerParseError,
+            });
+        }
+        Ok(BigIntLiteral {
+            token: self.cur_token.clone(),
+            value: value.unwrap(),
+        })
+    }
+
     fn parse_bool_literal(&mut self) -> BooleanLiteral {
         let value = self.cur_token.literal.parse().unwrap();
 
@@ -310,11 +472,12 @@ impl Parser {
         let token = self.cur_token.clone();
 
         if token.kind != Kind::LBRACE {
-            let mut errs: Vec<Box<dyn ParserError>> = Vec::new();
-            errs.push(Box::new(errors::ParseError {
-                detail: "LBRACE not found (on block Statement)".to_string(),
-                position: self.lexer.get_pos(),
-            }));
+            let errs: Vec<Box<dyn ParserError>> = vec![
+                Box::new(errors::ParseError {
+                    detail: "LBRACE not found (on block Statement)".to_string(),
+                    position: self.lexer.get_pos(),
+                }),
+            ];
             return Err(errs);
         }
         self.next(); // consume LBRACE
@@ -339,12 +502,13 @@ impl Parser {
         }
 
         if self.cur_token.kind != Kind::RBRACE {
-            let mut errs: Vec<Box<dyn ParserError>> = Vec::new();
-            errs.push(Box::new(errors::ParseError {
-                detail: "matching RBRACE not found (on block Statement)"
-                    .to_string(),
-                position: self.lexer.get_pos(),
-            }));
+            let errs: Vec<Box<dyn ParserError>> = vec![
+                Box::new(errors::ParseError {
+                    detail: "matching RBRACE not found (on block Statement)"
+                        .to_string(),
+                    position: self.lexer.get_pos(),
+                }),
+            ];
             return Err(errs);
         }
 
@@ -378,11 +542,12 @@ impl Parser {
             return Err(errs);
         }
         if !self.peek_next_is(&Kind::RPAREN) {
-            let mut errs: Vec<Box<dyn ParserError>> = Vec::new();
-            errs.push(Box::new(errors::ParseError {
-                detail: "RPAREN not found".to_string(),
-                position: self.lexer.get_pos(),
-            }));
+            let errs: Vec<Box<dyn ParserError>> = vec![
+                Box::new(errors::ParseError {
+                    detail: "RPAREN not found".to_string(),
+                    position: self.lexer.get_pos(),
+                }),
+            ];
             return Err(errs);
         }
         self.next(); // consume RPAREN
@@ -394,11 +559,12 @@ impl Parser {
     ) -> Result<IfExpression, Vec<Box<dyn ParserError>>> {
         let if_token = self.cur_token.clone();
         if !self.expect_next_is(&Kind::LPAREN) {
-            let mut errs: Vec<Box<dyn ParserError>> = Vec::new();
-            errs.push(Box::new(errors::ParseError {
-                detail: "LPAREN not found (next of IF token)".to_string(),
-                position: self.lexer.get_pos(),
-            }));
+            let errs: Vec<Box<dyn ParserError>> = vec![
+                Box::new(errors::ParseError {
+                    detail: "LPAREN not found (next of IF token)".to_string(),
+                    position: self.lexer.get_pos(),
+                }),
+            ];
             return Err(errs);
         } // LPAREN had consumed
 
@@ -418,21 +584,23 @@ impl Parser {
         let condition = condition.ok().unwrap();
 
         if !self.expect_next_is(&Kind::RPAREN) {
-            let mut errs: Vec<Box<dyn ParserError>> = Vec::new();
-            errs.push(Box::new(errors::ParseError {
-                detail: "Cannot found RPAREN in IfExpression".to_string(),
-                position: self.lexer.get_pos(),
-            }));
+            let errs: Vec<Box<dyn ParserError>> = vec![
+                Box::new(errors::ParseError {
+                    detail: "Cannot found RPAREN in IfExpression".to_string(),
+                    position: self.lexer.get_pos(),
+                }),
+            ];
             return Err(errs);
         } // RPAREN had consumed
 
         if !self.expect_next_is(&Kind::LBRACE) {
-            let mut errs: Vec<Box<dyn ParserError>> = Vec::new();
-            errs.push(Box::new(errors::ParseError {
-                detail: "Cannot found LBRACE on next side of condition (on IfExpression)"
-                    .to_string(),
-                position: self.lexer.get_pos(),
-            }));
+            let errs: Vec<Box<dyn ParserError>> = vec![
+                Box::new(errors::ParseError {
+                    detail: "Cannot found LBRACE on next side of condition (on IfExpression)"
+                        .to_string(),
+                    position: self.lexer.get_pos(),
+                }),
+            ];
             return Err(errs);
         } // LBRACE had consumed (cur_token == LBRACE)
 
@@ -453,12 +621,13 @@ impl Parser {
 
         if self.expect_next_is(&Kind::Else) {
             if !self.expect_next_is(&Kind::LBRACE) {
-                let mut errs: Vec<Box<dyn ParserError>> = Vec::new();
-                errs.push(Box::new(errors::ParseError {
-                    detail: "Cannot found LBRACE on right side of else (on IfExpression)"
-                        .to_string(),
-                    position: self.lexer.get_pos(),
-                }));
+                let errs: Vec<Box<dyn ParserError>> = vec![
+                    Box::new(errors::ParseError {
+                        detail: "Cannot found LBRACE on right side of else (on IfExpression)"
+                            .to_string(),
+                        position: self.lexer.get_pos(),
+                    }),
+                ];
                 return Err(errs);
             } // LBRACE had consumed (cur_token == LBRACE)
 
@@ -484,6 +653,132 @@ impl Parser {
         })
     }
 
+    fn parse_try_expression(
+        &mut self,
+    ) -> Result<TryExpression, Vec<Box<dyn ParserError>>> {
+        let try_token = self.cur_token.clone();
+
+        if !self.expect_next_is(&Kind::LBRACE) {
+            let errs: Vec<Box<dyn ParserError>> = vec![
+                Box::new(errors::ParseError {
+                    detail: "Cannot found LBRACE on next side of try (on TryExpression)"
+                        .to_string(),
+                    position: self.lexer.get_pos(),
+                }),
+            ];
+            return Err(errs);
+        } // LBRACE had consumed (cur_token == LBRACE)
+
+        let block = self.parse_block_statement();
+        if block.is_err() {
+            let mut errs: Vec<Box<dyn ParserError>> = block.err().unwrap();
+            errs.push(Box::new(errors::ParseError {
+                detail: "Faild to parse try block (on TryExpression)".to_string(),
+                position: self.lexer.get_pos(),
+            }));
+            return Err(errs);
+        }
+        let block = block.ok().unwrap();
+
+        let mut catch_ident = None;
+        let mut catch = None;
+        let mut finally = None;
+
+        if self.expect_next_is(&Kind::Catch) {
+            if self.expect_next_is(&Kind::LPAREN) {
+                if !self.expect_next_is(&Kind::Ident) {
+                    let errs: Vec<Box<dyn ParserError>> = vec![
+                        Box::new(errors::ParseError {
+                            detail: "Ident not found in catch (on TryExpression)"
+                                .to_string(),
+                            position: self.lexer.get_pos(),
+                        }),
+                    ];
+                    return Err(errs);
+                }
+                catch_ident = Some(self.parse_identifier());
+
+                if !self.expect_next_is(&Kind::RPAREN) {
+                    let errs: Vec<Box<dyn ParserError>> = vec![
+                        Box::new(errors::ParseError {
+                            detail: "Cannot found RPAREN in catch (on TryExpression)"
+                                .to_string(),
+                            position: self.lexer.get_pos(),
+                        }),
+                    ];
+                    return Err(errs);
+                } // RPAREN had consumed
+            }
+
+            if !self.expect_next_is(&Kind::LBRACE) {
+                let errs: Vec<Box<dyn ParserError>> = vec![
+                    Box::new(errors::ParseError {
+                        detail: "Cannot found LBRACE of catch block (on TryExpression)"
+                            .to_string(),
+                        position: self.lexer.get_pos(),
+                    }),
+                ];
+                return Err(errs);
+            } // LBRACE had consumed (cur_token == LBRACE)
+
+            let res = self.parse_block_statement();
+            if res.is_err() {
+                let mut errs: Vec<Box<dyn ParserError>> = res.err().unwrap();
+                errs.push(Box::new(errors::ParseError {
+                    detail: "Faild to parse catch block (on TryExpression)"
+                        .to_string(),
+                    position: self.lexer.get_pos(),
+                }));
+                return Err(errs);
+            }
+            catch = Some(res.ok().unwrap());
+        }
+
+        if self.expect_next_is(&Kind::Finally) {
+            if !self.expect_next_is(&Kind::LBRACE) {
+                let errs: Vec<Box<dyn ParserError>> = vec![
+                    Box::new(errors::ParseError {
+                        detail: "Cannot found LBRACE of finally block (on TryExpression)"
+                            .to_string(),
+                        position: self.lexer.get_pos(),
+                    }),
+                ];
+                return Err(errs);
+            } // LBRACE had consumed (cur_token == LBRACE)
+
+            let res = self.parse_block_statement();
+            if res.is_err() {
+                let mut errs: Vec<Box<dyn ParserError>> = res.err().unwrap();
+                errs.push(Box::new(errors::ParseError {
+                    detail: "Faild to parse finally block (on TryExpression)"
+                        .to_string(),
+                    position: self.lexer.get_pos(),
+                }));
+                return Err(errs);
+            }
+            finally = Some(res.ok().unwrap());
+        }
+
+        if catch.is_none() && finally.is_none() {
+            let errs: Vec<Box<dyn ParserError>> = vec![
+                Box::new(errors::ParseError {
+                    detail: "try without catch or finally (on TryExpression)"
+                        .to_string(),
+                    position: self.lexer.get_pos(),
+                }),
+            ];
+            return Err(errs);
+        }
+
+        Ok(TryExpression {
+            token: try_token,
+            block,
+            catch_ident,
+            catch,
+            finally,
+        })
+    }
+
     fn parse_function_literal(
         &mut self,
     ) -> Result<FunctionLiteral, Vec<Box<dyn ParserError>>> {
@@ -494,22 +789,23 @@ impl Parser {
             ident = Some(Identifier {
                 token: self.cur_token.clone(),
                 value: self.cur_token.literal.clone(),
+                binding: None,
             })
         }
 
         if !self.expect_next_is(&Kind::LPAREN) {
-            let mut errs: Vec<Box<dyn ParserError>> = Vec::new();
-            errs.push(Box::new(errors::ParseError {
-                detail: "LPAREN not found (on FunctionLiteral)".to_string(),
-                position: self.lexer.get_pos(),
-            }));
+            let errs: Vec<Box<dyn ParserError>> = vec![
+                Box::new(errors::ParseError {
+                    detail: "LPAREN not found (on FunctionLiteral)".to_string(),
+                    position: self.lexer.get_pos(),
+                }),
+            ];
             return Err(errs);
         }
 
         let params = self.parse_function_parameters();
         if params.is_err() {
-            let mut errs: Vec<Box<dyn ParserError>> = Vec::new();
-            errs.push(Box::new(params.err().unwrap()));
+            let mut errs: Vec<Box<dyn ParserError>> = params.err().unwrap();
             errs.push(Box::new(errors::ParseError {
                 detail: "Error while parsing parmams (on Function literal)"
                     .to_string(),
@@ -517,14 +813,15 @@ impl Parser {
             }));
             return Err(errs);
         }
-        let parameters = params.unwrap();
+        let (parameters, rest) = params.unwrap();
 
         if !self.expect_next_is(&Kind::LBRACE) {
-            let mut errs: Vec<Box<dyn ParserError>> = Vec::new();
-            errs.push(Box::new(errors::ParseError {
-                detail: "LBRACE not found (on Function literal)".to_string(),
-                position: self.lexer.get_pos(),
-            }));
+            let errs: Vec<Box<dyn ParserError>> = vec![
+                Box::new(errors::ParseError {
+                    detail: "LBRACE not found (on Function literal)".to_string(),
+                    position: self.lexer.get_pos(),
+                }),
+            ];
             return Err(errs);
         } // self.cur_token == Kind::LBRACE
 
@@ -545,57 +842,99 @@ impl Parser {
             token,
             ident,
             parameters,
+            rest,
             body,
         })
     }
 
     fn parse_function_parameters(
         &mut self,
-    ) -> Result<Vec<Identifier>, errors::ParseError> {
-        let mut identifiers = Vec::new();
+    ) -> Result<(Vec<Parameter>, Option<Identifier>), ParserErrors> {
+        let mut parameters: Vec<Parameter> = Vec::new();
+        let mut rest = None;
 
         if self.peek_next_is(&Kind::RPAREN) {
             self.next(); // consume RPAREN
-            return Ok(identifiers);
+            return Ok((parameters, rest));
         }
 
-        if !self.expect_next_is(&Kind::Ident) {
-            return Err(errors::ParseError {
-                detail: "Ident token not found (on parsing function params)"
-                    .to_string(),
-                position: self.lexer.get_pos(),
-            });
-        } // somethings wrong.
-
-        identifiers.push(Identifier {
-            token: self.cur_token.clone(),
-            value: self.cur_token.literal.clone(),
-        });
+        loop {
+            if self.expect_next_is(&Kind::Ellipsis) {
+                // rest parameter, have to be the last one
+                if !self.expect_next_is(&Kind::Ident) {
+                    let errs: Vec<Box<dyn ParserError>> = vec![
+                        Box::new(errors::ParseError {
+                            detail: "Ident token not found after ... (on parsing function params)"
+                                .to_string(),
+                            position: self.lexer.get_pos(),
+                        }),
+                    ];
+                    return Err(errs);
+                }
+                rest = Some(self.parse_identifier());
+                break;
+            }
 
-        while self.peek_next_is(&Kind::Comma) {
-            self.next(); // consume comma
             if !self.expect_next_is(&Kind::Ident) {
-                return Err(errors::ParseError {
-                    detail: "Ident token not found (on parsing function params)"
-                        .to_string(),
-                    position: self.lexer.get_pos(),
-                });
+                let errs: Vec<Box<dyn ParserError>> = vec![
+                    Box::new(errors::ParseError {
+                        detail: "Ident token not found (on parsing function params)"
+                            .to_string(),
+                        position: self.lexer.get_pos(),
+                    }),
+                ];
+                return Err(errs);
             } // got next id
 
-            identifiers.push(Identifier {
-                token: self.cur_token.clone(),
-                value: self.cur_token.literal.clone(),
-            });
+            let ident = self.parse_identifier();
+            let mut default = None;
+
+            if self.expect_next_is(&Kind::Assign) {
+                self.next(); // cur_token will be rightside of =
+                let res = self.parse_expression(Precedence::Lowest);
+                if res.is_err() {
+                    let mut errs = res.err().unwrap();
+                    errs.push(Box::new(errors::ParseError {
+                        detail: "faild to parse default value (on parsing function params)"
+                            .to_string(),
+                        position: self.lexer.get_pos(),
+                    }));
+                    return Err(errs);
+                }
+                default = res.ok();
+            } else if parameters.iter().any(|param| param.default.is_some()) {
+                let errs: Vec<Box<dyn ParserError>> = vec![
+                    Box::new(errors::ParseError {
+                        detail: format!(
+                            "parameter {} without default follows parameter with default (on parsing function params)",
+                            ident.value
+                        ),
+                        position: self.lexer.get_pos(),
+                    }),
+                ];
+                return Err(errs);
+            }
+
+            parameters.push(Parameter { ident, default });
+
+            if !self.peek_next_is(&Kind::Comma) {
+                break;
+            }
+            self.next(); // consume comma
         }
+
         if !self.expect_next_is(&Kind::RPAREN) {
-            return Err(errors::ParseError {
-                detail: "matching RPAREN not found (on parsing function params)"
-                    .to_string(),
-                position: self.lexer.get_pos(),
-            });
+            let errs: Vec<Box<dyn ParserError>> = vec![
+                Box::new(errors::ParseError {
+                    detail: "matching RPAREN not found (on parsing function params)"
+                        .to_string(),
+                    position: self.lexer.get_pos(),
+                }),
+            ];
+            return Err(errs);
         }
 
-        Ok(identifiers)
+        Ok((parameters, rest))
     }
 
     fn parse_call_expression(
@@ -614,66 +953,94 @@ impl Parser {
             return Err(errs);
         }
 
-        let arguments = arguments.unwrap();
+        let (arguments, named_arguments) = arguments.unwrap();
         Ok(CallExpression {
             token,
             function: Box::new(function),
             arguments,
+            named_arguments,
         })
     }
 
     fn parse_call_args(
         &mut self,
-    ) -> Result<Vec<Expression>, Vec<Box<dyn ParserError>>> {
+    ) -> Result<(Vec<Expression>, Vec<NamedArgument>), ParserErrors> {
         let mut args = Vec::new();
+        let mut named_args = Vec::new();
         if self.peek_next_is(&Kind::RPAREN) {
             self.next(); // consume RPAREN
-            return Ok(args);
+            return Ok((args, named_args));
         }
-        self.next();
 
-        let res = self.parse_expression(Precedence::Lowest);
-        if res.is_err() {
-            let mut errs = res.err().unwrap();
-            errs.push(Box::new(errors::ParseError {
-                detail: "faild to parse inner expression (on parse call args)"
-                    .to_string(),
-                position: self.lexer.get_pos(),
-            }));
-            return Err(errs);
-        }
+        loop {
+            self.next();
 
-        args.push(res.unwrap());
+            // named argument looks like `ident = expression`
+            if self.cur_token.kind == Kind::Ident
+                && self.peek_next_is(&Kind::Assign)
+            {
+                let ident = self.parse_identifier();
+                self.next(); // cur_token will be = (assign)
+                self.next(); // cur_token will be rightside of =
 
-        while self.peek_next_is(&Kind::Comma) {
-            self.next(); // consume Comma
-            self.next();
+                let res = self.parse_expression(Precedence::Lowest);
+                if res.is_err() {
+                    let mut errs = res.err().unwrap();
+                    errs.push(Box::new(errors::ParseError {
+                        detail: "faild to parse named argument (on parse call args)"
+                            .to_string(),
+                        position: self.lexer.get_pos(),
+                    }));
+                    return Err(errs);
+                }
+                named_args.push(NamedArgument {
+                    ident,
+                    value: res.unwrap(),
+                });
+            } else {
+                if !named_args.is_empty() {
+                    let errs: Vec<Box<dyn ParserError>> = vec![
+                        Box::new(errors::ParseError {
+                            detail:
+                                "positional argument follows named argument (on parse call args)"
+                                    .to_string(),
+                            position: self.lexer.get_pos(),
+                        }),
+                    ];
+                    return Err(errs);
+                }
 
-            let res = self.parse_expression(Precedence::Lowest);
-            if res.is_err() {
-                let mut errs = res.err().unwrap();
-                errs.push(Box::new(errors::ParseError {
-                    detail: "faild to parse inner expression (on parse call args)"
-                        .to_string(),
-                    position: self.lexer.get_pos(),
-                }));
-                return Err(errs);
+                let res = self.parse_expression(Precedence::Lowest);
+                if res.is_err() {
+                    let mut errs = res.err().unwrap();
+                    errs.push(Box::new(errors::ParseError {
+                        detail: "faild to parse inner expression (on parse call args)"
+                            .to_string(),
+                        position: self.lexer.get_pos(),
+                    }));
+                    return Err(errs);
+                }
+                args.push(res.unwrap());
             }
 
-            args.push(res.unwrap());
+            if !self.peek_next_is(&Kind::Comma) {
+                break;
+            }
+            self.next(); // consume Comma
         }
 
         if !self.expect_next_is(&Kind::RPAREN) {
-            let mut errs: Vec<Box<dyn ParserError>> = Vec::new();
-            errs.push(Box::new(errors::ParseError {
-                detail: "matching RPAREN not found (on parse call args)"
-                    .to_string(),
-                position: self.lexer.get_pos(),
-            }));
+            let errs: Vec<Box<dyn ParserError>> = vec![
+                Box::new(errors::ParseError {
+                    detail: "matching RPAREN not found (on parse call args)"
+                        .to_string(),
+                    position: self.lexer.get_pos(),
+                }),
+            ];
             return Err(errs);
         }
 
-        Ok(args)
+        Ok((args, named_args))
     }
 
     fn parse_array_elements(
@@ -718,12 +1085,13 @@ impl Parser {
         }
 
         if !self.expect_next_is(&Kind::RBRACKET) {
-            let mut errs: Vec<Box<dyn ParserError>> = Vec::new();
-            errs.push(Box::new(errors::ParseError {
-                detail: "matching RBRACKET not found (on parse call args)"
-                    .to_string(),
-                position: self.lexer.get_pos(),
-            }));
+            let errs: Vec<Box<dyn ParserError>> = vec![
+                Box::new(errors::ParseError {
+                    detail: "matching RBRACKET not found (on parse call args)"
+                        .to_string(),
+                    position: self.lexer.get_pos(),
+                }),
+            ];
             return Err(errs);
         }
 
@@ -739,12 +1107,23 @@ impl Parser {
             Kind::Int => {
                 let res = self.parse_integer_literal();
                 if res.is_err() {
-                    let mut errs: Vec<Box<dyn ParserError>> = Vec::new();
-                    errs.push(Box::new(res.err().unwrap()));
+                    let errs: Vec<Box<dyn ParserError>> = vec![
+                        Box::new(res.err().unwrap()),
+                    ];
                     return Err(errs);
                 }
                 Ok(Expression::IntegerLiteral(res.ok().unwrap()))
             }
+            Kind::BigInt => {
+                let res = self.parse_big_int_literal();
+                if res.is_err() {
+                    let errs: Vec<Box<dyn ParserError>> = vec![
+                        Box::new(res.err().unwrap()),
+                    ];
+                    return Err(errs);
+                }
+                Ok(Expression::BigIntLiteral(res.ok().unwrap()))
+            }
             Kind::String => {
                 let res = self.parse_string_literal();
                 Ok(Expression::StringLiteral(res))
@@ -792,6 +1171,19 @@ impl Parser {
                 }
                 Ok(Expression::IfExpression(res.ok().unwrap()))
             }
+            Kind::Try => {
+                let res = self.parse_try_expression();
+                if res.is_err() {
+                    let mut errs: Vec<Box<dyn ParserError>> = res.err().unwrap();
+                    errs.push(Box::new(PrefixFunctionError {
+                        detail: "failed to parse try expression".to_string(),
+                        position: self.lexer.get_pos(),
+                        kind: errors::PrefixFunctionErrorKind::TryExpressionError,
+                    }));
+                    return Err(errs);
+                }
+                Ok(Expression::TryExpression(res.ok().unwrap()))
+            }
             Kind::Function => {
                 let res = self.parse_function_literal();
                 if res.is_err() {
@@ -821,15 +1213,16 @@ impl Parser {
                 }))
             }
             not_matched => {
-                let mut errs: Vec<Box<dyn ParserError>> = Vec::new();
-                errs.push(Box::new(PrefixFunctionError {
-                    detail: format!(
-                        "failed to find matching parse function on {:?}",
-                        not_matched
-                    ),
-                    position: self.lexer.get_pos(),
-                    kind: errors::PrefixFunctionErrorKind::NoPrefixFunction,
-                }));
+                let errs: Vec<Box<dyn ParserError>> = vec![
+                    Box::new(PrefixFunctionError {
+                        detail: format!(
+                            "failed to find matching parse function on {:?}",
+                            not_matched
+                        ),
+                        position: self.lexer.get_pos(),
+                        kind: errors::PrefixFunctionErrorKind::NoPrefixFunction,
+                    }),
+                ];
                 Err(errs)
             }
         }
@@ -856,15 +1249,14 @@ impl Parser {
                     }));
                     return Err(errs);
                 }
-                return Ok(Expression::CallExpression(call_expression.unwrap()));
+                Ok(Expression::CallExpression(call_expression.unwrap()))
             }
             Kind::LBRACKET => {
                 let token = self.cur_token.clone();
-                let cur_precedence = self.cur_precedence();
 
                 self.next();
 
-                let index = self.parse_expression(cur_precedence);
+                let index = self.parse_expression(Precedence::Lowest);
                 if index.is_err() {
                     let mut errs: Vec<Box<dyn ParserError>> = index.err().unwrap();
                     errs.push(Box::new(InfixFunctionError {
@@ -878,13 +1270,44 @@ impl Parser {
                 }
                 let index = index.ok().unwrap();
 
+                if !self.expect_next_is(&Kind::RBRACKET) {
+                    let errs: Vec<Box<dyn ParserError>> = vec![
+                        Box::new(errors::ParseError {
+                            detail: "matching RBRACKET not found (on parse index)"
+                                .to_string(),
+                            position: self.lexer.get_pos(),
+                        }),
+                    ];
+                    return Err(errs);
+                }
+
                 Ok(Expression::IndexExpression(IndexExpression {
                     token,
                     left: Box::new(left),
                     index: Box::new(index),
                 }))
             }
-            not_matched => {
+            Kind::Dot => {
+                let token = self.cur_token.clone();
+
+                if !self.expect_next_is(&Kind::Ident) {
+                    let errs: Vec<Box<dyn ParserError>> = vec![
+                        Box::new(errors::ParseError {
+                            detail: "Ident not found after Dot (on parse member)"
+                                .to_string(),
+                            position: self.lexer.get_pos(),
+                        }),
+                    ];
+                    return Err(errs);
+                }
+
+                Ok(Expression::MemberExpression(MemberExpression {
+                    token,
+                    left: Box::new(left),
+                    member: self.parse_identifier(),
+                }))
+            }
+            _ => {
                 let operator = self.cur_token.clone();
                 let cur_precedence = self.cur_precedence();
 
@@ -903,12 +1326,12 @@ impl Parser {
                     return Err(errs);
                 }
                 let right = right.ok().unwrap();
-                return Ok(Expression::InfixExpression(InfixExpression {
+                Ok(Expression::InfixExpression(InfixExpression {
                     token: cur_token,
                     left: Box::new(left),
                     operator,
                     right: Box::new(right),
-                }));
+                }))
             }
         }
     }
@@ -10,6 +10,9 @@ pub trait ParserError: Debug {
     // to show error stack?
 }
 
+/// error stack of a failed parse, deeper first.
+pub type ParserErrors = Vec<Box<dyn ParserError>>;
+
 #[derive(Debug)]
 pub struct ParseError {
     pub detail: String,
@@ -21,7 +24,7 @@ impl ParserError for ParseError {
         self.detail.clone()
     }
     fn position(&self) -> Position {
-        self.position.clone()
+        self.position
     }
 }
 
@@ -38,7 +41,7 @@ impl ParserError for PrefixFunctionError {
     }
 
     fn position(&self) -> Position {
-        self.position.clone()
+        self.position
     }
 }
 
@@ -49,6 +52,7 @@ pub enum PrefixFunctionErrorKind {
     ParentheseError,
     PrefixExpressionError,
     IfExpressionError,
+    TryExpressionError,
     GroupExpressionError,
     FunctionLiteralError,
 }
@@ -66,7 +70,7 @@ impl ParserError for InfixFunctionError {
     }
 
     fn position(&self) -> Position {
-        self.position.clone()
+        self.position
     }
 }
 
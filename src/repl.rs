@@ -1,102 +1,559 @@
+pub mod command;
+pub mod editor;
+pub mod history;
+pub mod session;
+
 use std::{
     cell::RefCell,
-    io::{self, BufRead, Write},
+    fmt::Write as _,
+    fs,
+    io::{self, BufRead, IsTerminal, Write},
+    mem,
+    path::Path,
     rc::Rc,
+    time::Instant,
 };
 
 use crate::{
-    ast::Nodetrait,
-    eval::evaluate,
-    lexer::Lexer,
-    object::{environment::Environment, ObjectTrait},
+    ast::{Nodetrait, Program},
+    eval::{errors::EvalError, interrupt::InterruptHandle, Evaluator},
+    lexer::{errors::UNTERMINATED_STRING, Lexer},
+    object::{
+        environment::{Environ, Environment},
+        Object, ObjectTrait,
+    },
     parser::Parser,
-    token::Kind,
+    resolver::{resolve_program, ResolveError},
+    token::{Kind, Token, KEYWORDS},
+};
+
+use self::{
+    command::{Command, DebugOutput, COMMANDS, HELP},
+    editor::{Editor, ReadLine},
+    history::{history_path, History},
+    session::snapshot,
 };
+
 const PROMPT: &str = "-> ";
+// while the input is incomplete
+const CONTINUATION_PROMPT: &str = ".. ";
 
-pub fn start() {
-    let mut buf = String::new();
-    let mut stdin = io::stdin().lock(); // We get `Stdin` here.
+/// run repl on stdin, `stack_limit` bounds native stack taken by calls
+pub fn start(stack_limit: usize) {
+    let mut repl = Repl::new();
+    repl.evaluator.set_stack_limit(stack_limit);
+    // Ctrl-C stops evaluation, and keeps repl running
+    sigint::install(repl.interrupt_handle());
+
+    // lines are edited on terminal, and read as they are from pipes
+    // or terminals which can't be switched to raw mode
+    if !io::stdin().is_terminal() || !Editor::is_supported() {
+        read_lines(&mut repl);
+        return;
+    }
+
+    let history = match history_path() {
+        Some(path) => History::load(path),
+        None => History::new(),
+    };
+    let mut editor = Editor::new(history);
+    loop {
+        let read = editor.read_line(repl.prompt(), &|before: &str| repl.complete(before));
+        let line = match read {
+            Ok(ReadLine::Line(line)) => line + "\n",
+            Ok(ReadLine::Interrupted) => {
+                repl.cancel();
+                continue;
+            }
+            Ok(ReadLine::Eof) => return,
+            Err(err) => {
+                println!("Error occured during reading stdin");
+                println!("{:?}", err);
+                return;
+            }
+        };
 
-    let env = Rc::new(RefCell::new(Environment::new()));
+        let mut out = String::new();
+        let flow = repl.run_line(&line, &mut out);
+        print!("{}", out);
+        if flow == Flow::Quit {
+            return;
+        }
+    }
+}
 
-    let debug_lexer = false;
-    let debug_parser = false;
-    let debug_evaluator = false;
-    let show_error = true;
+fn read_lines(repl: &mut Repl) {
+    let mut buf = String::new();
+    let mut stdin = io::stdin().lock(); // We get `Stdin` here.
 
     loop {
-        io::stdout().lock().write_all(PROMPT.as_bytes()).unwrap();
+        io::stdout()
+            .lock()
+            .write_all(repl.prompt().as_bytes())
+            .unwrap();
         io::stdout().flush().unwrap();
         match stdin.read_line(&mut buf) {
+            // end of input
+            Ok(0) => {
+                println!();
+                return;
+            }
             Ok(_) => {
-                if buf == "printenv\n" {
-                    dbg!(&env);
-                    dbg!(Rc::weak_count(&env));
-                    dbg!(Rc::strong_count(&env));
+                let mut out = String::new();
+                let flow = repl.run_line(&buf, &mut out);
+                print!("{}", out);
+                if flow == Flow::Quit {
+                    return;
                 }
+                buf.clear();
+            }
+            Err(err) => {
+                println!("Error occured during reading stdin");
+                println!("{:?}", err);
+                return;
+            }
+        }
+    }
+}
 
-                let lexer = Lexer::new(buf.clone());
+/// whether repl goes on after a line
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Flow {
+    Continue,
+    Quit,
+}
 
-                let mut cloned_lexer = lexer.clone();
+/// session of repl, names bound by inputs are kept until reset
+pub struct Repl {
+    env: Environ<String>,
+    // kept across inputs, so that imported modules are cached
+    evaluator: Evaluator,
+    interrupt: InterruptHandle,
+    // lines of incomplete input
+    pending: String,
+    debug_lexer: bool,
+    debug_parser: bool,
+    debug_evaluator: bool,
+    show_error: bool,
+}
 
-                if debug_lexer {
-                    loop {
-                        let cur_token = cloned_lexer.next();
+impl Default for Repl {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Repl {
+    pub fn new() -> Self {
+        let evaluator = Evaluator::new();
+        Repl {
+            env: Rc::new(RefCell::new(Environment::new())),
+            interrupt: evaluator.interrupt_handle(),
+            evaluator,
+            pending: String::new(),
+            debug_lexer: false,
+            debug_parser: false,
+            debug_evaluator: false,
+            show_error: true,
+        }
+    }
+
+    pub fn interrupt_handle(&self) -> InterruptHandle {
+        self.interrupt.clone()
+    }
+
+    pub fn env(&self) -> &Environ<String> {
+        &self.env
+    }
+
+    /// prompt for the next line, which differs while input is incomplete
+    pub fn prompt(&self) -> &'static str {
+        if self.pending.is_empty() {
+            PROMPT
+        } else {
+            CONTINUATION_PROMPT
+        }
+    }
 
-                        println!("Debug Output (Lexer) >> {:?}", cur_token);
+    /// drop incomplete input
+    pub fn cancel(&mut self) {
+        self.pending.clear();
+    }
+
+    /// completions of the word before cursor among commands, keywords and names of the session.
+    /// returns char offset where the word starts, and candidates sorted
+    pub fn complete(&self, before: &str) -> (usize, Vec<String>) {
+        if self.pending.is_empty() && before.starts_with(':') && !before.contains(' ') {
+            let candidates = COMMANDS
+                .iter()
+                .filter(|command| command.starts_with(before))
+                .map(|command| command.to_string())
+                .collect();
+            return (0, candidates);
+        }
 
-                        if cur_token.kind == Kind::EOF {
-                            break;
+        let chars: Vec<char> = before.chars().collect();
+        let mut start = chars.len();
+        while start > 0 && (chars[start - 1].is_alphanumeric() || chars[start - 1] == '_') {
+            start -= 1;
+        }
+        let word: String = chars[start..].iter().collect();
+        // members of modules and numbers are not completed
+        let member = start > 0 && chars[start - 1] == '.';
+        if word.is_empty() || member || word.starts_with(|ch: char| ch.is_numeric()) {
+            return (start, Vec::new());
+        }
+
+        let mut candidates: Vec<String> = KEYWORDS
+            .iter()
+            .map(|(keyword, _)| keyword.to_string())
+            .collect();
+        let bindings = self.env.borrow().bindings();
+        candidates.extend(bindings.into_iter().map(|(name, _)| name));
+        candidates.retain(|candidate| candidate.starts_with(&word));
+        candidates.sort();
+        candidates.dedup();
+        (start, candidates)
+    }
+
+    /// run a line of input, a command or source, writing what to print to `out`.
+    /// incomplete source waits for following lines, a blank line runs it anyway
+    pub fn run_line(&mut self, line: &str, out: &mut String) -> Flow {
+        let command = if self.pending.is_empty() {
+            Command::parse(line)
+        } else {
+            None
+        };
+        if command.is_none() {
+            let blank = line.trim().is_empty();
+            self.pending.push_str(line);
+            if !blank && is_incomplete(&self.pending) {
+                return Flow::Continue;
+            }
+            let source = mem::take(&mut self.pending);
+            let result = self.eval_source(&source, out);
+            self.print_result(result, out);
+            return Flow::Continue;
+        }
+        let command = command.unwrap();
+        if command.is_err() {
+            self.print_error(&command.err().unwrap(), out);
+            return Flow::Continue;
+        }
+
+        match command.unwrap() {
+            Command::Tokens(source) => {
+                for token in tokens(&source) {
+                    writeln!(out, "{:?} {:?}", token.kind, token.literal).unwrap();
+                }
+            }
+            Command::Ast(source) => {
+                let program = self.parse(&source, out);
+                if let Some(program) = program {
+                    writeln!(out, "{:#?}", program).unwrap();
+                }
+            }
+            Command::Env => {
+                let mut bindings = self.env.borrow().bindings();
+                bindings.sort_by(|a, b| a.0.cmp(&b.0));
+                for (name, obj) in bindings {
+                    writeln!(out, "{} = {}", name, obj.inspect()).unwrap();
+                }
+            }
+            Command::Load(path) => {
+                let result = self.load(&path);
+                self.print_result(Some(result), out);
+            }
+            Command::Reset => {
+                // imported modules stay cached in evaluator
+                self.env = Rc::new(RefCell::new(Environment::new()));
+                writeln!(out, "environment is reset").unwrap();
+            }
+            Command::Save(path) => {
+                let snapshot = snapshot(&self.env);
+                match fs::write(&path, &snapshot.source) {
+                    Ok(_) => {
+                        let path = path.display();
+                        writeln!(out, "saved {} names to {}", snapshot.saved, path).unwrap();
+                        for (name, reason) in snapshot.skipped {
+                            writeln!(out, "{} is not saved, {}", name, reason).unwrap();
                         }
                     }
+                    Err(err) => self.print_error(&err.to_string(), out),
                 }
+            }
+            Command::Restore(path) => match self.restore(&path) {
+                Ok(restored) => writeln!(out, "restored {} names", restored).unwrap(),
+                Err(err) => self.print_error(&err, out),
+            },
+            Command::Time(source) => {
+                let started = Instant::now();
+                let result = self.eval_source(&source, out);
+                let elapsed = started.elapsed();
+                self.print_result(result, out);
+                writeln!(out, "time: {:?}", elapsed).unwrap();
+            }
+            Command::Type(source) => match self.eval_source(&source, out) {
+                Some(Ok(Some(obj))) => writeln!(out, "{:?}", obj.get_type()).unwrap(),
+                Some(Ok(None)) => writeln!(out, "no value").unwrap(),
+                result => self.print_result(result, out),
+            },
+            Command::Debug(None) => {
+                writeln!(out, "lexer: {}", on_off(self.debug_lexer)).unwrap();
+                writeln!(out, "parser: {}", on_off(self.debug_parser)).unwrap();
+                writeln!(out, "eval: {}", on_off(self.debug_evaluator)).unwrap();
+            }
+            Command::Debug(Some((output, state))) => {
+                let (name, flag) = match output {
+                    DebugOutput::Lexer => ("lexer", &mut self.debug_lexer),
+                    DebugOutput::Parser => ("parser", &mut self.debug_parser),
+                    DebugOutput::Evaluator => ("eval", &mut self.debug_evaluator),
+                };
+                *flag = state.unwrap_or(!*flag);
+                writeln!(out, "{}: {}", name, on_off(*flag)).unwrap();
+            }
+            Command::Help => writeln!(out, "{}", HELP).unwrap(),
+            Command::Quit => return Flow::Quit,
+        }
+        Flow::Continue
+    }
 
-                let mut parser = Parser::new(lexer);
-                let program = parser.parse();
+    /// evaluate file in the session, names it binds are kept
+    pub fn load(&mut self, path: &Path) -> Result<Option<Object>, EvalError> {
+        // drop Ctrl-C pressed while waiting input
+        self.interrupt.reset();
+        self.evaluator.evaluate_file(path, &self.env)
+    }
 
-                if debug_parser {
-                    println!("Debug Output (Parser) >> {:?}", program);
-                }
+    /// replace the session by names bound by the file, which is written by `:save`.
+    /// session stays as it was if the file fails.
+    /// names not found in the file are left to fail when used, like names of values not saved
+    pub fn restore(&mut self, path: &Path) -> Result<usize, String> {
+        let source = fs::read_to_string(path);
+        if source.is_err() {
+            return Err(format!("{}: {}", path.display(), source.err().unwrap()));
+        }
+        let program = Parser::new(Lexer::new(source.unwrap())).parse();
+        if program.is_err() {
+            let mut details = Vec::new();
+            for errs in program.err().unwrap() {
+                let err = &errs[0];
+                let line = err.position().line_num + 1;
+                details.push(format!("{} (line {})", err.detail(), line));
+            }
+            return Err(format!("{}: {}", path.display(), details.join("; ")));
+        }
+
+        let env = Rc::new(RefCell::new(Environment::new()));
+        // drop Ctrl-C pressed while waiting input
+        self.interrupt.reset();
+        let result = self.evaluator.evaluate(program.unwrap().to_node(), &env);
+        if result.is_err() {
+            return Err(format!(
+                "{}: {}",
+                path.display(),
+                result.err().unwrap().message()
+            ));
+        }
+        let restored = env.borrow().bindings().len();
+        self.env = env;
+        Ok(restored)
+    }
 
-                if program.is_ok() {
-                    let program = program.unwrap();
-                    let result = evaluate(program.to_node(), &env);
+    /// parse source, printing debug output and errors.
+    /// None if it is not a valid program
+    fn parse(&mut self, source: &str, out: &mut String) -> Option<Program> {
+        if self.debug_lexer {
+            for token in tokens(source) {
+                writeln!(out, "Debug Output (Lexer) >> {:?}", token).unwrap();
+            }
+        }
 
-                    if debug_evaluator {
-                        println!("Debug Output (Eval) >> {:?}", result);
-                    }
+        let mut parser = Parser::new(Lexer::new(source.to_string()));
+        let program = parser.parse();
 
-                    if result.is_ok() {
-                        let eval = result.unwrap();
-                        if eval.is_some() {
-                            let val = eval.unwrap();
-                            println!("{}", val.to_str());
-                        }
-                    } else if show_error {
-                        println!("!!!> ERROR OCCURED <!!!");
-                        println!(">> ERROR DETAIL ");
-                        println!("{:?}", result.err().unwrap());
-                    }
-                } else {
-                    if show_error {
-                        println!("!!!> ERROR OCCURED <!!!");
-                        for errs in program.err().unwrap() {
-                            println!(">> ERROR DETAIL ");
-                            for err in errs {
-                                println!("Pos>> {:?}", err.as_ref().position());
-                                println!("Detail>> {} ", err.as_ref().detail());
-                            }
-                        }
+        if self.debug_parser {
+            writeln!(out, "Debug Output (Parser) >> {:?}", program).unwrap();
+        }
+
+        if program.is_err() {
+            if self.show_error {
+                writeln!(out, "!!!> ERROR OCCURED <!!!").unwrap();
+                for errs in program.err().unwrap() {
+                    writeln!(out, ">> ERROR DETAIL ").unwrap();
+                    for err in errs {
+                        let line = err.as_ref().position().line_num + 1;
+                        writeln!(out, "Pos>> line {}", line).unwrap();
+                        writeln!(out, "Detail>> {} ", err.as_ref().detail()).unwrap();
                     }
                 }
-                buf.clear();
             }
-            Err(err) => {
-                println!("Error occured during reading stdin");
-                println!("{:?}", err);
-                return;
+            return None;
+        }
+        Some(program.unwrap())
+    }
+
+    /// evaluate source in the session.
+    /// None if it does not parse or resolve, whose errors are printed already
+    fn eval_source(
+        &mut self,
+        source: &str,
+        out: &mut String,
+    ) -> Option<Result<Option<Object>, EvalError>> {
+        let mut program = self.parse(source, out)?;
+
+        // names defined by former inputs are known, others are looked up by name
+        // on evaluation, so that functions can refer names defined by later inputs
+        let errors: Vec<ResolveError> =
+            resolve_program(&mut program, &self.env.borrow().names())
+                .into_iter()
+                .filter(|err| !matches!(err, ResolveError::UndefinedVariable { .. }))
+                .collect();
+        if !errors.is_empty() {
+            if self.show_error {
+                writeln!(out, "!!!> ERROR OCCURED <!!!").unwrap();
+                for err in errors {
+                    writeln!(out, ">> ERROR DETAIL ").unwrap();
+                    writeln!(out, "Pos>> line {}", err.position().line_num + 1).unwrap();
+                    writeln!(out, "Detail>> {} ", err.message()).unwrap();
+                }
+            }
+            return None;
+        }
+
+        // drop Ctrl-C pressed while waiting input
+        self.interrupt.reset();
+        let result = self.evaluator.evaluate_resolved(program.to_node(), &self.env);
+
+        if self.debug_evaluator {
+            writeln!(out, "Debug Output (Eval) >> {:?}", result).unwrap();
+        }
+        Some(result)
+    }
+
+    fn print_result(&self, result: Option<Result<Option<Object>, EvalError>>, out: &mut String) {
+        match result {
+            Some(Ok(Some(obj))) => writeln!(out, "{}", obj.inspect()).unwrap(),
+            Some(Err(err)) if self.show_error => {
+                writeln!(out, "!!!> ERROR OCCURED <!!!").unwrap();
+                writeln!(out, ">> ERROR DETAIL ").unwrap();
+                writeln!(out, "{}", err.message()).unwrap();
             }
+            _ => {}
         }
     }
+
+    fn print_error(&self, detail: &str, out: &mut String) {
+        if self.show_error {
+            writeln!(out, "!!!> ERROR OCCURED <!!!").unwrap();
+            writeln!(out, ">> ERROR DETAIL ").unwrap();
+            writeln!(out, "{}", detail).unwrap();
+        }
+    }
+}
+
+/// whether source needs more lines to be a statement:
+/// brackets left open, string left unterminated or ending with an operator
+pub fn is_incomplete(source: &str) -> bool {
+    let mut lexer = Lexer::new(source.to_string());
+    let mut depth: i64 = 0;
+    let mut last = Kind::EOF;
+    loop {
+        let token = lexer.next();
+        match token.kind {
+            Kind::LPAREN | Kind::LBRACE | Kind::LBRACKET => depth += 1,
+            Kind::RPAREN | Kind::RBRACE | Kind::RBRACKET => depth -= 1,
+            // lexer reads string to the end of input
+            Kind::Illegal if token.literal == UNTERMINATED_STRING => return true,
+            Kind::EOF => break,
+            _ => {}
+        }
+        last = token.kind;
+    }
+
+    // closed too many, which is left for parser to report
+    if depth != 0 {
+        return depth > 0;
+    }
+    matches!(
+        last,
+        Kind::Assign
+            | Kind::Plus
+            | Kind::Minus
+            | Kind::Product
+            | Kind::Divide
+            | Kind::Mod
+            | Kind::Bang
+            | Kind::LT
+            | Kind::LT_OR_EQ
+            | Kind::GT
+            | Kind::GT_OR_EQ
+            | Kind::EQ
+            | Kind::NOT_EQ
+            | Kind::And
+            | Kind::Or
+            | Kind::Bit_And
+            | Kind::Bit_Or
+            | Kind::Comma
+            | Kind::Ellipsis
+            | Kind::Dot
+    )
+}
+
+fn tokens(source: &str) -> Vec<Token> {
+    let mut lexer = Lexer::new(source.to_string());
+    let mut tokens = Vec::new();
+    loop {
+        let token = lexer.next();
+        if token.kind == Kind::EOF {
+            return tokens;
+        }
+        tokens.push(token);
+    }
+}
+
+fn on_off(flag: bool) -> &'static str {
+    if flag {
+        "on"
+    } else {
+        "off"
+    }
+}
+
+#[cfg(unix)]
+mod sigint {
+    use std::sync::OnceLock;
+
+    use super::InterruptHandle;
+
+    const SIGINT: i32 = 2;
+
+    // signal handler can't capture, so handle is kept here
+    static HANDLE: OnceLock<InterruptHandle> = OnceLock::new();
+
+    extern "C" {
+        fn signal(signum: i32, handler: extern "C" fn(i32)) -> usize;
+    }
+
+    extern "C" fn on_sigint(_: i32) {
+        if let Some(handle) = HANDLE.get() {
+            handle.interrupt();
+        }
+    }
+
+    /// interrupt evaluation on SIGINT instead of terminating process
+    pub fn install(handle: InterruptHandle) {
+        if HANDLE.set(handle).is_ok() {
+            unsafe {
+                signal(SIGINT, on_sigint);
+            }
+        }
+    }
+}
+
+#[cfg(not(unix))]
+mod sigint {
+    use super::InterruptHandle;
+
+    pub fn install(_handle: InterruptHandle) {}
 }
@@ -0,0 +1,113 @@
+use std::path::PathBuf;
+
+pub const HELP: &str = "commands:
+  :tokens <source>        print tokens of the source
+  :ast <source>           print syntax tree of the source
+  :env                    print names bound in the session
+  :load <file>            evaluate file in the session
+  :reset                  drop all names bound in the session
+  :save <file>            write names bound in the session to file
+  :restore <file>         replace the session by one written by :save
+  :time <source>          evaluate and print how long it took
+  :type <source>          evaluate and print type of the result
+  :debug [<output> [on|off]]
+                          toggle debug output of lexer, parser or eval
+  :help                   print this
+  :quit                   leave repl";
+
+pub const COMMANDS: [&str; 12] = [
+    ":tokens", ":ast", ":env", ":load", ":reset", ":save", ":restore", ":time", ":type", ":debug",
+    ":help", ":quit",
+];
+
+/// debug output printed while evaluating inputs
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DebugOutput {
+    Lexer,
+    Parser,
+    Evaluator,
+}
+
+/// meta-command of repl, a line starting with `:`
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    Tokens(String),
+    Ast(String),
+    Env,
+    Load(PathBuf),
+    Reset,
+    Save(PathBuf),
+    Restore(PathBuf),
+    Time(String),
+    Type(String),
+    // None to print which are on, None state to toggle
+    Debug(Option<(DebugOutput, Option<bool>)>),
+    Help,
+    Quit,
+}
+
+impl Command {
+    /// parse command of the line, None if the line is not a command
+    pub fn parse(line: &str) -> Option<Result<Command, String>> {
+        let line = line.trim().strip_prefix(':')?;
+        let (name, arg) = match line.split_once(char::is_whitespace) {
+            Some((name, arg)) => (name, arg.trim()),
+            None => (line, ""),
+        };
+
+        let command = match name {
+            "tokens" => with_arg(name, arg).map(|arg| Command::Tokens(arg.to_string())),
+            "ast" => with_arg(name, arg).map(|arg| Command::Ast(arg.to_string())),
+            "load" => with_arg(name, arg).map(|arg| Command::Load(PathBuf::from(arg))),
+            "save" => with_arg(name, arg).map(|arg| Command::Save(PathBuf::from(arg))),
+            "restore" => with_arg(name, arg).map(|arg| Command::Restore(PathBuf::from(arg))),
+            "time" => with_arg(name, arg).map(|arg| Command::Time(arg.to_string())),
+            "type" => with_arg(name, arg).map(|arg| Command::Type(arg.to_string())),
+            "debug" => parse_debug(arg),
+            "env" | "reset" | "help" | "quit" if !arg.is_empty() => {
+                Err(format!(":{} takes no argument", name))
+            }
+            "env" => Ok(Command::Env),
+            "reset" => Ok(Command::Reset),
+            "help" => Ok(Command::Help),
+            "quit" => Ok(Command::Quit),
+            _ => Err(format!("unknown command :{}, :help lists commands", name)),
+        };
+        Some(command)
+    }
+}
+
+fn with_arg<'a>(name: &str, arg: &'a str) -> Result<&'a str, String> {
+    if arg.is_empty() {
+        return Err(format!(":{} needs an argument", name));
+    }
+    Ok(arg)
+}
+
+fn parse_debug(arg: &str) -> Result<Command, String> {
+    let mut words = arg.split_whitespace();
+    let Some(output) = words.next() else {
+        return Ok(Command::Debug(None));
+    };
+    let output = match output {
+        "lexer" => DebugOutput::Lexer,
+        "parser" => DebugOutput::Parser,
+        "eval" => DebugOutput::Evaluator,
+        _ => {
+            return Err(format!(
+                "unknown debug output {}, one of lexer, parser, eval",
+                output
+            ))
+        }
+    };
+    let state = match words.next() {
+        None => None,
+        Some("on") => Some(true),
+        Some("off") => Some(false),
+        Some(state) => return Err(format!("unknown state {}, on or off", state)),
+    };
+    if words.next().is_some() {
+        return Err("usage: :debug [<output> [on|off]]".to_string());
+    }
+    Ok(Command::Debug(Some((output, state))))
+}
@@ -0,0 +1,562 @@
+use std::io::{self, Read, Write};
+
+use super::history::History;
+
+/// key read from terminal, control keys named by what they do
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Key {
+    Char(char),
+    Enter,
+    Tab,
+    Backspace,
+    Delete,
+    Left,
+    Right,
+    Up,
+    Down,
+    Home,
+    End,
+    // ctrl-k
+    KillToEnd,
+    // ctrl-u
+    KillToStart,
+    // ctrl-w
+    KillWord,
+    // ctrl-c
+    Interrupt,
+    // ctrl-d
+    Eof,
+    // ctrl-r
+    Search,
+    // ctrl-l
+    ClearScreen,
+    // esc or ctrl-g
+    Cancel,
+    Unknown,
+}
+
+/// read a key from raw terminal input, None at the end of input
+pub fn read_key<R: Read>(input: &mut R) -> io::Result<Option<Key>> {
+    let byte = read_byte(input);
+    if byte.is_err() {
+        return Err(byte.err().unwrap());
+    }
+    let Some(byte) = byte.unwrap() else {
+        return Ok(None);
+    };
+
+    let key = match byte {
+        1 => Key::Home,
+        2 => Key::Left,
+        3 => Key::Interrupt,
+        4 => Key::Eof,
+        5 => Key::End,
+        6 => Key::Right,
+        7 => Key::Cancel,
+        8 | 127 => Key::Backspace,
+        9 => Key::Tab,
+        10 | 13 => Key::Enter,
+        11 => Key::KillToEnd,
+        12 => Key::ClearScreen,
+        14 => Key::Down,
+        16 => Key::Up,
+        18 => Key::Search,
+        21 => Key::KillToStart,
+        23 => Key::KillWord,
+        27 => return read_escape(input),
+        byte if byte < 32 => Key::Unknown,
+        byte if byte < 128 => Key::Char(byte as char),
+        byte => return read_utf8(input, byte),
+    };
+    Ok(Some(key))
+}
+
+fn read_byte<R: Read>(input: &mut R) -> io::Result<Option<u8>> {
+    let mut buf = [0; 1];
+    loop {
+        match input.read(&mut buf) {
+            Ok(0) => return Ok(None),
+            Ok(_) => return Ok(Some(buf[0])),
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+/// `ESC [ ...` or `ESC O ...` sequence of arrows and such
+fn read_escape<R: Read>(input: &mut R) -> io::Result<Option<Key>> {
+    let byte = read_byte(input);
+    if byte.is_err() {
+        return Err(byte.err().unwrap());
+    }
+    // lone escape at the end of input
+    let Some(byte) = byte.unwrap() else {
+        return Ok(Some(Key::Cancel));
+    };
+    if byte != b'[' && byte != b'O' {
+        return Ok(Some(Key::Cancel));
+    }
+
+    let mut params = String::new();
+    loop {
+        let byte = read_byte(input);
+        if byte.is_err() {
+            return Err(byte.err().unwrap());
+        }
+        let Some(byte) = byte.unwrap() else {
+            return Ok(Some(Key::Unknown));
+        };
+        // final byte of the sequence
+        if (0x40..=0x7e).contains(&byte) {
+            let key = match (byte, params.as_str()) {
+                (b'A', _) => Key::Up,
+                (b'B', _) => Key::Down,
+                (b'C', _) => Key::Right,
+                (b'D', _) => Key::Left,
+                (b'H', _) | (b'~', "1") | (b'~', "7") => Key::Home,
+                (b'F', _) | (b'~', "4") | (b'~', "8") => Key::End,
+                (b'~', "3") => Key::Delete,
+                _ => Key::Unknown,
+            };
+            return Ok(Some(key));
+        }
+        params.push(byte as char);
+    }
+}
+
+fn read_utf8<R: Read>(input: &mut R, first: u8) -> io::Result<Option<Key>> {
+    let len = match first {
+        0xc0..=0xdf => 2,
+        0xe0..=0xef => 3,
+        0xf0..=0xf7 => 4,
+        _ => return Ok(Some(Key::Unknown)),
+    };
+    let mut bytes = vec![first];
+    for _ in 1..len {
+        let byte = read_byte(input);
+        if byte.is_err() {
+            return Err(byte.err().unwrap());
+        }
+        let Some(byte) = byte.unwrap() else {
+            return Ok(Some(Key::Unknown));
+        };
+        bytes.push(byte);
+    }
+    let key = match std::str::from_utf8(&bytes) {
+        Ok(text) => Key::Char(text.chars().next().unwrap()),
+        Err(_) => Key::Unknown,
+    };
+    Ok(Some(key))
+}
+
+/// what editor did with a key
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+    // line or cursor changed, to be drawn again
+    Edited,
+    // line is entered
+    Done(String),
+    // line is dropped by ctrl-c
+    Interrupted,
+    // ctrl-d on empty line
+    Eof,
+    // completions to list under the line
+    Candidates(Vec<String>),
+    ClearScreen,
+}
+
+/// what reading a line from terminal ends with
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReadLine {
+    Line(String),
+    Interrupted,
+    Eof,
+}
+
+/// reverse search over history
+struct Search {
+    query: String,
+    // entry matching the query
+    found: Option<usize>,
+}
+
+/// editor of a line with history.
+/// completer gets the line before the cursor and returns
+/// char offset of the word being completed and candidates for it
+pub struct Editor {
+    pub history: History,
+    line: Vec<char>,
+    // char offset in line
+    cursor: usize,
+    // history entry shown, history.len() while writing a new line
+    shown: usize,
+    // new line kept while history is browsed
+    draft: Vec<char>,
+    search: Option<Search>,
+}
+
+impl Editor {
+    pub fn new(history: History) -> Self {
+        let shown = history.len();
+        Editor {
+            history,
+            line: Vec::new(),
+            cursor: 0,
+            shown,
+            draft: Vec::new(),
+            search: None,
+        }
+    }
+
+    pub fn line(&self) -> String {
+        self.line.iter().collect()
+    }
+
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    /// start a new line
+    fn clear(&mut self) {
+        self.line.clear();
+        self.cursor = 0;
+        self.shown = self.history.len();
+        self.draft.clear();
+        self.search = None;
+    }
+
+    fn set_line(&mut self, line: Vec<char>) {
+        self.cursor = line.len();
+        self.line = line;
+    }
+
+    /// apply a key to the line
+    pub fn feed<F>(&mut self, key: Key, complete: &F) -> Event
+    where
+        F: Fn(&str) -> (usize, Vec<String>),
+    {
+        if self.search.is_some() {
+            let searched = self.feed_search(key);
+            if let Some(searched) = searched {
+                return searched;
+            }
+        }
+
+        match key {
+            Key::Char(ch) => {
+                self.line.insert(self.cursor, ch);
+                self.cursor += 1;
+            }
+            Key::Enter => {
+                let line = self.line();
+                self.history.add(&line);
+                self.clear();
+                return Event::Done(line);
+            }
+            Key::Tab => return self.complete(complete),
+            Key::Backspace => {
+                if self.cursor > 0 {
+                    self.cursor -= 1;
+                    self.line.remove(self.cursor);
+                }
+            }
+            Key::Delete => {
+                if self.cursor < self.line.len() {
+                    self.line.remove(self.cursor);
+                }
+            }
+            Key::Left => self.cursor = self.cursor.saturating_sub(1),
+            Key::Right => self.cursor = (self.cursor + 1).min(self.line.len()),
+            Key::Home => self.cursor = 0,
+            Key::End => self.cursor = self.line.len(),
+            Key::Up => {
+                if self.shown > 0 {
+                    if self.shown == self.history.len() {
+                        self.draft = self.line.clone();
+                    }
+                    self.shown -= 1;
+                    let entry = self.history.get(self.shown).unwrap_or("");
+                    self.set_line(entry.chars().collect());
+                }
+            }
+            Key::Down => {
+                if self.shown < self.history.len() {
+                    self.shown += 1;
+                    let line = match self.history.get(self.shown) {
+                        Some(entry) => entry.chars().collect(),
+                        None => self.draft.clone(),
+                    };
+                    self.set_line(line);
+                }
+            }
+            Key::KillToEnd => self.line.truncate(self.cursor),
+            Key::KillToStart => {
+                self.line.drain(..self.cursor);
+                self.cursor = 0;
+            }
+            Key::KillWord => {
+                // spaces before the cursor, then the word
+                let mut start = self.cursor;
+                while start > 0 && self.line[start - 1].is_whitespace() {
+                    start -= 1;
+                }
+                while start > 0 && !self.line[start - 1].is_whitespace() {
+                    start -= 1;
+                }
+                self.line.drain(start..self.cursor);
+                self.cursor = start;
+            }
+            Key::Interrupt => {
+                self.clear();
+                return Event::Interrupted;
+            }
+            Key::Eof => {
+                if self.line.is_empty() {
+                    return Event::Eof;
+                }
+                if self.cursor < self.line.len() {
+                    self.line.remove(self.cursor);
+                }
+            }
+            Key::Search => {
+                self.search = Some(Search {
+                    query: String::new(),
+                    found: None,
+                });
+            }
+            Key::ClearScreen => return Event::ClearScreen,
+            Key::Cancel | Key::Unknown => {}
+        }
+        Event::Edited
+    }
+
+    /// apply a key while searching, None if the key leaves search to be applied to the line
+    fn feed_search(&mut self, key: Key) -> Option<Event> {
+        let search = self.search.as_mut().unwrap();
+        match key {
+            Key::Char(ch) => {
+                search.query.push(ch);
+                // current match stays if it still matches
+                let from = search.found.map(|idx| idx + 1);
+                let found = self
+                    .history
+                    .search(&search.query, from.unwrap_or(self.history.len()));
+                search.found = found;
+                Some(Event::Edited)
+            }
+            Key::Backspace => {
+                search.query.pop();
+                search.found = self.history.search(&search.query, self.history.len());
+                Some(Event::Edited)
+            }
+            Key::Search => {
+                let before = search.found.unwrap_or(self.history.len());
+                let older = self.history.search(&search.query, before);
+                if older.is_some() {
+                    search.found = older;
+                }
+                Some(Event::Edited)
+            }
+            // line stays as before search
+            Key::Cancel | Key::Interrupt => {
+                self.search = None;
+                Some(Event::Edited)
+            }
+            _ => {
+                let found = search.found;
+                self.search = None;
+                if let Some(found) = found {
+                    self.shown = found;
+                    let entry = self.history.get(self.shown).unwrap_or("");
+                    self.set_line(entry.chars().collect());
+                }
+                None
+            }
+        }
+    }
+
+    fn complete<F>(&mut self, complete: &F) -> Event
+    where
+        F: Fn(&str) -> (usize, Vec<String>),
+    {
+        let before: String = self.line[..self.cursor].iter().collect();
+        let (start, candidates) = complete(&before);
+        if candidates.is_empty() {
+            return Event::Edited;
+        }
+
+        let typed = self.cursor - start.min(self.cursor);
+        let prefix = common_prefix(&candidates);
+        let rest: Vec<char> = prefix.chars().skip(typed).collect();
+        if rest.is_empty() {
+            if candidates.len() > 1 {
+                return Event::Candidates(candidates);
+            }
+            return Event::Edited;
+        }
+        for ch in rest {
+            self.line.insert(self.cursor, ch);
+            self.cursor += 1;
+        }
+        Event::Edited
+    }
+
+    /// escape sequence drawing the line over the current line of terminal
+    pub fn render(&self, prompt: &str) -> String {
+        if let Some(search) = &self.search {
+            let found = search.found.and_then(|idx| self.history.get(idx));
+            return format!(
+                "\r(reverse-i-search)`{}': {}\x1b[K",
+                search.query,
+                found.unwrap_or("")
+            );
+        }
+
+        let mut out = format!("\r{}{}\x1b[K", prompt, self.line());
+        let back = self.line.len() - self.cursor;
+        if back > 0 {
+            out.push_str(&format!("\x1b[{}D", back));
+        }
+        out
+    }
+
+    /// whether lines can be edited on the terminal, raw mode is tried and restored
+    pub fn is_supported() -> bool {
+        terminal::RawMode::enable().is_ok()
+    }
+
+    /// read a line from terminal, editing it by keys
+    pub fn read_line<F>(&mut self, prompt: &str, complete: &F) -> io::Result<ReadLine>
+    where
+        F: Fn(&str) -> (usize, Vec<String>),
+    {
+        let raw = terminal::RawMode::enable();
+        if raw.is_err() {
+            return Err(raw.err().unwrap());
+        }
+        let mut input = io::stdin().lock();
+        let mut output = io::stdout().lock();
+
+        // output is not translated in raw mode, so lines end with \r\n
+        let mut draw = self.render(prompt);
+        loop {
+            let written = output.write_all(draw.as_bytes());
+            if written.is_err() {
+                return Err(written.err().unwrap());
+            }
+            let flushed = output.flush();
+            if flushed.is_err() {
+                return Err(flushed.err().unwrap());
+            }
+
+            let key = read_key(&mut input);
+            if key.is_err() {
+                return Err(key.err().unwrap());
+            }
+            let Some(key) = key.unwrap() else {
+                self.clear();
+                let _ = output.write_all(b"\r\n");
+                return Ok(ReadLine::Eof);
+            };
+
+            let end = match self.feed(key, complete) {
+                Event::Edited => None,
+                Event::Done(line) => Some(("\r\n", ReadLine::Line(line))),
+                Event::Interrupted => Some(("^C\r\n", ReadLine::Interrupted)),
+                Event::Eof => Some(("\r\n", ReadLine::Eof)),
+                Event::Candidates(candidates) => {
+                    draw = format!("\r\n{}\r\n{}", candidates.join("  "), self.render(prompt));
+                    continue;
+                }
+                Event::ClearScreen => {
+                    draw = format!("\x1b[H\x1b[2J{}", self.render(prompt));
+                    continue;
+                }
+            };
+            if let Some(end) = end {
+                let (tail, read) = end;
+                let _ = output.write_all(tail.as_bytes());
+                let _ = output.flush();
+                return Ok(read);
+            }
+            draw = self.render(prompt);
+        }
+    }
+}
+
+/// longest prefix shared by all
+fn common_prefix(words: &[String]) -> String {
+    let mut prefix: Vec<char> = words[0].chars().collect();
+    for word in words[1..].iter() {
+        let same = prefix
+            .iter()
+            .zip(word.chars())
+            .take_while(|(a, b)| *a == b)
+            .count();
+        prefix.truncate(same);
+    }
+    prefix.into_iter().collect()
+}
+
+#[cfg(unix)]
+mod terminal {
+    use std::io;
+
+    const STDIN: i32 = 0;
+    const TCSANOW: i32 = 0;
+
+    // termios is opaque here, big enough for its layout on any platform
+    type Termios = [u64; 32];
+
+    extern "C" {
+        fn tcgetattr(fd: i32, termios: *mut Termios) -> i32;
+        fn tcsetattr(fd: i32, action: i32, termios: *const Termios) -> i32;
+        fn cfmakeraw(termios: *mut Termios);
+    }
+
+    /// raw mode of the terminal, restored on drop
+    pub struct RawMode {
+        saved: Termios,
+    }
+
+    impl RawMode {
+        pub fn enable() -> io::Result<RawMode> {
+            let mut saved = [0; 32];
+            unsafe {
+                if tcgetattr(STDIN, &mut saved) != 0 {
+                    return Err(io::Error::last_os_error());
+                }
+                let mut raw = saved;
+                cfmakeraw(&mut raw);
+                if tcsetattr(STDIN, TCSANOW, &raw) != 0 {
+                    return Err(io::Error::last_os_error());
+                }
+            }
+            Ok(RawMode { saved })
+        }
+    }
+
+    impl Drop for RawMode {
+        fn drop(&mut self) {
+            unsafe {
+                tcsetattr(STDIN, TCSANOW, &self.saved);
+            }
+        }
+    }
+}
+
+#[cfg(not(unix))]
+mod terminal {
+    use std::io;
+
+    pub struct RawMode;
+
+    impl RawMode {
+        pub fn enable() -> io::Result<RawMode> {
+            Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "line editing needs a unix terminal",
+            ))
+        }
+    }
+}
@@ -0,0 +1,101 @@
+use std::{
+    env,
+    fs::{self, OpenOptions},
+    io::Write,
+    path::PathBuf,
+};
+
+// entries kept, older ones are dropped
+pub const MAX_ENTRIES: usize = 1000;
+
+/// lines given to repl, oldest first, saved to a file if it has one
+#[derive(Debug, Clone, Default)]
+pub struct History {
+    entries: Vec<String>,
+    path: Option<PathBuf>,
+}
+
+impl History {
+    /// history kept only in memory
+    pub fn new() -> Self {
+        History {
+            entries: Vec::new(),
+            path: None,
+        }
+    }
+
+    /// history read from the file, and saved to it as lines are added.
+    /// missing file is created on first line
+    pub fn load(path: PathBuf) -> Self {
+        let mut entries: Vec<String> = match fs::read_to_string(&path) {
+            Ok(text) => text.lines().map(|line| line.to_string()).collect(),
+            Err(_) => Vec::new(),
+        };
+        if entries.len() > MAX_ENTRIES {
+            entries.drain(..entries.len() - MAX_ENTRIES);
+            // not to grow forever, failing to shrink is harmless
+            let _ = fs::write(&path, entries.join("\n") + "\n");
+        }
+        History {
+            entries,
+            path: Some(path),
+        }
+    }
+
+    pub fn entries(&self) -> &[String] {
+        &self.entries
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn get(&self, idx: usize) -> Option<&str> {
+        self.entries.get(idx).map(|entry| entry.as_str())
+    }
+
+    /// add a line, blank lines and repeats of the last one are skipped
+    pub fn add(&mut self, line: &str) {
+        let line = line.trim_end_matches(['\r', '\n']);
+        if line.trim().is_empty() || self.entries.last().is_some_and(|last| last == line) {
+            return;
+        }
+        self.entries.push(line.to_string());
+        if self.entries.len() > MAX_ENTRIES {
+            self.entries.remove(0);
+        }
+
+        if let Some(path) = &self.path {
+            if let Some(dir) = path.parent() {
+                let _ = fs::create_dir_all(dir);
+            }
+            // history is a convenience, repl goes on without it
+            let file = OpenOptions::new().create(true).append(true).open(path);
+            if let Ok(mut file) = file {
+                let _ = writeln!(file, "{}", line);
+            }
+        }
+    }
+
+    /// latest entry before `before` containing the query
+    pub fn search(&self, query: &str, before: usize) -> Option<usize> {
+        let before = before.min(self.entries.len());
+        self.entries[..before]
+            .iter()
+            .rposition(|entry| entry.contains(query))
+    }
+}
+
+/// file of history, `$XDG_STATE_HOME/dlang/history` or `~/.dlang_history`
+pub fn history_path() -> Option<PathBuf> {
+    let state = env::var_os("XDG_STATE_HOME").filter(|dir| !dir.is_empty());
+    if let Some(state) = state {
+        return Some(PathBuf::from(state).join("dlang").join("history"));
+    }
+    let home = env::var_os("HOME").filter(|dir| !dir.is_empty());
+    home.map(|home| PathBuf::from(home).join(".dlang_history"))
+}
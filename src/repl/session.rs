@@ -0,0 +1,83 @@
+use std::rc::Rc;
+
+use crate::object::{environment::Environ, Object, ObjectTrait};
+
+pub const HEADER: &str = "// dlang session, load it back with :restore";
+
+/// names of a session, written as source binding them again
+pub struct Snapshot {
+    pub source: String,
+    pub saved: usize,
+    // names left out, with why
+    pub skipped: Vec<(String, String)>,
+}
+
+/// write global names of the session as `import` and `let` statements.
+/// names whose value can't be written as source are left out with a comment
+pub fn snapshot(env: &Environ<String>) -> Snapshot {
+    let mut bindings = env.borrow().bindings();
+    bindings.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let mut imports = Vec::new();
+    let mut lets = Vec::new();
+    let mut skipped = Vec::new();
+    for (name, obj) in bindings {
+        if let Object::Module(module) = &obj {
+            imports.push(format!("import {:?} as {};", module.path, name));
+            continue;
+        }
+        match to_source(&obj, env) {
+            Ok(source) => lets.push(format!("let {} = {};", name, source)),
+            Err(reason) => skipped.push((name, reason)),
+        }
+    }
+
+    let saved = imports.len() + lets.len();
+    let mut lines = vec![HEADER.to_string()];
+    lines.extend(imports);
+    lines.extend(lets);
+    for (name, reason) in skipped.iter() {
+        lines.push(format!("// {} is not saved, {}", name, reason));
+    }
+    Snapshot {
+        source: lines.join("\n") + "\n",
+        saved,
+        skipped,
+    }
+}
+
+/// source evaluating to the object in the session, Err with why if there is none
+pub fn to_source(obj: &Object, env: &Environ<String>) -> Result<String, String> {
+    match obj {
+        // literal of the minimum overflows before negated
+        Object::Int(int) if int.value == i64::MIN => Ok(format!("({} - 1)", int.value + 1)),
+        Object::Int(int) => Ok(int.value.to_string()),
+        Object::BigInt(int) => Ok(format!("{}n", int.value)),
+        Object::Bool(boolean) => Ok(boolean.value.to_string()),
+        Object::String(string) => {
+            if string.value.contains('"') {
+                return Err("string literal can't hold a quote".to_string());
+            }
+            Ok(format!("\"{}\"", string.value))
+        }
+        Object::Array(array) => {
+            let mut elements = Vec::new();
+            for element in array.elements.iter() {
+                let source = to_source(element, env);
+                if source.is_err() {
+                    return Err(source.err().unwrap());
+                }
+                elements.push(source.unwrap());
+            }
+            Ok(format!("[{}]", elements.join(", ")))
+        }
+        // names of the function are looked up in the session again
+        Object::Function(function) => {
+            if !Rc::ptr_eq(&function.env, env) {
+                return Err("function refers to names out of the session".to_string());
+            }
+            Ok(function.to_str())
+        }
+        _ => Err(format!("{:?} can't be written as source", obj.get_type())),
+    }
+}
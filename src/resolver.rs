@@ -0,0 +1,360 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    ast::{
+        Binding, BlockStatement, Expression, FunctionLiteral, Identifier, Node, Program, Statement,
+    },
+    lexer::Position,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ResolveError {
+    UndefinedVariable { name: String, position: Position },
+    DuplicatedParameter { name: String, position: Position },
+}
+
+impl ResolveError {
+    pub fn position(&self) -> Position {
+        match self {
+            ResolveError::UndefinedVariable { position, .. }
+            | ResolveError::DuplicatedParameter { position, .. } => *position,
+        }
+    }
+
+    pub fn message(&self) -> String {
+        match self {
+            ResolveError::UndefinedVariable { name, .. } => {
+                format!("undefined variable {}", name)
+            }
+            ResolveError::DuplicatedParameter { name, .. } => {
+                format!("duplicated parameter {}", name)
+            }
+        }
+    }
+}
+
+/// resolve every identifier of the program to the environment it is bound.
+/// `globals` are names already defined in top level environment, e.g. by former repl inputs.
+/// undefined identifiers are looked up by name on evaluation
+pub fn resolve_program(program: &mut Program, globals: &[String]) -> Vec<ResolveError> {
+    let mut resolver = Resolver::new(globals);
+    resolver.declare_block(&program.statements);
+    for stm in program.statements.iter_mut() {
+        resolver.statement(stm);
+    }
+    resolver.errors
+}
+
+pub fn resolve(node: &mut Node, globals: &[String]) -> Vec<ResolveError> {
+    match node {
+        Node::Program(pro) => resolve_program(pro, globals),
+        Node::Statement(stm) => {
+            let mut resolver = Resolver::new(globals);
+            resolver.declare_block(std::slice::from_ref(stm));
+            resolver.statement(stm);
+            resolver.errors
+        }
+        Node::Expression(exp) => {
+            let mut resolver = Resolver::new(globals);
+            resolver.declare_functions(exp);
+            resolver.expression(exp);
+            resolver.errors
+        }
+    }
+}
+
+/// names of a scope, which is an environment on evaluation
+#[derive(Default)]
+struct Scope {
+    // slot of each name declared in the scope
+    slots: HashMap<String, usize>,
+    // names already bound, others are declared later in the scope
+    defined: HashSet<String>,
+}
+
+impl Scope {
+    fn declare(&mut self, name: &str) -> usize {
+        let next = self.slots.len();
+        *self.slots.entry(name.to_string()).or_insert(next)
+    }
+
+    fn define(&mut self, name: &str) -> usize {
+        self.defined.insert(name.to_string());
+        self.declare(name)
+    }
+}
+
+struct Resolver {
+    // innermost last, first one is top level
+    scopes: Vec<Scope>,
+    // index of the first scope of each function being resolved
+    functions: Vec<usize>,
+    errors: Vec<ResolveError>,
+}
+
+impl Resolver {
+    fn new(globals: &[String]) -> Self {
+        let mut top = Scope::default();
+        for name in globals {
+            top.define(name);
+        }
+        Resolver {
+            scopes: vec![top],
+            functions: Vec::new(),
+            errors: Vec::new(),
+        }
+    }
+
+    /// declare names bound by statements of a block, before resolving it.
+    /// nested functions can refer to them before they are bound
+    fn declare_block(&mut self, stms: &[Statement]) {
+        for stm in stms {
+            self.declare_statement(stm);
+        }
+    }
+
+    fn declare_statement(&mut self, stm: &Statement) {
+        match stm {
+            Statement::LetStatement(stm) => {
+                if let Some(value) = &stm.value {
+                    self.declare_functions(value);
+                }
+                self.current().declare(&stm.identifier.value);
+            }
+            Statement::ExpressionStatement(stm) => {
+                if let Some(expression) = &stm.expression {
+                    self.declare_functions(expression);
+                }
+            }
+            Statement::ReturnStatement(stm) => {
+                if let Some(value) = &stm.value {
+                    self.declare_functions(value);
+                }
+            }
+            Statement::ThrowStatement(stm) => self.declare_functions(&stm.value),
+            Statement::ImportStatement(stm) => {
+                self.current().declare(&stm.alias.value);
+            }
+            Statement::ExportStatement(stm) => self.declare_statement(&stm.statement),
+            // block has its own scope
+            Statement::BlockStatement(_) => {}
+        }
+    }
+
+    /// named function literals bind their name to the current scope
+    fn declare_functions(&mut self, exp: &Expression) {
+        match exp {
+            Expression::FunctionLiteral(func) if func.ident.is_some() => {
+                self.current().declare(&func.ident.as_ref().unwrap().value);
+            }
+            Expression::ArrayLiteral(arr) => {
+                for element in &arr.elements {
+                    self.declare_functions(element);
+                }
+            }
+            Expression::InfixExpression(exp) => {
+                self.declare_functions(&exp.left);
+                self.declare_functions(&exp.right);
+            }
+            Expression::PrefixExpression(exp) => self.declare_functions(&exp.right),
+            Expression::IfExpression(exp) => self.declare_functions(&exp.condition),
+            Expression::CallExpression(exp) => {
+                self.declare_functions(&exp.function);
+                for arg in &exp.arguments {
+                    self.declare_functions(arg);
+                }
+                for arg in &exp.named_arguments {
+                    self.declare_functions(&arg.value);
+                }
+            }
+            Expression::IndexExpression(exp) => {
+                self.declare_functions(&exp.left);
+                self.declare_functions(&exp.index);
+            }
+            Expression::MemberExpression(exp) => self.declare_functions(&exp.left),
+            _ => {}
+        }
+    }
+
+    fn current(&mut self) -> &mut Scope {
+        self.scopes.last_mut().unwrap()
+    }
+
+    /// bind identifier to the current scope
+    fn define(&mut self, ident: &mut Identifier) {
+        let slot = self.current().define(&ident.value);
+        ident.binding = Some(if self.scopes.len() == 1 {
+            Binding::Global { depth: 0 }
+        } else {
+            Binding::Local { depth: 0, slot }
+        });
+    }
+
+    fn lookup(&mut self, ident: &mut Identifier) {
+        let innermost = self.scopes.len() - 1;
+        // scopes of the current function are visible only after binding
+        let function_start = self.functions.last().copied().unwrap_or(0);
+
+        for idx in (0..=innermost).rev() {
+            let scope = &self.scopes[idx];
+            let slot = scope.slots.get(&ident.value);
+            if slot.is_none() {
+                continue;
+            }
+            if idx >= function_start && !scope.defined.contains(&ident.value) {
+                continue;
+            }
+
+            let depth = innermost - idx;
+            ident.binding = Some(if idx == 0 {
+                Binding::Global { depth }
+            } else {
+                Binding::Local {
+                    depth,
+                    slot: *slot.unwrap(),
+                }
+            });
+            return;
+        }
+
+        self.errors.push(ResolveError::UndefinedVariable {
+            name: ident.value.clone(),
+            position: ident.token.position,
+        });
+        // left for lookup by name, which fails on evaluation
+        ident.binding = Some(Binding::Global { depth: innermost });
+    }
+
+    fn block(&mut self, block: &mut BlockStatement) {
+        self.scopes.push(Scope::default());
+        self.declare_block(&block.statements);
+        for stm in block.statements.iter_mut() {
+            self.statement(stm);
+        }
+        self.scopes.pop();
+    }
+
+    fn statement(&mut self, stm: &mut Statement) {
+        match stm {
+            Statement::LetStatement(stm) => {
+                if let Some(value) = &mut stm.value {
+                    self.expression(value);
+                }
+                self.define(&mut stm.identifier);
+            }
+            Statement::ExpressionStatement(stm) => {
+                if let Some(expression) = &mut stm.expression {
+                    self.expression(expression);
+                }
+            }
+            Statement::ReturnStatement(stm) => {
+                if let Some(value) = &mut stm.value {
+                    self.expression(value);
+                }
+            }
+            Statement::BlockStatement(stm) => self.block(stm),
+            Statement::ThrowStatement(stm) => self.expression(&mut stm.value),
+            Statement::ImportStatement(stm) => self.define(&mut stm.alias),
+            Statement::ExportStatement(stm) => self.statement(&mut stm.statement),
+        }
+    }
+
+    fn expression(&mut self, exp: &mut Expression) {
+        match exp {
+            Expression::Identifier(ident) => self.lookup(ident),
+            Expression::FunctionLiteral(func) => self.function(func),
+            Expression::ArrayLiteral(arr) => {
+                for element in arr.elements.iter_mut() {
+                    self.expression(element);
+                }
+            }
+            Expression::InfixExpression(exp) => {
+                self.expression(&mut exp.left);
+                self.expression(&mut exp.right);
+            }
+            Expression::PrefixExpression(exp) => self.expression(&mut exp.right),
+            Expression::IfExpression(exp) => {
+                self.expression(&mut exp.condition);
+                self.block(&mut exp.consequence);
+                if let Some(alternative) = &mut exp.alternative {
+                    self.block(alternative);
+                }
+            }
+            // names of named arguments are matched with parameters on call
+            Expression::CallExpression(exp) => {
+                self.expression(&mut exp.function);
+                for arg in exp.arguments.iter_mut() {
+                    self.expression(arg);
+                }
+                for arg in exp.named_arguments.iter_mut() {
+                    self.expression(&mut arg.value);
+                }
+            }
+            Expression::IndexExpression(exp) => {
+                self.expression(&mut exp.left);
+                self.expression(&mut exp.index);
+            }
+            Expression::TryExpression(exp) => {
+                self.block(&mut exp.block);
+                if let Some(catch) = &mut exp.catch {
+                    self.scopes.push(Scope::default());
+                    if let Some(catch_ident) = &mut exp.catch_ident {
+                        self.define(catch_ident);
+                    }
+                    self.block(catch);
+                    self.scopes.pop();
+                }
+                if let Some(finally) = &mut exp.finally {
+                    self.block(finally);
+                }
+            }
+            // member is looked up in environment of the module
+            Expression::MemberExpression(exp) => self.expression(&mut exp.left),
+            Expression::IntegerLiteral(_)
+            | Expression::BigIntLiteral(_)
+            | Expression::BooleanLiteral(_)
+            | Expression::StringLiteral(_) => {}
+        }
+    }
+
+    fn function(&mut self, func: &mut FunctionLiteral) {
+        // body may call the function by its name
+        if let Some(ident) = &func.ident {
+            self.current().declare(&ident.value);
+        }
+
+        // scope of parameters
+        self.functions.push(self.scopes.len());
+        self.scopes.push(Scope::default());
+
+        for param in func.parameters.iter_mut() {
+            // defaults can refer to former parameters
+            if let Some(default) = &mut param.default {
+                self.expression(default);
+            }
+            self.parameter(&mut param.ident);
+        }
+        if let Some(rest) = &mut func.rest {
+            self.parameter(rest);
+        }
+
+        self.block(&mut func.body);
+
+        self.scopes.pop();
+        self.functions.pop();
+
+        if let Some(ident) = &mut func.ident {
+            self.define(ident);
+        }
+    }
+
+    fn parameter(&mut self, ident: &mut Identifier) {
+        if self.current().defined.contains(&ident.value) {
+            self.errors.push(ResolveError::DuplicatedParameter {
+                name: ident.value.clone(),
+                position: ident.token.position,
+            });
+        }
+        self.define(ident);
+    }
+}
@@ -1,3 +1,5 @@
+use crate::lexer::Position;
+
 #[allow(non_camel_case_types)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Kind {
@@ -27,6 +29,8 @@ pub enum Kind {
 
     Comma,
     Semicolon,
+    Ellipsis, // ...
+    Dot,
 
     LPAREN,   // (
     RPAREN,   // )
@@ -45,8 +49,17 @@ pub enum Kind {
     If,
     Else,
     Return,
+    Try,
+    Catch,
+    Finally,
+    Throw,
+    Import,
+    Export,
+    As,
 
     Int,
+    // integer literal with `n` suffix, literal is digits without it
+    BigInt,
     String,
 }
 
@@ -78,6 +91,8 @@ impl Kind {
 
             Kind::Comma => ",",
             Kind::Semicolon => ";",
+            Kind::Ellipsis => "...",
+            Kind::Dot => ".",
 
             Kind::LPAREN => "(",
             Kind::RPAREN => ")",
@@ -96,17 +111,27 @@ impl Kind {
             Kind::If => "if",
             Kind::Else => "else",
             Kind::Return => "return",
+            Kind::Try => "try",
+            Kind::Catch => "catch",
+            Kind::Finally => "finally",
+            Kind::Throw => "throw",
+            Kind::Import => "import",
+            Kind::Export => "export",
+            Kind::As => "as",
             Kind::Int => "Int",
+            Kind::BigInt => "BigInt",
             Kind::String => "String",
         }
     }
 }
 
 #[allow(dead_code)]
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone)]
 pub struct Token {
     pub kind: Kind,
     pub literal: String,
+    // where the token starts in the source
+    pub position: Position,
 }
 
 impl Token {
@@ -114,25 +139,49 @@ impl Token {
         Token {
             kind,
             literal: kind.to_str().to_string(),
+            position: Position::default(),
         }
     }
     pub fn with(kind: Kind, literal: &str) -> Self {
         Token {
             kind,
             literal: literal.to_string(),
+            position: Position::default(),
         }
     }
 }
 
+// position is not a part of identity of token,
+// so that same source parsed at different offsets compares equal
+impl PartialEq for Token {
+    fn eq(&self, other: &Self) -> bool {
+        self.kind == other.kind && self.literal == other.literal
+    }
+}
+
+/// reserved words and their kinds
+pub const KEYWORDS: [(&str, Kind); 14] = [
+    ("fn", Kind::Function),
+    ("let", Kind::Let),
+    ("true", Kind::True),
+    ("false", Kind::False),
+    ("if", Kind::If),
+    ("else", Kind::Else),
+    ("return", Kind::Return),
+    ("try", Kind::Try),
+    ("catch", Kind::Catch),
+    ("finally", Kind::Finally),
+    ("throw", Kind::Throw),
+    ("import", Kind::Import),
+    ("export", Kind::Export),
+    ("as", Kind::As),
+];
+
 pub fn get_token_kind(word: &str) -> Kind {
-    match word {
-        "fn" => Kind::Function,
-        "let" => Kind::Let,
-        "true" => Kind::True,
-        "false" => Kind::False,
-        "if" => Kind::If,
-        "else" => Kind::Else,
-        "return" => Kind::Return,
-        &_ => Kind::Ident,
+    for (keyword, kind) in KEYWORDS {
+        if keyword == word {
+            return kind;
+        }
     }
+    Kind::Ident
 }
@@ -0,0 +1,742 @@
+pub mod code;
+pub mod compiler;
+pub mod format;
+
+use std::{cell::RefCell, path::Path, rc::Rc};
+
+use crate::{
+    ast::Program,
+    eval::{
+        error_object,
+        errors::{ArgumentsLength, EvalError, ImportErrorDetail},
+        index_object, infix_object,
+        interrupt::InterruptHandle,
+        module::{exported_names, ModuleLoader},
+        prefix_object, read_program, thrown_error, DEFAULT_MAX_DEPTH,
+    },
+    lexer::Position,
+    object::{
+        environment::Environment, Array, Bool, Closure, Int, Module, Object,
+        ObjectTrait, ObjectType, StringObject,
+    },
+};
+
+use self::{
+    code::{Bytecode, Cell, Constant, Expect, Globals, Instruction},
+    compiler::compile,
+};
+
+/// stack-based virtual machine running compiled programs.
+/// it gives same results as the tree-walking evaluator,
+/// but only depth of calls and interruption are limited, not fuel nor memory
+pub struct Vm {
+    // values of frames, None is no value
+    stack: Vec<Option<Object>>,
+    frames: Vec<Frame>,
+    handlers: Vec<Handler>,
+    // errors being handled by catch or finally block, with position raised
+    errors: Vec<(EvalError, Position)>,
+    // stack height on entering each finally block being run
+    finallies: Vec<usize>,
+    loader: ModuleLoader,
+    // number of function calls currently running
+    depth: usize,
+    max_depth: usize,
+    interrupt: InterruptHandle,
+}
+
+struct Frame {
+    closure: Closure,
+    ip: usize,
+    // first slot of the frame
+    base: usize,
+    // stack is truncated to here on return
+    start: usize,
+    cells: Vec<Cell>,
+    // top level of program or module is not a function call
+    counted: bool,
+}
+
+/// where to go on error in try block
+struct Handler {
+    frame: usize,
+    stack: usize,
+    target: usize,
+    errors: usize,
+    finallies: usize,
+}
+
+/// error with position of the statement which raised it
+#[derive(Debug)]
+struct Fault {
+    error: EvalError,
+    position: Option<Position>,
+}
+
+impl From<EvalError> for Fault {
+    fn from(error: EvalError) -> Self {
+        Fault {
+            error,
+            position: None,
+        }
+    }
+}
+
+impl Default for Vm {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Vm {
+    pub fn new() -> Self {
+        Vm {
+            stack: Vec::new(),
+            frames: Vec::new(),
+            handlers: Vec::new(),
+            errors: Vec::new(),
+            finallies: Vec::new(),
+            loader: ModuleLoader::new(),
+            depth: 0,
+            max_depth: DEFAULT_MAX_DEPTH,
+            interrupt: InterruptHandle::new(),
+        }
+    }
+
+    /// limit nested function calls, calls in tail position are not counted.
+    /// exceeding it results in StackOverflow error
+    pub fn set_max_depth(&mut self, max_depth: usize) {
+        self.max_depth = max_depth;
+    }
+
+    /// handle to stop running program from another thread.
+    /// interrupted program results in Interrupted error
+    pub fn interrupt_handle(&self) -> InterruptHandle {
+        self.interrupt.clone()
+    }
+
+    /// compile and run source file.
+    /// imports in the file are resolved relative to the file.
+    pub fn evaluate_file(&mut self, path: &Path) -> Result<Option<Object>, EvalError> {
+        let import_path = path.display().to_string();
+        let path = path.canonicalize();
+        if path.is_err() {
+            return Err(EvalError::ModuleNotFound(ImportErrorDetail {
+                path: import_path,
+                position: None,
+                reason: path.err().unwrap().to_string(),
+            }));
+        }
+        let path = path.unwrap();
+
+        let program = read_program(&path, &import_path, None);
+        if program.is_err() {
+            return Err(program.err().unwrap());
+        }
+
+        // root file can't be a part of cycle yet
+        self.loader.enter(path).unwrap();
+        let result = self.evaluate(&program.unwrap());
+        self.loader.leave();
+
+        result
+    }
+
+    /// compile and run program
+    pub fn evaluate(&mut self, program: &Program) -> Result<Option<Object>, EvalError> {
+        let bytecode = compile(program);
+        if bytecode.is_err() {
+            return Err(bytecode.err().unwrap());
+        }
+        self.run(bytecode.unwrap())
+    }
+
+    pub fn run(&mut self, bytecode: Bytecode) -> Result<Option<Object>, EvalError> {
+        let (result, _) = self.execute(Rc::new(bytecode));
+        result.map_err(|fault| fault.error)
+    }
+
+    /// run bytecode loaded from compiled file.
+    /// imports in it are resolved relative to the file.
+    pub fn run_file(
+        &mut self,
+        path: &Path,
+        bytecode: Bytecode,
+    ) -> Result<Option<Object>, EvalError> {
+        let path = path.canonicalize();
+        if path.is_err() {
+            return self.run(bytecode);
+        }
+
+        // root file can't be a part of cycle yet
+        self.loader.enter(path.unwrap()).unwrap();
+        let result = self.run(bytecode);
+        self.loader.leave();
+
+        result
+    }
+
+    /// run top level of program with its own globals
+    fn execute(
+        &mut self,
+        program: Rc<Bytecode>,
+    ) -> (Result<Option<Object>, Fault>, Rc<RefCell<Globals>>) {
+        let globals = Rc::new(RefCell::new(Globals {
+            names: program.globals.clone(),
+            values: vec![None; program.globals.len()],
+        }));
+        let closure = Closure {
+            identifier: None,
+            program,
+            function: 0,
+            captures: Vec::new(),
+            globals: globals.clone(),
+            identity: Rc::new(()),
+        };
+
+        let base = self.frames.len();
+        let errors = self.errors.len();
+        let finallies = self.finallies.len();
+        let start = self.stack.len();
+        self.push_frame(closure, start, start, false);
+
+        loop {
+            let fault = match self.dispatch(base) {
+                Ok(value) => return (Ok(value), globals),
+                Err(fault) => fault,
+            };
+
+            let position = fault.position.unwrap_or_else(|| self.position());
+            let handler = self.handlers.last();
+            if fault.error.is_catchable() && handler.is_some_and(|h| h.frame >= base) {
+                self.unwind(fault.error, position);
+                continue;
+            }
+
+            // error is out of this program
+            self.drop_frames(base);
+            self.stack.truncate(start);
+            while self.handlers.last().is_some_and(|h| h.frame >= base) {
+                self.handlers.pop();
+            }
+            self.errors.truncate(errors);
+            self.finallies.truncate(finallies);
+
+            let fault = Fault {
+                error: fault.error,
+                position: Some(position),
+            };
+            return (Err(fault), globals);
+        }
+    }
+
+    /// jump to innermost handler
+    fn unwind(&mut self, error: EvalError, position: Position) {
+        let handler = self.handlers.pop().unwrap();
+        self.drop_frames(handler.frame + 1);
+        self.stack.truncate(handler.stack);
+        self.errors.truncate(handler.errors);
+        self.errors.push((error, position));
+        self.finallies.truncate(handler.finallies);
+        self.frames[handler.frame].ip = handler.target;
+    }
+
+    fn drop_frames(&mut self, len: usize) {
+        for frame in self.frames.drain(len..) {
+            if frame.counted {
+                self.depth -= 1;
+            }
+        }
+    }
+
+    fn push_frame(&mut self, closure: Closure, start: usize, base: usize, counted: bool) {
+        let function = &closure.program.functions[closure.function];
+        let cells = (0..function.cells.len())
+            .map(|_| Rc::new(RefCell::new(None)))
+            .collect();
+        self.stack.resize(base + function.slots as usize, None);
+        if counted {
+            self.depth += 1;
+        }
+        self.frames.push(Frame {
+            closure,
+            ip: 0,
+            base,
+            start,
+            cells,
+            counted,
+        });
+    }
+
+    /// position of statement running, or of the call which runs it
+    fn position(&self) -> Position {
+        for frame in self.frames.iter().rev() {
+            let function = &frame.closure.program.functions[frame.closure.function];
+            let position = function.position(frame.ip.saturating_sub(1));
+            if let Some(position) = position {
+                return position;
+            }
+        }
+        Position::default()
+    }
+
+    fn pop(&mut self) -> Option<Object> {
+        self.stack.pop().unwrap()
+    }
+
+    /// run instructions until frame at base returns
+    fn dispatch(&mut self, base: usize) -> Result<Option<Object>, Fault> {
+        loop {
+            let frame = self.frames.last_mut().unwrap();
+            let ins = frame.closure.program.functions[frame.closure.function].code[frame.ip];
+            frame.ip += 1;
+
+            match ins {
+                Instruction::Constant(idx) => {
+                    let obj = match &frame.closure.program.constants[idx as usize] {
+                        Constant::Int(value) => Object::Int(Int { value: *value }),
+                        Constant::BigInt(value) => Object::from(value.clone()),
+                        Constant::String(value) => Object::String(StringObject {
+                            value: value.clone(),
+                        }),
+                        Constant::Names(_) => unreachable!("names are not a value"),
+                    };
+                    self.stack.push(Some(obj));
+                }
+                Instruction::True => self.stack.push(Some(Object::Bool(Bool { value: true }))),
+                Instruction::False => self.stack.push(Some(Object::Bool(Bool { value: false }))),
+                Instruction::Nothing => self.stack.push(None),
+                Instruction::Pop => {
+                    self.stack.pop();
+                }
+
+                Instruction::GetGlobal(slot) => {
+                    let globals = frame.closure.globals.borrow();
+                    let value = globals.values[slot as usize].clone();
+                    if value.is_none() {
+                        let name = globals.names[slot as usize].clone();
+                        return Err(EvalError::IdentifierNotFound(name).into());
+                    }
+                    drop(globals);
+                    self.stack.push(value);
+                }
+                Instruction::SetGlobal(slot) => {
+                    let globals = frame.closure.globals.clone();
+                    globals.borrow_mut().values[slot as usize] = self.pop();
+                }
+                Instruction::GetLocal(slot) => {
+                    let value = self.stack[frame.base + slot as usize].clone();
+                    self.stack.push(value);
+                }
+                Instruction::SetLocal(slot) => {
+                    let slot = frame.base + slot as usize;
+                    self.stack[slot] = self.stack.pop().unwrap();
+                }
+                Instruction::GetCell(idx) => {
+                    let value = frame.cells[idx as usize].borrow().clone();
+                    if value.is_none() {
+                        let function = &frame.closure.program.functions[frame.closure.function];
+                        let name = function.cells[idx as usize].clone();
+                        return Err(EvalError::IdentifierNotFound(name).into());
+                    }
+                    self.stack.push(value);
+                }
+                Instruction::SetCell(idx) => {
+                    let cell = frame.cells[idx as usize].clone();
+                    *cell.borrow_mut() = self.pop();
+                }
+                Instruction::GetCapture(idx) => {
+                    let value = frame.closure.captures[idx as usize].borrow().clone();
+                    if value.is_none() {
+                        let function = &frame.closure.program.functions[frame.closure.function];
+                        let name = function.captures[idx as usize].0.clone();
+                        return Err(EvalError::IdentifierNotFound(name).into());
+                    }
+                    self.stack.push(value);
+                }
+                Instruction::NameFunction(name) => {
+                    let Constant::String(name) = &frame.closure.program.constants[name as usize]
+                    else {
+                        unreachable!("name of function is a string")
+                    };
+                    if let Some(Some(Object::Closure(closure))) = self.stack.last_mut() {
+                        closure.identifier = Some(name.clone());
+                    }
+                }
+
+                Instruction::Closure(idx) => {
+                    let function = &frame.closure.program.functions[idx as usize];
+                    let mut captures = Vec::new();
+                    for (_, capture) in function.captures.iter() {
+                        captures.push(match capture {
+                            code::Capture::Cell(cell) => frame.cells[*cell as usize].clone(),
+                            code::Capture::Capture(idx) => {
+                                frame.closure.captures[*idx as usize].clone()
+                            }
+                        });
+                    }
+                    let closure = Closure {
+                        identifier: function.name.clone(),
+                        program: frame.closure.program.clone(),
+                        function: idx as usize,
+                        captures,
+                        globals: frame.closure.globals.clone(),
+                        identity: Rc::new(()),
+                    };
+                    self.stack.push(Some(Object::Closure(closure)));
+                }
+                Instruction::Array(len) => {
+                    let from = self.stack.len() - len as usize;
+                    let elements = self.stack.drain(from..).map(Option::unwrap).collect();
+                    self.stack.push(Some(Object::Array(Array { elements })));
+                }
+                Instruction::Infix(operator) => {
+                    let right = self.pop().unwrap();
+                    let left = self.pop().unwrap();
+                    let result = infix_object(left, operator, right);
+                    if result.is_err() {
+                        return Err(result.err().unwrap().into());
+                    }
+                    self.stack.push(Some(result.unwrap()));
+                }
+                Instruction::Prefix(operator) => {
+                    let right = self.pop().unwrap();
+                    let result = prefix_object(operator, right);
+                    if result.is_err() {
+                        return Err(result.err().unwrap().into());
+                    }
+                    self.stack.push(Some(result.unwrap()));
+                }
+                Instruction::Index => {
+                    let index = self.pop().unwrap();
+                    let left = self.pop().unwrap();
+                    let result = index_object(left, index);
+                    if result.is_err() {
+                        return Err(result.err().unwrap().into());
+                    }
+                    self.stack.push(result.unwrap());
+                }
+                Instruction::Member(name) => {
+                    let Constant::String(name) = &frame.closure.program.constants[name as usize]
+                    else {
+                        unreachable!("member is a string")
+                    };
+                    let name = name.clone();
+                    let left = self.pop().unwrap();
+                    let Object::Module(module) = left else {
+                        return Err(EvalError::NotAModule(Box::new(left)).into());
+                    };
+                    if !module.exports.contains(&name) {
+                        return Err(EvalError::NotExported(name).into());
+                    }
+                    let obj = module.env.borrow().get_clone(&name);
+                    if obj.is_none() {
+                        return Err(EvalError::IdentifierNotFound(name).into());
+                    }
+                    self.stack.push(obj);
+                }
+                Instruction::Expect(expect) => {
+                    let top = self.stack.last().unwrap();
+                    let checked = match (expect, top) {
+                        (Expect::Value(exp), None) => {
+                            let exp = frame.closure.program.expressions[exp as usize].clone();
+                            Err(EvalError::EvaluationOfExpressionIsNone(Box::new(exp)))
+                        }
+                        (Expect::Left, None) => Err(EvalError::LeftExpressionIsNone),
+                        (Expect::Right, None) => Err(EvalError::RightExpressionIsNone),
+                        (Expect::Element, None) => Err(EvalError::ElementIsNone),
+                        (Expect::Callee, None) => Err(EvalError::FunctionIsNone),
+                        (Expect::Callee, Some(Object::Closure(_))) => Ok(()),
+                        (Expect::Callee, Some(obj)) => {
+                            Err(EvalError::NotAFunction(Box::new(obj.clone())))
+                        }
+                        (Expect::Indexable | Expect::Index, None) => Err(EvalError::ArrayIsNone),
+                        (Expect::Indexable, Some(obj))
+                            if obj.get_type() != ObjectType::Array
+                                && obj.get_type() != ObjectType::Error =>
+                        {
+                            Err(EvalError::NotArray)
+                        }
+                        _ => Ok(()),
+                    };
+                    if checked.is_err() {
+                        return Err(checked.err().unwrap().into());
+                    }
+                }
+
+                Instruction::Jump(target) => frame.ip = target as usize,
+                Instruction::JumpIfFalse(target) => {
+                    let condition = self.stack.pop().unwrap();
+                    match condition {
+                        None => return Err(EvalError::ConditionIsNone.into()),
+                        Some(Object::Bool(flag)) => {
+                            if !flag.value {
+                                self.frames.last_mut().unwrap().ip = target as usize;
+                            }
+                        }
+                        Some(obj) => return Err(EvalError::NotABoolean(Box::new(obj)).into()),
+                    }
+                }
+
+                Instruction::Call(args, names) => {
+                    let called = self.call(args, names, false);
+                    if called.is_err() {
+                        return Err(called.err().unwrap().into());
+                    }
+                }
+                Instruction::TailCall(args, names) => {
+                    let called = self.call(args, names, true);
+                    if called.is_err() {
+                        return Err(called.err().unwrap().into());
+                    }
+                }
+                Instruction::Return => {
+                    let value = self.pop();
+                    let frame = self.frames.pop().unwrap();
+                    if frame.counted {
+                        self.depth -= 1;
+                    }
+                    self.stack.truncate(frame.start);
+                    if self.frames.len() == base {
+                        return Ok(value);
+                    }
+                    self.stack.push(value);
+                }
+
+                Instruction::Throw => {
+                    let obj = self.pop().unwrap();
+                    return Err(thrown_error(obj, self.position()).into());
+                }
+                Instruction::PushHandler(target) => {
+                    let handler = Handler {
+                        frame: self.frames.len() - 1,
+                        stack: self.stack.len(),
+                        target: target as usize,
+                        errors: self.errors.len(),
+                        finallies: self.finallies.len(),
+                    };
+                    self.handlers.push(handler);
+                }
+                Instruction::PopHandler => {
+                    self.handlers.pop();
+                }
+                Instruction::Catch => {
+                    let (error, position) = self.errors.pop().unwrap();
+                    let error = error_object(error, position);
+                    self.stack.push(Some(Object::Error(error)));
+                }
+                Instruction::Rethrow => {
+                    let (error, position) = self.errors.pop().unwrap();
+                    return Err(Fault {
+                        error,
+                        position: Some(position),
+                    });
+                }
+                Instruction::EnterFinally => self.finallies.push(self.stack.len()),
+                Instruction::LeaveFinally => {
+                    self.finallies.pop();
+                }
+                Instruction::AbortFinally(target) => {
+                    frame.ip = target as usize;
+                    let height = *self.finallies.last().unwrap();
+                    self.stack.truncate(height);
+                    self.stack.push(None);
+                }
+
+                Instruction::Import(path) => {
+                    let Constant::String(path) = &frame.closure.program.constants[path as usize]
+                    else {
+                        unreachable!("path of import is a string")
+                    };
+                    let path = path.clone();
+                    let module = self.load_module(&path);
+                    if module.is_err() {
+                        return Err(module.err().unwrap());
+                    }
+                    self.stack.push(Some(Object::Module(module.unwrap())));
+                }
+
+                Instruction::Default(slot, target) => {
+                    if self.stack[frame.base + slot as usize].is_some() {
+                        frame.ip = target as usize;
+                    }
+                }
+                Instruction::Required(slot) => {
+                    if self.stack[frame.base + slot as usize].is_none() {
+                        let function = &frame.closure.program.functions[frame.closure.function];
+                        let name = function.parameters[slot as usize].clone();
+                        return Err(EvalError::MissingArgument(name).into());
+                    }
+                }
+                Instruction::MoveToCell(slot, cell) => {
+                    let value = self.stack[frame.base + slot as usize].take();
+                    *frame.cells[cell as usize].borrow_mut() = value;
+                }
+            }
+        }
+    }
+
+    /// call closure under its arguments on stack.
+    /// tail call replaces frame of caller
+    fn call(&mut self, args: u32, names: Option<u32>, tail: bool) -> Result<(), EvalError> {
+        if !tail && self.depth >= self.max_depth {
+            return Err(EvalError::StackOverflow { depth: self.depth });
+        }
+        if self.interrupt.take() {
+            return Err(EvalError::Interrupted);
+        }
+
+        let caller = self.frames.last().unwrap();
+        let names = match names {
+            Some(idx) => match &caller.closure.program.constants[idx as usize] {
+                Constant::Names(names) => names.clone(),
+                _ => unreachable!("named arguments are names"),
+            },
+            None => Vec::new(),
+        };
+
+        let named_from = self.stack.len() - names.len();
+        let callee_idx = named_from - args as usize - 1;
+        let Some(Object::Closure(closure)) = self.stack[callee_idx].take() else {
+            unreachable!("callee is checked to be a function")
+        };
+        let function = &closure.program.functions[closure.function];
+
+        let min_args = function
+            .defaults
+            .iter()
+            .filter(|default| !**default)
+            .count();
+        let max_args = if function.rest.is_some() {
+            None
+        } else {
+            Some(function.parameters.len())
+        };
+        let called_with = args as usize + names.len();
+        if called_with < min_args || max_args.is_some_and(|max| called_with > max) {
+            return Err(EvalError::FunctionArgLengthNotMatched(ArgumentsLength {
+                min_args,
+                max_args,
+                called_with,
+            }));
+        }
+
+        // arrange arguments into parameter slots
+        let named: Vec<Option<Object>> = self.stack.drain(named_from..).collect();
+        let params = function.parameters.len();
+        let base = callee_idx + 1;
+        let mut rest = Vec::new();
+        if args as usize > params {
+            rest = self
+                .stack
+                .drain(base + params..)
+                .map(Option::unwrap)
+                .collect();
+        }
+        self.stack.resize(base + params, None);
+
+        for (name, value) in names.into_iter().zip(named) {
+            let idx = function.parameters.iter().position(|param| param == &name);
+            if idx.is_none() {
+                return Err(EvalError::UnknownNamedArgument(name));
+            }
+            let slot = &mut self.stack[base + idx.unwrap()];
+            if slot.is_some() {
+                return Err(EvalError::DuplicatedArgument(name));
+            }
+            *slot = value;
+        }
+        if function.rest.is_some() {
+            self.stack
+                .push(Some(Object::Array(Array { elements: rest })));
+        }
+
+        if !tail {
+            self.push_frame(closure, callee_idx, base, true);
+            return Ok(());
+        }
+
+        // move arguments down to the frame of caller
+        let caller = self.frames.pop().unwrap();
+        self.stack.drain(caller.base..base);
+        self.push_frame(closure, caller.start, caller.base, false);
+        self.frames.last_mut().unwrap().counted = caller.counted;
+        Ok(())
+    }
+
+    fn load_module(&mut self, import_path: &str) -> Result<Module, Fault> {
+        let position = self.position();
+
+        let path = self.loader.resolve(import_path).canonicalize();
+        if path.is_err() {
+            return Err(EvalError::ModuleNotFound(ImportErrorDetail {
+                path: import_path.to_string(),
+                position: Some(position),
+                reason: path.err().unwrap().to_string(),
+            })
+            .into());
+        }
+        let path = path.unwrap();
+
+        // each module is run only once
+        let cached = self.loader.get(&path);
+        if let Some(cached) = cached {
+            return Ok(cached);
+        }
+
+        let entered = self.loader.enter(path.clone());
+        if entered.is_err() {
+            let mut chain = Vec::new();
+            for file in entered.err().unwrap() {
+                chain.push(file.display().to_string());
+            }
+            return Err(EvalError::ImportCycle(ImportErrorDetail {
+                path: import_path.to_string(),
+                position: Some(position),
+                reason: format!("import cycle {}", chain.join(" -> ")),
+            })
+            .into());
+        }
+
+        let program = read_program(&path, import_path, Some(position));
+        if program.is_err() {
+            self.loader.leave();
+            return Err(program.err().unwrap().into());
+        }
+        let program = program.unwrap();
+
+        let module = Module {
+            path: path.display().to_string(),
+            env: Rc::new(RefCell::new(Environment::new())),
+            exports: exported_names(&program),
+        };
+
+        if !program.statements.is_empty() {
+            let bytecode = compile(&program);
+            if bytecode.is_err() {
+                self.loader.leave();
+                return Err(bytecode.err().unwrap().into());
+            }
+
+            let (result, globals) = self.execute(Rc::new(bytecode.unwrap()));
+            if result.is_err() {
+                self.loader.leave();
+                return Err(result.err().unwrap());
+            }
+
+            let globals = globals.borrow();
+            for (name, value) in globals.names.iter().zip(globals.values.iter()) {
+                if value.is_some() {
+                    module
+                        .env
+                        .borrow_mut()
+                        .set(name.clone(), value.clone().unwrap());
+                }
+            }
+        }
+        self.loader.leave();
+
+        self.loader.insert(path, module.clone());
+        Ok(module)
+    }
+}
@@ -0,0 +1,166 @@
+use std::{cell::RefCell, rc::Rc};
+
+use crate::{
+    ast::Expression,
+    lexer::Position,
+    object::{bigint::BigInt, Object},
+    token::Kind,
+};
+
+/// variable shared between function and closures created in it
+pub type Cell = Rc<RefCell<Option<Object>>>;
+
+/// instruction of vm.
+/// operands index constant pool, function table or slots of the frame
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Instruction {
+    Constant(u32),
+    True,
+    False,
+    // push no value, like a let statement or if without else
+    Nothing,
+    Pop,
+
+    GetGlobal(u32),
+    SetGlobal(u32),
+    GetLocal(u32),
+    SetLocal(u32),
+    GetCell(u32),
+    SetCell(u32),
+    GetCapture(u32),
+    // name function on top of stack, as let statement does
+    NameFunction(u32),
+
+    Closure(u32),
+    Array(u32),
+    Infix(Kind),
+    Prefix(Kind),
+    Index,
+    Member(u32),
+    Expect(Expect),
+
+    Jump(u32),
+    // condition must be a boolean
+    JumpIfFalse(u32),
+
+    // positional arguments, and constant of names for named arguments
+    Call(u32, Option<u32>),
+    TailCall(u32, Option<u32>),
+    Return,
+
+    Throw,
+    // jump to operand on error, until handler is popped
+    PushHandler(u32),
+    PopHandler,
+    // push caught error as an error object
+    Catch,
+    Rethrow,
+    EnterFinally,
+    LeaveFinally,
+    // return in finally block ends the block only, jump to its LeaveFinally
+    AbortFinally(u32),
+
+    Import(u32),
+
+    // prologue of function, for parameters not given by caller
+    Default(u32, u32),
+    Required(u32),
+    MoveToCell(u32, u32),
+}
+
+/// check of value on top of stack, each fails with its own error
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Expect {
+    // index of expression table
+    Value(u32),
+    Left,
+    Right,
+    Element,
+    Callee,
+    Indexable,
+    Index,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Constant {
+    Int(i64),
+    BigInt(BigInt),
+    String(String),
+    // names of named arguments of call
+    Names(Vec<String>),
+}
+
+/// how closure gets a variable of enclosing function
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Capture {
+    Cell(u32),
+    Capture(u32),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompiledFunction {
+    pub name: Option<String>,
+    // parameter names, slot of each parameter is its index
+    pub parameters: Vec<String>,
+    pub defaults: Vec<bool>,
+    // parameters as written, to print function
+    pub signature: Vec<String>,
+    // body as written, to print function
+    pub body: String,
+    // slot of rest parameter follows parameters
+    pub rest: Option<String>,
+    pub slots: u32,
+    // names of cells, for errors
+    pub cells: Vec<String>,
+    pub captures: Vec<(String, Capture)>,
+    pub code: Vec<Instruction>,
+    // position of statement which starts at the instruction
+    pub lines: Vec<(u32, Position)>,
+}
+
+impl CompiledFunction {
+    pub fn new(name: Option<String>) -> Self {
+        CompiledFunction {
+            name,
+            parameters: Vec::new(),
+            defaults: Vec::new(),
+            signature: Vec::new(),
+            body: String::new(),
+            rest: None,
+            slots: 0,
+            cells: Vec::new(),
+            captures: Vec::new(),
+            code: Vec::new(),
+            lines: Vec::new(),
+        }
+    }
+
+    /// position of statement which holds the instruction
+    pub fn position(&self, ip: usize) -> Option<Position> {
+        let idx = self
+            .lines
+            .partition_point(|(start, _)| *start as usize <= ip);
+        if idx == 0 {
+            return None;
+        }
+        Some(self.lines[idx - 1].1)
+    }
+}
+
+/// compiled program, first function is the top level
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bytecode {
+    pub constants: Vec<Constant>,
+    pub functions: Vec<CompiledFunction>,
+    // names of global slots
+    pub globals: Vec<String>,
+    // expressions reported by errors
+    pub expressions: Vec<Expression>,
+}
+
+/// global variables of a program
+#[derive(Debug)]
+pub struct Globals {
+    pub names: Vec<String>,
+    pub values: Vec<Option<Object>>,
+}
@@ -0,0 +1,949 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    ast::{
+        BlockStatement, CallExpression, Expression, ExpressionStatement, FunctionLiteral,
+        IfExpression, Nodetrait, Program, ReturnStatement, Statement, TryExpression,
+    },
+    eval::errors::EvalError,
+    lexer::Position,
+};
+
+use super::code::{Bytecode, Capture, CompiledFunction, Constant, Expect, Instruction};
+
+/// compile program to bytecode, top level statements become the first function
+pub fn compile(program: &Program) -> Result<Bytecode, EvalError> {
+    Compiler::new().compile(program)
+}
+
+pub struct Compiler {
+    constants: Vec<Constant>,
+    functions: Vec<CompiledFunction>,
+    globals: Vec<String>,
+    global_slots: HashMap<String, u32>,
+    expressions: Vec<Expression>,
+    // functions being compiled, innermost last
+    scopes: Vec<FunctionScope>,
+}
+
+struct FunctionScope {
+    function: CompiledFunction,
+    // block scopes, innermost last.
+    // names on top level of program, outside of any block, are global
+    blocks: Vec<HashMap<String, Local>>,
+    // names referred by closures created in this function, they are kept in cells
+    captured: HashSet<String>,
+    tries: Vec<TryContext>,
+    // positions of statements being compiled, innermost last
+    positions: Vec<Position>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Local {
+    place: Place,
+    // declared ahead for closures, but not bound by its let statement yet
+    defined: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Place {
+    Slot(u32),
+    Cell(u32),
+}
+
+enum Access {
+    Local(Place),
+    Capture(u32),
+    Global,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TryState {
+    Block,
+    Catch,
+    Finally,
+}
+
+struct TryContext {
+    state: TryState,
+    // handler is pushed while compiling try block, or catch block with finally
+    handler: bool,
+    finally: Option<BlockStatement>,
+    // return statements of finally block, jumping to its end
+    aborts: Vec<usize>,
+}
+
+impl Default for Compiler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Compiler {
+    pub fn new() -> Self {
+        Compiler {
+            constants: Vec::new(),
+            functions: Vec::new(),
+            globals: Vec::new(),
+            global_slots: HashMap::new(),
+            expressions: Vec::new(),
+            scopes: Vec::new(),
+        }
+    }
+
+    pub fn compile(mut self, program: &Program) -> Result<Bytecode, EvalError> {
+        if program.statements.is_empty() {
+            return Err(EvalError::BlankResult);
+        }
+
+        // reserve first function for top level
+        self.functions.push(CompiledFunction::new(None));
+
+        let mut captured = HashSet::new();
+        for stm in program.statements.iter() {
+            collect_captured_stm(stm, false, &mut captured);
+        }
+        self.scopes
+            .push(FunctionScope::new(CompiledFunction::new(None), captured));
+
+        let last_idx = program.statements.len() - 1;
+        for (idx, stm) in program.statements.iter().enumerate() {
+            let compiled = self.compile_stm(stm, None);
+            if compiled.is_err() {
+                return Err(compiled.err().unwrap());
+            }
+            if idx != last_idx {
+                self.emit(Instruction::Pop);
+            }
+        }
+        self.emit(Instruction::Return);
+
+        let scope = self.scopes.pop().unwrap();
+        self.functions[0] = scope.function;
+
+        Ok(Bytecode {
+            constants: self.constants,
+            functions: self.functions,
+            globals: self.globals,
+            expressions: self.expressions,
+        })
+    }
+
+    fn scope(&mut self) -> &mut FunctionScope {
+        self.scopes.last_mut().unwrap()
+    }
+
+    fn emit(&mut self, ins: Instruction) -> usize {
+        let code = &mut self.scope().function.code;
+        code.push(ins);
+        code.len() - 1
+    }
+
+    fn ip(&mut self) -> u32 {
+        self.scope().function.code.len() as u32
+    }
+
+    /// point jump at idx to current instruction
+    fn patch(&mut self, idx: usize) {
+        let target = self.ip();
+        let code = &mut self.scope().function.code;
+        code[idx] = match code[idx] {
+            Instruction::Jump(_) => Instruction::Jump(target),
+            Instruction::JumpIfFalse(_) => Instruction::JumpIfFalse(target),
+            Instruction::PushHandler(_) => Instruction::PushHandler(target),
+            Instruction::AbortFinally(_) => Instruction::AbortFinally(target),
+            Instruction::Default(slot, _) => Instruction::Default(slot, target),
+            ins => unreachable!("{:?} is not a jump", ins),
+        };
+    }
+
+    fn constant(&mut self, constant: Constant) -> u32 {
+        self.constants.push(constant);
+        (self.constants.len() - 1) as u32
+    }
+
+    fn expression(&mut self, exp: &Expression) -> u32 {
+        self.expressions.push(exp.clone());
+        (self.expressions.len() - 1) as u32
+    }
+
+    fn global_slot(&mut self, name: &str) -> u32 {
+        if let Some(slot) = self.global_slots.get(name) {
+            return *slot;
+        }
+        let slot = self.globals.len() as u32;
+        self.globals.push(name.to_string());
+        self.global_slots.insert(name.to_string(), slot);
+        slot
+    }
+
+    /// record position of statement which starts here
+    fn mark_position(&mut self, position: Position) {
+        let ip = self.ip();
+        let lines = &mut self.scope().function.lines;
+        if lines.last().is_some_and(|(start, _)| *start == ip) {
+            lines.pop();
+        }
+        lines.push((ip, position));
+    }
+
+    fn compile_stm(&mut self, stm: &Statement, tail: Option<bool>) -> Result<(), EvalError> {
+        let position = stm.token().position;
+        self.mark_position(position);
+        self.scope().positions.push(position);
+
+        let result = self.dispatch_stm(stm, tail);
+
+        // rest of outer statement
+        self.scope().positions.pop();
+        let outer = self.scope().positions.last().copied();
+        if let Some(outer) = outer {
+            self.mark_position(outer);
+        }
+        result
+    }
+
+    fn dispatch_stm(&mut self, stm: &Statement, tail: Option<bool>) -> Result<(), EvalError> {
+        match stm {
+            Statement::LetStatement(stm) => {
+                if stm.value.is_none() {
+                    return Err(EvalError::LetStatementValueIsNone);
+                }
+                let value = stm.value.as_ref().unwrap();
+                let compiled = self.compile_exp(value);
+                if compiled.is_err() {
+                    return Err(compiled.err().unwrap());
+                }
+                let exp = self.expression(value);
+                self.emit(Instruction::Expect(Expect::Value(exp)));
+
+                let name = self.constant(Constant::String(stm.identifier.value.clone()));
+                self.emit(Instruction::NameFunction(name));
+                self.define(&stm.identifier.value);
+                self.emit(Instruction::Nothing);
+                Ok(())
+            }
+
+            // calls in tail position are not evaluated on stack of caller
+            Statement::ExpressionStatement(ExpressionStatement {
+                expression: Some(Expression::CallExpression(call)),
+                ..
+            }) if tail == Some(true) => self.compile_call(call, true),
+
+            Statement::ExpressionStatement(ExpressionStatement {
+                expression: Some(Expression::IfExpression(exp)),
+                ..
+            }) if tail.is_some() => self.compile_if(exp, tail),
+
+            Statement::ExpressionStatement(stm) => {
+                if stm.expression.is_none() {
+                    self.emit(Instruction::Nothing);
+                    return Ok(());
+                }
+                self.compile_exp(stm.expression.as_ref().unwrap())
+            }
+
+            Statement::ReturnStatement(stm) => self.compile_return(stm, tail.is_some()),
+
+            Statement::BlockStatement(stm) => self.compile_block(stm, None),
+
+            Statement::ThrowStatement(stm) => {
+                let compiled = self.compile_exp(&stm.value);
+                if compiled.is_err() {
+                    return Err(compiled.err().unwrap());
+                }
+                let exp = self.expression(&stm.value);
+                self.emit(Instruction::Expect(Expect::Value(exp)));
+                self.emit(Instruction::Throw);
+                Ok(())
+            }
+
+            Statement::ImportStatement(stm) => {
+                let path = self.constant(Constant::String(stm.path.value.clone()));
+                self.emit(Instruction::Import(path));
+                self.define(&stm.alias.value);
+                self.emit(Instruction::Nothing);
+                Ok(())
+            }
+
+            Statement::ExportStatement(stm) => self.compile_stm(&stm.statement, None),
+        }
+    }
+
+    /// compile block in new scope, it leaves value of its last statement.
+    /// `tail` is set for blocks of function body, and is true if
+    /// the last statement of the block is in tail position
+    fn compile_block(
+        &mut self,
+        block: &BlockStatement,
+        tail: Option<bool>,
+    ) -> Result<(), EvalError> {
+        self.enter_block(&block.statements);
+
+        if block.statements.is_empty() {
+            self.emit(Instruction::Nothing);
+        }
+
+        let last_idx = block.statements.len().saturating_sub(1);
+        for (idx, stm) in block.statements.iter().enumerate() {
+            let is_tail = tail.map(|tail| tail && idx == last_idx);
+            let compiled = self.compile_stm(stm, is_tail);
+            if compiled.is_err() {
+                return Err(compiled.err().unwrap());
+            }
+            if idx != last_idx {
+                self.emit(Instruction::Pop);
+            }
+        }
+
+        self.scope().blocks.pop();
+        Ok(())
+    }
+
+    /// open scope, and declare cells of names which closures in the block refer
+    fn enter_block(&mut self, statements: &[Statement]) {
+        self.scope().blocks.push(HashMap::new());
+
+        for stm in statements.iter() {
+            let name = declared_name(stm);
+            if name.is_none() {
+                continue;
+            }
+            let name = name.unwrap();
+            let scope = self.scope();
+            if scope.captured.contains(&name) && !scope.blocks.last().unwrap().contains_key(&name) {
+                let cell = scope.new_cell(&name);
+                scope.blocks.last_mut().unwrap().insert(
+                    name,
+                    Local {
+                        place: cell,
+                        defined: false,
+                    },
+                );
+            }
+        }
+    }
+
+    fn compile_exp(&mut self, exp: &Expression) -> Result<(), EvalError> {
+        match exp {
+            Expression::Identifier(ident) => {
+                self.load(&ident.value);
+                Ok(())
+            }
+            Expression::IntegerLiteral(lit) => {
+                let constant = self.constant(Constant::Int(lit.value));
+                self.emit(Instruction::Constant(constant));
+                Ok(())
+            }
+            Expression::BigIntLiteral(lit) => {
+                let constant = self.constant(Constant::BigInt(lit.value.clone()));
+                self.emit(Instruction::Constant(constant));
+                Ok(())
+            }
+            Expression::BooleanLiteral(lit) => {
+                if lit.value {
+                    self.emit(Instruction::True);
+                } else {
+                    self.emit(Instruction::False);
+                }
+                Ok(())
+            }
+            Expression::StringLiteral(lit) => {
+                let constant = self.constant(Constant::String(lit.value.clone()));
+                self.emit(Instruction::Constant(constant));
+                Ok(())
+            }
+
+            Expression::FunctionLiteral(func) => {
+                let function = self.compile_function(func);
+                if function.is_err() {
+                    return Err(function.err().unwrap());
+                }
+                self.emit(Instruction::Closure(function.unwrap()));
+
+                // named function is bound to current scope too
+                if let Some(ident) = &func.ident {
+                    let name = &ident.value;
+                    self.define(name);
+                    self.load(name);
+                }
+                Ok(())
+            }
+
+            Expression::ArrayLiteral(arr) => {
+                for element in arr.elements.iter() {
+                    let compiled = self.compile_exp(element);
+                    if compiled.is_err() {
+                        return Err(compiled.err().unwrap());
+                    }
+                    self.emit(Instruction::Expect(Expect::Element));
+                }
+                self.emit(Instruction::Array(arr.elements.len() as u32));
+                Ok(())
+            }
+
+            Expression::InfixExpression(exp) => {
+                let compiled = self.compile_exp(&exp.left);
+                if compiled.is_err() {
+                    return Err(compiled.err().unwrap());
+                }
+                self.emit(Instruction::Expect(Expect::Left));
+
+                let compiled = self.compile_exp(&exp.right);
+                if compiled.is_err() {
+                    return Err(compiled.err().unwrap());
+                }
+                self.emit(Instruction::Expect(Expect::Right));
+
+                self.emit(Instruction::Infix(exp.operator.kind));
+                Ok(())
+            }
+
+            Expression::PrefixExpression(exp) => {
+                let compiled = self.compile_exp(&exp.right);
+                if compiled.is_err() {
+                    return Err(compiled.err().unwrap());
+                }
+                let right = self.expression(&exp.right);
+                self.emit(Instruction::Expect(Expect::Value(right)));
+                self.emit(Instruction::Prefix(exp.token.kind));
+                Ok(())
+            }
+
+            Expression::IfExpression(exp) => self.compile_if(exp, None),
+            Expression::CallExpression(exp) => self.compile_call(exp, false),
+
+            Expression::IndexExpression(exp) => {
+                let compiled = self.compile_exp(&exp.left);
+                if compiled.is_err() {
+                    return Err(compiled.err().unwrap());
+                }
+                self.emit(Instruction::Expect(Expect::Indexable));
+
+                let compiled = self.compile_exp(&exp.index);
+                if compiled.is_err() {
+                    return Err(compiled.err().unwrap());
+                }
+                self.emit(Instruction::Expect(Expect::Index));
+                self.emit(Instruction::Index);
+                Ok(())
+            }
+
+            Expression::TryExpression(exp) => self.compile_try(exp),
+
+            Expression::MemberExpression(exp) => {
+                let compiled = self.compile_exp(&exp.left);
+                if compiled.is_err() {
+                    return Err(compiled.err().unwrap());
+                }
+                let left = self.expression(&exp.left);
+                self.emit(Instruction::Expect(Expect::Value(left)));
+
+                let name = self.constant(Constant::String(exp.member.value.clone()));
+                self.emit(Instruction::Member(name));
+                Ok(())
+            }
+        }
+    }
+
+    fn compile_if(&mut self, exp: &IfExpression, tail: Option<bool>) -> Result<(), EvalError> {
+        let compiled = self.compile_exp(&exp.condition);
+        if compiled.is_err() {
+            return Err(compiled.err().unwrap());
+        }
+        let jump_else = self.emit(Instruction::JumpIfFalse(0));
+
+        let compiled = self.compile_block(&exp.consequence, tail);
+        if compiled.is_err() {
+            return Err(compiled.err().unwrap());
+        }
+        let jump_end = self.emit(Instruction::Jump(0));
+
+        self.patch(jump_else);
+        if let Some(alternative) = &exp.alternative {
+            let compiled = self.compile_block(alternative, tail);
+            if compiled.is_err() {
+                return Err(compiled.err().unwrap());
+            }
+        } else {
+            self.emit(Instruction::Nothing);
+        }
+        self.patch(jump_end);
+        Ok(())
+    }
+
+    fn compile_call(&mut self, call: &CallExpression, tail: bool) -> Result<(), EvalError> {
+        let compiled = self.compile_exp(&call.function);
+        if compiled.is_err() {
+            return Err(compiled.err().unwrap());
+        }
+        self.emit(Instruction::Expect(Expect::Callee));
+
+        for arg in call.arguments.iter() {
+            let compiled = self.compile_exp(arg);
+            if compiled.is_err() {
+                return Err(compiled.err().unwrap());
+            }
+            let exp = self.expression(arg);
+            self.emit(Instruction::Expect(Expect::Value(exp)));
+        }
+
+        let mut names = None;
+        if !call.named_arguments.is_empty() {
+            let mut idents = Vec::new();
+            for arg in call.named_arguments.iter() {
+                let compiled = self.compile_exp(&arg.value);
+                if compiled.is_err() {
+                    return Err(compiled.err().unwrap());
+                }
+                let exp = self.expression(&arg.value);
+                self.emit(Instruction::Expect(Expect::Value(exp)));
+                idents.push(arg.ident.value.clone());
+            }
+            names = Some(self.constant(Constant::Names(idents)));
+        }
+
+        let args = call.arguments.len() as u32;
+        if tail {
+            self.emit(Instruction::TailCall(args, names));
+        } else {
+            self.emit(Instruction::Call(args, names));
+        }
+        Ok(())
+    }
+
+    fn compile_return(&mut self, stm: &ReturnStatement, tail: bool) -> Result<(), EvalError> {
+        // return in tail position hands the call over to caller
+        if let Some(Expression::CallExpression(call)) = &stm.value {
+            if tail {
+                return self.compile_call(call, true);
+            }
+        }
+
+        if let Some(value) = &stm.value {
+            let compiled = self.compile_exp(value);
+            if compiled.is_err() {
+                return Err(compiled.err().unwrap());
+            }
+        } else {
+            self.emit(Instruction::Nothing);
+        }
+
+        // leave enclosing try expressions, innermost first
+        let mut idx = self.scope().tries.len();
+        while idx > 0 {
+            idx -= 1;
+            let ctx = &self.scope().tries[idx];
+
+            // return in finally block ends the finally block only
+            if ctx.state == TryState::Finally {
+                let abort = self.emit(Instruction::AbortFinally(0));
+                self.scope().tries[idx].aborts.push(abort);
+                return Ok(());
+            }
+
+            let finally = ctx.finally.clone();
+            if ctx.handler {
+                self.emit(Instruction::PopHandler);
+            }
+            if let Some(finally) = &finally {
+                // finally block sees only the try expressions outside of it
+                let inner = self.scope().tries.split_off(idx);
+                let compiled = self.compile_finally(finally);
+                self.scope().tries.extend(inner);
+                if compiled.is_err() {
+                    return Err(compiled.err().unwrap());
+                }
+            }
+        }
+
+        self.emit(Instruction::Return);
+        Ok(())
+    }
+
+    fn compile_try(&mut self, exp: &TryExpression) -> Result<(), EvalError> {
+        self.scope().tries.push(TryContext {
+            state: TryState::Block,
+            handler: true,
+            finally: exp.finally.clone(),
+            aborts: Vec::new(),
+        });
+
+        let push = self.emit(Instruction::PushHandler(0));
+        let compiled = self.compile_block(&exp.block, None);
+        if compiled.is_err() {
+            return Err(compiled.err().unwrap());
+        }
+        self.emit(Instruction::PopHandler);
+        let mut jumps_done = vec![self.emit(Instruction::Jump(0))];
+
+        // on error
+        self.patch(push);
+        let mut push = None;
+        if let Some(catch) = &exp.catch {
+            let ctx = self.scope().tries.last_mut().unwrap();
+            ctx.state = TryState::Catch;
+            ctx.handler = exp.finally.is_some();
+            if exp.finally.is_some() {
+                push = Some(self.emit(Instruction::PushHandler(0)));
+            }
+
+            // scope holding caught error
+            self.enter_block(&[]);
+            self.emit(Instruction::Catch);
+            if let Some(catch_ident) = &exp.catch_ident {
+                self.define(&catch_ident.value);
+            } else {
+                self.emit(Instruction::Pop);
+            }
+            let compiled = self.compile_block(catch, None);
+            if compiled.is_err() {
+                return Err(compiled.err().unwrap());
+            }
+            self.scope().blocks.pop();
+
+            if exp.finally.is_some() {
+                self.emit(Instruction::PopHandler);
+            }
+            jumps_done.push(self.emit(Instruction::Jump(0)));
+        }
+        self.scope().tries.pop();
+
+        // error which is not caught, or raised in catch block
+        if let Some(finally) = &exp.finally {
+            if let Some(push) = push {
+                self.patch(push);
+            }
+            let compiled = self.compile_finally(finally);
+            if compiled.is_err() {
+                return Err(compiled.err().unwrap());
+            }
+            self.emit(Instruction::Rethrow);
+        }
+
+        for jump in jumps_done {
+            self.patch(jump);
+        }
+        if let Some(finally) = &exp.finally {
+            return self.compile_finally(finally);
+        }
+        Ok(())
+    }
+
+    /// finally block is compiled on each way out of try expression.
+    /// it keeps value under it
+    fn compile_finally(&mut self, finally: &BlockStatement) -> Result<(), EvalError> {
+        self.scope().tries.push(TryContext {
+            state: TryState::Finally,
+            handler: false,
+            finally: None,
+            aborts: Vec::new(),
+        });
+
+        self.emit(Instruction::EnterFinally);
+        let compiled = self.compile_block(finally, None);
+
+        let ctx = self.scope().tries.pop().unwrap();
+        if compiled.is_err() {
+            return Err(compiled.err().unwrap());
+        }
+        for abort in ctx.aborts {
+            self.patch(abort);
+        }
+        self.emit(Instruction::LeaveFinally);
+        self.emit(Instruction::Pop);
+        Ok(())
+    }
+
+    fn compile_function(&mut self, func: &FunctionLiteral) -> Result<u32, EvalError> {
+        // reserve index, so that nested functions come after
+        let idx = self.functions.len() as u32;
+        self.functions.push(CompiledFunction::new(None));
+
+        let mut function =
+            CompiledFunction::new(func.ident.as_ref().map(|ident| ident.value.clone()));
+        for param in func.parameters.iter() {
+            function.parameters.push(param.ident.value.clone());
+            function.defaults.push(param.default.is_some());
+            function.signature.push(param.to_str());
+        }
+        if let Some(rest) = &func.rest {
+            let rest = rest.value.clone();
+            function.signature.push(format!("...{}", rest));
+            function.rest = Some(rest);
+        }
+        function.body = func.body.to_str();
+        function.slots = (func.parameters.len() + func.rest.iter().len()) as u32;
+
+        let mut captured = HashSet::new();
+        for param in func.parameters.iter() {
+            if let Some(default) = &param.default {
+                collect_captured_exp(default, false, &mut captured);
+            }
+        }
+        for stm in func.body.statements.iter() {
+            collect_captured_stm(stm, false, &mut captured);
+        }
+        self.scopes.push(FunctionScope::new(function, captured));
+        self.scope().blocks.push(HashMap::new());
+
+        // parameters not given are filled in order,
+        // so that defaults can refer to former parameters
+        for (slot, param) in func.parameters.iter().enumerate() {
+            let slot = slot as u32;
+            if let Some(default) = &param.default {
+                let jump = self.emit(Instruction::Default(slot, 0));
+                let compiled = self.compile_exp(default);
+                if compiled.is_err() {
+                    return Err(compiled.err().unwrap());
+                }
+                let exp = self.expression(default);
+                self.emit(Instruction::Expect(Expect::Value(exp)));
+                self.emit(Instruction::SetLocal(slot));
+                self.patch(jump);
+            } else {
+                self.emit(Instruction::Required(slot));
+            }
+            self.declare_parameter(&param.ident.value, slot);
+        }
+        if let Some(rest) = &func.rest {
+            let slot = func.parameters.len() as u32;
+            self.declare_parameter(&rest.value, slot);
+        }
+
+        let compiled = self.compile_block(&func.body, Some(true));
+        if compiled.is_err() {
+            return Err(compiled.err().unwrap());
+        }
+        self.emit(Instruction::Return);
+
+        let scope = self.scopes.pop().unwrap();
+        self.functions[idx as usize] = scope.function;
+        Ok(idx)
+    }
+
+    fn declare_parameter(&mut self, name: &str, slot: u32) {
+        let scope = self.scope();
+        let mut place = Place::Slot(slot);
+        if scope.captured.contains(name) {
+            place = scope.new_cell(name);
+            let Place::Cell(cell) = place else {
+                unreachable!()
+            };
+            scope
+                .function
+                .code
+                .push(Instruction::MoveToCell(slot, cell));
+        }
+        scope.blocks.last_mut().unwrap().insert(
+            name.to_string(),
+            Local {
+                place,
+                defined: true,
+            },
+        );
+    }
+
+    /// bind value on top of stack to name in current scope
+    fn define(&mut self, name: &str) {
+        let scope = self.scope();
+        if scope.blocks.is_empty() {
+            let slot = self.global_slot(name);
+            self.emit(Instruction::SetGlobal(slot));
+            return;
+        }
+
+        let declared = scope.blocks.last().unwrap().get(name).copied();
+        let place = match declared {
+            Some(local) => local.place,
+            None if scope.captured.contains(name) => scope.new_cell(name),
+            None => {
+                scope.function.slots += 1;
+                Place::Slot(scope.function.slots - 1)
+            }
+        };
+        scope.blocks.last_mut().unwrap().insert(
+            name.to_string(),
+            Local {
+                place,
+                defined: true,
+            },
+        );
+
+        match place {
+            Place::Slot(slot) => self.emit(Instruction::SetLocal(slot)),
+            Place::Cell(cell) => self.emit(Instruction::SetCell(cell)),
+        };
+    }
+
+    /// push value bound to name
+    fn load(&mut self, name: &str) {
+        let depth = self.scopes.len() - 1;
+        match self.resolve(depth, name, false) {
+            Access::Local(Place::Slot(slot)) => self.emit(Instruction::GetLocal(slot)),
+            Access::Local(Place::Cell(cell)) => self.emit(Instruction::GetCell(cell)),
+            Access::Capture(idx) => self.emit(Instruction::GetCapture(idx)),
+            Access::Global => {
+                let slot = self.global_slot(name);
+                self.emit(Instruction::GetGlobal(slot))
+            }
+        };
+    }
+
+    /// find binding of name seen from function at depth.
+    /// closures see names of enclosing functions declared after them too
+    fn resolve(&mut self, depth: usize, name: &str, from_closure: bool) -> Access {
+        let scope = &self.scopes[depth];
+        for block in scope.blocks.iter().rev() {
+            let local = block.get(name);
+            if local.is_some_and(|local| local.defined || from_closure) {
+                return Access::Local(local.unwrap().place);
+            }
+        }
+
+        let captured = scope.function.captures.iter().position(|(n, _)| n == name);
+        if let Some(captured) = captured {
+            return Access::Capture(captured as u32);
+        }
+        if depth == 0 {
+            return Access::Global;
+        }
+
+        let capture = match self.resolve(depth - 1, name, true) {
+            Access::Local(Place::Cell(cell)) => Capture::Cell(cell),
+            Access::Capture(idx) => Capture::Capture(idx),
+            // names closures refer are kept in cells
+            Access::Local(Place::Slot(_)) => unreachable!("{} is not captured", name),
+            Access::Global => return Access::Global,
+        };
+        let captures = &mut self.scopes[depth].function.captures;
+        captures.push((name.to_string(), capture));
+        Access::Capture((captures.len() - 1) as u32)
+    }
+}
+
+impl FunctionScope {
+    fn new(function: CompiledFunction, captured: HashSet<String>) -> Self {
+        FunctionScope {
+            function,
+            blocks: Vec::new(),
+            captured,
+            tries: Vec::new(),
+            positions: Vec::new(),
+        }
+    }
+
+    fn new_cell(&mut self, name: &str) -> Place {
+        self.function.cells.push(name.to_string());
+        Place::Cell((self.function.cells.len() - 1) as u32)
+    }
+}
+
+/// name bound by statement to the scope which holds it
+fn declared_name(stm: &Statement) -> Option<String> {
+    match stm {
+        Statement::LetStatement(stm) => Some(stm.identifier.value.clone()),
+        Statement::ImportStatement(stm) => Some(stm.alias.value.clone()),
+        Statement::ExportStatement(stm) => stm.name(),
+        Statement::ExpressionStatement(ExpressionStatement {
+            expression: Some(Expression::FunctionLiteral(func)),
+            ..
+        }) => func.ident.as_ref().map(|ident| ident.value.clone()),
+        _ => None,
+    }
+}
+
+// collect names referred inside of function literals.
+// it may contain names bound by the literals themselves, which is harmless
+
+fn collect_captured_stm(stm: &Statement, inside: bool, names: &mut HashSet<String>) {
+    match stm {
+        Statement::LetStatement(stm) => {
+            if let Some(value) = &stm.value {
+                collect_captured_exp(value, inside, names);
+            }
+        }
+        Statement::ExpressionStatement(stm) => {
+            if let Some(expression) = &stm.expression {
+                collect_captured_exp(expression, inside, names);
+            }
+        }
+        Statement::ReturnStatement(stm) => {
+            if let Some(value) = &stm.value {
+                collect_captured_exp(value, inside, names);
+            }
+        }
+        Statement::BlockStatement(stm) => collect_captured_block(stm, inside, names),
+        Statement::ThrowStatement(stm) => collect_captured_exp(&stm.value, inside, names),
+        Statement::ImportStatement(_) => {}
+        Statement::ExportStatement(stm) => collect_captured_stm(&stm.statement, inside, names),
+    }
+}
+
+fn collect_captured_block(block: &BlockStatement, inside: bool, names: &mut HashSet<String>) {
+    for stm in block.statements.iter() {
+        collect_captured_stm(stm, inside, names);
+    }
+}
+
+fn collect_captured_exp(exp: &Expression, inside: bool, names: &mut HashSet<String>) {
+    match exp {
+        Expression::Identifier(ident) => {
+            if inside {
+                names.insert(ident.value.clone());
+            }
+        }
+        Expression::IntegerLiteral(_)
+        | Expression::BigIntLiteral(_)
+        | Expression::BooleanLiteral(_)
+        | Expression::StringLiteral(_) => {}
+        Expression::FunctionLiteral(func) => {
+            for param in func.parameters.iter() {
+                if let Some(default) = &param.default {
+                    collect_captured_exp(default, true, names);
+                }
+            }
+            collect_captured_block(&func.body, true, names);
+        }
+        Expression::ArrayLiteral(arr) => {
+            for element in arr.elements.iter() {
+                collect_captured_exp(element, inside, names);
+            }
+        }
+        Expression::InfixExpression(exp) => {
+            collect_captured_exp(&exp.left, inside, names);
+            collect_captured_exp(&exp.right, inside, names);
+        }
+        Expression::PrefixExpression(exp) => collect_captured_exp(&exp.right, inside, names),
+        Expression::IfExpression(exp) => {
+            collect_captured_exp(&exp.condition, inside, names);
+            collect_captured_block(&exp.consequence, inside, names);
+            if let Some(alternative) = &exp.alternative {
+                collect_captured_block(alternative, inside, names);
+            }
+        }
+        Expression::CallExpression(exp) => {
+            collect_captured_exp(&exp.function, inside, names);
+            for arg in exp.arguments.iter() {
+                collect_captured_exp(arg, inside, names);
+            }
+            for arg in exp.named_arguments.iter() {
+                collect_captured_exp(&arg.value, inside, names);
+            }
+        }
+        Expression::IndexExpression(exp) => {
+            collect_captured_exp(&exp.left, inside, names);
+            collect_captured_exp(&exp.index, inside, names);
+        }
+        Expression::TryExpression(exp) => {
+            collect_captured_block(&exp.block, inside, names);
+            if let Some(catch) = &exp.catch {
+                collect_captured_block(catch, inside, names);
+            }
+            if let Some(finally) = &exp.finally {
+                collect_captured_block(finally, inside, names);
+            }
+        }
+        Expression::MemberExpression(exp) => collect_captured_exp(&exp.left, inside, names),
+    }
+}
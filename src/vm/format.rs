@@ -0,0 +1,1231 @@
+use crate::{
+    ast::{Expression, ExpressionStatement, Identifier, Nodetrait, Statement},
+    lexer::{Lexer, Position},
+    object::bigint::BigInt,
+    parser::Parser,
+    token::{Kind, Token},
+};
+
+use super::code::{Bytecode, Capture, CompiledFunction, Constant, Expect, Instruction};
+
+/// first bytes of a compiled file
+pub const MAGIC: [u8; 4] = *b"DLBC";
+/// version of the format, files of other versions are rejected
+pub const VERSION: u16 = 3;
+
+// magic, version, length and checksum of the rest
+const HEADER_SIZE: usize = 4 + 2 + 4 + 8;
+
+// operators of infix and prefix instructions, encoded by index
+const OPERATORS: [Kind; 16] = [
+    Kind::Plus,
+    Kind::Minus,
+    Kind::Product,
+    Kind::Divide,
+    Kind::Mod,
+    Kind::Bang,
+    Kind::LT,
+    Kind::LT_OR_EQ,
+    Kind::GT,
+    Kind::GT_OR_EQ,
+    Kind::EQ,
+    Kind::NOT_EQ,
+    Kind::And,
+    Kind::Or,
+    Kind::Bit_And,
+    Kind::Bit_Or,
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FormatError {
+    NotBytecode,
+    UnsupportedVersion(u16),
+    ChecksumMismatch,
+    // input ended while reading the item
+    Truncated {
+        offset: usize,
+        item: &'static str,
+    },
+    InvalidTag {
+        offset: usize,
+        item: &'static str,
+        tag: u8,
+    },
+    InvalidString {
+        offset: usize,
+    },
+    TrailingBytes {
+        offset: usize,
+    },
+    NoFunction,
+    // instruction refers something which does not exist
+    InvalidOperand {
+        function: usize,
+        ip: usize,
+        reason: String,
+    },
+    InvalidFunction {
+        function: usize,
+        reason: String,
+    },
+    // instruction takes values which are not on stack
+    InvalidStack {
+        function: usize,
+        ip: usize,
+        reason: String,
+    },
+}
+
+impl FormatError {
+    pub fn message(&self) -> String {
+        match self {
+            FormatError::NotBytecode => "not a compiled dlang file".to_string(),
+            FormatError::UnsupportedVersion(version) => format!(
+                "unsupported bytecode version {} (expected {})",
+                version, VERSION
+            ),
+            FormatError::ChecksumMismatch => "checksum mismatch, file is corrupted".to_string(),
+            FormatError::Truncated { offset, item } => {
+                format!("file is truncated, expected {} at byte {}", item, offset)
+            }
+            FormatError::InvalidTag { offset, item, tag } => {
+                format!("invalid {} tag {} at byte {}", item, tag, offset)
+            }
+            FormatError::InvalidString { offset } => {
+                format!("string at byte {} is not valid utf-8", offset)
+            }
+            FormatError::TrailingBytes { offset } => {
+                format!("unexpected bytes after end of program at byte {}", offset)
+            }
+            FormatError::NoFunction => "program has no top level function".to_string(),
+            FormatError::InvalidOperand {
+                function,
+                ip,
+                reason,
+            } => format!(
+                "invalid instruction {} of function {}: {}",
+                ip, function, reason
+            ),
+            FormatError::InvalidFunction { function, reason } => {
+                format!("invalid function {}: {}", function, reason)
+            }
+            FormatError::InvalidStack {
+                function,
+                ip,
+                reason,
+            } => format!(
+                "invalid stack at instruction {} of function {}: {}",
+                ip, function, reason
+            ),
+        }
+    }
+}
+
+/// encode bytecode to bytes of the versioned format.
+/// expressions for error messages are kept as source text
+pub fn serialize(bytecode: &Bytecode) -> Vec<u8> {
+    let mut writer = Writer { buf: Vec::new() };
+
+    writer.u32(bytecode.constants.len() as u32);
+    for constant in bytecode.constants.iter() {
+        match constant {
+            Constant::Int(value) => {
+                writer.u8(0);
+                writer.i64(*value);
+            }
+            Constant::String(value) => {
+                writer.u8(1);
+                writer.str(value);
+            }
+            Constant::Names(names) => {
+                writer.u8(2);
+                writer.strs(names);
+            }
+            Constant::BigInt(value) => {
+                writer.u8(3);
+                writer.u8(value.is_negative() as u8);
+                writer.u32(value.limbs().len() as u32);
+                for limb in value.limbs() {
+                    writer.u32(*limb);
+                }
+            }
+        }
+    }
+
+    writer.strs(&bytecode.globals);
+
+    writer.u32(bytecode.expressions.len() as u32);
+    for exp in bytecode.expressions.iter() {
+        writer.str(&exp.to_str());
+    }
+
+    writer.u32(bytecode.functions.len() as u32);
+    for function in bytecode.functions.iter() {
+        writer.function(function);
+    }
+
+    let mut bytes = Vec::with_capacity(HEADER_SIZE + writer.buf.len());
+    bytes.extend_from_slice(&MAGIC);
+    bytes.extend_from_slice(&VERSION.to_le_bytes());
+    bytes.extend_from_slice(&(writer.buf.len() as u32).to_le_bytes());
+    bytes.extend_from_slice(&checksum(&writer.buf).to_le_bytes());
+    bytes.extend_from_slice(&writer.buf);
+    bytes
+}
+
+/// decode and validate bytes made by serialize
+pub fn deserialize(bytes: &[u8]) -> Result<Bytecode, FormatError> {
+    if bytes.len() < MAGIC.len() || bytes[..MAGIC.len()] != MAGIC {
+        return Err(FormatError::NotBytecode);
+    }
+    if bytes.len() < HEADER_SIZE {
+        return Err(FormatError::Truncated {
+            offset: bytes.len(),
+            item: "header",
+        });
+    }
+    let version = u16::from_le_bytes([bytes[4], bytes[5]]);
+    if version != VERSION {
+        return Err(FormatError::UnsupportedVersion(version));
+    }
+    let len = u32::from_le_bytes([bytes[6], bytes[7], bytes[8], bytes[9]]) as usize;
+    if bytes.len() - HEADER_SIZE < len {
+        return Err(FormatError::Truncated {
+            offset: bytes.len(),
+            item: "program",
+        });
+    }
+    if bytes.len() - HEADER_SIZE > len {
+        return Err(FormatError::TrailingBytes {
+            offset: HEADER_SIZE + len,
+        });
+    }
+    let mut sum = [0; 8];
+    sum.copy_from_slice(&bytes[10..HEADER_SIZE]);
+    if u64::from_le_bytes(sum) != checksum(&bytes[HEADER_SIZE..]) {
+        return Err(FormatError::ChecksumMismatch);
+    }
+
+    let mut reader = Reader {
+        bytes,
+        offset: HEADER_SIZE,
+        error: None,
+    };
+
+    let mut constants = Vec::new();
+    let count = reader.u32("constant count");
+    for _ in 0..count {
+        if reader.error.is_some() {
+            break;
+        }
+        let offset = reader.offset;
+        let constant = match reader.u8("constant tag") {
+            0 => Constant::Int(reader.i64("integer constant")),
+            1 => Constant::String(reader.str("string constant")),
+            2 => Constant::Names(reader.strs("names constant")),
+            3 => Constant::BigInt(reader.big_int("big integer constant")),
+            tag => {
+                reader.fail(FormatError::InvalidTag {
+                    offset,
+                    item: "constant",
+                    tag,
+                });
+                break;
+            }
+        };
+        constants.push(constant);
+    }
+
+    let globals = reader.strs("global names");
+
+    let mut expressions = Vec::new();
+    let count = reader.u32("expression count");
+    for _ in 0..count {
+        if reader.error.is_some() {
+            break;
+        }
+        expressions.push(parse_expression(reader.str("expression")));
+    }
+
+    let mut functions = Vec::new();
+    let count = reader.u32("function count");
+    for _ in 0..count {
+        if reader.error.is_some() {
+            break;
+        }
+        functions.push(reader.function());
+    }
+
+    if let Some(error) = reader.error {
+        return Err(error);
+    }
+    if reader.offset != bytes.len() {
+        return Err(FormatError::TrailingBytes {
+            offset: reader.offset,
+        });
+    }
+
+    let bytecode = Bytecode {
+        constants,
+        functions,
+        globals,
+        expressions,
+    };
+    let validated = validate(&bytecode);
+    if validated.is_err() {
+        return Err(validated.err().unwrap());
+    }
+    Ok(bytecode)
+}
+
+/// FNV-1a, to detect corrupted files
+fn checksum(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in bytes {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+/// parse expression back from its source text.
+/// if the text doesn't parse, it is kept as is to be printed by errors
+fn parse_expression(source: String) -> Expression {
+    let parsed = Parser::new(Lexer::new(source.clone())).parse();
+    if let Ok(program) = parsed {
+        if let [Statement::ExpressionStatement(ExpressionStatement {
+            expression: Some(exp),
+            ..
+        })] = program.statements.as_slice()
+        {
+            if exp.to_str() == source {
+                return exp.clone();
+            }
+        }
+    }
+    Expression::Identifier(Identifier {
+        token: Token {
+            kind: Kind::Ident,
+            literal: source.clone(),
+            position: Position::default(),
+        },
+        value: source,
+        binding: None,
+    })
+}
+
+/// check every operand refers something in the program,
+/// so that vm can run it without going out of bounds
+fn validate(bytecode: &Bytecode) -> Result<(), FormatError> {
+    if bytecode.functions.is_empty() {
+        return Err(FormatError::NoFunction);
+    }
+
+    for (idx, function) in bytecode.functions.iter().enumerate() {
+        let params = function.parameters.len();
+        let invalid = |reason: &str| FormatError::InvalidFunction {
+            function: idx,
+            reason: reason.to_string(),
+        };
+        if function.defaults.len() != params {
+            return Err(invalid("defaults don't match parameters"));
+        }
+        if (function.slots as usize) < params + function.rest.iter().len() {
+            return Err(invalid("fewer slots than parameters"));
+        }
+        if function.code.last() != Some(&Instruction::Return) {
+            return Err(invalid("code doesn't end with return"));
+        }
+        if function.lines.windows(2).any(|pair| pair[0].0 >= pair[1].0) {
+            return Err(invalid("line table is not sorted"));
+        }
+
+        for (ip, ins) in function.code.iter().enumerate() {
+            let checked = validate_instruction(bytecode, function, *ins);
+            if checked.is_err() {
+                return Err(FormatError::InvalidOperand {
+                    function: idx,
+                    ip,
+                    reason: checked.err().unwrap(),
+                });
+            }
+        }
+
+        let checked = validate_stack(bytecode, function);
+        if checked.is_err() {
+            let (ip, reason) = checked.err().unwrap();
+            return Err(FormatError::InvalidStack {
+                function: idx,
+                ip,
+                reason,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// what a value on the stack is known to be
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Held {
+    // checked to be a function by `Expect(Callee)`
+    Callee,
+    // checked not to be nothing
+    Value,
+    // may be nothing
+    Maybe,
+}
+
+impl Held {
+    /// what is known of the value when paths of both meet
+    fn join(self, other: Held) -> Held {
+        match (self, other) {
+            (a, b) if a == b => a,
+            (Held::Maybe, _) | (_, Held::Maybe) => Held::Maybe,
+            _ => Held::Value,
+        }
+    }
+}
+
+/// state of a frame before an instruction, relative to the start of the function
+#[derive(Debug, Clone, PartialEq)]
+struct Height {
+    // values pushed above slots of the frame
+    values: Vec<Held>,
+    // errors raised and not caught yet
+    errors: usize,
+    // values when finally blocks were entered, innermost last
+    finallies: Vec<usize>,
+}
+
+impl Height {
+    /// state where paths of both meet, None if stack differs in its height
+    fn join(&self, other: &Height) -> Option<Height> {
+        if self.values.len() != other.values.len()
+            || self.errors != other.errors
+            || self.finallies != other.finallies
+        {
+            return None;
+        }
+        let values = self
+            .values
+            .iter()
+            .zip(other.values.iter())
+            .map(|(a, b)| a.join(*b))
+            .collect();
+        Some(Height {
+            values,
+            errors: self.errors,
+            finallies: self.finallies.clone(),
+        })
+    }
+}
+
+/// simulate stack of the function along every path,
+/// so that the vm can pop values and unwrap them without checking
+fn validate_stack(
+    bytecode: &Bytecode,
+    function: &CompiledFunction,
+) -> Result<(), (usize, String)> {
+    let mut seen: Vec<Option<Height>> = vec![None; function.code.len()];
+    let start = Height {
+        values: Vec::new(),
+        errors: 0,
+        finallies: Vec::new(),
+    };
+    let mut pending = vec![(0, start)];
+
+    while let Some((ip, height)) = pending.pop() {
+        // a path reaching known instruction goes on only if it tells less of the values
+        let height = match &seen[ip] {
+            Some(known) => {
+                let Some(joined) = known.join(&height) else {
+                    return Err((ip, "reached with different stack heights".to_string()));
+                };
+                if &joined == known {
+                    continue;
+                }
+                joined
+            }
+            None => height,
+        };
+        seen[ip] = Some(height.clone());
+
+        let ins = function.code[ip];
+        let depth = height.values.len();
+        let underflow = |taken: usize| {
+            if depth >= taken {
+                return Ok(());
+            }
+            let reason = format!("{:?} takes {} values of {}", ins, taken, depth);
+            Err((ip, reason))
+        };
+        // values unwrapped by the vm must be checked not to be nothing
+        let unchecked = |taken: usize| {
+            let checked = underflow(taken);
+            if checked.is_err() {
+                return Err(checked.err().unwrap());
+            }
+            if height.values[depth - taken..].contains(&Held::Maybe) {
+                let reason = format!("{:?} takes a value which may be nothing", ins);
+                return Err((ip, reason));
+            }
+            Ok(())
+        };
+        // values taken and what is pushed, when it goes on to the next instruction
+        let (taken, pushed) = match ins {
+            Instruction::Constant(_)
+            | Instruction::True
+            | Instruction::False
+            | Instruction::Closure(_)
+            | Instruction::Import(_) => (0, Some(Held::Value)),
+            Instruction::Nothing
+            | Instruction::GetGlobal(_)
+            | Instruction::GetLocal(_)
+            | Instruction::GetCell(_)
+            | Instruction::GetCapture(_) => (0, Some(Held::Maybe)),
+            Instruction::Pop
+            | Instruction::SetGlobal(_)
+            | Instruction::SetLocal(_)
+            | Instruction::SetCell(_) => (1, None),
+            Instruction::NameFunction(_) => {
+                let checked = underflow(1);
+                if checked.is_err() {
+                    return Err(checked.err().unwrap());
+                }
+                (1, Some(height.values[depth - 1]))
+            }
+            Instruction::Expect(Expect::Callee) => (1, Some(Held::Callee)),
+            Instruction::Expect(_) => (1, Some(Held::Value)),
+            Instruction::Array(len) => {
+                let checked = unchecked(len as usize);
+                if checked.is_err() {
+                    return Err(checked.err().unwrap());
+                }
+                (len as usize, Some(Held::Value))
+            }
+            Instruction::Infix(_) | Instruction::Index => {
+                let checked = unchecked(2);
+                if checked.is_err() {
+                    return Err(checked.err().unwrap());
+                }
+                let pushed = match ins {
+                    Instruction::Index => Held::Maybe,
+                    _ => Held::Value,
+                };
+                (2, Some(pushed))
+            }
+            Instruction::Prefix(_) | Instruction::Member(_) => {
+                let checked = unchecked(1);
+                if checked.is_err() {
+                    return Err(checked.err().unwrap());
+                }
+                let pushed = match ins {
+                    Instruction::Member(_) => Held::Maybe,
+                    _ => Held::Value,
+                };
+                (1, Some(pushed))
+            }
+            Instruction::Call(args, names) | Instruction::TailCall(args, names) => {
+                let named = match names.map(|idx| &bytecode.constants[idx as usize]) {
+                    Some(Constant::Names(names)) => names.len(),
+                    _ => 0,
+                };
+                let taken = 1 + args as usize + named;
+                let checked = underflow(taken);
+                if checked.is_err() {
+                    return Err(checked.err().unwrap());
+                }
+                if height.values[depth - taken] != Held::Callee {
+                    let reason = format!("{:?} calls a value not checked to be a function", ins);
+                    return Err((ip, reason));
+                }
+                (taken, Some(Held::Maybe))
+            }
+
+            Instruction::Jump(target) => {
+                pending.push((target as usize, height));
+                continue;
+            }
+            Instruction::JumpIfFalse(target) => {
+                let checked = underflow(1);
+                if checked.is_err() {
+                    return Err(checked.err().unwrap());
+                }
+                let mut next = height.clone();
+                next.values.pop();
+                pending.push((target as usize, next.clone()));
+                pending.push((ip + 1, next));
+                continue;
+            }
+            Instruction::Default(_, target) => {
+                pending.push((target as usize, height.clone()));
+                pending.push((ip + 1, height));
+                continue;
+            }
+            Instruction::Return => {
+                let checked = underflow(1);
+                if checked.is_err() {
+                    return Err(checked.err().unwrap());
+                }
+                continue;
+            }
+            Instruction::Throw => {
+                let checked = unchecked(1);
+                if checked.is_err() {
+                    return Err(checked.err().unwrap());
+                }
+                continue;
+            }
+
+            // on error, stack is cut back to the height of handler and error is raised
+            Instruction::PushHandler(target) => {
+                let mut caught = height.clone();
+                caught.errors += 1;
+                pending.push((target as usize, caught));
+                pending.push((ip + 1, height));
+                continue;
+            }
+            Instruction::Catch | Instruction::Rethrow if height.errors == 0 => {
+                return Err((ip, format!("{:?} without error raised", ins)));
+            }
+            Instruction::Catch => {
+                let mut next = height.clone();
+                next.errors -= 1;
+                next.values.push(Held::Value);
+                pending.push((ip + 1, next));
+                continue;
+            }
+            Instruction::Rethrow => continue,
+            Instruction::EnterFinally => {
+                let mut next = height.clone();
+                next.finallies.push(depth);
+                pending.push((ip + 1, next));
+                continue;
+            }
+            Instruction::LeaveFinally | Instruction::AbortFinally(_)
+                if height.finallies.is_empty() =>
+            {
+                return Err((ip, format!("{:?} out of finally block", ins)));
+            }
+            Instruction::LeaveFinally => {
+                let mut next = height.clone();
+                next.finallies.pop();
+                pending.push((ip + 1, next));
+                continue;
+            }
+            // value of finally block is replaced by nothing
+            Instruction::AbortFinally(target) => {
+                let mut next = height.clone();
+                next.values.truncate(*next.finallies.last().unwrap());
+                next.values.push(Held::Maybe);
+                pending.push((target as usize, next));
+                continue;
+            }
+
+            Instruction::PopHandler
+            | Instruction::Required(_)
+            | Instruction::MoveToCell(_, _) => (0, None),
+        };
+
+        let checked = underflow(taken);
+        if checked.is_err() {
+            return Err(checked.err().unwrap());
+        }
+        let mut next = height;
+        next.values.truncate(depth - taken);
+        next.values.extend(pushed);
+        pending.push((ip + 1, next));
+    }
+    Ok(())
+}
+
+fn validate_instruction(
+    bytecode: &Bytecode,
+    function: &CompiledFunction,
+    ins: Instruction,
+) -> Result<(), String> {
+    let check = |valid: bool, reason: &str| {
+        if valid {
+            Ok(())
+        } else {
+            Err(format!("{:?} {}", ins, reason))
+        }
+    };
+    let is_string = |idx: u32| {
+        matches!(
+            bytecode.constants.get(idx as usize),
+            Some(Constant::String(_))
+        )
+    };
+    let is_names = |names: Option<u32>| {
+        names.is_none()
+            || matches!(
+                bytecode.constants.get(names.unwrap() as usize),
+                Some(Constant::Names(_))
+            )
+    };
+    let code = function.code.len() as u32;
+    let slots = function.slots;
+    let params = function.parameters.len() as u32;
+    let cells = function.cells.len() as u32;
+    let captures = function.captures.len() as u32;
+
+    match ins {
+        Instruction::Constant(idx) => check(
+            matches!(
+                bytecode.constants.get(idx as usize),
+                Some(Constant::Int(_) | Constant::BigInt(_) | Constant::String(_))
+            ),
+            "refers no value constant",
+        ),
+        Instruction::GetGlobal(slot) | Instruction::SetGlobal(slot) => {
+            check((slot as usize) < bytecode.globals.len(), "refers no global")
+        }
+        Instruction::GetLocal(slot) | Instruction::SetLocal(slot) => {
+            check(slot < slots, "refers no slot")
+        }
+        Instruction::GetCell(cell) | Instruction::SetCell(cell) => {
+            check(cell < cells, "refers no cell")
+        }
+        Instruction::GetCapture(idx) => check(idx < captures, "refers no capture"),
+        Instruction::NameFunction(idx) | Instruction::Member(idx) | Instruction::Import(idx) => {
+            check(is_string(idx), "refers no string constant")
+        }
+        Instruction::Closure(idx) => {
+            let target = bytecode.functions.get(idx as usize);
+            if idx == 0 || target.is_none() {
+                return check(false, "refers no function");
+            }
+            for (_, capture) in target.unwrap().captures.iter() {
+                let captured = match capture {
+                    Capture::Cell(cell) => *cell < cells,
+                    Capture::Capture(idx) => *idx < captures,
+                };
+                if !captured {
+                    return check(false, "captures nothing");
+                }
+            }
+            Ok(())
+        }
+        Instruction::Expect(Expect::Value(idx)) => check(
+            (idx as usize) < bytecode.expressions.len(),
+            "refers no expression",
+        ),
+        Instruction::Jump(target)
+        | Instruction::JumpIfFalse(target)
+        | Instruction::PushHandler(target)
+        | Instruction::AbortFinally(target) => check(target < code, "jumps out of function"),
+        Instruction::Call(_, names) | Instruction::TailCall(_, names) => {
+            check(is_names(names), "refers no names constant")
+        }
+        Instruction::Default(slot, target) => check(
+            slot < params && target < code,
+            "refers no parameter or jumps out of function",
+        ),
+        Instruction::Required(slot) => check(slot < params, "refers no parameter"),
+        Instruction::MoveToCell(slot, cell) => {
+            check(slot < slots && cell < cells, "refers no slot or cell")
+        }
+        _ => Ok(()),
+    }
+}
+
+struct Writer {
+    buf: Vec<u8>,
+}
+
+impl Writer {
+    fn u8(&mut self, value: u8) {
+        self.buf.push(value);
+    }
+
+    fn u32(&mut self, value: u32) {
+        self.buf.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn i64(&mut self, value: i64) {
+        self.buf.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn str(&mut self, value: &str) {
+        self.u32(value.len() as u32);
+        self.buf.extend_from_slice(value.as_bytes());
+    }
+
+    fn strs(&mut self, values: &[String]) {
+        self.u32(values.len() as u32);
+        for value in values {
+            self.str(value);
+        }
+    }
+
+    fn opt_str(&mut self, value: &Option<String>) {
+        match value {
+            Some(value) => {
+                self.u8(1);
+                self.str(value);
+            }
+            None => self.u8(0),
+        }
+    }
+
+    fn function(&mut self, function: &CompiledFunction) {
+        self.opt_str(&function.name);
+        self.u32(function.parameters.len() as u32);
+        for (param, default) in function.parameters.iter().zip(function.defaults.iter()) {
+            self.str(param);
+            self.u8(*default as u8);
+        }
+        self.strs(&function.signature);
+        self.str(&function.body);
+        self.opt_str(&function.rest);
+        self.u32(function.slots);
+        self.strs(&function.cells);
+
+        self.u32(function.captures.len() as u32);
+        for (name, capture) in function.captures.iter() {
+            self.str(name);
+            match capture {
+                Capture::Cell(cell) => {
+                    self.u8(0);
+                    self.u32(*cell);
+                }
+                Capture::Capture(idx) => {
+                    self.u8(1);
+                    self.u32(*idx);
+                }
+            }
+        }
+
+        self.u32(function.code.len() as u32);
+        for ins in function.code.iter() {
+            self.instruction(*ins);
+        }
+
+        self.u32(function.lines.len() as u32);
+        for (ip, position) in function.lines.iter() {
+            self.u32(*ip);
+            self.u32(position.pos as u32);
+            self.u32(position.line_num as u32);
+        }
+    }
+
+    fn operator(&mut self, kind: Kind) {
+        let idx = OPERATORS.iter().position(|op| *op == kind);
+        self.u8(idx.expect("operator of infix or prefix expression") as u8);
+    }
+
+    fn call(&mut self, args: u32, names: Option<u32>) {
+        self.u32(args);
+        match names {
+            Some(names) => {
+                self.u8(1);
+                self.u32(names);
+            }
+            None => self.u8(0),
+        }
+    }
+
+    fn instruction(&mut self, ins: Instruction) {
+        match ins {
+            Instruction::Constant(idx) => {
+                self.u8(0);
+                self.u32(idx);
+            }
+            Instruction::True => self.u8(1),
+            Instruction::False => self.u8(2),
+            Instruction::Nothing => self.u8(3),
+            Instruction::Pop => self.u8(4),
+            Instruction::GetGlobal(slot) => {
+                self.u8(5);
+                self.u32(slot);
+            }
+            Instruction::SetGlobal(slot) => {
+                self.u8(6);
+                self.u32(slot);
+            }
+            Instruction::GetLocal(slot) => {
+                self.u8(7);
+                self.u32(slot);
+            }
+            Instruction::SetLocal(slot) => {
+                self.u8(8);
+                self.u32(slot);
+            }
+            Instruction::GetCell(cell) => {
+                self.u8(9);
+                self.u32(cell);
+            }
+            Instruction::SetCell(cell) => {
+                self.u8(10);
+                self.u32(cell);
+            }
+            Instruction::GetCapture(idx) => {
+                self.u8(11);
+                self.u32(idx);
+            }
+            Instruction::NameFunction(idx) => {
+                self.u8(12);
+                self.u32(idx);
+            }
+            Instruction::Closure(idx) => {
+                self.u8(13);
+                self.u32(idx);
+            }
+            Instruction::Array(len) => {
+                self.u8(14);
+                self.u32(len);
+            }
+            Instruction::Infix(kind) => {
+                self.u8(15);
+                self.operator(kind);
+            }
+            Instruction::Prefix(kind) => {
+                self.u8(16);
+                self.operator(kind);
+            }
+            Instruction::Index => self.u8(17),
+            Instruction::Member(idx) => {
+                self.u8(18);
+                self.u32(idx);
+            }
+            Instruction::Expect(expect) => {
+                self.u8(19);
+                match expect {
+                    Expect::Value(idx) => {
+                        self.u8(0);
+                        self.u32(idx);
+                    }
+                    Expect::Left => self.u8(1),
+                    Expect::Right => self.u8(2),
+                    Expect::Element => self.u8(3),
+                    Expect::Callee => self.u8(4),
+                    Expect::Indexable => self.u8(5),
+                    Expect::Index => self.u8(6),
+                }
+            }
+            Instruction::Jump(target) => {
+                self.u8(20);
+                self.u32(target);
+            }
+            Instruction::JumpIfFalse(target) => {
+                self.u8(21);
+                self.u32(target);
+            }
+            Instruction::Call(args, names) => {
+                self.u8(22);
+                self.call(args, names);
+            }
+            Instruction::TailCall(args, names) => {
+                self.u8(23);
+                self.call(args, names);
+            }
+            Instruction::Return => self.u8(24),
+            Instruction::Throw => self.u8(25),
+            Instruction::PushHandler(target) => {
+                self.u8(26);
+                self.u32(target);
+            }
+            Instruction::PopHandler => self.u8(27),
+            Instruction::Catch => self.u8(28),
+            Instruction::Rethrow => self.u8(29),
+            Instruction::EnterFinally => self.u8(30),
+            Instruction::LeaveFinally => self.u8(31),
+            Instruction::AbortFinally(target) => {
+                self.u8(32);
+                self.u32(target);
+            }
+            Instruction::Import(idx) => {
+                self.u8(33);
+                self.u32(idx);
+            }
+            Instruction::Default(slot, target) => {
+                self.u8(34);
+                self.u32(slot);
+                self.u32(target);
+            }
+            Instruction::Required(slot) => {
+                self.u8(35);
+                self.u32(slot);
+            }
+            Instruction::MoveToCell(slot, cell) => {
+                self.u8(36);
+                self.u32(slot);
+                self.u32(cell);
+            }
+        }
+    }
+}
+
+/// reads bytes in order.
+/// after first error, it gives default values, so that error is checked once
+struct Reader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+    error: Option<FormatError>,
+}
+
+impl Reader<'_> {
+    fn fail(&mut self, error: FormatError) {
+        if self.error.is_none() {
+            self.error = Some(error);
+        }
+    }
+
+    fn take(&mut self, len: usize, item: &'static str) -> &[u8] {
+        if self.error.is_some() || self.bytes.len() - self.offset < len {
+            self.fail(FormatError::Truncated {
+                offset: self.offset,
+                item,
+            });
+            return &[];
+        }
+        self.offset += len;
+        &self.bytes[self.offset - len..self.offset]
+    }
+
+    fn u8(&mut self, item: &'static str) -> u8 {
+        let bytes = self.take(1, item);
+        if bytes.is_empty() {
+            return 0;
+        }
+        bytes[0]
+    }
+
+    fn u32(&mut self, item: &'static str) -> u32 {
+        let bytes = self.take(4, item);
+        if bytes.is_empty() {
+            return 0;
+        }
+        u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+    }
+
+    fn i64(&mut self, item: &'static str) -> i64 {
+        let bytes = self.take(8, item);
+        if bytes.is_empty() {
+            return 0;
+        }
+        let mut buf = [0; 8];
+        buf.copy_from_slice(bytes);
+        i64::from_le_bytes(buf)
+    }
+
+    fn bool(&mut self, item: &'static str) -> bool {
+        let offset = self.offset;
+        match self.u8(item) {
+            0 => false,
+            1 => true,
+            tag => {
+                self.fail(FormatError::InvalidTag { offset, item, tag });
+                false
+            }
+        }
+    }
+
+    fn big_int(&mut self, item: &'static str) -> BigInt {
+        let negative = self.bool(item);
+        let count = self.u32(item);
+        let mut limbs = Vec::new();
+        for _ in 0..count {
+            if self.error.is_some() {
+                break;
+            }
+            limbs.push(self.u32(item));
+        }
+        BigInt::from_limbs(negative, limbs)
+    }
+
+    fn str(&mut self, item: &'static str) -> String {
+        let len = self.u32(item) as usize;
+        let offset = self.offset;
+        let bytes = self.take(len, item);
+        match String::from_utf8(bytes.to_vec()) {
+            Ok(value) => value,
+            Err(_) => {
+                self.fail(FormatError::InvalidString { offset });
+                String::new()
+            }
+        }
+    }
+
+    fn strs(&mut self, item: &'static str) -> Vec<String> {
+        let mut values = Vec::new();
+        let count = self.u32(item);
+        for _ in 0..count {
+            if self.error.is_some() {
+                break;
+            }
+            values.push(self.str(item));
+        }
+        values
+    }
+
+    fn opt_str(&mut self, item: &'static str) -> Option<String> {
+        if self.bool(item) {
+            return Some(self.str(item));
+        }
+        None
+    }
+
+    fn function(&mut self) -> CompiledFunction {
+        let mut function = CompiledFunction::new(self.opt_str("function name"));
+
+        let count = self.u32("parameter count");
+        for _ in 0..count {
+            if self.error.is_some() {
+                break;
+            }
+            function.parameters.push(self.str("parameter"));
+            function.defaults.push(self.bool("parameter default"));
+        }
+        function.signature = self.strs("signature");
+        function.body = self.str("function body");
+        function.rest = self.opt_str("rest parameter");
+        function.slots = self.u32("slot count");
+        function.cells = self.strs("cell names");
+
+        let count = self.u32("capture count");
+        for _ in 0..count {
+            if self.error.is_some() {
+                break;
+            }
+            let name = self.str("capture name");
+            let offset = self.offset;
+            let capture = match self.u8("capture tag") {
+                0 => Capture::Cell(self.u32("captured cell")),
+                1 => Capture::Capture(self.u32("captured capture")),
+                tag => {
+                    self.fail(FormatError::InvalidTag {
+                        offset,
+                        item: "capture",
+                        tag,
+                    });
+                    break;
+                }
+            };
+            function.captures.push((name, capture));
+        }
+
+        let count = self.u32("instruction count");
+        for _ in 0..count {
+            if self.error.is_some() {
+                break;
+            }
+            function.code.push(self.instruction());
+        }
+
+        let count = self.u32("line count");
+        for _ in 0..count {
+            if self.error.is_some() {
+                break;
+            }
+            let ip = self.u32("line instruction");
+            let position = Position {
+                pos: self.u32("line position") as usize,
+                line_num: self.u32("line number") as usize,
+            };
+            function.lines.push((ip, position));
+        }
+
+        function
+    }
+
+    fn operator(&mut self) -> Kind {
+        let offset = self.offset;
+        let idx = self.u8("operator");
+        if idx as usize >= OPERATORS.len() {
+            self.fail(FormatError::InvalidTag {
+                offset,
+                item: "operator",
+                tag: idx,
+            });
+            return OPERATORS[0];
+        }
+        OPERATORS[idx as usize]
+    }
+
+    fn call(&mut self) -> (u32, Option<u32>) {
+        let args = self.u32("argument count");
+        if self.bool("named arguments") {
+            return (args, Some(self.u32("names constant")));
+        }
+        (args, None)
+    }
+
+    fn instruction(&mut self) -> Instruction {
+        let offset = self.offset;
+        match self.u8("opcode") {
+            0 => Instruction::Constant(self.u32("constant")),
+            1 => Instruction::True,
+            2 => Instruction::False,
+            3 => Instruction::Nothing,
+            4 => Instruction::Pop,
+            5 => Instruction::GetGlobal(self.u32("global")),
+            6 => Instruction::SetGlobal(self.u32("global")),
+            7 => Instruction::GetLocal(self.u32("slot")),
+            8 => Instruction::SetLocal(self.u32("slot")),
+            9 => Instruction::GetCell(self.u32("cell")),
+            10 => Instruction::SetCell(self.u32("cell")),
+            11 => Instruction::GetCapture(self.u32("capture")),
+            12 => Instruction::NameFunction(self.u32("constant")),
+            13 => Instruction::Closure(self.u32("function")),
+            14 => Instruction::Array(self.u32("length")),
+            15 => Instruction::Infix(self.operator()),
+            16 => Instruction::Prefix(self.operator()),
+            17 => Instruction::Index,
+            18 => Instruction::Member(self.u32("constant")),
+            19 => {
+                let offset = self.offset;
+                let expect = match self.u8("expect") {
+                    0 => Expect::Value(self.u32("expression")),
+                    1 => Expect::Left,
+                    2 => Expect::Right,
+                    3 => Expect::Element,
+                    4 => Expect::Callee,
+                    5 => Expect::Indexable,
+                    6 => Expect::Index,
+                    tag => {
+                        self.fail(FormatError::InvalidTag {
+                            offset,
+                            item: "expect",
+                            tag,
+                        });
+                        Expect::Left
+                    }
+                };
+                Instruction::Expect(expect)
+            }
+            20 => Instruction::Jump(self.u32("jump target")),
+            21 => Instruction::JumpIfFalse(self.u32("jump target")),
+            22 => {
+                let (args, names) = self.call();
+                Instruction::Call(args, names)
+            }
+            23 => {
+                let (args, names) = self.call();
+                Instruction::TailCall(args, names)
+            }
+            24 => Instruction::Return,
+            25 => Instruction::Throw,
+            26 => Instruction::PushHandler(self.u32("handler target")),
+            27 => Instruction::PopHandler,
+            28 => Instruction::Catch,
+            29 => Instruction::Rethrow,
+            30 => Instruction::EnterFinally,
+            31 => Instruction::LeaveFinally,
+            32 => Instruction::AbortFinally(self.u32("finally end")),
+            33 => Instruction::Import(self.u32("constant")),
+            34 => Instruction::Default(self.u32("slot"), self.u32("default end")),
+            35 => Instruction::Required(self.u32("slot")),
+            36 => Instruction::MoveToCell(self.u32("slot"), self.u32("cell")),
+            tag => {
+                self.fail(FormatError::InvalidTag {
+                    offset,
+                    item: "opcode",
+                    tag,
+                });
+                Instruction::Nothing
+            }
+        }
+    }
+}
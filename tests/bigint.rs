@@ -0,0 +1,100 @@
+use dlang::object::bigint::BigInt;
+
+fn big(text: &str) -> BigInt {
+    BigInt::parse(text).unwrap_or_else(|| panic!("{text} is not a number"))
+}
+
+#[test]
+fn test_big_int_parse_and_display() {
+    for text in [
+        "0",
+        "7",
+        "-7",
+        "4294967295",
+        "4294967296",
+        "-9223372036854775808",
+        "1000000000",
+        "123456789012345678901234567890123456789",
+    ] {
+        assert_eq!(big(text).to_string(), text);
+    }
+    assert_eq!(big("-0"), BigInt::default());
+    assert_eq!(big("000123").to_string(), "123");
+    for text in ["", "-", "12a", "+1", "1 2"] {
+        assert_eq!(BigInt::parse(text), None, "{text}");
+    }
+}
+
+#[test]
+fn test_big_int_i64() {
+    for value in [0, 1, -1, i64::MAX, i64::MIN, 1 << 32, -(1 << 32)] {
+        let int = BigInt::from(value);
+        assert_eq!(int.to_string(), value.to_string());
+        assert_eq!(int.to_i64(), Some(value));
+    }
+    assert_eq!(big("9223372036854775808").to_i64(), None);
+    assert_eq!(big("-9223372036854775809").to_i64(), None);
+}
+
+#[test]
+fn test_big_int_arithmetic() {
+    let a = big("340282366920938463463374607431768211457");
+    let b = big("-18446744073709551617");
+    assert_eq!(
+        (&a + &b).to_string(),
+        "340282366920938463444927863358058659840"
+    );
+    assert_eq!(
+        (&b - &a).to_string(),
+        "-340282366920938463481821351505477763074"
+    );
+    assert_eq!(
+        (&a * &b).to_string(),
+        "-6277101735386680764176071790128604879584176795969512275969"
+    );
+    assert_eq!((-&b).to_string(), "18446744073709551617");
+    assert!(b < a && -&a < b);
+    assert_eq!(a.div_rem(&BigInt::default()), None);
+}
+
+#[test]
+fn test_big_int_bitwise() {
+    // same as i64 where it fits
+    let values = [0, 1, -1, 6, -8, i64::MAX, i64::MIN, 1 << 32, -(1 << 32) - 1];
+    for left in values {
+        for right in values {
+            let (big_left, big_right) = (BigInt::from(left), BigInt::from(right));
+            assert_eq!(&big_left & &big_right, BigInt::from(left & right), "{left} & {right}");
+            assert_eq!(&big_left | &big_right, BigInt::from(left | right), "{left} | {right}");
+        }
+    }
+
+    let a = big("340282367079394788491903282618856112135");
+    let b = big("-79228162514264337593543950339");
+    assert_eq!((&a & &b).to_string(), "340282367079394788491903282618856112133");
+    assert_eq!((&a | &b).to_string(), "-79228162514264337593543950337");
+    assert_eq!((&b & &-&a).to_string(), "-340282367158622951006167620212400062471");
+    assert_eq!((&b | &-&a).to_string(), "-3");
+}
+
+#[test]
+fn test_big_int_div_rem() {
+    let values = [
+        big("340282366920938463463374607431768211455"),
+        big("-79228162514264337593543950336"),
+        big("18446744073709551615"),
+        big("-4294967297"),
+        big("65536"),
+        big("3"),
+    ];
+    for dividend in values.iter() {
+        for divisor in values.iter() {
+            let (quotient, remainder) = dividend.div_rem(divisor).unwrap();
+            // dividend = quotient * divisor + remainder, |remainder| < |divisor|
+            assert_eq!(&(&quotient * divisor) + &remainder, *dividend);
+            let abs = |int: &BigInt| if int.is_negative() { -int } else { int.clone() };
+            assert!(abs(&remainder) < abs(divisor), "{dividend} % {divisor}");
+            assert!(remainder.is_zero() || remainder.is_negative() == dividend.is_negative());
+        }
+    }
+}
@@ -0,0 +1,243 @@
+use std::{
+    path::PathBuf,
+    sync::mpsc::{self, Receiver, Sender},
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use dlang::{dap::serve, lsp::json::Json};
+
+/// client side of a debug session served on another thread
+struct Client {
+    seq: usize,
+    requests: Sender<Json>,
+    events: Receiver<Json>,
+    server: JoinHandle<i32>,
+}
+
+impl Client {
+    fn start() -> Self {
+        let (requests, request_rx) = mpsc::channel();
+        let (event_tx, events) = mpsc::channel();
+        let server = thread::Builder::new()
+            .stack_size(64 * 1024 * 1024)
+            .spawn(move || serve(request_rx, event_tx, 32 * 1024 * 1024))
+            .unwrap();
+        Client {
+            seq: 0,
+            requests,
+            events,
+            server,
+        }
+    }
+
+    /// send request and wait for its response
+    fn request(&mut self, command: &str, arguments: Json) -> Json {
+        self.seq += 1;
+        let request = Json::object(vec![
+            ("seq", Json::from(self.seq)),
+            ("type", Json::from("request")),
+            ("command", Json::from(command)),
+            ("arguments", arguments),
+        ]);
+        self.requests.send(request).unwrap();
+        let seq = self.seq as i64;
+        self.wait(|msg| msg.get("request_seq").as_i64() == Some(seq))
+    }
+
+    /// skip messages until one matches
+    fn wait(&self, matches: impl Fn(&Json) -> bool) -> Json {
+        loop {
+            let msg = self.events.recv_timeout(Duration::from_secs(5)).unwrap();
+            if matches(&msg) {
+                return msg;
+            }
+        }
+    }
+
+    fn event(&self, event: &str) -> Json {
+        self.wait(|msg| msg.get("event").as_str() == Some(event))
+    }
+
+    /// reason of the next stop and (name, line) of frames, innermost first
+    fn stopped(&mut self) -> (String, Vec<(String, i64)>) {
+        let stopped = self.event("stopped");
+        let reason = stopped.get("body").get("reason").as_str().unwrap();
+        let trace = self.request(
+            "stackTrace",
+            Json::object(vec![("threadId", Json::from(1_usize))]),
+        );
+        let frames = trace.get("body").get("stackFrames").as_array().unwrap();
+        let frames = frames
+            .iter()
+            .map(|frame| {
+                let name = frame.get("name").as_str().unwrap().to_string();
+                (name, frame.get("line").as_i64().unwrap())
+            })
+            .collect();
+        (reason.to_string(), frames)
+    }
+
+    /// names of scopes of the frame and `name = value` of their variables
+    fn scopes(&mut self, frame: usize) -> Vec<(String, Vec<String>)> {
+        let scopes = self.request("scopes", Json::object(vec![("frameId", Json::from(frame))]));
+        let mut found = Vec::new();
+        for scope in scopes.get("body").get("scopes").as_array().unwrap() {
+            let reference = scope.get("variablesReference").clone();
+            let variables = self.request(
+                "variables",
+                Json::object(vec![("variablesReference", reference)]),
+            );
+            let variables = variables.get("body").get("variables").as_array().unwrap();
+            let variables = variables
+                .iter()
+                .map(|var| {
+                    let name = var.get("name").as_str().unwrap();
+                    format!("{} = {}", name, var.get("value").as_str().unwrap())
+                })
+                .collect();
+            found.push((scope.get("name").as_str().unwrap().to_string(), variables));
+        }
+        found
+    }
+}
+
+fn program() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("modules")
+        .join("debug.dl")
+}
+
+fn frames(frames: &[(&str, i64)]) -> Vec<(String, i64)> {
+    frames
+        .iter()
+        .map(|(name, line)| (name.to_string(), *line))
+        .collect()
+}
+
+fn launch(client: &mut Client, lines: &[usize], stop_on_entry: bool) -> Json {
+    let init = client.request("initialize", Json::object(Vec::new()));
+    assert_eq!(init.get("success"), &Json::Bool(true));
+    client.event("initialized");
+
+    let path = program().display().to_string();
+    let launched = client.request(
+        "launch",
+        Json::object(vec![
+            ("program", Json::from(path.as_str())),
+            ("stopOnEntry", Json::from(stop_on_entry)),
+        ]),
+    );
+    assert_eq!(launched.get("success"), &Json::Bool(true));
+
+    let breakpoints = lines
+        .iter()
+        .map(|line| Json::object(vec![("line", Json::from(*line))]))
+        .collect();
+    let set = client.request(
+        "setBreakpoints",
+        Json::object(vec![
+            ("source", Json::object(vec![("path", Json::from(path))])),
+            ("breakpoints", Json::Array(breakpoints)),
+        ]),
+    );
+    client.request("configurationDone", Json::object(Vec::new()));
+    set
+}
+
+#[test]
+fn test_dap_breakpoint_and_steps() {
+    let mut client = Client::start();
+    let set = launch(&mut client, &[7, 5], false);
+    let verified: Vec<_> = set
+        .get("body")
+        .get("breakpoints")
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|bp| bp.get("verified").as_bool().unwrap())
+        .collect();
+    // line 5 closes the function, no statement starts there
+    assert_eq!(verified, vec![true, false]);
+
+    assert_eq!(
+        client.stopped(),
+        ("breakpoint".to_string(), frames(&[("main", 7)]))
+    );
+    assert_eq!(
+        client.scopes(0),
+        vec![(
+            "Global".to_string(),
+            vec![
+                "add = fn add(n) {let m = (n * 2);(m + base)}".to_string(),
+                "base = 10".to_string(),
+                "x = 3".to_string(),
+            ]
+        )]
+    );
+
+    client.request("stepIn", Json::object(Vec::new()));
+    assert_eq!(
+        client.stopped(),
+        ("step".to_string(), frames(&[("add", 3), ("main", 7)]))
+    );
+
+    client.request("next", Json::object(Vec::new()));
+    assert_eq!(
+        client.stopped(),
+        ("step".to_string(), frames(&[("add", 4), ("main", 7)]))
+    );
+    // frame of the call is 1, as frames are counted from main
+    let scopes = client.scopes(1);
+    let names: Vec<_> = scopes.iter().map(|(name, _)| name.as_str()).collect();
+    assert_eq!(names, vec!["Local", "Outer 1", "Global"]);
+    assert_eq!(scopes[0].1, vec!["m = 6".to_string()]);
+    assert_eq!(scopes[1].1, vec!["n = 3".to_string()]);
+
+    client.request("stepOut", Json::object(Vec::new()));
+    assert_eq!(
+        client.stopped(),
+        ("step".to_string(), frames(&[("main", 8)]))
+    );
+
+    client.request("continue", Json::object(Vec::new()));
+    let output = client.event("output");
+    assert_eq!(output.get("body").get("output").as_str(), Some("17\n"));
+    let exited = client.event("exited");
+    assert_eq!(exited.get("body").get("exitCode").as_i64(), Some(0));
+    client.event("terminated");
+
+    client.request("disconnect", Json::object(Vec::new()));
+    assert_eq!(client.server.join().unwrap(), 0);
+}
+
+#[test]
+fn test_dap_stop_on_entry_and_disconnect() {
+    let mut client = Client::start();
+    launch(&mut client, &[], true);
+    assert_eq!(
+        client.stopped(),
+        ("entry".to_string(), frames(&[("main", 1)]))
+    );
+
+    // stepping over a call stays in main
+    client.request("next", Json::object(Vec::new()));
+    assert_eq!(client.stopped().1, frames(&[("main", 2)]));
+    // function literal is not entered until called
+    client.request("next", Json::object(Vec::new()));
+    assert_eq!(client.stopped().1, frames(&[("main", 6)]));
+
+    let threads = client.request("threads", Json::object(Vec::new()));
+    let threads = threads.get("body").get("threads").as_array().unwrap();
+    assert_eq!(threads.len(), 1);
+
+    let unknown = client.request("evaluate", Json::object(Vec::new()));
+    assert_eq!(unknown.get("success"), &Json::Bool(false));
+
+    // program is stopped without running the rest
+    client.request("disconnect", Json::object(Vec::new()));
+    let exited = client.event("exited");
+    assert_eq!(exited.get("body").get("exitCode").as_i64(), Some(0));
+    assert_eq!(client.server.join().unwrap(), 0);
+}
@@ -1,13 +1,18 @@
-use std::{cell::RefCell, rc::Rc};
+use std::{cell::RefCell, path::Path, rc::Rc, thread, time::Duration};
 
 use dlang::{
-    ast::Nodetrait,
+    ast::{Nodetrait, Statement},
     eval::{
         errors::{ArgumentsLength, EvalError},
         evaluate,
+        hook::Hook,
+        Evaluator,
     },
     lexer,
-    object::{environment::Environment, Int, Object, ObjectTrait, ObjectType},
+    object::{
+        environment::{Environ, Environment},
+        Array, Bool, Function, Int, Object, ObjectTrait, ObjectType, StringObject,
+    },
     parser,
     token::Kind,
 };
@@ -46,9 +51,9 @@ fn test_eval(input: String) -> Result<Option<Object>, EvalError> {
     let mut parser = parser::Parser::new(lex);
     let prog = parser.parse().unwrap();
 
-    let mut env = Rc::new(RefCell::new(Environment::new()));
+    let env = Rc::new(RefCell::new(Environment::new()));
 
-    evaluate(prog.to_node(), &mut env)
+    evaluate(prog.to_node(), &env)
 }
 
 fn test_integer_object_with_result(
@@ -200,9 +205,7 @@ fn test_if_object_with_result(
     if obj.is_none() != expect.is_none() {
         panic!("[{idx}]: {:?} not matched with {:?}", obj, expect);
     }
-    if obj.is_some() {
-        let obj = obj.unwrap();
-        let expect = expect.unwrap();
+    if let (Some(obj), Some(expect)) = (obj, expect) {
         if obj.get_type() != expect.get_type() {
             panic!("[{idx}]: {:?} not matched with {:?}", obj, expect);
         }
@@ -252,12 +255,12 @@ fn test_eval_errors() {
 
     tests.add((
         "if (1) {true}",
-        EvalError::NotABoolean(Object::Int(Int { value: 1 })),
+        EvalError::NotABoolean(Box::new(Object::Int(Int { value: 1 }))),
     ));
 
     tests.add((
         "if (0) {true}",
-        EvalError::NotABoolean(Object::Int(Int { value: 0 })),
+        EvalError::NotABoolean(Box::new(Object::Int(Int { value: 0 }))),
     ));
 
     tests.add(("foo", EvalError::IdentifierNotFound("foo".to_string())));
@@ -269,7 +272,8 @@ fn test_eval_errors() {
     tests.add((
         "fn (x,y,z) { x + y + z}(1,2)",
         EvalError::FunctionArgLengthNotMatched(ArgumentsLength {
-            function_args: 3,
+            min_args: 3,
+            max_args: Some(3),
             called_with: 2,
         }),
     ));
@@ -277,13 +281,53 @@ fn test_eval_errors() {
     tests.add((
         "fn (x,y) { x + y }(1,2,3)",
         EvalError::FunctionArgLengthNotMatched(ArgumentsLength {
-            function_args: 2,
+            min_args: 2,
+            max_args: Some(2),
             called_with: 3,
         }),
     ));
 
+    tests.add((
+        "fn (x, y = 1, ...z) { x }()",
+        EvalError::FunctionArgLengthNotMatched(ArgumentsLength {
+            min_args: 1,
+            max_args: None,
+            called_with: 0,
+        }),
+    ));
+
+    tests.add((
+        "fn (x, y) { x }(1, z = 2)",
+        EvalError::UnknownNamedArgument("z".to_string()),
+    ));
+    tests.add((
+        "fn (x, y) { x }(1, x = 2)",
+        EvalError::DuplicatedArgument("x".to_string()),
+    ));
+    tests.add((
+        "fn (x, y) { x }(y = 2, y = 3)",
+        EvalError::DuplicatedArgument("y".to_string()),
+    ));
+    tests.add((
+        "fn (x, y = 1) { x }(y = 2)",
+        EvalError::MissingArgument("x".to_string()),
+    ));
+
     tests.add(("100/0", EvalError::DivideWithZero));
     tests.add(("0/0", EvalError::DivideWithZero));
+    tests.add(("5 % 0", EvalError::DivideWithZero));
+    tests.add(("7n / 0", EvalError::DivideWithZero));
+    tests.add(("7n % 0n", EvalError::DivideWithZero));
+    // squared past the size limit of big integer
+    tests.add((
+        "let sq = fn(x, n) { if (n == 0) { x } else { sq(x * x, n - 1) } }; sq(2n, 20)",
+        EvalError::IntegerOverflow,
+    ));
+    tests.add((
+        "9223372036854775808n && 1",
+        EvalError::InvalidIntegerInfixOperation(Kind::And),
+    ));
+    tests.add(("1n + \"a\"", EvalError::NotSameType));
 
     tests.add((
         "!\"Hello\"",
@@ -337,6 +381,129 @@ fn test_eval_errors() {
     }
 }
 
+#[test]
+fn test_eval_try_expression() {
+    let mut tests: Tests<Object> = Tests::new();
+
+    tests.add((
+        "try { 10 / 0 } catch (e) { e[\"kind\"] }",
+        Object::String(StringObject {
+            value: "DivideWithZero".to_string(),
+        }),
+    ));
+    tests.add((
+        "let arr = [1, 2]; try { arr[2] } catch (e) { e[\"kind\"] }",
+        Object::String(StringObject {
+            value: "IndexOutOfRange".to_string(),
+        }),
+    ));
+    tests.add((
+        "try { throw \"oops\"; } catch (e) { e[\"message\"] }",
+        Object::String(StringObject {
+            value: "oops".to_string(),
+        }),
+    ));
+    // messages write values as users read them
+    for (input, message) in [
+        ("throw 5;", "5"),
+        ("throw [1, \"a\"];", "[1, \"a\"]"),
+        ("5()", "5 is not a function"),
+        ("let a = [1]; a[-1]", "index -1 is negative"),
+        ("[1][\"a\"]", "index \"a\" is not a integer"),
+    ] {
+        tests.add((
+            format!("try {{ {} }} catch (e) {{ e[\"message\"] }}", input).as_str(),
+            Object::String(StringObject {
+                value: message.to_string(),
+            }),
+        ));
+    }
+    tests.add((
+        "try { throw 42; } catch (e) { e[\"value\"] }",
+        Object::Int(Int { value: 42 }),
+    ));
+    tests.add(("try { 1 } catch (e) { 2 }", Object::Int(Int { value: 1 })));
+    tests.add((
+        "fn fail() { throw 1; } try { fail() } catch { 2 }",
+        Object::Int(Int { value: 2 }),
+    ));
+    tests.add((
+        "try { try { throw 1; } catch (e) { throw e; } } catch (e) { e[\"value\"] }",
+        Object::Int(Int { value: 1 }),
+    ));
+    tests.add((
+        "let x = 1;\ntry {\n  x / 0\n} catch (e) { e[\"span\"] }",
+        Object::Array(Array {
+            elements: vec![Object::Int(Int { value: 3 }), Object::Int(Int { value: 20 })],
+        }),
+    ));
+    // counted from 1, like lines in messages
+    tests.add((
+        "try { 1 / 0 } catch (e) { e[\"span\"] }",
+        Object::Array(Array {
+            elements: vec![Object::Int(Int { value: 1 }), Object::Int(Int { value: 7 })],
+        }),
+    ));
+    tests.add((
+        "try { 1 } finally { 2 }",
+        Object::Int(Int { value: 1 }),
+    ));
+    tests.add((
+        "try { 1 / 0 } catch { 2 } finally { 3 }",
+        Object::Int(Int { value: 2 }),
+    ));
+
+    for (idx, test) in tests.cases.iter().enumerate() {
+        let res = test_eval(test.input.clone());
+        test_let_stm_with_result(idx, test.expect.clone(), res);
+    }
+
+    let mut tests: Tests<EvalError> = Tests::new();
+
+    tests.add(("try { 1 / 0 } finally { 2 }", EvalError::DivideWithZero));
+    tests.add(("try { 1 } finally { 2 / 0 }", EvalError::DivideWithZero));
+    tests.add((
+        "try { 1 / 0 } catch (e) { e[\"nothing\"] }",
+        EvalError::KeyNotFound("nothing".to_string()),
+    ));
+
+    for (idx, test) in tests.cases.iter().enumerate() {
+        let res = test_eval(test.input.clone());
+        test_error_with_result(idx, test.expect.clone(), res);
+    }
+}
+
+#[test]
+fn test_eval_tail_call() {
+    let mut tests: Tests<Object> = Tests::new();
+
+    tests.add((
+        "fn countdown(n) { if (n == 0) { return 0; } return countdown(n - 1); }
+        countdown(1000000)",
+        Object::Int(Int { value: 0 }),
+    ));
+    tests.add((
+        "fn sum(n, acc) { if (n == 0) { acc } else { sum(n - 1, acc + n) } }
+        sum(100000, 0)",
+        Object::Int(Int { value: 5000050000 }),
+    ));
+    tests.add((
+        "fn even(n) { if (n == 0) { return true; } odd(n - 1) }
+        fn odd(n) { if (n == 0) { return false; } even(n - 1) }
+        even(100001)",
+        Object::Bool(Bool { value: false }),
+    ));
+    tests.add((
+        "fn fact(n) { if (n == 0) { return 1; } n * fact(n - 1) } fact(10)",
+        Object::Int(Int { value: 3628800 }),
+    ));
+
+    for (idx, test) in tests.cases.iter().enumerate() {
+        let res = test_eval(test.input.clone());
+        test_let_stm_with_result(idx, test.expect.clone(), res);
+    }
+}
+
 fn test_let_stm_with_result(idx: usize, expect: Object, res: Result<Option<Object>, EvalError>) {
     let obj = res.unwrap().unwrap();
 
@@ -410,6 +577,38 @@ fn test_eval_function() {
         Object::Int(Int { value: 50 }),
     ));
 
+    tests.add(("fn (x, y = 2) { x*y }(5)", Object::Int(Int { value: 10 })));
+    tests.add(("fn (x, y = 2) { x*y }(5, 3)", Object::Int(Int { value: 15 })));
+    tests.add((
+        "fn (x, y = x + 1) { x*y }(5)",
+        Object::Int(Int { value: 30 }),
+    ));
+    tests.add((
+        "fn (x, y = 2, z = 3) { x - y - z }(10, z = 1)",
+        Object::Int(Int { value: 7 }),
+    ));
+    tests.add((
+        "fn (x, y) { x - y }(y = 1, x = 10)",
+        Object::Int(Int { value: 9 }),
+    ));
+    tests.add((
+        "fn (first, ...others) { others }(1, 2, 3)",
+        Object::Array(Array {
+            elements: vec![Object::Int(Int { value: 2 }), Object::Int(Int { value: 3 })],
+        }),
+    ));
+    tests.add((
+        "fn (first, ...others) { others }(1)",
+        Object::Array(Array {
+            elements: Vec::new(),
+        }),
+    ));
+
+    tests.add((
+        "fn first(x) { if (x > 0) { return 1; } 2 } \n first(5)",
+        Object::Int(Int { value: 1 }),
+    ));
+
     // tests.add((
     //     "
     //     let createAdder = fn (x) {
@@ -430,3 +629,517 @@ fn test_eval_function() {
         test_function_with_result(idx, test.expect.clone(), res);
     }
 }
+
+#[test]
+fn test_eval_stack_overflow() {
+    let input = "fn depth(n) { 1 + depth(n + 1) } depth(0)";
+    let prog = parser::Parser::new(lexer::Lexer::new(input.to_string()))
+        .parse()
+        .unwrap();
+    let env = Rc::new(RefCell::new(Environment::new()));
+
+    let mut evaluator = Evaluator::new();
+    evaluator.set_max_depth(10);
+
+    let res = evaluator.evaluate(prog.to_node(), &env);
+    assert_eq!(res, Err(EvalError::StackOverflow { depth: 10 }));
+
+    // evaluator and environment are still usable after overflow
+    let prog = parser::Parser::new(lexer::Lexer::new("depth".to_string()))
+        .parse()
+        .unwrap();
+    let res = evaluator.evaluate(prog.to_node(), &env);
+    assert!(matches!(res, Ok(Some(Object::Function(_)))));
+
+    let input = "fn fact(n) { if (n == 0) { return 1; } n * fact(n - 1) } fact(9)";
+    let prog = parser::Parser::new(lexer::Lexer::new(input.to_string()))
+        .parse()
+        .unwrap();
+    let res = evaluator.evaluate(prog.to_node(), &env);
+    assert_eq!(res, Ok(Some(Object::Int(Int { value: 362880 }))));
+
+    let input = "try { depth(0) } catch (e) { e[\"kind\"] }";
+    let prog = parser::Parser::new(lexer::Lexer::new(input.to_string()))
+        .parse()
+        .unwrap();
+    let res = evaluator.evaluate(prog.to_node(), &env);
+    assert_eq!(
+        res,
+        Ok(Some(Object::String(StringObject {
+            value: "StackOverflow".to_string()
+        })))
+    );
+}
+
+#[test]
+fn test_eval_default_max_depth() {
+    // runs on stack of test thread, the default limits keep it from overflowing
+    let res = test_eval("fn depth(n) { 1 + depth(n + 1) } depth(0)".to_string());
+    assert!(matches!(res, Err(EvalError::StackOverflow { .. })));
+
+    let input = "fn depth(n) { if (n > 0) { let m = n; [depth(m - 1)] } else { 0 } } depth(100000)";
+    let res = test_eval(input.to_string());
+    assert!(matches!(res, Err(EvalError::StackOverflow { .. })));
+}
+
+#[test]
+fn test_eval_out_of_fuel() {
+    let env = Rc::new(RefCell::new(Environment::new()));
+    let mut evaluator = Evaluator::new();
+    evaluator.set_fuel(Some(10000));
+
+    let input = "fn forever(n) { forever(n + 1) } try { forever(0) } catch (e) { 0 }";
+    let prog = parser::Parser::new(lexer::Lexer::new(input.to_string()))
+        .parse()
+        .unwrap();
+    let res = evaluator.evaluate(prog.to_node(), &env);
+    assert_eq!(res, Err(EvalError::OutOfFuel));
+    assert_eq!(evaluator.remaining_fuel(), Some(0));
+
+    // topped up evaluator continues from the statement which ran out of fuel
+    let input = "let a = 1; let b = [a, a + 1]; fn sum(x, y) { x + y } sum(b[0], b[1])";
+    let prog = parser::Parser::new(lexer::Lexer::new(input.to_string()))
+        .parse()
+        .unwrap();
+    let mut res = evaluator.evaluate(prog.to_node(), &env);
+    let mut resumed = 0;
+    while res == Err(EvalError::OutOfFuel) {
+        assert!(evaluator.is_suspended());
+        evaluator.add_fuel(20);
+        res = evaluator.resume();
+        resumed += 1;
+    }
+    assert!(resumed > 1);
+    assert!(!evaluator.is_suspended());
+    assert_eq!(res, Ok(Some(Object::Int(Int { value: 3 }))));
+
+    evaluator.set_fuel(None);
+    let prog = parser::Parser::new(lexer::Lexer::new("sum(a, 10)".to_string()))
+        .parse()
+        .unwrap();
+    let res = evaluator.evaluate(prog.to_node(), &env);
+    assert_eq!(res, Ok(Some(Object::Int(Int { value: 11 }))));
+    assert_eq!(evaluator.resume(), Err(EvalError::BlankResult));
+}
+
+#[test]
+fn test_eval_memory_limit() {
+    let env = Rc::new(RefCell::new(Environment::new()));
+    let mut evaluator = Evaluator::new();
+    evaluator.set_memory_limit(Some(1024 * 1024));
+
+    let inputs = [
+        "fn grow(s, n) { if (n == 0) { s } else { grow(s + s, n - 1) } } grow(\"ab\", 64)",
+        "fn grow(a, n) { if (n == 0) { a } else { grow([a, a], n - 1) } } grow([1], 64)",
+        "fn grow(a, n) { if (n == 0) { a } else { grow([a, a], n - 1) } }
+        try { grow([1], 64) } catch (e) { 0 } finally { 1 }",
+    ];
+    for input in inputs {
+        let prog = parser::Parser::new(lexer::Lexer::new(input.to_string()))
+            .parse()
+            .unwrap();
+        let res = evaluator.evaluate(prog.to_node(), &env);
+        assert_eq!(
+            res,
+            Err(EvalError::MemoryLimitExceeded { limit: 1024 * 1024 })
+        );
+    }
+
+    // each evaluation has its own budget
+    let input = "grow([1], 4)";
+    let prog = parser::Parser::new(lexer::Lexer::new(input.to_string()))
+        .parse()
+        .unwrap();
+    let res = evaluator.evaluate(prog.to_node(), &env);
+    assert!(matches!(res, Ok(Some(Object::Array(_)))));
+    assert!(evaluator.allocated_memory() > 0);
+    assert!(evaluator.allocated_memory() < 1024 * 1024);
+
+    // reading a value creates nothing, only its creation is accounted
+    let input = "let s = \"abcdefgh\";";
+    let prog = parser::Parser::new(lexer::Lexer::new(input.to_string()))
+        .parse()
+        .unwrap();
+    evaluator.evaluate(prog.to_node(), &env).unwrap();
+    let mut allocated = Vec::new();
+    for input in ["[s][0] == s", "s == s && s == s && s == s && [s][0] == s"] {
+        let prog = parser::Parser::new(lexer::Lexer::new(input.to_string()))
+            .parse()
+            .unwrap();
+        let res = evaluator.evaluate(prog.to_node(), &env);
+        assert!(matches!(res, Ok(Some(Object::Bool(_)))), "{:?}", res);
+        allocated.push(evaluator.allocated_memory());
+    }
+    // only the array is accounted
+    assert!(allocated[0] > 0);
+    assert_eq!(allocated[0], allocated[1]);
+}
+
+#[test]
+fn test_eval_memory_limit_loop() {
+    let env = Rc::new(RefCell::new(Environment::new()));
+    let mut evaluator = Evaluator::new();
+    evaluator.set_memory_limit(Some(64 * 1024));
+
+    // holds one short string at a time, but each call allocates another
+    let input = "fn spin(n) { if (n == 0) { 0 } else { let s = \"ab\"; spin(n - 1) } }";
+    let prog = parser::Parser::new(lexer::Lexer::new(input.to_string()))
+        .parse()
+        .unwrap();
+    evaluator.evaluate(prog.to_node(), &env).unwrap();
+
+    let mut allocated = Vec::new();
+    for n in [10, 20] {
+        let input = format!("spin({})", n);
+        let prog = parser::Parser::new(lexer::Lexer::new(input.clone()))
+            .parse()
+            .unwrap();
+        let res = evaluator.evaluate(prog.to_node(), &env);
+        assert_eq!(res, Ok(Some(Object::Int(Int { value: 0 }))), "{input}");
+        allocated.push(evaluator.allocated_memory());
+    }
+    // the budget is cumulative, allocation grows with calls rather than live memory
+    assert!(allocated[1] > allocated[0]);
+
+    // so a long enough loop exceeds it
+    let input = "spin(100000)";
+    let prog = parser::Parser::new(lexer::Lexer::new(input.to_string()))
+        .parse()
+        .unwrap();
+    let res = evaluator.evaluate(prog.to_node(), &env);
+    assert_eq!(res, Err(EvalError::MemoryLimitExceeded { limit: 64 * 1024 }));
+}
+
+#[test]
+fn test_eval_interrupted() {
+    let env = Rc::new(RefCell::new(Environment::new()));
+    let mut evaluator = Evaluator::new();
+    let handle = evaluator.interrupt_handle();
+
+    let interrupter = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(50));
+        handle.interrupt();
+    });
+
+    let input = "let x = 1; fn forever(n) { forever(n + 1) }
+        try { forever(0) } catch (e) { 0 } finally { 1 }";
+    let prog = parser::Parser::new(lexer::Lexer::new(input.to_string()))
+        .parse()
+        .unwrap();
+    let res = evaluator.evaluate(prog.to_node(), &env);
+    interrupter.join().unwrap();
+    assert_eq!(res, Err(EvalError::Interrupted));
+
+    // interruption is consumed, and environment survives
+    let prog = parser::Parser::new(lexer::Lexer::new("forever(x)".to_string()))
+        .parse()
+        .unwrap();
+    evaluator.set_fuel(Some(1000));
+    let res = evaluator.evaluate(prog.to_node(), &env);
+    assert_eq!(res, Err(EvalError::OutOfFuel));
+}
+
+#[test]
+fn test_eval_function_source() {
+    let input = "fn add(a, b = \"x\", ...rest) { let c = a + b; if (c) { c } else { rest } } add";
+    let res = test_eval(input.to_string()).unwrap().unwrap();
+    let source = res.to_str();
+    assert_eq!(
+        source,
+        "fn add(a, b = \"x\", ...rest) {let c = (a + b);if (c) {c} else {rest}}"
+    );
+
+    // printed function evaluates to the same function
+    let reparsed = test_eval(format!("{} add", source)).unwrap().unwrap();
+    assert_eq!(reparsed.to_str(), source);
+}
+
+/// records what the evaluator reports
+struct Recorder(Rc<RefCell<Vec<String>>>);
+
+impl Hook for Recorder {
+    fn before_statement(&mut self, stm: &Statement, _env: &Environ<String>, _file: Option<&Path>) {
+        let line = stm.token().position.line_num;
+        self.0
+            .borrow_mut()
+            .push(format!("{}: {}", line, stm.to_str()));
+    }
+
+    fn on_call(&mut self, function: &Function, args: &[Object]) {
+        let args: Vec<String> = args.iter().map(|arg| arg.to_str()).collect();
+        let name = function.identifier.clone().unwrap_or_default();
+        self.0
+            .borrow_mut()
+            .push(format!("call {}({})", name, args.join(", ")));
+    }
+
+    fn on_return(&mut self, function: &Function, result: &Result<Option<Object>, EvalError>) {
+        let name = function.identifier.clone().unwrap_or_default();
+        let result = match result {
+            Ok(obj) => obj.as_ref().map(|obj| obj.to_str()).unwrap_or_default(),
+            Err(err) => err.kind(),
+        };
+        self.0
+            .borrow_mut()
+            .push(format!("return {} {}", name, result));
+    }
+}
+
+#[test]
+fn test_eval_hook() {
+    let input = "let inc = fn(x) { x + 1 };
+let y = inc(1);
+y";
+    let events = Rc::new(RefCell::new(Vec::new()));
+    let mut evaluator = Evaluator::new();
+    evaluator.set_hook(Some(Box::new(Recorder(Rc::clone(&events)))));
+
+    let program = parser::Parser::new(lexer::Lexer::new(input.to_string()))
+        .parse()
+        .unwrap();
+    let env = Rc::new(RefCell::new(Environment::new()));
+    let res = evaluator
+        .evaluate(program.to_node(), &env)
+        .unwrap()
+        .unwrap();
+    assert_eq!(res, Object::Int(Int { value: 2 }));
+    assert_eq!(
+        *events.borrow(),
+        vec![
+            "0: let inc = fn(x) {(x + 1)};",
+            "1: let y = inc(1);",
+            "call inc(Int:1)",
+            "0: (x + 1)",
+            "return inc Int:2",
+            "2: y",
+        ]
+    );
+}
+
+#[test]
+fn test_eval_resume_restarts_statement() {
+    let input = "let inc = fn(x) { x + 1 };
+let y = inc(inc(1));
+y";
+    let events = Rc::new(RefCell::new(Vec::new()));
+    let mut evaluator = Evaluator::new();
+    evaluator.set_hook(Some(Box::new(Recorder(Rc::clone(&events)))));
+    // runs out in the second call of inc
+    evaluator.set_fuel(Some(13));
+
+    let program = parser::Parser::new(lexer::Lexer::new(input.to_string()))
+        .parse()
+        .unwrap();
+    let env = Rc::new(RefCell::new(Environment::new()));
+    let res = evaluator.evaluate(program.to_node(), &env);
+    assert_eq!(res, Err(EvalError::OutOfFuel));
+    let suspended_at = events.borrow().len();
+    assert_eq!(events.borrow()[suspended_at - 1], "return inc OutOfFuel");
+
+    // the first call of inc is made again, finished statements are not
+    evaluator.add_fuel(100);
+    let res = evaluator.resume();
+    assert_eq!(res, Ok(Some(Object::Int(Int { value: 3 }))));
+    assert_eq!(
+        events.borrow()[suspended_at..],
+        vec![
+            "1: let y = inc(inc(1));",
+            "call inc(Int:1)",
+            "0: (x + 1)",
+            "return inc Int:2",
+            "call inc(Int:2)",
+            "0: (x + 1)",
+            "return inc Int:3",
+            "2: y",
+        ]
+    );
+}
+
+#[test]
+fn test_eval_display_and_inspect() {
+    for (input, display, inspect) in [
+        ("5", "5", "5"),
+        ("-5", "-5", "-5"),
+        ("true", "true", "true"),
+        ("\"hi\"", "hi", "\"hi\""),
+        ("\"a\nb\"", "a\nb", "\"a\\nb\""),
+        (
+            "[1, \"a\", [true, \"b\"]]",
+            "[1, \"a\", [true, \"b\"]]",
+            "[1, \"a\", [true, \"b\"]]",
+        ),
+        ("fn(x) { x }", "fn(x) {x}", "fn(x) {x}"),
+        (
+            "try { throw \"bad\"; } catch (e) { e }",
+            "Thrown: bad",
+            "<error Thrown: \"bad\">",
+        ),
+    ] {
+        let res = test_eval(input.to_string()).unwrap().unwrap();
+        assert_eq!(res.to_string(), display, "{input}");
+        assert_eq!(res.inspect(), inspect, "{input}");
+    }
+}
+
+#[test]
+fn test_eval_big_int() {
+    for (input, display, inspect) in [
+        // promoted on overflow
+        (
+            "9223372036854775807 + 1",
+            "9223372036854775808",
+            "9223372036854775808n",
+        ),
+        (
+            "-9223372036854775807 - 2",
+            "-9223372036854775809",
+            "-9223372036854775809n",
+        ),
+        (
+            "4611686018427387904 * 2",
+            "9223372036854775808",
+            "9223372036854775808n",
+        ),
+        (
+            "let min = -9223372036854775807 - 1; [min / -1, min % -1, -min]",
+            "[9223372036854775808n, 0, 9223372036854775808n]",
+            "[9223372036854775808n, 0, 9223372036854775808n]",
+        ),
+        // Int again if it fits, also mixed with int
+        (
+            "(9223372036854775807 + 1) - 1",
+            "9223372036854775807",
+            "9223372036854775807",
+        ),
+        ("2n + 3", "5", "5"),
+        ("[1, 2n]", "[1, 2]", "[1, 2]"),
+        (
+            "123456789012345678901234567890n * 1000000000n",
+            "123456789012345678901234567890000000000",
+            "123456789012345678901234567890000000000n",
+        ),
+        (
+            "100000000000000000000n / 7",
+            "14285714285714285714",
+            "14285714285714285714n",
+        ),
+        ("100000000000000000000n % 7", "2", "2"),
+        // truncated toward zero as Int
+        ("-7n / 2", "-3", "-3"),
+        ("-7n % 2", "-1", "-1"),
+        ("5n - 5n", "0", "0"),
+        // bitwise not as Int, -x - 1
+        ("!5n", "-6", "-6"),
+        ("!9223372036854775807", "-9223372036854775808", "-9223372036854775808"),
+        (
+            "!9223372036854775808n",
+            "-9223372036854775809",
+            "-9223372036854775809n",
+        ),
+        (
+            "!-9223372036854775809n",
+            "9223372036854775808",
+            "9223372036854775808n",
+        ),
+        // bitwise and, or of two's complement as Int
+        ("6n & 3", "2", "2"),
+        ("-8n | 3n", "-5", "-5"),
+        (
+            "18446744073709551616n | 1",
+            "18446744073709551617",
+            "18446744073709551617n",
+        ),
+        ("18446744073709551617n & 7", "1", "1"),
+        (
+            "-18446744073709551616n & -1",
+            "-18446744073709551616",
+            "-18446744073709551616n",
+        ),
+        ("-18446744073709551617n | 18446744073709551616n", "-1", "-1"),
+        // intermediate exceeds i64
+        ("(9223372036854775807 + 1) & 9223372036854775807", "0", "0"),
+    ] {
+        let res = test_eval(input.to_string()).unwrap().unwrap();
+        assert_eq!(res.to_string(), display, "{input}");
+        assert_eq!(res.inspect(), inspect, "{input}");
+    }
+
+    for (input, expect) in [
+        ("5 == 5n", true),
+        ("2n != 2", false),
+        ("[1, 2n] == [1n, 2]", true),
+        ("5n < 6", true),
+        ("-1n > -2n", true),
+        ("9223372036854775807 < 9223372036854775807 + 1", true),
+        ("-100000000000000000000n < -99999999999999999999n", true),
+        ("(9223372036854775807 + 1) - 1 == 9223372036854775807", true),
+        ("[9223372036854775808n - 1] == [9223372036854775807]", true),
+        ("9223372036854775808n == 9223372036854775807", false),
+    ] {
+        let res = test_eval(input.to_string()).unwrap().unwrap();
+        assert_eq!(res, Object::Bool(Bool { value: expect }), "{input}");
+    }
+
+    // results which fit are Int, usable as index
+    for (input, expect) in [
+        ("[10, 20][1n]", 20),
+        ("[10, 20][(9223372036854775807 + 1) - 9223372036854775807]", 20),
+        ("(9223372036854775807 + 1) - 1 & 1", 1),
+    ] {
+        let res = test_eval(input.to_string()).unwrap().unwrap();
+        assert_eq!(res, Object::Int(Int { value: expect }), "{input}");
+    }
+}
+
+#[test]
+fn test_eval_equality_and_ordering() {
+    for (input, expect) in [
+        ("[1, [2, \"a\"]] == [1, [2, \"a\"]]", true),
+        ("[1, [2, \"a\"]] == [1, [2, \"b\"]]", false),
+        ("[1, 2] != [1, 2, 3]", true),
+        ("[] == []", true),
+        ("1 == \"1\"", false),
+        ("1 != true", true),
+        ("[1] == 1", false),
+        ("\"abc\" < \"abd\"", true),
+        ("\"ab\" < \"abc\"", true),
+        ("\"b\" > \"abc\"", true),
+        ("\"a\" <= \"a\"", true),
+        ("\"Z\" >= \"a\"", false),
+        ("[1, 2] < [1, 3]", true),
+        ("[1, 2] < [1, 2, 0]", true),
+        ("[2] > [1, 9]", true),
+        ("[\"a\", [1]] >= [\"a\", [1]]", true),
+        ("[] <= []", true),
+        ("let f = fn(x) { x }; f == f", true),
+        ("let f = fn(x) { x }; let g = f; [g] == [f]", true),
+        ("fn(x) { x } == fn(x) { x }", false),
+        ("let make = fn() { fn() { 1 } }; make() == make()", false),
+        (
+            "let e = try { throw [1]; } catch (e) { e }; e == try { throw [1]; } catch (e) { e }",
+            true,
+        ),
+    ] {
+        let res = test_eval(input.to_string()).unwrap().unwrap();
+        assert_eq!(res, Object::Bool(Bool { value: expect }), "{input}");
+    }
+
+    for (input, err) in [
+        ("1 < \"1\"", EvalError::NotSameType),
+        ("[1] < [\"1\"]", EvalError::NotSameType),
+        (
+            "true < false",
+            EvalError::InvalidBoolInfixOperation(Kind::LT),
+        ),
+        (
+            "[fn() { 1 }] < [fn() { 1 }]",
+            EvalError::InvalidInfixOperationTarget(ObjectType::Function, Kind::LT),
+        ),
+        (
+            "[1] + [2]",
+            EvalError::InvalidInfixOperationTarget(ObjectType::Array, Kind::Plus),
+        ),
+    ] {
+        assert_eq!(test_eval(input.to_string()), Err(err), "{input}");
+    }
+}
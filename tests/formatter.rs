@@ -0,0 +1,147 @@
+use std::{fs, path::PathBuf};
+
+use dlang::{
+    ast::{Nodetrait, Program},
+    formatter::{format, format_program},
+    lexer::Lexer,
+    parser::Parser,
+};
+
+fn parse(input: &str) -> Program {
+    Parser::new(Lexer::new(input.to_string())).parse().unwrap()
+}
+
+#[test]
+fn test_format() {
+    let input = "// header
+import \"lib/math.dl\" as math;   // helpers
+let x = 2*(3+4) - -1;
+
+
+let f = fn(a,b=1,...rest) { // opening
+  let c = a+b*(a-b);
+  // before return
+
+  if (c>1) {return c;} else { throw \"no\" }
+  // end of body
+};
+fn g(n) { if (n == 0) { 0 } else { g(n - 1) } }
+try { f(1, b = 2) } catch (e) { e[\"message\"] } finally {}
+let y = (-x).y[0](fn() {1});
+let empty = fn() {};
+// tail
+";
+    let expect = "// header
+import \"lib/math.dl\" as math; // helpers
+let x = 2 * (3 + 4) - -1;
+
+let f = fn(a, b = 1, ...rest) { // opening
+    let c = a + b * (a - b);
+    // before return
+
+    if (c > 1) {
+        return c;
+    } else {
+        throw \"no\";
+    }
+    // end of body
+};
+fn g(n) {
+    if (n == 0) {
+        0
+    } else {
+        g(n - 1)
+    }
+}
+try {
+    f(1, b = 2)
+} catch (e) {
+    e[\"message\"]
+} finally {}
+let y = (-x).y[0](fn() {
+    1
+});
+let empty = fn() {};
+// tail
+";
+
+    assert_eq!(format(input).unwrap(), expect);
+    assert_eq!(format(expect).unwrap(), expect);
+}
+
+#[test]
+fn test_format_grouping() {
+    let cases = [
+        ("(1 + 2) * 3", "(1 + 2) * 3\n"),
+        ("1 + (2 * 3)", "1 + 2 * 3\n"),
+        ("1 - (2 - 3)", "1 - (2 - 3)\n"),
+        ("(1 - 2) - 3", "1 - 2 - 3\n"),
+        ("!(a == b) && (c || d)", "!(a == b) && (c || d)\n"),
+        ("-(a[0])", "-a[0]\n"),
+        ("(-a)[0]", "(-a)[0]\n"),
+        (
+            "(if (a) { f } else { g })(1)",
+            "(if (a) {\n    f\n} else {\n    g\n})(1)\n",
+        ),
+        ("m.f(a)(b)", "m.f(a)(b)\n"),
+    ];
+
+    for (input, expect) in cases {
+        assert_eq!(format(input).unwrap(), expect, "{}", input);
+    }
+}
+
+/// a statement is printed on one line, so comments inside it follow the statement
+#[test]
+fn test_format_comment_in_statement() {
+    let input = "let x = 1 + // c\n    2; // after\nx";
+    let expect = "let x = 1 + 2; // after\n// c\nx\n";
+    assert_eq!(format(input).unwrap(), expect);
+    assert_eq!(format(expect).unwrap(), expect);
+
+    // multibyte characters in comments and strings
+    let input = "let s = \"café\";  // 日本\ns";
+    assert_eq!(format(input).unwrap(), "let s = \"café\"; // 日本\ns\n");
+}
+
+#[test]
+fn test_format_errors() {
+    let err = format("let a = ;").unwrap_err();
+    assert!(err.contains("(line 1)"), "{}", err);
+}
+
+/// formatting keeps program, and formatted source is stable
+#[test]
+fn test_format_idempotent() {
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests");
+    let mut sources = Vec::new();
+    for dir in [dir.join("modules"), dir.join("modules").join("lib")] {
+        for entry in fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.extension().is_some_and(|ext| ext == "dl") {
+                sources.push(fs::read_to_string(path).unwrap());
+            }
+        }
+    }
+    sources.push(
+        "let fib = fn(n) { if (n < 2) { n } else { fib(n - 1) + fib(n - 2) } }; fib(10)"
+            .to_string(),
+    );
+    sources.push("try { throw [1, 2] } catch { 1 } // done".to_string());
+
+    for source in sources {
+        // unparsable sources are out of interest
+        let Ok(program) = Parser::new(Lexer::new(source.clone())).parse() else {
+            continue;
+        };
+        let formatted = format(&source).unwrap();
+        assert_eq!(parse(&formatted).to_str(), program.to_str(), "{}", source);
+        assert_eq!(format(&formatted).unwrap(), formatted, "{}", source);
+        assert_eq!(
+            format_program(&program),
+            format_program(&parse(&formatted)),
+            "{}",
+            source
+        );
+    }
+}
@@ -173,3 +173,107 @@ fn test_lexer() {
         assert_eq!(expect.literal, cur_token.literal);
     }
 }
+
+#[test]
+fn test_lexer_ellipsis() {
+    let input = "fn(a, ...b)";
+
+    let mut expects = Vec::new();
+    let mut lex = lexer::Lexer::new(input.to_string());
+
+    expects.push(Token::with(Kind::Function, "fn"));
+    expects.push(Token::with(Kind::LPAREN, "("));
+    expects.push(Token::with(Kind::Ident, "a"));
+    expects.push(Token::with(Kind::Comma, ","));
+    expects.push(Token::with(Kind::Ellipsis, "..."));
+    expects.push(Token::with(Kind::Ident, "b"));
+    expects.push(Token::with(Kind::RPAREN, ")"));
+    expects.push(Token::with(Kind::EOF, "EOF"));
+
+    for expect in expects {
+        let cur_token = lex.next();
+        assert_eq!(expect.kind, cur_token.kind);
+        assert_eq!(expect.literal, cur_token.literal);
+    }
+}
+
+#[test]
+fn test_lexer_big_int() {
+    let input = "12n + 3 n";
+
+    let mut expects = Vec::new();
+    let mut lex = lexer::Lexer::new(input.to_string());
+
+    expects.push(Token::with(Kind::BigInt, "12"));
+    expects.push(Token::with(Kind::Plus, "+"));
+    expects.push(Token::with(Kind::Int, "3"));
+    expects.push(Token::with(Kind::Ident, "n"));
+    expects.push(Token::with(Kind::EOF, "EOF"));
+
+    for expect in expects {
+        let cur_token = lex.next();
+        assert_eq!(expect.kind, cur_token.kind);
+        assert_eq!(expect.literal, cur_token.literal);
+    }
+}
+
+#[test]
+fn test_lexer_comment() {
+    let input = "// header\na / b // divide  \n//\n";
+
+    let mut expects = Vec::new();
+    let mut lex = lexer::Lexer::new(input.to_string());
+
+    expects.push(Token::with(Kind::Ident, "a"));
+    expects.push(Token::with(Kind::Divide, "/"));
+    expects.push(Token::with(Kind::Ident, "b"));
+    expects.push(Token::with(Kind::EOF, "EOF"));
+
+    for expect in expects {
+        let cur_token = lex.next();
+        assert_eq!(expect.kind, cur_token.kind);
+        assert_eq!(expect.literal, cur_token.literal);
+    }
+
+    let comments: Vec<(usize, &str)> = lex
+        .comments()
+        .iter()
+        .map(|comment| (comment.position.line_num, comment.text.as_str()))
+        .collect();
+    assert_eq!(comments, [(0, " header"), (1, " divide"), (2, "")]);
+}
+
+#[test]
+fn test_lexer_multibyte() {
+    let input = "// café\nlet s = \"naïve 日本\"; s // ✓ ok\nlet é = 1;";
+
+    let mut expects = Vec::new();
+    let mut lex = lexer::Lexer::new(input.to_string());
+
+    expects.push(Token::with(Kind::Let, "let"));
+    expects.push(Token::with(Kind::Ident, "s"));
+    expects.push(Token::with(Kind::Assign, "="));
+    expects.push(Token::with(Kind::String, "naïve 日本"));
+    expects.push(Token::with(Kind::Semicolon, ";"));
+    expects.push(Token::with(Kind::Ident, "s"));
+    expects.push(Token::with(Kind::Let, "let"));
+    expects.push(Token::with(Kind::Ident, "é"));
+
+    for expect in expects {
+        let cur_token = lex.next();
+        assert_eq!(expect.kind, cur_token.kind);
+        assert_eq!(expect.literal, cur_token.literal);
+    }
+
+    // positions are byte offsets in input
+    let token = lex.next();
+    assert_eq!(token.kind, Kind::Assign);
+    assert_eq!(&input[token.position.pos..], "= 1;");
+
+    let comments: Vec<(usize, &str)> = lex
+        .comments()
+        .iter()
+        .map(|comment| (comment.position.line_num, comment.text.as_str()))
+        .collect();
+    assert_eq!(comments, [(0, " café"), (1, " ✓ ok")]);
+}
@@ -0,0 +1,268 @@
+use std::io::Cursor;
+
+use dlang::{
+    ast::Program,
+    lexer::Lexer,
+    lsp::{
+        analysis::Analysis,
+        json::{self, Json},
+        read_message, run, write_message, Server,
+    },
+    parser::Parser,
+};
+
+const URI: &str = "file:///main.dl";
+
+const SOURCE: &str = "let add = fn(a, b = 1) { a + b };
+fn twice(x) { add(x, x) }
+let y = twice(2);
+add(y)";
+
+fn request(id: usize, method: &str, params: Json) -> Json {
+    Json::object(vec![
+        ("jsonrpc", Json::from("2.0")),
+        ("id", Json::from(id)),
+        ("method", Json::from(method)),
+        ("params", params),
+    ])
+}
+
+fn notification(method: &str, params: Json) -> Json {
+    Json::object(vec![
+        ("jsonrpc", Json::from("2.0")),
+        ("method", Json::from(method)),
+        ("params", params),
+    ])
+}
+
+fn at(line: usize, character: usize) -> Json {
+    Json::object(vec![
+        ("textDocument", Json::object(vec![("uri", Json::from(URI))])),
+        (
+            "position",
+            Json::object(vec![
+                ("line", Json::from(line)),
+                ("character", Json::from(character)),
+            ]),
+        ),
+    ])
+}
+
+fn open(server: &mut Server, text: &str) -> Vec<Json> {
+    server.handle(&notification(
+        "textDocument/didOpen",
+        Json::object(vec![(
+            "textDocument",
+            Json::object(vec![
+                ("uri", Json::from(URI)),
+                ("languageId", Json::from("dlang")),
+                ("version", Json::from(1_usize)),
+                ("text", Json::from(text)),
+            ]),
+        )]),
+    ))
+}
+
+/// result of a request, which must succeed
+fn result(server: &mut Server, method: &str, params: Json) -> Json {
+    let replies = server.handle(&request(1, method, params));
+    assert_eq!(replies.len(), 1);
+    assert!(replies[0].get("error").is_null(), "{}", replies[0].to_str());
+    replies[0].get("result").clone()
+}
+
+/// `line:character` of start of each range
+fn starts(locations: &Json) -> Vec<String> {
+    let mut starts = Vec::new();
+    for location in locations.as_array().unwrap() {
+        let start = location.get("range").get("start");
+        starts.push(format!(
+            "{}:{}",
+            start.get("line").as_i64().unwrap(),
+            start.get("character").as_i64().unwrap()
+        ));
+    }
+    starts
+}
+
+fn parse(input: &str) -> Program {
+    Parser::new(Lexer::new(input.to_string())).parse().unwrap()
+}
+
+#[test]
+fn test_lsp_json() {
+    let input =
+        r#"{"a": [1, -2.5, 3e2, true, false, null], "b": "q\"\\\n\u00e9\ud83d\ude00", "c": {}}"#;
+    let value = json::parse(input).unwrap();
+
+    assert_eq!(value.get("a").as_array().unwrap().len(), 6);
+    assert_eq!(value.get("a").as_array().unwrap()[2].as_i64(), Some(300));
+    assert_eq!(value.get("b").as_str(), Some("q\"\\\né😀"));
+    assert!(value.get("missing").is_null());
+    assert_eq!(json::parse(&value.to_str()).unwrap(), value);
+    assert_eq!(
+        value.to_str(),
+        r#"{"a":[1,-2.5,300,true,false,null],"b":"q\"\\\né😀","c":{}}"#
+    );
+
+    for invalid in ["", "{", "[1,]", "{\"a\" 1}", "\"\\x\"", "tru", "1 2"] {
+        assert!(json::parse(invalid).is_err(), "{}", invalid);
+    }
+}
+
+#[test]
+fn test_lsp_analysis() {
+    // nested function refers a name bound later, inner let shadows outer one
+    let source = "fn f() { g() }\nfn g() { let f = 1; f }\nf";
+    let analysis = Analysis::of(&parse(source), source);
+
+    let g = analysis.definition_at(10).unwrap();
+    assert_eq!(analysis.definitions[g].position.line_num, 1);
+
+    let inner = analysis.definition_at(36).unwrap();
+    assert_eq!(analysis.definitions[inner].detail, "let f");
+    let outer = analysis.definition_at(source.len() - 1).unwrap();
+    assert_eq!(analysis.definitions[outer].detail, "fn f()");
+    assert_eq!(analysis.references_of(outer).len(), 1);
+
+    let visible: Vec<&str> = analysis
+        .visible_at(36)
+        .iter()
+        .map(|def| def.name.as_str())
+        .collect();
+    assert_eq!(visible, ["f", "g"]);
+}
+
+#[test]
+fn test_lsp_session() {
+    let mut server = Server::new();
+    let init = result(&mut server, "initialize", Json::object(Vec::new()));
+    assert_eq!(
+        init.get("capabilities").get("definitionProvider"),
+        &Json::Bool(true)
+    );
+
+    // parse errors are published
+    let published = open(&mut server, "let x = 1");
+    assert_eq!(
+        published[0].get("method").as_str(),
+        Some("textDocument/publishDiagnostics")
+    );
+    let diagnostics = published[0].get("params").get("diagnostics");
+    assert_eq!(diagnostics.as_array().unwrap().len(), 1);
+    let range = diagnostics.as_array().unwrap()[0].get("range");
+    assert_eq!(range.get("start").get("line").as_i64(), Some(0));
+
+    let changed = server.handle(&notification(
+        "textDocument/didChange",
+        Json::object(vec![
+            ("textDocument", Json::object(vec![("uri", Json::from(URI))])),
+            (
+                "contentChanges",
+                Json::Array(vec![Json::object(vec![("text", Json::from(SOURCE))])]),
+            ),
+        ]),
+    ));
+    let diagnostics = changed[0].get("params").get("diagnostics");
+    assert!(diagnostics.as_array().unwrap().is_empty());
+
+    let definition = result(&mut server, "textDocument/definition", at(3, 1));
+    assert_eq!(starts(&Json::Array(vec![definition])), ["0:4"]);
+    let definition = result(&mut server, "textDocument/definition", at(2, 9));
+    assert_eq!(starts(&Json::Array(vec![definition])), ["1:3"]);
+    assert!(result(&mut server, "textDocument/definition", at(0, 27)).is_null());
+
+    let references = result(&mut server, "textDocument/references", at(0, 5));
+    assert_eq!(starts(&references), ["0:4", "1:14", "3:0"]);
+
+    let hover = result(&mut server, "textDocument/hover", at(1, 4));
+    assert_eq!(
+        hover.get("contents").get("value").as_str(),
+        Some("```dlang\nfn twice(x)\n```")
+    );
+    let hover = result(&mut server, "textDocument/hover", at(3, 0));
+    assert_eq!(
+        hover.get("contents").get("value").as_str(),
+        Some("```dlang\nlet add = fn(a, b = 1)\n```")
+    );
+
+    let symbols = result(&mut server, "textDocument/documentSymbol", at(0, 0));
+    let names: Vec<&str> = symbols
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|symbol| symbol.get("name").as_str().unwrap())
+        .collect();
+    assert_eq!(names, ["add", "twice", "y"]);
+
+    // inside body of twice, y is not bound yet
+    let completion = result(&mut server, "textDocument/completion", at(1, 14));
+    let labels: Vec<&str> = completion
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|item| item.get("label").as_str().unwrap())
+        .collect();
+    assert_eq!(&labels[..3], ["x", "twice", "add"]);
+    assert!(labels.contains(&"let") && labels.contains(&"finally"));
+    assert!(!labels.contains(&"y"));
+
+    let unknown = server.handle(&request(9, "textDocument/rename", at(0, 0)));
+    assert_eq!(unknown[0].get("error").get("code").as_i64(), Some(-32601));
+
+    assert!(result(&mut server, "shutdown", Json::Null).is_null());
+    server.handle(&notification("exit", Json::Null));
+    assert_eq!(server.exit_code(), Some(0));
+}
+
+/// positions in messages count utf-16 code units, text before them may be multibyte
+#[test]
+fn test_lsp_multibyte() {
+    let mut server = Server::new();
+    let published = open(&mut server, "let s = \"é😀\"; let é = s;\n\"😀\" + é +");
+    let diagnostics = published[0].get("params").get("diagnostics");
+    let start = diagnostics.as_array().unwrap()[0].get("range").get("start");
+    assert_eq!(start.get("line").as_i64(), Some(1));
+    // end of input, after `"😀" + é +`
+    assert_eq!(start.get("character").as_i64(), Some(10));
+
+    let published = open(&mut server, "let s = \"é😀\"; let é = s;\né");
+    let diagnostics = published[0].get("params").get("diagnostics");
+    assert!(diagnostics.as_array().unwrap().is_empty());
+
+    let definition = result(&mut server, "textDocument/definition", at(0, 23));
+    assert_eq!(starts(&Json::Array(vec![definition])), ["0:4"]);
+    let references = result(&mut server, "textDocument/references", at(1, 0));
+    assert_eq!(starts(&references), ["0:19", "1:0"]);
+    let hover = result(&mut server, "textDocument/hover", at(0, 19));
+    assert_eq!(
+        hover.get("contents").get("value").as_str(),
+        Some("```dlang\nlet é\n```")
+    );
+}
+
+#[test]
+fn test_lsp_stdio() {
+    let mut input = Vec::new();
+    for message in [
+        request(1, "initialize", Json::object(Vec::new())),
+        request(2, "shutdown", Json::Null),
+        notification("exit", Json::Null),
+    ] {
+        write_message(&mut input, &message.to_str()).unwrap();
+    }
+
+    let mut output = Vec::new();
+    let code = run(&mut Cursor::new(input), &mut output);
+    assert_eq!(code, 0);
+
+    let mut output = Cursor::new(output);
+    let first = json::parse(&read_message(&mut output).unwrap().unwrap()).unwrap();
+    assert_eq!(first.get("id").as_i64(), Some(1));
+    let second = json::parse(&read_message(&mut output).unwrap().unwrap()).unwrap();
+    assert_eq!(second.get("id").as_i64(), Some(2));
+    assert!(read_message(&mut output).unwrap().is_none());
+
+    // closed without exit
+    assert_eq!(run(&mut Cursor::new(Vec::new()), &mut Vec::new()), 1);
+}
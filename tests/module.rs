@@ -0,0 +1,72 @@
+use std::{cell::RefCell, path::PathBuf, rc::Rc};
+
+use dlang::{
+    eval::{errors::EvalError, Evaluator},
+    object::{environment::Environment, Int, Object},
+    vm::Vm,
+};
+
+fn eval_module_file(name: &str) -> Result<Option<Object>, EvalError> {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("modules")
+        .join(name);
+
+    let env = Rc::new(RefCell::new(Environment::new()));
+    Evaluator::new().evaluate_file(&path, &env)
+}
+
+#[test]
+fn test_import_module() {
+    let res = eval_module_file("main.dl").unwrap().unwrap();
+
+    assert_eq!(res, Object::Int(Int { value: 12 }));
+}
+
+#[test]
+fn test_import_errors() {
+    let err = eval_module_file("missing.dl").err().unwrap();
+    let EvalError::ModuleNotFound(detail) = &err else {
+        panic!("{:?}", err)
+    };
+    assert_eq!(detail.path, "lib/nothing.dl");
+    assert_eq!(detail.position.unwrap().line_num, 2);
+    assert!(
+        err.message()
+            .starts_with("import of \"lib/nothing.dl\" failed at line 3: "),
+        "{}",
+        err.message()
+    );
+
+    let err = eval_module_file("cycle_a.dl").err().unwrap();
+    let EvalError::ImportCycle(detail) = err else { panic!("{:?}", err) };
+    assert_eq!(detail.path, "cycle_a.dl");
+
+    let err = eval_module_file("broken.dl").err().unwrap();
+    let EvalError::ModuleParseFailed(detail) = err else { panic!("{:?}", err) };
+    assert_eq!(detail.path, "lib/broken.dl");
+    assert!(detail.reason.contains("(line 1)"), "{}", detail.reason);
+
+    let err = eval_module_file("private.dl").err().unwrap();
+    assert_eq!(err, EvalError::NotExported("hidden".to_string()));
+
+    let err = eval_module_file("nothing.dl").err().unwrap();
+    let EvalError::ModuleNotFound(_) = err else { panic!("{:?}", err) };
+
+    // errors of the file being run are its own, not of an import
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("modules")
+        .join("lib")
+        .join("broken.dl");
+    for err in [
+        eval_module_file("lib/broken.dl").err().unwrap(),
+        Vm::new().evaluate_file(&path).err().unwrap(),
+    ] {
+        let EvalError::ModuleParseFailed(detail) = &err else { panic!("{:?}", err) };
+        assert_eq!(detail.position, None);
+        let message = err.message();
+        assert!(message.starts_with(&format!("{}: ", path.display())), "{}", message);
+        assert!(message.contains("(line 1)"), "{}", message);
+    }
+}
@@ -0,0 +1,155 @@
+use std::{cell::RefCell, rc::Rc};
+
+use dlang::{
+    ast::{Nodetrait, Program},
+    eval::Evaluator,
+    lexer::Lexer,
+    object::{environment::Environment, ObjectTrait},
+    optimizer::optimize,
+    parser::Parser,
+    vm::Vm,
+};
+
+fn parse(input: &str) -> Program {
+    Parser::new(Lexer::new(input.to_string())).parse().unwrap()
+}
+
+fn eval(program: Program) -> String {
+    let env = Rc::new(RefCell::new(Environment::new()));
+    match Evaluator::new().evaluate(program.to_node(), &env) {
+        Ok(obj) => obj.map(|obj| obj.to_str()).unwrap_or_default(),
+        Err(err) => err.message(),
+    }
+}
+
+fn eval_vm(program: Program) -> String {
+    match Vm::new().evaluate(&program) {
+        Ok(obj) => obj.map(|obj| obj.to_str()).unwrap_or_default(),
+        Err(err) => err.message(),
+    }
+}
+
+/// optimized program evaluates same as the original, on both engines
+fn assert_same(input: &str) {
+    let program = parse(input);
+    let optimized = optimize(program.clone());
+
+    assert_eq!(eval(optimized.clone()), eval(program.clone()), "{}", input);
+    assert_eq!(eval_vm(optimized), eval_vm(program), "{}", input);
+}
+
+#[test]
+fn test_optimize_folding() {
+    let cases = [
+        ("2 * 60 * 60", "7200"),
+        ("let a = 1 + 2 * 3 - -4; a", "let a = 11;a"),
+        ("!(1 < 2) == false", "true"),
+        ("\"ab\" + \"cd\" == \"abcd\"", "true"),
+        // partially constant
+        ("let f = fn(x) { x * (3 + 4) };", "let f = fn(x) {(x * 7)};"),
+        // overflow is promoted to big integer
+        ("9223372036854775807 + 1", "9223372036854775808n"),
+        ("-(-9223372036854775807 - 1)", "9223372036854775808n"),
+        ("2n * 3 - 10", "-4"),
+        ("100000000000000000000n - 1", "99999999999999999999n"),
+        // errors are left to evaluation
+        ("1 / (2 - 2)", "(1 / 0)"),
+        ("1 + true", "(1 + true)"),
+        ("-\"a\"", "-(\"a\")"),
+        ("5 % 0", "(5 % 0)"),
+    ];
+
+    for (input, expect) in cases {
+        assert_eq!(optimize(parse(input)).to_str(), expect, "{}", input);
+        assert_same(input);
+    }
+}
+
+#[test]
+fn test_optimize_branches() {
+    let cases = [
+        ("if (true) { 1 } else { 2 }", "if (true) {1}"),
+        ("if (1 > 2) { 1 } else { 2 }", "if (true) {2}"),
+        ("if (false) { 1 }", "if (true) {}"),
+        // condition which is not constant
+        ("let a = 1; if (a > 2) { 1 } else { 2 }", ""),
+    ];
+
+    for (input, expect) in cases {
+        let optimized = optimize(parse(input)).to_str();
+        if !expect.is_empty() {
+            assert_eq!(optimized, expect, "{}", input);
+        } else {
+            assert!(optimized.contains("else"), "{}", optimized);
+        }
+        assert_same(input);
+    }
+
+    let programs = [
+        "let f = fn(n) { if (1 == 1) { return n + 1; } n }; f(1)",
+        "let f = fn(n) { if (!true) { n } else { let m = n * (2 + 3); m } }; f(3)",
+        "let f = fn(n) { if (false) { fn g() { 1 } } let g = 2; g + n }; f(1)",
+        "let f = fn(n) { if (\"a\" == \"a\") { f(n - 1) } else { n } }; 1",
+        "try { if (true) { throw 1 + 2 } } catch (e) { e[\"value\"] * 2 }",
+    ];
+    for input in programs {
+        assert_same(input);
+    }
+}
+
+/// xorshift, to generate expressions without external crates
+struct Random(u64);
+
+impl Random {
+    fn next(&mut self, bound: u64) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0 % bound
+    }
+
+    fn expression(&mut self, depth: u32) -> String {
+        if depth == 0 {
+            return match self.next(5) {
+                0 => "true".to_string(),
+                1 => "false".to_string(),
+                2 => format!("\"{}\"", ["", "a", "bc"][self.next(3) as usize]),
+                _ => self.next(10).to_string(),
+            };
+        }
+        match self.next(4) {
+            0 => {
+                let operator = ["-", "!"][self.next(2) as usize];
+                format!("{}({})", operator, self.expression(depth - 1))
+            }
+            1 => format!(
+                "if ({}) {{ {} }} else {{ {} }}",
+                self.expression(depth - 1),
+                self.expression(depth - 1),
+                self.expression(depth - 1)
+            ),
+            _ => {
+                let operators = [
+                    "+", "-", "*", "/", "<", "<=", ">", ">=", "==", "!=", "&&", "||", "&", "|",
+                ];
+                let operator = operators[self.next(operators.len() as u64) as usize];
+                format!(
+                    "({} {} {})",
+                    self.expression(depth - 1),
+                    operator,
+                    self.expression(depth - 1)
+                )
+            }
+        }
+    }
+}
+
+#[test]
+fn test_optimize_differential() {
+    let mut random = Random(0x2545_f491_4f6c_dd1d);
+    for _ in 0..500 {
+        let depth = random.next(4) as u32 + 1;
+        let input = random.expression(depth);
+        assert_same(&input);
+    }
+}
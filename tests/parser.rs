@@ -1,7 +1,11 @@
 use dlang::{
-    ast::{ArrayLiteral, Nodetrait},
+    ast::{
+        visit_mut::{self, VisitorMut},
+        ArrayLiteral, ExpressionStatement, Nodetrait, Program,
+    },
     lexer::Lexer,
     parser::Parser,
+    token::{Kind, Token},
 };
 
 #[test]
@@ -55,12 +59,12 @@ fn test_bool_literal() {
 
 #[test]
 fn test_parentheses() {
-    let mut tests = Vec::new();
-
-    tests.push((
-        "((true || (false || true)))".to_string(),
-        "(true || (false || true))".to_string(),
-    ));
+    let tests = vec![
+        (
+            "((true || (false || true)))".to_string(),
+            "(true || (false || true))".to_string(),
+        ),
+    ];
 
     for (input, expect) in tests {
         let lexer = Lexer::new(input.clone());
@@ -99,34 +103,104 @@ fn test_prefix_expression() {
 
 #[test]
 fn test_if_expression() {
-    let mut tests = Vec::new();
+    let tests = [
+        (
+            "if (x > 10) { return x; } else {return 0;}".to_string(),
+            "if (x > 10) {return x;} else {return 0;}".to_string(),
+        ),
+        (
+            "if (flag) {return true;}".to_string(),
+            "if (flag) {return true;}".to_string(),
+        ),
+    ];
+
+    for (input, expect) in tests.iter() {
+        let lexer = Lexer::new(input.clone());
 
-    tests.push((
-        "if (x > 10) { return x; } else {return 0;}".to_string(),
-        "if (x > 10) {return x;} else {return 0;}".to_string(),
-    ));
-    tests.push((
-        "if (flag) {return true;}".to_string(),
-        "if flag {return true;}".to_string(),
-    ));
+        let mut parser = Parser::new(lexer);
+        let res = parser.parse().ok().unwrap();
+        let result = res.statements[0].to_str();
+        assert!(&result == expect);
+    }
+}
+
+#[test]
+fn test_try_expression() {
+    let tests = vec![
+        (
+            "try { throw x; } catch (e) { return e; }".to_string(),
+            "try {throw x;} catch (e) {return e;}".to_string(),
+        ),
+        (
+            "try { x } catch { 0 } finally { y }".to_string(),
+            "try {x} catch {0} finally {y}".to_string(),
+        ),
+    ];
 
-    for (idx, (input, expect)) in tests.iter().enumerate() {
+    for (input, expect) in tests {
         let lexer = Lexer::new(input.clone());
 
         let mut parser = Parser::new(lexer);
         let res = parser.parse().ok().unwrap();
         let result = res.statements[0].to_str();
-        assert!(&result == expect);
+        assert!(result == expect);
     }
+
+    let lexer = Lexer::new("try { x }".to_string());
+    let mut parser = Parser::new(lexer);
+    assert!(parser.parse().is_err());
 }
 
 #[test]
-fn test_call_expression() {
-    let mut tests = Vec::new();
+fn test_import_export_statement() {
+    let tests = vec![
+        (
+            "import \"lib/math.dl\" as math;".to_string(),
+            "import \"lib/math.dl\" as math;".to_string(),
+        ),
+        ("export let x = 1;".to_string(), "export let x = 1;".to_string()),
+        (
+            "export fn f(x) { x }".to_string(),
+            "export fn f(x) {x}".to_string(),
+        ),
+        ("math.square(2)".to_string(), "math.square(2)".to_string()),
+    ];
+
+    for (input, expect) in tests {
+        let lexer = Lexer::new(input.clone());
 
-    tests.push(("call(foo, bar)".to_string(), "call(foo, bar)".to_string()));
-    tests.push(("call2()".to_string(), "call2()".to_string()));
-    tests.push(("call3(30)".to_string(), "call3(30)".to_string()));
+        let mut parser = Parser::new(lexer);
+        let res = parser.parse().ok().unwrap();
+        let result = res.statements[0].to_str();
+        assert!(result == expect);
+    }
+
+    let inputs = vec![
+        "export 1 + 1".to_string(),
+        "export fn (x) { x }".to_string(),
+        "import math;".to_string(),
+        "import \"math.dl\";".to_string(),
+    ];
+
+    for input in inputs {
+        let lexer = Lexer::new(input.clone());
+
+        let mut parser = Parser::new(lexer);
+        assert!(parser.parse().is_err());
+    }
+}
+
+#[test]
+fn test_call_expression() {
+    let tests = vec![
+        ("call(foo, bar)".to_string(), "call(foo, bar)".to_string()),
+        ("call2()".to_string(), "call2()".to_string()),
+        ("call3(30)".to_string(), "call3(30)".to_string()),
+        (
+            "call4(1, b=2, c=3)".to_string(),
+            "call4(1, b = 2, c = 3)".to_string(),
+        ),
+    ];
 
     for (input, expect) in tests {
         let lexer = Lexer::new(input.clone());
@@ -141,32 +215,36 @@ fn test_call_expression() {
 
 #[test]
 fn test_function_literal() {
-    let mut tests = Vec::new();
-
-    tests.push((
-        "fn call(foo) {
-         let foo = true;
-         return foo;
-         }"
-        .to_string(),
-        "fn call(foo) {let foo = true;return foo;}".to_string(),
-    ));
-    tests.push((
-        "fn call2() {
-         let hello = true;
-         return hello;
-         }"
-        .to_string(),
-        "fn call2() {let hello = true;return hello;}".to_string(),
-    ));
-    tests.push((
-        "let hey = fn () {
-         let hello = true;
-         return hello;
-         };"
-        .to_string(),
-        "let hey = fn() {let hello = true;return hello;};".to_string(),
-    ));
+    let tests = vec![
+        (
+            "fn call(foo) {
+             let foo = true;
+             return foo;
+             }"
+            .to_string(),
+            "fn call(foo) {let foo = true;return foo;}".to_string(),
+        ),
+        (
+            "fn call2() {
+             let hello = true;
+             return hello;
+             }"
+            .to_string(),
+            "fn call2() {let hello = true;return hello;}".to_string(),
+        ),
+        (
+            "let hey = fn () {
+             let hello = true;
+             return hello;
+             };"
+            .to_string(),
+            "let hey = fn() {let hello = true;return hello;};".to_string(),
+        ),
+        (
+            "fn call3(a, b = 2, ...rest) { a }".to_string(),
+            "fn call3(a, b = 2, ...rest) {a}".to_string(),
+        ),
+    ];
     for (input, expect) in tests {
         let lexer = Lexer::new(input.clone());
 
@@ -179,16 +257,33 @@ fn test_function_literal() {
 }
 
 #[test]
-fn test_array_literal() {
-    let mut tests = Vec::new();
+fn test_function_parameters_errors() {
+    let mut inputs = Vec::new();
 
-    tests.push((
-        "[]".to_string(),
-        ArrayLiteral {
-            elements: Vec::new(),
-        }
-        .to_str(),
-    ));
+    inputs.push("fn (a = 1, b) { a }".to_string());
+    inputs.push("fn (...a, b) { a }".to_string());
+    inputs.push("fn (...) { 1 }".to_string());
+    inputs.push("call(a = 1, 2)".to_string());
+
+    for input in inputs {
+        let lexer = Lexer::new(input.clone());
+
+        let mut parser = Parser::new(lexer);
+        assert!(parser.parse().is_err());
+    }
+}
+
+#[test]
+fn test_array_literal() {
+    let mut tests = vec![
+        (
+            "[]".to_string(),
+            ArrayLiteral {
+                elements: Vec::new(),
+            }
+            .to_str(),
+        ),
+    ];
 
     tests.push(("[1,2,3,4]".to_string(), "[1, 2, 3, 4]".to_string()));
 
@@ -197,6 +292,9 @@ fn test_array_literal() {
         "[[1, 2, 3, 4], [5, 6, 7, 8]]".to_string(),
     ));
 
+    tests.push(("arr[1 + 1] * 2".to_string(), "(arr[(1 + 1)] * 2)".to_string()));
+    tests.push(("[1, 2][0][1]".to_string(), "[1, 2][0][1]".to_string()));
+
     for (input, expect) in tests {
         let lexer = Lexer::new(input.clone());
 
@@ -207,3 +305,206 @@ fn test_array_literal() {
         assert!(result == expect);
     }
 }
+
+/// expression statement takes the first token of its expression,
+/// which changes when parentheses are added around
+struct ForgetStatementToken;
+
+impl VisitorMut for ForgetStatementToken {
+    fn visit_expression_statement_mut(&mut self, stm: &mut ExpressionStatement) {
+        stm.token = Token::new(Kind::Illegal);
+        visit_mut::walk_expression_statement_mut(self, stm);
+    }
+}
+
+/// syntax tree regardless of positions, which differ after printing
+fn shape(mut program: Program) -> String {
+    ForgetStatementToken.visit_program_mut(&mut program);
+    let debug = format!("{:?}", program);
+
+    let mut buf = String::new();
+    let mut rest = debug.as_str();
+    while let Some(start) = rest.find("position: Position {") {
+        buf.push_str(&rest[..start]);
+        rest = &rest[start..];
+        rest = &rest[rest.find('}').unwrap() + 1..];
+    }
+    buf.push_str(rest);
+    buf
+}
+
+/// parse(print(parse(input))) equals parse(input)
+fn assert_round_trip(input: &str) {
+    let parsed = Parser::new(Lexer::new(input.to_string())).parse();
+    assert!(parsed.is_ok(), "{}", input);
+    let parsed = parsed.ok().unwrap();
+
+    let printed = parsed.to_str();
+    let reparsed = Parser::new(Lexer::new(printed.clone())).parse();
+    assert!(reparsed.is_ok(), "{}\nprinted: {}", input, printed);
+
+    assert_eq!(
+        shape(reparsed.ok().unwrap()),
+        shape(parsed),
+        "{}\nprinted: {}",
+        input,
+        printed
+    );
+}
+
+#[test]
+fn test_print_round_trip() {
+    let inputs = [
+        "let a = 1; a b",
+        "fn f(x) { x } g",
+        "(-a)(1) (-a)[0] (-a).b",
+        "if (flag) { \"yes\" } else { \"no\" }",
+        "if (if (a) { b } else { c }) { 1 } + 2",
+        "import \"lib/math.dl\" as math; math.square(2)",
+        "export fn f(a, b = \"x\", ...rest) { return a; }",
+        "try { throw 1; } catch (e) { e[\"value\"] } finally { 0 }",
+        "f(1, b = -2)(3)[4]",
+    ];
+    for input in inputs {
+        assert_round_trip(input);
+    }
+}
+
+/// xorshift, to generate programs without external crates
+struct Random(u64);
+
+impl Random {
+    fn next(&mut self, bound: u64) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0 % bound
+    }
+
+    fn pick<'a>(&mut self, items: &[&'a str]) -> &'a str {
+        items[self.next(items.len() as u64) as usize]
+    }
+
+    fn block(&mut self, depth: u32) -> String {
+        let mut stms = Vec::new();
+        for _ in 0..self.next(3) {
+            stms.push(self.statement(depth));
+        }
+        format!("{{ {} }}", stms.join("\n"))
+    }
+
+    fn statement(&mut self, depth: u32) -> String {
+        match self.next(6) {
+            0 => format!(
+                "let {} = {};",
+                self.pick(&["a", "b", "c"]),
+                self.expression(depth)
+            ),
+            1 => format!("return {};", self.expression(depth)),
+            2 => format!("throw {};", self.expression(depth)),
+            _ => self.expression(depth),
+        }
+    }
+
+    fn expression(&mut self, depth: u32) -> String {
+        if depth == 0 {
+            return match self.next(5) {
+                0 => self.pick(&["true", "false"]).to_string(),
+                1 => format!("\"{}\"", self.pick(&["", "a", "b c"])),
+                2 => self.pick(&["a", "b", "c", "f"]).to_string(),
+                _ => self.next(100).to_string(),
+            };
+        }
+        let depth = depth - 1;
+        match self.next(11) {
+            0 => format!("{}{}", self.pick(&["-", "!"]), self.expression(depth)),
+            1 => format!(
+                "if ({}) {}{}",
+                self.expression(depth),
+                self.block(depth),
+                if self.next(2) == 0 {
+                    format!(" else {}", self.block(depth))
+                } else {
+                    String::new()
+                }
+            ),
+            2 => {
+                let name = if self.next(2) == 0 { " g" } else { "" };
+                let mut params = Vec::new();
+                for param in ["x", "y"].iter().take(self.next(3) as usize) {
+                    params.push(param.to_string());
+                }
+                if self.next(2) == 0 {
+                    params.push(format!("z = {}", self.expression(0)));
+                }
+                if self.next(3) == 0 {
+                    params.push("...rest".to_string());
+                }
+                format!("fn{}({}) {}", name, params.join(", "), self.block(depth))
+            }
+            3 => {
+                let mut args = Vec::new();
+                for _ in 0..self.next(3) {
+                    args.push(self.expression(depth));
+                }
+                if self.next(3) == 0 {
+                    args.push(format!("z = {}", self.expression(depth)));
+                }
+                format!("{}({})", self.expression(depth), args.join(", "))
+            }
+            4 => format!("{}[{}]", self.expression(depth), self.expression(depth)),
+            5 => format!("{}.{}", self.expression(depth), self.pick(&["x", "y"])),
+            6 => {
+                let mut elements = Vec::new();
+                for _ in 0..self.next(3) {
+                    elements.push(self.expression(depth));
+                }
+                format!("[{}]", elements.join(", "))
+            }
+            7 => {
+                let mut buf = format!("try {}", self.block(depth));
+                if self.next(2) == 0 {
+                    let ident = if self.next(2) == 0 { "(e) " } else { "" };
+                    buf += &format!(" catch {}{}", ident, self.block(depth));
+                }
+                if !buf.contains("catch") || self.next(2) == 0 {
+                    buf += &format!(" finally {}", self.block(depth));
+                }
+                buf
+            }
+            8 => format!("({})", self.expression(depth)),
+            _ => {
+                let operators = [
+                    "+", "-", "*", "/", "%", "<", "<=", ">", ">=", "==", "!=", "&&", "||", "&", "|",
+                ];
+                format!(
+                    "{} {} {}",
+                    self.expression(depth),
+                    self.pick(&operators),
+                    self.expression(depth)
+                )
+            }
+        }
+    }
+}
+
+#[test]
+fn test_print_round_trip_random() {
+    let mut random = Random(0x9e37_79b9_7f4a_7c15);
+    let mut checked = 0;
+    for _ in 0..1000 {
+        let depth = random.next(4) as u32 + 1;
+        let mut stms = Vec::new();
+        for _ in 0..random.next(4) + 1 {
+            stms.push(random.statement(depth));
+        }
+        let input = stms.join("\n");
+        // e.g. statement starting with `[` is read as index of the one before
+        if Parser::new(Lexer::new(input.clone())).parse().is_err() {
+            continue;
+        }
+        assert_round_trip(&input);
+        checked += 1;
+    }
+    assert!(checked > 500, "{}", checked);
+}
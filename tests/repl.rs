@@ -0,0 +1,527 @@
+use std::{env, fs, io::Cursor, path::PathBuf, process};
+
+use dlang::repl::{
+    command::{Command, DebugOutput},
+    editor::{read_key, Editor, Event, Key},
+    history::History,
+    is_incomplete, Flow, Repl,
+};
+
+/// run lines in a session, returns what each printed
+fn run(repl: &mut Repl, lines: &[&str]) -> Vec<String> {
+    lines
+        .iter()
+        .map(|line| {
+            let mut out = String::new();
+            assert_eq!(repl.run_line(line, &mut out), Flow::Continue, "{line}");
+            out
+        })
+        .collect()
+}
+
+#[test]
+fn test_repl_command_parse() {
+    assert_eq!(Command::parse("let a = 1;\n"), None);
+    assert_eq!(Command::parse(":env\n"), Some(Ok(Command::Env)));
+    assert_eq!(
+        Command::parse("  :type  1 + 2 \n"),
+        Some(Ok(Command::Type("1 + 2".to_string())))
+    );
+    assert_eq!(
+        Command::parse(":load lib/math.dl"),
+        Some(Ok(Command::Load(PathBuf::from("lib/math.dl"))))
+    );
+    assert_eq!(Command::parse(":debug"), Some(Ok(Command::Debug(None))));
+    assert_eq!(
+        Command::parse(":debug parser on"),
+        Some(Ok(Command::Debug(Some((DebugOutput::Parser, Some(true))))))
+    );
+    assert_eq!(
+        Command::parse(":debug eval"),
+        Some(Ok(Command::Debug(Some((DebugOutput::Evaluator, None)))))
+    );
+
+    for (line, err) in [
+        (":ast", ":ast needs an argument"),
+        (":quit now", ":quit takes no argument"),
+        (
+            ":debug vm",
+            "unknown debug output vm, one of lexer, parser, eval",
+        ),
+        (":debug lexer yes", "unknown state yes, on or off"),
+        (":exit", "unknown command :exit, :help lists commands"),
+    ] {
+        assert_eq!(Command::parse(line), Some(Err(err.to_string())), "{line}");
+    }
+}
+
+#[test]
+fn test_repl_commands() {
+    let mut repl = Repl::new();
+    let out = run(
+        &mut repl,
+        &[
+            "let a = 1;\n",
+            "let b = [a, 2];\n",
+            ":env\n",
+            ":type b\n",
+            ":type let c = 1;\n",
+            ":tokens a + 1\n",
+            ":reset\n",
+            ":env\n",
+        ],
+    );
+    assert_eq!(out[0], "");
+    assert_eq!(out[2], "a = 1\nb = [1, 2]\n");
+    assert_eq!(out[3], "Array\n");
+    assert_eq!(out[4], "no value\n");
+    assert_eq!(out[5], "Ident \"a\"\nPlus \"+\"\nInt \"1\"\n");
+    assert_eq!(out[6], "environment is reset\n");
+    assert_eq!(out[7], "");
+
+    // names are gone with the environment
+    let out = run(&mut repl, &["a\n"]);
+    assert!(out[0].contains("ERROR"), "{}", out[0]);
+
+    let out = run(&mut repl, &[":time 1 + 2\n", ":ast 1\n", ":nope\n"]);
+    assert!(out[0].starts_with("3\ntime: "), "{}", out[0]);
+    assert!(out[1].contains("IntegerLiteral"), "{}", out[1]);
+    assert!(out[2].contains("unknown command :nope"), "{}", out[2]);
+
+    let mut out = String::new();
+    assert_eq!(repl.run_line(":quit\n", &mut out), Flow::Quit);
+}
+
+#[test]
+fn test_repl_debug_toggle() {
+    let mut repl = Repl::new();
+    let out = run(
+        &mut repl,
+        &[
+            ":debug eval on\n",
+            "1\n",
+            ":debug eval\n",
+            "1\n",
+            ":debug\n",
+        ],
+    );
+    assert_eq!(out[0], "eval: on\n");
+    assert_eq!(
+        out[1],
+        "Debug Output (Eval) >> Ok(Some(Int(Int { value: 1 })))\n1\n"
+    );
+    assert_eq!(out[2], "eval: off\n");
+    assert_eq!(out[3], "1\n");
+    assert_eq!(out[4], "lexer: off\nparser: off\neval: off\n");
+}
+
+#[test]
+fn test_repl_load() {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("modules")
+        .join("debug.dl");
+    let mut repl = Repl::new();
+    let out = run(
+        &mut repl,
+        &[&format!(":load {}\n", path.display()), "add(1)\n"],
+    );
+    assert_eq!(out, vec!["17\n", "12\n"]);
+}
+
+#[test]
+fn test_repl_later_definition() {
+    let mut repl = Repl::new();
+    let out = run(
+        &mut repl,
+        &[
+            "let twice = fn(x) { double(double(x)) };\n",
+            "fn triple(x) { add(x, add(x, x)) }\n",
+            "let double = fn(x) { x * 2 };\n",
+            "let add = fn(a, b) { a + b };\n",
+            "[twice(3), triple(3)]\n",
+        ],
+    );
+    assert_eq!(out, vec!["", "fn triple(x) {add(x, add(x, x))}\n", "", "", "[12, 9]\n"]);
+
+    // still undefined when called
+    let out = run(&mut repl, &["let f = fn() { nothing };\n", "f()\n"]);
+    assert_eq!(out[0], "");
+    assert!(out[1].contains("identifier nothing not found"), "{}", out[1]);
+
+    // other errors of resolution are reported before evaluation
+    let out = run(&mut repl, &["let g = fn(a, a) { a };\n", "g\n"]);
+    assert!(out[0].contains("duplicated parameter a"), "{}", out[0]);
+    assert!(out[1].contains("identifier g not found"), "{}", out[1]);
+}
+
+#[test]
+fn test_repl_incomplete_input() {
+    for source in [
+        "let add = fn(a, b) {\n",
+        "add(1,\n",
+        "[1, [2\n",
+        "let s = \"abc\n",
+        "1 +\n",
+        "let a =\n",
+        "a.\n",
+        "true &&\n",
+    ] {
+        assert!(is_incomplete(source), "{source:?}");
+    }
+    for source in [
+        "let add = fn(a, b) { a + b };\n",
+        "add(1, 2)\n",
+        "let s = \"a{b\";\n",
+        "-1\n",
+        "\n",
+        // extra closing is an error, not an incomplete input
+        "1 }\n",
+    ] {
+        assert!(!is_incomplete(source), "{source:?}");
+    }
+}
+
+#[test]
+fn test_repl_multi_line() {
+    let mut repl = Repl::new();
+    let lines = [
+        "let add = fn(a, b) {\n",
+        "    a +\n",
+        "    b\n",
+        "};\n",
+        "add(\n",
+        "  1, 2)\n",
+    ];
+    let mut prompts = Vec::new();
+    let mut outs = Vec::new();
+    for line in lines {
+        let mut out = String::new();
+        repl.run_line(line, &mut out);
+        prompts.push(repl.prompt());
+        outs.push(out);
+    }
+    assert_eq!(prompts, vec![".. ", ".. ", ".. ", "-> ", ".. ", "-> "]);
+    assert_eq!(outs, vec!["", "", "", "", "", "3\n"]);
+
+    // blank line gives up waiting, and the error is shown
+    let out = run(&mut repl, &["let a = (1\n", "\n", ":env\n"]);
+    assert_eq!(out[0], "");
+    assert!(out[1].contains("ERROR"), "{}", out[1]);
+    assert!(out[2].starts_with("add = "), "{}", out[2]);
+
+    // commands are not read while input is incomplete
+    let out = run(&mut repl, &["[1,\n", ":env\n"]);
+    assert_eq!(out[1], "");
+    assert_eq!(repl.prompt(), ".. ");
+    let out = run(&mut repl, &["\n"]);
+    assert!(out[0].contains("ERROR"), "{}", out[0]);
+    assert_eq!(repl.prompt(), "-> ");
+}
+
+/// keys of the bytes a terminal sends
+fn keys(bytes: &[u8]) -> Vec<Key> {
+    let mut input = Cursor::new(bytes.to_vec());
+    let mut keys = Vec::new();
+    while let Some(key) = read_key(&mut input).unwrap() {
+        keys.push(key);
+    }
+    keys
+}
+
+fn no_completion(_: &str) -> (usize, Vec<String>) {
+    (0, Vec::new())
+}
+
+/// type text into editor, returning event of the last key
+fn type_keys(editor: &mut Editor, keys: &[Key]) -> Event {
+    let mut event = Event::Edited;
+    for key in keys {
+        event = editor.feed(*key, &no_completion);
+    }
+    event
+}
+
+fn chars(text: &str) -> Vec<Key> {
+    text.chars().map(Key::Char).collect()
+}
+
+#[test]
+fn test_repl_read_key() {
+    assert_eq!(
+        keys(b"a\x1b[A\x1b[B\x1b[C\x1b[D\x1b[H\x1bOF\x1b[3~\x1b[1~\x7f\r\t"),
+        vec![
+            Key::Char('a'),
+            Key::Up,
+            Key::Down,
+            Key::Right,
+            Key::Left,
+            Key::Home,
+            Key::End,
+            Key::Delete,
+            Key::Home,
+            Key::Backspace,
+            Key::Enter,
+            Key::Tab,
+        ]
+    );
+    assert_eq!(
+        keys("\x01\x03\x04\x05\x0b\x12\x15\x17é한".as_bytes()),
+        vec![
+            Key::Home,
+            Key::Interrupt,
+            Key::Eof,
+            Key::End,
+            Key::KillToEnd,
+            Key::Search,
+            Key::KillToStart,
+            Key::KillWord,
+            Key::Char('é'),
+            Key::Char('한'),
+        ]
+    );
+    assert_eq!(keys(b"\x1b[5~\x1b"), vec![Key::Unknown, Key::Cancel]);
+}
+
+#[test]
+fn test_repl_editor() {
+    let mut editor = Editor::new(History::new());
+    type_keys(&mut editor, &chars("let x = 1"));
+    type_keys(
+        &mut editor,
+        &[Key::Left, Key::Left, Key::Backspace, Key::Char('+')],
+    );
+    assert_eq!(
+        (editor.line(), editor.cursor()),
+        ("let x + 1".to_string(), 7)
+    );
+    assert_eq!(editor.render("-> "), "\r-> let x + 1\x1b[K\x1b[2D");
+
+    type_keys(
+        &mut editor,
+        &[Key::Home, Key::Delete, Key::End, Key::KillWord],
+    );
+    assert_eq!(editor.line(), "et x + ");
+    type_keys(
+        &mut editor,
+        &[Key::Left, Key::Left, Key::Left, Key::KillToEnd],
+    );
+    assert_eq!(editor.line(), "et x");
+    type_keys(&mut editor, &[Key::Left, Key::KillToStart]);
+    assert_eq!((editor.line(), editor.cursor()), ("x".to_string(), 0));
+
+    assert_eq!(
+        type_keys(&mut editor, &[Key::Eof, Key::Eof]),
+        Event::Eof,
+        "ctrl-d deletes, then ends on empty line"
+    );
+    type_keys(&mut editor, &chars("oops"));
+    assert_eq!(
+        type_keys(&mut editor, &[Key::Interrupt]),
+        Event::Interrupted
+    );
+    assert_eq!(editor.line(), "");
+    assert_eq!(type_keys(&mut editor, &chars("1 + 2")), Event::Edited);
+    assert_eq!(
+        type_keys(&mut editor, &[Key::Enter]),
+        Event::Done("1 + 2".to_string())
+    );
+    assert_eq!(editor.history.entries(), ["1 + 2"]);
+}
+
+#[test]
+fn test_repl_editor_history() {
+    let mut history = History::new();
+    for line in ["let a = 1;", "let b = 2;", "let b = 2;", "  ", "a + b"] {
+        history.add(line);
+    }
+    assert_eq!(history.entries(), ["let a = 1;", "let b = 2;", "a + b"]);
+    assert_eq!(history.search("let", 3), Some(1));
+    assert_eq!(history.search("let", 1), Some(0));
+    assert_eq!(history.search("c", 3), None);
+
+    let mut editor = Editor::new(history);
+    type_keys(&mut editor, &chars("draft"));
+    type_keys(&mut editor, &[Key::Up, Key::Up]);
+    assert_eq!(editor.line(), "let b = 2;");
+    type_keys(&mut editor, &[Key::Up, Key::Up]);
+    assert_eq!(editor.line(), "let a = 1;");
+    type_keys(&mut editor, &[Key::Down, Key::Down, Key::Down]);
+    assert_eq!(editor.line(), "draft");
+
+    // reverse search, ctrl-r again for older match
+    type_keys(&mut editor, &[Key::Search, Key::Char('l'), Key::Char('e')]);
+    assert_eq!(
+        editor.render("-> "),
+        "\r(reverse-i-search)`le': let b = 2;\x1b[K"
+    );
+    type_keys(&mut editor, &[Key::Search]);
+    assert_eq!(
+        editor.render("-> "),
+        "\r(reverse-i-search)`le': let a = 1;\x1b[K"
+    );
+    // other key accepts the match and edits it
+    type_keys(&mut editor, &[Key::End, Key::Backspace]);
+    assert_eq!(editor.line(), "let a = 1");
+
+    // cancel keeps the line
+    type_keys(&mut editor, &[Key::Search, Key::Char('+'), Key::Cancel]);
+    assert_eq!(editor.line(), "let a = 1");
+    assert_eq!(
+        type_keys(&mut editor, &[Key::Search, Key::Char('+'), Key::Enter]),
+        Event::Done("a + b".to_string())
+    );
+}
+
+#[test]
+fn test_repl_history_file() {
+    let path = env::temp_dir()
+        .join(format!("dlang-history-{}", process::id()))
+        .join("history");
+    let _ = fs::remove_file(&path);
+
+    let mut history = History::load(path.clone());
+    assert!(history.is_empty());
+    history.add("let a = 1;\n");
+    history.add("a");
+    assert_eq!(fs::read_to_string(&path).unwrap(), "let a = 1;\na\n");
+
+    let history = History::load(path.clone());
+    assert_eq!(history.entries(), ["let a = 1;", "a"]);
+    fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn test_repl_completion() {
+    let mut repl = Repl::new();
+    run(&mut repl, &["let total = 1;\n", "let tally = [1];\n"]);
+
+    assert_eq!(
+        repl.complete("1 + t"),
+        (
+            4,
+            vec![
+                "tally".to_string(),
+                "throw".to_string(),
+                "total".to_string(),
+                "true".to_string(),
+                "try".to_string()
+            ]
+        )
+    );
+    assert_eq!(repl.complete("ret"), (0, vec!["return".to_string()]));
+    assert_eq!(
+        repl.complete(":t"),
+        (
+            0,
+            vec![
+                ":tokens".to_string(),
+                ":time".to_string(),
+                ":type".to_string()
+            ]
+        )
+    );
+    assert_eq!(repl.complete("m.t"), (2, Vec::new()));
+    assert_eq!(repl.complete("1 + "), (4, Vec::new()));
+
+    // common prefix is inserted, then candidates are listed
+    let mut editor = Editor::new(History::new());
+    let complete = |before: &str| repl.complete(before);
+    for key in chars("to") {
+        editor.feed(key, &complete);
+    }
+    editor.feed(Key::Tab, &complete);
+    assert_eq!(editor.line(), "total");
+    editor.feed(Key::KillToStart, &complete);
+    editor.feed(Key::Char('t'), &complete);
+    // nothing shared beyond what is typed
+    assert!(
+        matches!(editor.feed(Key::Tab, &complete), Event::Candidates(found) if found.len() == 5)
+    );
+    editor.feed(Key::Char('r'), &complete);
+    assert_eq!(
+        editor.feed(Key::Tab, &complete),
+        Event::Candidates(vec!["true".to_string(), "try".to_string()])
+    );
+}
+
+#[test]
+fn test_repl_save_restore() {
+    let dir = env::temp_dir().join(format!("dlang-session-{}", process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("session.dl");
+    let math = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("modules")
+        .join("lib")
+        .join("math.dl")
+        .canonicalize()
+        .unwrap();
+
+    let mut repl = Repl::new();
+    let out = run(
+        &mut repl,
+        &[
+            "let n = -2;\n",
+            "let arr = [\"a b\", true, [n]];\n",
+            "let scale = fn(x) { x * n };\n",
+            "let make = fn(k) { fn(x) { x + k } };\n",
+            "let add1 = make(1);\n",
+            "let e = try { throw 1; } catch (err) { err };\n",
+            &format!("import {:?} as m;\n", math.display().to_string()),
+            &format!(":save {}\n", path.display()),
+        ],
+    );
+    assert_eq!(
+        out[7],
+        format!(
+            "saved 5 names to {}\n\
+             add1 is not saved, function refers to names out of the session\n\
+             e is not saved, Error can't be written as source\n",
+            path.display()
+        )
+    );
+    assert_eq!(
+        fs::read_to_string(&path).unwrap(),
+        format!(
+            "// dlang session, load it back with :restore\n\
+             import {:?} as m;\n\
+             let arr = [\"a b\", true, [-2]];\n\
+             let make = fn make(k) {{fn(x) {{(x + k)}}}};\n\
+             let n = -2;\n\
+             let scale = fn scale(x) {{(x * n)}};\n\
+             // add1 is not saved, function refers to names out of the session\n\
+             // e is not saved, Error can't be written as source\n",
+            math.display().to_string()
+        )
+    );
+
+    let mut restored = Repl::new();
+    let out = run(
+        &mut restored,
+        &[
+            "let gone = 1;\n",
+            &format!(":restore {}\n", path.display()),
+            "scale(5)\n",
+            ":type make\n",
+            "arr[0]\n",
+            "gone\n",
+        ],
+    );
+    assert_eq!(out[1], "restored 5 names\n");
+    assert_eq!(out[2], "-10\n");
+    assert_eq!(out[3], "Function\n");
+    assert_eq!(out[4], "\"a b\"\n");
+    assert!(out[5].contains("ERROR"), "{}", out[5]);
+
+    // failed restore keeps the session
+    fs::write(&path, "let broken = ;").unwrap();
+    let out = run(
+        &mut restored,
+        &[&format!(":restore {}\n", path.display()), "n\n"],
+    );
+    assert!(out[0].contains("ERROR"), "{}", out[0]);
+    assert!(out[0].contains("(line 1)"), "{}", out[0]);
+    assert_eq!(out[1], "-2\n");
+    fs::remove_dir_all(&dir).unwrap();
+}
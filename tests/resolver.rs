@@ -0,0 +1,194 @@
+use std::{cell::RefCell, path::PathBuf, rc::Rc};
+
+use dlang::{
+    ast::{Binding, Expression, Nodetrait, Program, Statement},
+    eval::{errors::EvalError, Evaluator},
+    lexer::Lexer,
+    object::{environment::Environment, ObjectTrait},
+    parser::Parser,
+    resolver::{resolve_program, ResolveError},
+};
+
+fn parse(input: &str) -> Program {
+    Parser::new(Lexer::new(input.to_string())).parse().unwrap()
+}
+
+fn errors(input: &str) -> Vec<String> {
+    let mut program = parse(input);
+    resolve_program(&mut program, &[])
+        .iter()
+        .map(|err| err.message())
+        .collect()
+}
+
+fn eval(input: &str) -> String {
+    let env = Rc::new(RefCell::new(Environment::new()));
+    let result = Evaluator::new().evaluate(parse(input).to_node(), &env);
+    match result {
+        Ok(Some(obj)) => obj.to_str(),
+        Ok(None) => "None".to_string(),
+        Err(err) => err.message(),
+    }
+}
+
+/// identifier of `return <ident>` as last statement of the function body
+fn returned_binding(program: &Program) -> Option<Binding> {
+    let Statement::LetStatement(stm) = &program.statements[0] else {
+        panic!("{:?}", program.statements[0])
+    };
+    let Some(Expression::FunctionLiteral(func)) = &stm.value else {
+        panic!("{:?}", stm.value)
+    };
+    let Some(Statement::ReturnStatement(rtn)) = func.body.statements.last() else {
+        panic!("{:?}", func.body.statements)
+    };
+    let Some(Expression::Identifier(ident)) = &rtn.value else {
+        panic!("{:?}", rtn.value)
+    };
+    ident.binding
+}
+
+#[test]
+fn test_resolve_bindings() {
+    let cases = [
+        // parameter, in parameter scope outside of body
+        (
+            "let f = fn(a) { return a; };",
+            Binding::Local { depth: 1, slot: 0 },
+        ),
+        // let in body
+        (
+            "let f = fn(a) { let b = a; return b; };",
+            Binding::Local { depth: 0, slot: 0 },
+        ),
+        // second of body
+        (
+            "let f = fn() { let a = 1; let b = a; return b; };",
+            Binding::Local { depth: 0, slot: 1 },
+        ),
+        // rest parameter after parameters
+        (
+            "let f = fn(a, ...b) { return b; };",
+            Binding::Local { depth: 1, slot: 1 },
+        ),
+        // top level
+        (
+            "let x = 1; let f = fn() { return x; };",
+            Binding::Global { depth: 2 },
+        ),
+        // later top level
+        (
+            "let f = fn() { return x; }; let x = 1;",
+            Binding::Global { depth: 2 },
+        ),
+        // named function calling itself
+        (
+            "let f = fn g() { return g; };",
+            Binding::Global { depth: 2 },
+        ),
+    ];
+
+    for (input, expect) in cases {
+        let mut program = parse(input);
+        let errs = resolve_program(&mut program, &[]);
+        assert!(errs.is_empty(), "{}: {:?}", input, errs);
+        // binding of `f` is the last of let statements
+        let idx = program
+            .statements
+            .iter()
+            .position(|stm| matches!(stm, Statement::LetStatement(s) if s.identifier.value == "f"))
+            .unwrap();
+        let rest = program.statements.split_off(idx);
+        assert_eq!(
+            returned_binding(&Program { statements: rest }),
+            Some(expect),
+            "{}",
+            input
+        );
+    }
+}
+
+#[test]
+fn test_resolve_errors() {
+    let cases: [(&str, &[&str]); 8] = [
+        ("let a = 1; a", &[]),
+        ("a", &["undefined variable a"]),
+        // top level is bound in order
+        ("let a = b; let b = 1;", &["undefined variable b"]),
+        // but functions can refer later ones
+        ("let f = fn() { g() }; let g = fn() { 1 };", &[]),
+        ("fn f(n) { if (n == 0) { 0 } else { f(n - 1) } }", &[]),
+        (
+            "let f = fn(a, b, a) { c };",
+            &["duplicated parameter a", "undefined variable c"],
+        ),
+        // block scope ends with the block
+        ("if (true) { let a = 1; a } a", &["undefined variable a"]),
+        (
+            "try { throw 1 } catch (e) { e } finally { e }",
+            &["undefined variable e"],
+        ),
+    ];
+
+    for (input, expect) in cases {
+        assert_eq!(errors(input), expect, "{}", input);
+    }
+
+    let mut program = parse("a + 1");
+    assert_eq!(
+        resolve_program(&mut program, &["a".to_string()]),
+        Vec::<ResolveError>::new()
+    );
+}
+
+#[test]
+fn test_resolved_evaluation() {
+    let cases = [
+        ("let a = 1; let f = fn(b) { let c = a + b; c * 2 }; f(3)", "Int:8"),
+        // closure refers slots of outer function
+        (
+            "let f = fn(a) { let add = fn(b) { a + b }; let c = add(5); c }; f(2)",
+            "Int:7",
+        ),
+        // shadowing in nested block
+        (
+            "let f = fn(a) { let b = if (a > 0) { let a = 10; a } else { a }; a + b }; f(1)",
+            "Int:11",
+        ),
+        // default refers former parameter
+        ("let f = fn(a, b = a * 2) { a + b }; f(3)", "Int:9"),
+        ("let f = fn(a, ...b) { b }; f(1, 2, 3)", "[Int:2, Int:3]"),
+        (
+            "let f = fn() { try { throw 5 } catch (e) { e[\"value\"] } }; f()",
+            "Int:5",
+        ),
+        (
+            "let f = fn(n) { fn g(m) { if (m == 0) { 0 } else { m + g(m - 1) } } let r = g(n); r }; f(4)",
+            "Int:10",
+        ),
+        // undefined names still fail when evaluated
+        ("let f = fn() { nothing }; f()", "identifier nothing not found"),
+    ];
+
+    for (input, expect) in cases {
+        assert_eq!(eval(input), expect, "{}", input);
+    }
+}
+
+#[test]
+fn test_resolve_file() {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("modules")
+        .join("undefined.dl");
+
+    let env = Rc::new(RefCell::new(Environment::new()));
+    let err = Evaluator::new().evaluate_file(&path, &env).err().unwrap();
+    let EvalError::ModuleResolveFailed(detail) = err else {
+        panic!("{:?}", err)
+    };
+    assert_eq!(
+        detail.reason,
+        "duplicated parameter a (line 1); undefined variable b (line 1)"
+    );
+}
@@ -0,0 +1,109 @@
+use dlang::{
+    ast::{
+        fold::{self, Fold},
+        visit::{self, Visitor},
+        visit_mut::VisitorMut,
+        Expression, FunctionLiteral, Identifier, IntegerLiteral, Nodetrait, PrefixExpression,
+        Program,
+    },
+    lexer::Lexer,
+    parser::Parser,
+};
+
+fn parse(input: &str) -> Program {
+    Parser::new(Lexer::new(input.to_string())).parse().unwrap()
+}
+
+const INPUT: &str =
+    "let f = fn(a, b = 2, ...c) { let d = [a, b][0]; if (d > 1) { d } else { -c[0] } };
+try { f(1, b = 3) } catch (e) { e.message } finally { throw 4 }";
+
+#[derive(Default)]
+struct Names<'ast> {
+    identifiers: Vec<&'ast str>,
+    integers: usize,
+    functions: usize,
+}
+
+impl<'ast> Visitor<'ast> for Names<'ast> {
+    fn visit_identifier(&mut self, ident: &'ast Identifier) {
+        self.identifiers.push(&ident.value);
+    }
+    fn visit_integer_literal(&mut self, _lit: &'ast IntegerLiteral) {
+        self.integers += 1;
+    }
+    fn visit_function_literal(&mut self, func: &'ast FunctionLiteral) {
+        self.functions += 1;
+        visit::walk_function_literal(self, func);
+    }
+}
+
+#[test]
+fn test_visitor() {
+    let program = parse(INPUT);
+    let mut names = Names::default();
+    names.visit_program(&program);
+
+    assert_eq!(
+        names.identifiers,
+        ["f", "a", "b", "c", "d", "a", "b", "d", "d", "c", "f", "b", "e", "e", "message"]
+    );
+    assert_eq!(names.integers, 7);
+    assert_eq!(names.functions, 1);
+}
+
+struct Rename;
+
+impl VisitorMut for Rename {
+    fn visit_identifier_mut(&mut self, ident: &mut Identifier) {
+        ident.value = ident.value.to_uppercase();
+    }
+}
+
+#[test]
+fn test_visitor_mut() {
+    let mut program = parse("let a = fn(b) { b + c }; a(d)");
+    Rename.visit_program_mut(&mut program);
+
+    assert_eq!(
+        program.to_str(),
+        parse("let A = fn(B) { B + C }; A(D)").to_str()
+    );
+}
+
+/// replaces negated integer literal with negative literal, and doubles every integer
+struct Negate;
+
+impl Fold for Negate {
+    fn fold_expression(&mut self, exp: Expression) -> Expression {
+        match fold::fold_expression(self, exp) {
+            Expression::PrefixExpression(prefix) => match *prefix.right {
+                Expression::IntegerLiteral(lit) if prefix.token.literal == "-" => {
+                    Expression::IntegerLiteral(IntegerLiteral {
+                        value: -lit.value,
+                        ..lit
+                    })
+                }
+                right => Expression::PrefixExpression(PrefixExpression {
+                    token: prefix.token,
+                    right: Box::new(right),
+                }),
+            },
+            exp => exp,
+        }
+    }
+
+    fn fold_integer_literal(&mut self, lit: IntegerLiteral) -> IntegerLiteral {
+        IntegerLiteral {
+            value: lit.value * 2,
+            ..lit
+        }
+    }
+}
+
+#[test]
+fn test_fold() {
+    let program = Negate.fold_program(parse("let a = -3 + 1; [!true, -a, f(-2)]"));
+
+    assert_eq!(program.to_str(), "let a = (-6 + 2);[!(true), -(a), f(-4)]");
+}
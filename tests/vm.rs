@@ -0,0 +1,537 @@
+use std::{cell::RefCell, fs, path::PathBuf, rc::Rc};
+
+use dlang::{
+    ast::{Nodetrait, Program},
+    eval::{errors::EvalError, Evaluator},
+    lexer,
+    object::{environment::Environment, Int, Object, ObjectTrait},
+    optimizer::optimize,
+    parser,
+    token::Kind,
+    vm::{
+        code::{Bytecode, Expect, Instruction},
+        compiler::compile,
+        format::{deserialize, serialize, FormatError},
+        Vm,
+    },
+};
+
+fn parse(input: &str) -> Program {
+    let lex = lexer::Lexer::new(input.to_string());
+    let mut parser = parser::Parser::new(lex);
+    parser
+        .parse()
+        .unwrap_or_else(|err| panic!("{input}: {:?}", err))
+}
+
+// reached before the native stack limit of tree-walking evaluator in debug build,
+// so that both engines fail at the same depth
+const MAX_DEPTH: usize = 8;
+
+fn eval_tree(input: &str) -> Result<Option<Object>, EvalError> {
+    let env = Rc::new(RefCell::new(Environment::new()));
+    let mut evaluator = Evaluator::new();
+    evaluator.set_max_depth(MAX_DEPTH);
+    evaluator.evaluate(parse(input).to_node(), &env)
+}
+
+fn eval_vm(input: &str) -> Result<Option<Object>, EvalError> {
+    let mut vm = Vm::new();
+    vm.set_max_depth(MAX_DEPTH);
+    vm.evaluate(&parse(input))
+}
+
+/// both engines give the same value, or the same error at the same position
+fn assert_same(input: &str) {
+    let tree = eval_tree(input);
+    let vm = eval_vm(input);
+    match (&tree, &vm) {
+        (Ok(tree), Ok(vm)) => assert_eq!(
+            tree.as_ref().map(|obj| obj.to_str()),
+            vm.as_ref().map(|obj| obj.to_str()),
+            "{input}"
+        ),
+        (Err(tree), Err(vm)) => assert_eq!(tree.message(), vm.message(), "{input}"),
+        _ => panic!("{input}\ntree: {:?}\nvm: {:?}", tree, vm),
+    }
+}
+
+#[test]
+fn test_vm_expressions() {
+    let inputs = [
+        "5",
+        "-5 + 10 * 2 - (3 / 2)",
+        "!true == false",
+        "1 < 2 != 2 > 1",
+        "\"foo\" + \"bar\"",
+        "\"a\" == \"a\"",
+        "[1, 2 * 2, \"three\"]",
+        "[1, 2, 3][1]",
+        "[[1, 2], [3]][0][1]",
+        "if (1 > 2) { 10 } else { 20 }",
+        "if (false) { 10 }",
+        "let x = 5; let y = x * 2; y + x",
+        "let x = 1; let x = x + 1; x",
+        "if (true) { let a = 1; a + 1 }",
+        "let a = 1; if (true) { let a = 2; } a",
+        "",
+        "let x = 1;",
+        "return 3; 4",
+        "if (true) { return 5; 6 } 7",
+    ];
+    for input in inputs {
+        assert_same(input);
+    }
+}
+
+#[test]
+fn test_vm_equality_and_ordering() {
+    let inputs = [
+        "[1, [2, \"a\"]] == [1, [2, \"a\"]]",
+        "[1, 2] != [1, 2, 3]",
+        "1 == \"1\"",
+        "\"ab\" < \"abc\"",
+        "[1, 2] < [1, 2, 0]",
+        "[2] > [1, 9]",
+        "1 < \"1\"",
+        "[fn() { 1 }] < [fn() { 1 }]",
+        "let f = fn(x) { x }; let g = f; [g] == [f]",
+        "fn(x) { x } == fn(x) { x }",
+        "let make = fn() { fn() { 1 } }; make() == make()",
+    ];
+    for input in inputs {
+        assert_same(input);
+    }
+}
+
+#[test]
+fn test_vm_big_int() {
+    let inputs = [
+        "9223372036854775807 + 1",
+        "let min = -9223372036854775807 - 1; [min / -1, min % -1, -min]",
+        "123456789012345678901234567890n * 1000000000n - 1",
+        "100000000000000000000n / 7 + 100000000000000000000n % 7",
+        "[-7n / 2, -7n % 2, 2n + 3]",
+        "[5 == 5n, 5n < 6, [1, 2n] == [1n, 2]]",
+        "[(9223372036854775807 + 1) - 1, !5n, !9223372036854775808n, [10, 20][1n]]",
+        "[6n & 3, -8n | 3n, 18446744073709551616n | 1, -18446744073709551617n & -1]",
+        "let sq = fn(x, n) { if (n == 0) { x } else { sq(x * x, n - 1) } }; sq(2n, 20)",
+    ];
+    for input in inputs {
+        assert_same(input);
+    }
+}
+
+#[test]
+fn test_vm_functions() {
+    let inputs = [
+        "let f = fn(x) { x * 2 }; f(3)",
+        "let f = fn(x) { return x + 1; 100 }; f(1)",
+        "fn add(a, b) { a + b } add(1, 2)",
+        "let f = fn(a, b = a + 1) { [a, b] }; [f(1), f(1, 5), f(b = 3, a = 2)]",
+        "let f = fn(a, ...rest) { [a, rest] }; [f(1), f(1, 2, 3)]",
+        "let f = fn(a, b = 2, ...rest) { rest }; f(1, 2, 3, 4)",
+        "let fib = fn(n) { if (n < 2) { n } else { fib(n - 1) + fib(n - 2) } }; fib(8)",
+        "let sum = fn(n, acc = 0) { if (n == 0) { return acc; } sum(n - 1, acc + n) }; sum(10000)",
+        "let even = fn(n) { if (n == 0) { true } else { odd(n - 1) } };
+         let odd = fn(n) { if (n == 0) { false } else { even(n - 1) } };
+         even(10001)",
+        "let make = fn(x) { let add = fn(y) { x + y }; let r = add(10); r }; make(5)",
+        "let f = fn() { let g = fn() { h() }; let h = fn() { 7 }; let r = g(); r }; f()",
+        "let f = fn(x) { let g = fn() { x }; let x = 3; let r = g(); r }; f(1)",
+        "let apply = fn(f, x) { f(x) }; apply(fn(x) { x * x }, 4)",
+        "let f = fn(a, b) { a }; f",
+        "fn named(a) { a }",
+        "let f = fn() { if (true) { return 1; } 2 }; f()",
+        "let f = fn(x) { if (x > 0) { if (true) { return x; } } 0 }; f(4)",
+        "let f = fn() { }; f()",
+    ];
+    for input in inputs {
+        assert_same(input);
+    }
+}
+
+#[test]
+fn test_vm_try() {
+    let inputs = [
+        "try { throw \"boom\" } catch (e) { e[\"message\"] }",
+        "try { 1 } catch (e) { 2 }",
+        "try { [1][5] } catch (e) { e[\"kind\"] }",
+        "try { x } catch (e) { e[\"span\"] }",
+        "let f = fn() {\n let a = 1;\n throw a\n}; try { f() } catch (e) { e[\"span\"] }",
+        "let f = fn() { throw 3 }; try { f() } catch (e) { e[\"value\"] }",
+        "try { throw 1 } catch (e) { throw 2 }",
+        "try { throw 1 } finally { 2 }",
+        "try { 1 } finally { throw 2 }",
+        "try { try { throw 1 } finally { 5 } } catch (e) { e[\"value\"] }",
+        "try { try { throw 1 } catch (e) { throw e[\"value\"] + 1 } } catch (e) { e[\"value\"] }",
+        "let f = fn() { try { return 1; } finally { throw 2 } }; try { f() } catch (e) { e[\"value\"] }",
+        "let f = fn() { try { throw 1 } catch (e) { return 2; } finally { 3 } }; f()",
+        "let f = fn() { try { throw 1 } finally { return 2; } }; try { f() } catch (e) { e[\"value\"] }",
+        "let f = fn() { let x = try { 1 } finally { return 2; }; x }; f()",
+        "let f = fn(n) { try { if (n == 0) { throw 9 } f(n - 1) } catch (e) { e[\"value\"] + n } }; f(3)",
+        "try { throw 1 } catch { 2 }",
+        "throw \"out\"",
+    ];
+    for input in inputs {
+        assert_same(input);
+    }
+}
+
+#[test]
+fn test_vm_errors() {
+    let inputs = [
+        "1 + true",
+        "-true",
+        "foobar",
+        "if (1) { 2 }",
+        "[1, 2][\"a\"]",
+        "5[0]",
+        "let f = fn() { }; let x = f();",
+        "let f = fn() { }; f() + 1",
+        "let f = fn() { }; -f()",
+        "let f = fn() { }; [f()]",
+        "let f = fn() { }; if (f()) { 1 }",
+        "1(2)",
+        "let f = fn(a) { a }; f()",
+        "let f = fn(a) { a }; f(1, 2)",
+        "let f = fn(a, b = 1) { a }; f(b = 2)",
+        "let f = fn(a) { a }; f(c = 2)",
+        "let f = fn(a) { a }; f(1, a = 2)",
+        "let f = fn() { 1 }; f.x",
+        "let f = fn() {\n let a = 1;\n a + true\n}; f()",
+        "let r = fn(n) { 1 + r(n + 1) }; r(0)",
+        "5 % 0",
+        "7n / 0",
+        "9223372036854775808n & 1",
+    ];
+    for input in inputs {
+        assert_same(input);
+    }
+}
+
+#[test]
+fn test_vm_stack_overflow() {
+    let input = "let r = fn(n) { 1 + r(n + 1) }; r(0)";
+    let mut vm = Vm::new();
+    vm.set_max_depth(10);
+    let err = vm.evaluate(&parse(input)).unwrap_err();
+    assert_eq!(err, EvalError::StackOverflow { depth: 10 });
+
+    // tail calls don't grow the stack
+    let input = "let r = fn(n) { if (n == 0) { return 0; } r(n - 1) }; r(100)";
+    assert_eq!(
+        vm.evaluate(&parse(input)).unwrap(),
+        Some(Object::Int(Int { value: 0 }))
+    );
+}
+
+#[test]
+fn test_vm_interrupted() {
+    let mut vm = Vm::new();
+    vm.interrupt_handle().interrupt();
+    let input = "let f = fn() { 1 }; try { f() } catch (e) { 2 }";
+    assert_eq!(
+        vm.evaluate(&parse(input)).unwrap_err(),
+        EvalError::Interrupted
+    );
+}
+
+#[test]
+fn test_vm_modules() {
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("modules");
+
+    for name in [
+        "main.dl",
+        "missing.dl",
+        "cycle_a.dl",
+        "broken.dl",
+        "private.dl",
+    ] {
+        let path = dir.join(name);
+        let env = Rc::new(RefCell::new(Environment::new()));
+        let tree = Evaluator::new().evaluate_file(&path, &env);
+        let vm = Vm::new().evaluate_file(&path);
+        match (&tree, &vm) {
+            (Ok(tree), Ok(vm)) => assert_eq!(tree, vm, "{name}"),
+            (Err(tree), Err(vm)) => assert_eq!(tree.message(), vm.message(), "{name}"),
+            _ => panic!("{name}\ntree: {:?}\nvm: {:?}", tree, vm),
+        }
+    }
+}
+
+fn round_trip(input: &str) -> Bytecode {
+    let bytes = serialize(&compile(&parse(input)).unwrap());
+    let bytecode = deserialize(&bytes).unwrap();
+    // encoding is stable
+    assert_eq!(serialize(&bytecode), bytes, "{input}");
+    bytecode
+}
+
+#[test]
+fn test_vm_serialize() {
+    let inputs = [
+        "let fib = fn(n) { if (n < 2) { n } else { fib(n - 1) + fib(n - 2) } }; fib(8)",
+        "let f = fn(a, b = a + 1, ...rest) { [a, b, rest] }; [f(1), f(1, b = 5), f(1, 2, 3)]",
+        "let make = fn(x) { let add = fn(y) { x + y }; let r = add(10); r }; make(5)",
+        "try { throw \"boom\" } catch (e) { e[\"span\"] } finally { 1 }",
+        "!true == -1 < 2",
+        "let f = fn() { }; let x = f();",
+        "let f = fn() { }; -f()",
+        "let f = fn() { }; fn(x) { x }(if (true) { f() })",
+        "[123456789012345678901234567890n, -5n, 9223372036854775807 + 1]",
+    ];
+    for input in inputs {
+        let expect = eval_vm(input);
+        let result = Vm::new().run(round_trip(input));
+        match (&expect, &result) {
+            (Ok(expect), Ok(result)) => assert_eq!(expect, result, "{input}"),
+            (Err(expect), Err(result)) => {
+                assert_eq!(expect.message(), result.message(), "{input}")
+            }
+            _ => panic!("{input}\nexpect: {:?}\nresult: {:?}", expect, result),
+        }
+    }
+}
+
+// value or error message, to compare outcomes of engines
+fn outcome(result: Result<Option<Object>, EvalError>) -> String {
+    match result {
+        Ok(obj) => obj.map(|obj| obj.to_str()).unwrap_or_default(),
+        Err(err) => err.message(),
+    }
+}
+
+#[test]
+fn test_vm_differential() {
+    let inputs = [
+        "let mk = fn(x) { fn(y) { x + y } }; mk(2)(3)",
+        "let mk = fn(x) { let add = fn(y) { x + y }; add }; let add = mk(2); [add(3), add(4)]",
+        "let f = fn(a) { fn(b) { fn(c) { a + b + c } } }; f(1)(2)(3)",
+        "let compose = fn(f, g) { fn(x) { f(g(x)) } }; compose(fn(x) { x * 2 }, fn(x) { x + 1 })(5)",
+        "let adders = fn(n) { [fn(x) { x + n }, fn(x) { x - n }] }; adders(3)[1](10)",
+        "let fib = fn(n) { if (n < 2) { n } else { fib(n - 1) + fib(n - 2) } }; fib(8)",
+        "let f = fn(a, b = a + 1, ...rest) { [a, b, rest] }; [f(1), f(1, b = 5), f(1, 2, 3)]",
+        "try { throw \"boom\" } catch (e) { e[\"message\"] }",
+        "let x = 2 * 3; if (x > 5) { x + 9223372036854775807 } else { 0 }",
+        "let f = fn() { 1 + true }; f()",
+    ];
+    for input in inputs {
+        let tree = outcome(eval_tree(input));
+        let optimized = optimize(parse(input));
+
+        let env = Rc::new(RefCell::new(Environment::new()));
+        let mut evaluator = Evaluator::new();
+        evaluator.set_max_depth(MAX_DEPTH);
+        let optimized_tree = outcome(evaluator.evaluate(optimized.clone().to_node(), &env));
+        assert_eq!(tree, optimized_tree, "optimized tree: {input}");
+
+        assert_eq!(tree, outcome(eval_vm(input)), "vm: {input}");
+        assert_eq!(tree, outcome(Vm::new().run(round_trip(input))), "serialized vm: {input}");
+        let mut vm = Vm::new();
+        vm.set_max_depth(MAX_DEPTH);
+        assert_eq!(tree, outcome(vm.evaluate(&optimized)), "optimized vm: {input}");
+    }
+}
+
+#[test]
+fn test_vm_run_file() {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("modules")
+        .join("main.dl");
+    let program = parse(&fs::read_to_string(&path).unwrap());
+    let bytecode = deserialize(&serialize(&compile(&program).unwrap())).unwrap();
+
+    // imports are resolved relative to the file
+    let res = Vm::new().run_file(&path, bytecode).unwrap();
+    assert_eq!(res, Some(Object::Int(Int { value: 12 })));
+}
+
+#[test]
+fn test_vm_deserialize_errors() {
+    let bytes = serialize(&compile(&parse("let f = fn(x) { x + 1 }; f(1)")).unwrap());
+
+    assert_eq!(
+        deserialize(b"let x = 1;").unwrap_err(),
+        FormatError::NotBytecode
+    );
+
+    let mut version = bytes.clone();
+    version[4] = 99;
+    assert_eq!(
+        deserialize(&version).unwrap_err(),
+        FormatError::UnsupportedVersion(99)
+    );
+
+    let err = deserialize(&bytes[..8]).unwrap_err();
+    assert_eq!(
+        err,
+        FormatError::Truncated {
+            offset: 8,
+            item: "header"
+        }
+    );
+    let err = deserialize(&bytes[..bytes.len() - 3]).unwrap_err();
+    assert!(matches!(err, FormatError::Truncated { .. }), "{:?}", err);
+    assert!(err.message().contains("truncated"));
+
+    let mut trailing = bytes.clone();
+    trailing.push(0);
+    assert!(matches!(
+        deserialize(&trailing).unwrap_err(),
+        FormatError::TrailingBytes { .. }
+    ));
+
+    let mut corrupted = bytes.clone();
+    let last = corrupted.len() - 1;
+    corrupted[last] ^= 0xff;
+    assert_eq!(
+        deserialize(&corrupted).unwrap_err(),
+        FormatError::ChecksumMismatch
+    );
+
+    // well formed, but refers nothing
+    let mut bytecode = compile(&parse("let x = 1; x")).unwrap();
+    bytecode.functions[0].code.insert(0, Instruction::Jump(100));
+    let err = deserialize(&serialize(&bytecode)).unwrap_err();
+    assert!(
+        matches!(
+            err,
+            FormatError::InvalidOperand {
+                function: 0,
+                ip: 0,
+                ..
+            }
+        ),
+        "{:?}",
+        err
+    );
+
+    let mut bytecode = compile(&parse("let x = 1; x")).unwrap();
+    bytecode.functions[0].code.push(Instruction::GetGlobal(7));
+    let err = deserialize(&serialize(&bytecode)).unwrap_err();
+    assert!(
+        matches!(err, FormatError::InvalidFunction { .. }),
+        "{:?}",
+        err
+    );
+
+    // operands are valid, but values are taken from empty stack
+    let mut bytecode = compile(&parse("let x = 1; x")).unwrap();
+    bytecode.functions[0].code.insert(0, Instruction::Pop);
+    let err = deserialize(&serialize(&bytecode)).unwrap_err();
+    assert!(
+        matches!(
+            err,
+            FormatError::InvalidStack {
+                function: 0,
+                ip: 0,
+                ..
+            }
+        ),
+        "{:?}",
+        err
+    );
+
+    let mut bytecode = compile(&parse("let f = fn(x) { [x, x] }; f(1)")).unwrap();
+    let array = bytecode.functions[1]
+        .code
+        .iter()
+        .position(|ins| matches!(ins, Instruction::Array(_)))
+        .unwrap();
+    bytecode.functions[1].code[array] = Instruction::Array(100);
+    let err = deserialize(&serialize(&bytecode)).unwrap_err();
+    assert!(
+        matches!(err, FormatError::InvalidStack { function: 1, .. }),
+        "{:?}",
+        err
+    );
+    assert!(err.message().contains("takes 100 values"), "{}", err.message());
+
+    // paths joining with different heights
+    let mut bytecode = compile(&parse("if (true) { 1 } else { 2 }")).unwrap();
+    let jump = bytecode.functions[0]
+        .code
+        .iter()
+        .position(|ins| matches!(ins, Instruction::Jump(_)))
+        .unwrap();
+    bytecode.functions[0].code.insert(jump, Instruction::True);
+    for ins in bytecode.functions[0].code.iter_mut() {
+        if let Instruction::Jump(target) | Instruction::JumpIfFalse(target) = ins {
+            *target += 1;
+        }
+    }
+    let err = deserialize(&serialize(&bytecode)).unwrap_err();
+    assert!(
+        matches!(err, FormatError::InvalidStack { .. }),
+        "{:?}",
+        err
+    );
+    assert!(err.message().contains("different stack heights"), "{}", err.message());
+
+    let mut bytecode = compile(&parse("1")).unwrap();
+    bytecode.functions[0].code.insert(0, Instruction::Catch);
+    let err = deserialize(&serialize(&bytecode)).unwrap_err();
+    assert!(
+        matches!(err, FormatError::InvalidStack { ip: 0, .. }),
+        "{:?}",
+        err
+    );
+}
+
+#[test]
+fn test_vm_deserialize_unchecked_values() {
+    // heights are right, but the vm would unwrap nothing or call a non function
+    let tests = [
+        (
+            vec![
+                Instruction::Nothing,
+                Instruction::Nothing,
+                Instruction::Infix(Kind::Plus),
+                Instruction::Return,
+            ],
+            "may be nothing",
+        ),
+        (
+            vec![
+                Instruction::Nothing,
+                Instruction::Nothing,
+                Instruction::Array(2),
+                Instruction::Return,
+            ],
+            "may be nothing",
+        ),
+        (
+            vec![
+                Instruction::True,
+                Instruction::Call(0, None),
+                Instruction::Return,
+            ],
+            "not checked to be a function",
+        ),
+    ];
+    for (code, expect) in tests {
+        let mut bytecode = compile(&parse("1")).unwrap();
+        bytecode.functions[0].code = code;
+        let err = deserialize(&serialize(&bytecode)).unwrap_err();
+        assert!(
+            matches!(err, FormatError::InvalidStack { function: 0, .. }),
+            "{:?}",
+            err
+        );
+        assert!(err.message().contains(expect), "{}", err.message());
+    }
+
+    // once checked, the vm raises an error instead of panicking
+    let mut bytecode = compile(&parse("1")).unwrap();
+    bytecode.functions[0].code = vec![
+        Instruction::Nothing,
+        Instruction::Expect(Expect::Callee),
+        Instruction::Call(0, None),
+        Instruction::Return,
+    ];
+    assert!(deserialize(&serialize(&bytecode)).is_ok());
+}